@@ -0,0 +1,33 @@
+## Messages shown by venice-cli. Copy this file to <config dir>/venice/locales/<lang>.ftl to
+## translate or override them.
+
+update-available = note: venice-cli { $latest } is available (you have { $current }) - { $url }
+explain-hint = For more information about this error, run `venice explain { $code }`
+
+deps-installed = ✓ Installed { $name } { $version }
+deps-added = ✓ Added { $name } { $version }
+deps-added-path = ✓ Added { $name } from { $path }
+deps-removed = ✓ Removed { $name }
+deps-published = ✓ Published { $name } { $version }
+deps-yanked = ✓ Yanked { $name } { $version }
+deps-unyanked = ✓ Un-yanked { $name } { $version }
+deps-vendored = ✓ Vendored { $count ->
+        [one] { $count } package
+       *[other] { $count } packages
+    } into { $dir }/
+
+rename-done = ✓ Renamed { $old } to { $new }, updated imports in { $count ->
+        [one] { $count } file
+       *[other] { $count } files
+    }
+rename-manual = warning: couldn't rewrite the import at { $location } - update it by hand
+rename-precompiled = warning: { $path } is precompiled, so its imports can't be rewritten - recompile it if it imports the renamed module
+
+dev-rebuilding = Change detected, rebuilding...
+dev-running = ✓ Running - watching for changes (Ctrl+C to stop)
+dev-built = ✓ Built a { $size } program table - watching for changes (Ctrl+C to stop)
+
+device-default-set = ✓ { $device } is now the default device
+device-port-busy-retry = { $port } is in use by another program, retrying ({ $attempt }/{ $attempts })...
+device-port-busy-waiting = Waiting for { $holder } to release { $port } (Ctrl+C to give up)...
+device-queue-waiting = Waiting for `venice { $command }` (pid { $pid }) to finish with { $port }...
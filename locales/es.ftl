@@ -0,0 +1,30 @@
+update-available = nota: venice-cli { $latest } está disponible (tienes { $current }) - { $url }
+explain-hint = Para más información sobre este error, ejecuta `venice explain { $code }`
+
+deps-installed = ✓ Instalado { $name } { $version }
+deps-added = ✓ Añadido { $name } { $version }
+deps-added-path = ✓ Añadido { $name } desde { $path }
+deps-removed = ✓ Eliminado { $name }
+deps-published = ✓ Publicado { $name } { $version }
+deps-yanked = ✓ Retirado { $name } { $version }
+deps-unyanked = ✓ Restaurado { $name } { $version }
+deps-vendored = ✓ { $count ->
+        [one] { $count } paquete copiado
+       *[other] { $count } paquetes copiados
+    } en { $dir }/
+
+rename-done = ✓ { $old } renombrado a { $new }, imports actualizados en { $count ->
+        [one] { $count } archivo
+       *[other] { $count } archivos
+    }
+rename-manual = aviso: no se pudo reescribir el import en { $location } - actualízalo a mano
+rename-precompiled = aviso: { $path } está precompilado, así que sus imports no se pueden reescribir - recompílalo si importa el módulo renombrado
+
+dev-rebuilding = Cambio detectado, recompilando...
+dev-running = ✓ En ejecución - vigilando cambios (Ctrl+C para salir)
+dev-built = ✓ Tabla de programa de { $size } compilada - vigilando cambios (Ctrl+C para salir)
+
+device-default-set = ✓ { $device } es ahora el dispositivo predeterminado
+device-port-busy-retry = { $port } está en uso por otro programa, reintentando ({ $attempt }/{ $attempts })...
+device-port-busy-waiting = Esperando a que { $holder } libere { $port } (Ctrl+C para cancelar)...
+device-queue-waiting = Esperando a que `venice { $command }` (pid { $pid }) termine con { $port }...
@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    TABLE_FILE,
+    errors::CliError,
+    manifest::{Project, get_project},
+    profile, project_dir,
+    registry::sha256_hex,
+    signing::verify,
+    vpt::read_programs,
+};
+
+/// What the brain shows for a program, and where it goes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProgramInfo {
+    pub name: String,
+    pub slot: u8,
+    pub icon: u16,
+    pub description: Option<String>,
+}
+
+impl ProgramInfo {
+    pub fn from_project(project: &Project) -> Result<Self, CliError> {
+        let slot = project
+            .slot
+            .filter(|slot| (1..=8).contains(slot))
+            .ok_or(CliError::SlotOutOfRange)?;
+
+        Ok(Self {
+            name: project.name.clone(),
+            slot,
+            icon: project.icon as u16,
+            description: project.description.clone(),
+        })
+    }
+}
+
+/// Sidecar written next to a program table by `build --artifact-metadata`, carrying everything
+/// needed to flash the table later without the project.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArtifactMetadata {
+    #[serde(flatten)]
+    pub program: ProgramInfo,
+    /// Runtime version the table was built and import-checked against
+    pub runtime: Option<semver::Version>,
+    /// SHA-256 of the whole table
+    pub sha256: String,
+    pub size: usize,
+    /// SHA-256 of each module's bytecode, by module name
+    pub modules: BTreeMap<String, String>,
+    /// Hex-encoded public key of the signer, if the table is signed
+    pub signed_by: Option<String>,
+    /// Whether the bytecode was built without debug info, so tracebacks have no line numbers
+    #[serde(default)]
+    pub stripped: bool,
+    pub cli_version: String,
+}
+
+/// `out.vpt` -> `out.vpt.json`
+pub fn metadata_path(vpt_path: &Path) -> PathBuf {
+    let mut path = vpt_path.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+fn module_hashes(vpt: &[u8]) -> Result<BTreeMap<String, String>, CliError> {
+    Ok(read_programs(vpt)?
+        .into_iter()
+        .map(|program| {
+            (
+                String::from_utf8_lossy(&program.name).into_owned(),
+                sha256_hex(&program.payload),
+            )
+        })
+        .collect())
+}
+
+/// Writes the sidecar for a table just built from the current project, returning its path.
+pub async fn write_metadata(
+    vpt: &[u8],
+    runtime: Option<semver::Version>,
+) -> Result<PathBuf, CliError> {
+    let project = get_project().await?;
+    let signer = verify(&read_programs(vpt)?)?;
+    let metadata = ArtifactMetadata {
+        program: ProgramInfo::from_project(&project)?,
+        runtime,
+        sha256: sha256_hex(vpt),
+        size: vpt.len(),
+        modules: module_hashes(vpt)?,
+        signed_by: signer.map(|key| hex::encode(key.to_bytes())),
+        stripped: project.stripped(profile()),
+        cli_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+
+    let table_path = project.out_dir(project_dir()?, profile()).join(TABLE_FILE);
+    let path = metadata_path(&table_path);
+    tokio::fs::write(&path, serde_json::to_string_pretty(&metadata)?).await?;
+    Ok(path)
+}
+
+/// Reads a table and its sidecar, checking that they belong together.
+pub async fn load(vpt_path: &Path) -> Result<(ArtifactMetadata, Vec<u8>), CliError> {
+    let vpt = tokio::fs::read(vpt_path).await?;
+    let metadata_path = metadata_path(vpt_path);
+    let metadata: ArtifactMetadata = match tokio::fs::read_to_string(&metadata_path).await {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CliError::NoArtifactMetadata(metadata_path));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if vpt.len() != metadata.size || sha256_hex(&vpt) != metadata.sha256 {
+        return Err(CliError::ArtifactMismatch("its hash differs".to_string()));
+    }
+    if module_hashes(&vpt)? != metadata.modules {
+        return Err(CliError::ArtifactMismatch(
+            "its module hashes differ".to_string(),
+        ));
+    }
+    let signer = verify(&read_programs(&vpt)?)?.map(|key| hex::encode(key.to_bytes()));
+    if signer != metadata.signed_by {
+        return Err(CliError::ArtifactMismatch(
+            "it's signed by a different key".to_string(),
+        ));
+    }
+
+    Ok((metadata, vpt))
+}
+
+/// `venice artifact verify`
+pub async fn verify_artifact(vpt_path: &Path) -> Result<(), CliError> {
+    let (metadata, _) = load(vpt_path).await?;
+    println!(
+        "✓ {} matches its metadata: `{}` for slot {}",
+        vpt_path.display(),
+        metadata.program.name,
+        metadata.program.slot
+    );
+    if let Some(runtime) = &metadata.runtime {
+        println!("  runtime {runtime}");
+    }
+    match &metadata.signed_by {
+        Some(key) => println!("  signed by {key}"),
+        None => println!("  not signed"),
+    }
+    if metadata.stripped {
+        println!("  stripped of debug info");
+    }
+    Ok(())
+}
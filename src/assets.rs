@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use venice_program_table::{ProgramBuilder, ProgramFlags};
+
+use crate::errors::CliError;
+
+/// Start of an asset's program name, followed by its path in the assets directory with `/`
+/// between components: `assets/paths/skills.json` is packed as `<venice-asset>paths/skills.json`.
+/// The runtime looks assets up by that name, and never imports them.
+pub const ASSET_PREFIX: &str = "<venice-asset>";
+
+/// Flag bit set on assets, beside the table's `IS_PACKAGE` and the build's `COMPRESSED_FLAG`, so
+/// tools reading a program table can tell data from bytecode without going by the name.
+const ASSET_FLAG: u8 = 1 << 7;
+
+pub fn asset_flags() -> ProgramFlags {
+    ProgramFlags::from_bits_retain(ASSET_FLAG.into())
+}
+
+fn collect(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), CliError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        // Editor swap files, .DS_Store and the like
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            collect(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Every file under `dir`, [tool.venice.assets].dir, sorted. A missing directory has none.
+pub fn asset_paths(dir: &Path) -> Result<Vec<PathBuf>, CliError> {
+    let mut paths = Vec::new();
+    if dir.is_dir() {
+        collect(dir, &mut paths)?;
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// A program for each of the [`asset_paths`] in `dir`.
+pub fn asset_programs(dir: &Path) -> Result<Vec<ProgramBuilder>, CliError> {
+    let mut assets = Vec::new();
+    for path in asset_paths(dir)? {
+        let relative = path
+            .strip_prefix(dir)
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/");
+        assets.push(ProgramBuilder {
+            name: format!("{ASSET_PREFIX}{relative}").into_bytes(),
+            payload: std::fs::read(&path)?,
+            flags: asset_flags(),
+        });
+    }
+    Ok(assets)
+}
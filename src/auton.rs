@@ -0,0 +1,80 @@
+use venice_program_table::{ProgramBuilder, ProgramFlags};
+use vex_v5_serial::commands::file::USER_PROGRAM_LOAD_ADDR;
+
+use crate::{
+    build::table_path, device::open_connection, errors::CliError, upload::upload_user_file,
+    vpt::read_programs,
+};
+
+/// Program in the VPT listing the autonomous routines, one name per line. The runtime reads it
+/// to show the routines, and to check the selection against.
+pub const AUTONS_PROGRAM: &[u8] = b"<venice-autons>";
+
+/// Brain file holding the name of the selected routine, read by the runtime at startup.
+pub const SELECTION_FILE: &str = "venice-auton.txt";
+
+/// The [`AUTONS_PROGRAM`] for [tool.venice].autons, if there are any.
+pub fn autons_program(autons: &[String]) -> Option<ProgramBuilder> {
+    (!autons.is_empty()).then(|| ProgramBuilder {
+        name: AUTONS_PROGRAM.to_vec(),
+        payload: autons.join("\n").into_bytes(),
+        flags: ProgramFlags::empty(),
+    })
+}
+
+/// The routines listed in a built program table.
+pub fn routines(vpt: &[u8]) -> Result<Vec<String>, CliError> {
+    Ok(read_programs(vpt)?
+        .into_iter()
+        .find(|program| program.name == AUTONS_PROGRAM)
+        .map(|program| {
+            String::from_utf8_lossy(&program.payload)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// The routines in the last build of the project.
+pub async fn built_routines() -> Result<Vec<String>, CliError> {
+    match tokio::fs::read(table_path().await?).await {
+        Ok(vpt) => routines(&vpt),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(CliError::NotBuilt),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `venice auton list`: the routines in the last build.
+pub async fn list() -> Result<(), CliError> {
+    let routines = built_routines().await?;
+    if routines.is_empty() {
+        println!("No autonomous routines. List them in [tool.venice].autons and rebuild.");
+    }
+    for routine in routines {
+        println!("{routine}");
+    }
+    Ok(())
+}
+
+/// `venice auton select`: tells the runtime which of `routines` to run.
+pub async fn select(name: &str, routines: &[String]) -> Result<(), CliError> {
+    if !routines.iter().any(|routine| routine == name) {
+        return Err(CliError::UnknownAuton {
+            name: name.to_string(),
+            available: routines.join(", "),
+        });
+    }
+
+    let mut conn = open_connection().await?;
+    upload_user_file(
+        &mut conn,
+        SELECTION_FILE,
+        name.as_bytes(),
+        USER_PROGRAM_LOAD_ADDR,
+        None,
+    )
+    .await?;
+    println!("✓ Selected autonomous routine `{name}`");
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use vex_v5_serial::{
+    Connection,
+    protocol::cdc2::system::{SystemFlagsPacket, SystemFlagsReplyPacket},
+    serial::{SerialConnection, SerialDevice, SerialError},
+};
+
+use crate::{
+    config,
+    device::{system_port, usb_serial_number},
+    errors::CliError,
+    store::{MAX_BATTERY_READINGS, Store},
+};
+
+/// Charge levels at one connection. The protocol only reports charge, in steps of 8%, not
+/// voltage.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct BatteryReading {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    /// USB serial number of the device the reading came through
+    pub device: Option<String>,
+    pub brain_percent: u8,
+    /// `None` when no controller is linked
+    pub controller_percent: Option<u8>,
+}
+
+/// Reads the brain's (and linked controller's) battery charge from the system flags.
+pub async fn read_battery(conn: &mut SerialConnection) -> Result<(u8, Option<u8>), SerialError> {
+    let reply = conn
+        .handshake::<SystemFlagsReplyPacket>(Duration::from_secs(1), 2, SystemFlagsPacket::new(()))
+        .await?;
+    let flags = reply.payload.map_err(SerialError::Nack)?;
+
+    // Brain charge in the high nibble, controller charge in the low one, both in units of 8%
+    let brain = ((flags.byte_1 >> 4) * 8).min(100);
+    let controller = ((flags.byte_1 & 0x0f) * 8).min(100);
+    Ok((brain, (controller != 0).then_some(controller)))
+}
+
+/// Appends a reading to the history. Called on every connection; never fails, since losing a
+/// history entry isn't worth failing the command the user asked for.
+pub async fn record(conn: &mut SerialConnection, device: &SerialDevice) -> Option<BatteryReading> {
+    let (brain_percent, controller_percent) = read_battery(conn).await.ok()?;
+    let reading = BatteryReading {
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        device: usb_serial_number(system_port(device)),
+        brain_percent,
+        controller_percent,
+    };
+
+    let _ = Store::update(|store| {
+        store.battery_history.push(reading.clone());
+        let excess = store
+            .battery_history
+            .len()
+            .saturating_sub(MAX_BATTERY_READINGS);
+        store.battery_history.drain(..excess);
+    });
+    Some(reading)
+}
+
+/// Warns when the brain is too flat for motor behavior to be representative.
+pub fn warn_if_low(brain_percent: u8) {
+    let threshold = config().battery.warn_below;
+    if brain_percent < threshold {
+        eprintln!(
+            "warning: the brain battery is at {brain_percent}% (below {threshold}%) - motors are noticeably weaker on a low battery, so charge it before tuning or testing autonomous"
+        );
+    }
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+/// `venice battery history`: the most recent readings, newest last.
+pub fn history(limit: usize) -> Result<(), CliError> {
+    let readings = Store::load()?.battery_history;
+    if readings.is_empty() {
+        println!("No battery readings yet. One is recorded every time venice connects to a brain.");
+        return Ok(());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    for reading in readings.iter().skip(readings.len().saturating_sub(limit)) {
+        let controller = reading
+            .controller_percent
+            .map(|percent| format!("  controller {percent:>3}%"))
+            .unwrap_or_default();
+        println!(
+            "{:>10}  {:<12}  brain {:>3}%{controller}",
+            format_age(now.saturating_sub(reading.time)),
+            reading.device.as_deref().unwrap_or("unknown"),
+            reading.brain_percent,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use semver::Version;
+
+use crate::{
+    ci::{self, CiOptions},
+    errors::CliError,
+    releases::ReleaseQuery,
+    runtime::{available_versions, fetch_source},
+};
+
+/// Accepts versions written like release tags, e.g. `v0.3.0`.
+pub fn parse_version(s: &str) -> Result<Version, semver::Error> {
+    s.trim_start_matches('v').parse()
+}
+
+/// Binary search for the first bad release among those published between a known-good and a
+/// known-bad one.
+struct Search {
+    /// Ascending, excluding the known-good and known-bad releases
+    candidates: Vec<Version>,
+    /// Candidates before this index are good
+    lo: usize,
+    /// Candidates from this index on are bad; the known-bad release when it's the length
+    hi: usize,
+}
+
+impl Search {
+    fn new(mut candidates: Vec<Version>) -> Self {
+        candidates.sort();
+        let hi = candidates.len();
+        Self {
+            candidates,
+            lo: 0,
+            hi,
+        }
+    }
+
+    /// The release to test next, or `None` once the first bad one is known.
+    fn next(&self) -> Option<&Version> {
+        (self.lo < self.hi).then(|| &self.candidates[(self.lo + self.hi) / 2])
+    }
+
+    fn record(&mut self, passed: bool) {
+        let mid = (self.lo + self.hi) / 2;
+        if passed {
+            self.lo = mid + 1;
+        } else {
+            self.hi = mid;
+        }
+    }
+
+    /// Releases still to narrow down, plus the known-bad one.
+    fn remaining(&self) -> usize {
+        self.hi - self.lo + 1
+    }
+
+    fn last_good<'a>(&'a self, good: &'a Version) -> &'a Version {
+        self.lo
+            .checked_sub(1)
+            .map_or(good, |index| &self.candidates[index])
+    }
+
+    fn first_bad<'a>(&'a self, bad: &'a Version) -> &'a Version {
+        self.candidates.get(self.lo).unwrap_or(bad)
+    }
+}
+
+/// `venice bisect`: finds the first runtime release between `good` and `bad` the program fails
+/// on, by uploading and running it against releases as `run --ci` would. Only the releases in
+/// between are tested; `good` and `bad` are taken at their word.
+pub async fn bisect(good: &Version, bad: &Version, options: CiOptions) -> Result<(), CliError> {
+    if good >= bad {
+        return Err(CliError::BisectRange {
+            good: good.clone(),
+            bad: bad.clone(),
+        });
+    }
+
+    let query = ReleaseQuery {
+        since: Some(good.clone()),
+        ..ReleaseQuery::all()
+    };
+    let candidates = available_versions(&query)
+        .await?
+        .into_iter()
+        .filter(|version| version > good && version < bad)
+        .collect();
+    let mut search = Search::new(candidates);
+    println!(
+        "Bisecting {} release(s) between v{good} and v{bad}",
+        search.candidates.len()
+    );
+
+    while let Some(version) = search.next().cloned() {
+        let steps = search.remaining().ilog2();
+        println!("\n── Testing v{version} (about {steps} step(s) left) ──\n");
+        let source = fetch_source(&version).await?;
+        let passed = match ci::run(options.clone(), false, Some(source)).await {
+            Ok(()) => true,
+            Err(e @ (CliError::CiTimeout { .. } | CliError::CiFailureOutput { .. })) => {
+                eprintln!("✗ v{version} is bad: {e}");
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        search.record(passed);
+    }
+
+    println!(
+        "\nv{} is the first bad release; v{} was the last good one.",
+        search.first_bad(bad),
+        search.last_good(good)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::Search;
+
+    fn search_for(first_bad: u64, releases: u64) -> (Version, usize) {
+        let candidates = (1..releases)
+            .map(|minor| Version::new(0, minor, 0))
+            .collect();
+        let mut search = Search::new(candidates);
+        let mut tested = 0;
+        while let Some(version) = search.next() {
+            let passed = version.minor < first_bad;
+            search.record(passed);
+            tested += 1;
+        }
+        let bad = Version::new(0, releases, 0);
+        (search.first_bad(&bad).clone(), tested)
+    }
+
+    #[test]
+    fn finds_the_first_bad_release() {
+        assert_eq!(search_for(4, 10).0, Version::new(0, 4, 0));
+        assert_eq!(search_for(1, 10).0, Version::new(0, 1, 0));
+        // Every release in between passed, so the known-bad one is the first bad one
+        assert_eq!(search_for(10, 10).0, Version::new(0, 10, 0));
+        assert!(search_for(7, 100).1 <= 7);
+    }
+}
@@ -0,0 +1,326 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::Deserialize;
+
+use vex_v5_serial::{
+    Connection,
+    commands::file::{DownloadFile, USER_PROGRAM_LOAD_ADDR},
+    protocol::{
+        FixedString,
+        cdc2::{
+            Cdc2Ack,
+            file::{
+                FileClearUpPacket, FileClearUpPayload, FileClearUpReplyPacket,
+                FileDirectoryEntryPacket, FileDirectoryEntryPayload, FileDirectoryEntryReplyPacket,
+                FileDirectoryPacket, FileDirectoryPayload, FileDirectoryReplyPacket,
+                FileErasePacket, FileErasePayload, FileEraseReplyPacket, FileLoadAction,
+                FileLoadActionPacket, FileLoadActionPayload, FileLoadActionReplyPacket,
+                FileTransferTarget, FileVendor,
+            },
+            system::{
+                KeyValueLoadPacket, KeyValueLoadReplyPacket, KeyValueSavePacket,
+                KeyValueSavePayload, KeyValueSaveReplyPacket,
+            },
+        },
+    },
+    serial::{SerialConnection, SerialError},
+};
+
+use crate::errors::CliError;
+
+/// System key holding the team number shown on the brain's home screen.
+pub const TEAM_NUMBER_KEY: &str = "teamnumber";
+/// System key holding the robot name shown on the brain's home screen.
+pub const ROBOT_NAME_KEY: &str = "robotname";
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+const RETRIES: usize = 2;
+
+/// Just enough of a `slot_N.ini` to describe the program in it.
+#[derive(Deserialize, Debug)]
+pub struct SlotIni {
+    pub program: SlotIniProgram,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SlotIniProgram {
+    pub name: String,
+    pub slot: u8,
+}
+
+/// Vendors besides venice's that store program files, and who uses them. VEXos won't say which
+/// tool a vendor belongs to, so these are only good guesses.
+const FOREIGN_VENDORS: &[(FileVendor, &str)] = &[
+    (FileVendor::VexVm, "VEXcode"),
+    (FileVendor::Vex, "VEX"),
+    (FileVendor::Dev1, "a third-party tool like PROS"),
+    (FileVendor::Dev2, "a third-party tool like PROS"),
+    (FileVendor::Dev3, "a third-party tool"),
+    (FileVendor::Dev4, "a third-party tool"),
+    (FileVendor::Dev5, "a third-party tool"),
+    (FileVendor::Dev6, "a third-party tool"),
+];
+
+/// A slot's file stored under another vendor's name.
+#[derive(Debug, Clone)]
+pub struct ForeignFile {
+    pub name: String,
+    pub vendor: FileVendor,
+    /// Who probably put it there
+    pub owner: &'static str,
+}
+
+/// A file stored on the brain.
+#[derive(Debug, Clone)]
+pub struct BrainFile {
+    pub name: String,
+    pub size: u32,
+    pub load_address: u32,
+    pub crc32: u32,
+}
+
+/// CRC32 as VEXos computes it for stored files: MSB-first, polynomial 0x04C11DB7, no initial
+/// value or final XOR.
+pub fn vex_crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Lists every file the brain stores for `vendor`.
+pub async fn list_files(
+    conn: &mut SerialConnection,
+    vendor: FileVendor,
+) -> Result<Vec<BrainFile>, SerialError> {
+    let reply = conn
+        .handshake::<FileDirectoryReplyPacket>(
+            TIMEOUT,
+            RETRIES,
+            FileDirectoryPacket::new(FileDirectoryPayload {
+                vendor,
+                reserved: 0,
+            }),
+        )
+        .await?;
+    let count = reply.payload.map_err(SerialError::Nack)?.file_count;
+
+    let mut files = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let reply = conn
+            .handshake::<FileDirectoryEntryReplyPacket>(
+                TIMEOUT,
+                RETRIES,
+                FileDirectoryEntryPacket::new(FileDirectoryEntryPayload {
+                    file_index: index as u8,
+                    reserved: 0,
+                }),
+            )
+            .await?;
+
+        if let Some(entry) = reply.payload.map_err(SerialError::Nack)? {
+            files.push(BrainFile {
+                name: entry.file_name.to_string(),
+                size: entry.size,
+                load_address: entry.load_address,
+                crc32: entry.crc32,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Names of the programs on the brain, by slot, read from their `slot_N.ini` files.
+pub async fn slot_programs(
+    conn: &mut SerialConnection,
+) -> Result<BTreeMap<u8, String>, SerialError> {
+    let mut programs = BTreeMap::new();
+    for file in list_files(conn, FileVendor::User).await? {
+        if !(file.name.starts_with("slot_") && file.name.ends_with(".ini")) {
+            continue;
+        }
+        let contents = download_file(conn, &file).await?;
+        if let Ok(ini) = serde_ini::from_str::<SlotIni>(&String::from_utf8_lossy(&contents)) {
+            programs.insert(ini.program.slot, ini.program.name);
+        }
+    }
+    Ok(programs)
+}
+
+pub async fn download_file(
+    conn: &mut SerialConnection,
+    file: &BrainFile,
+) -> Result<Vec<u8>, SerialError> {
+    conn.execute_command(DownloadFile {
+        file_name: FixedString::new(file.name.clone()).unwrap(),
+        vendor: FileVendor::User,
+        target: FileTransferTarget::Qspi,
+        load_addr: if file.load_address == 0 {
+            USER_PROGRAM_LOAD_ADDR
+        } else {
+            file.load_address
+        },
+        size: file.size,
+        progress_callback: None,
+    })
+    .await
+}
+
+/// Reads a system key-value pair, e.g. [`TEAM_NUMBER_KEY`]. Returns `None` if the key is unset.
+pub async fn read_key(
+    conn: &mut SerialConnection,
+    key: &str,
+) -> Result<Option<String>, SerialError> {
+    let reply = conn
+        .handshake::<KeyValueLoadReplyPacket>(
+            TIMEOUT,
+            RETRIES,
+            KeyValueLoadPacket::new(FixedString::new(key.to_string()).unwrap()),
+        )
+        .await?;
+
+    match reply.ack() {
+        Cdc2Ack::Ack => Ok(reply
+            .payload
+            .map_err(SerialError::Nack)?
+            .to_string()
+            .split('\0')
+            .next()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)),
+        nack => Err(SerialError::Nack(nack)),
+    }
+}
+
+pub async fn write_key(
+    conn: &mut SerialConnection,
+    key: &str,
+    value: &str,
+) -> Result<(), CliError> {
+    let too_long = || CliError::BrainKeyValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+    let reply = conn
+        .handshake::<KeyValueSaveReplyPacket>(
+            TIMEOUT,
+            RETRIES,
+            KeyValueSavePacket::new(KeyValueSavePayload {
+                key: FixedString::new(key.to_string()).map_err(|_| too_long())?,
+                value: FixedString::new(value.to_string()).map_err(|_| too_long())?,
+            }),
+        )
+        .await?;
+
+    Ok(reply.payload.map_err(SerialError::Nack)?)
+}
+
+/// Files for `slot` that other tools stored under their own vendor. The brain can show one of
+/// those programs in the slot instead of the one venice uploads.
+pub async fn foreign_slot_files(
+    conn: &mut SerialConnection,
+    slot: u8,
+) -> Result<Vec<ForeignFile>, SerialError> {
+    let prefix = format!("slot_{slot}.");
+    let mut foreign = Vec::new();
+    for &(vendor, owner) in FOREIGN_VENDORS {
+        let files = match list_files(conn, vendor).await {
+            Ok(files) => files,
+            // Some VEXos versions refuse to list vendors they don't know
+            Err(SerialError::Nack(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        foreign.extend(
+            files
+                .into_iter()
+                .filter(|file| file.name.starts_with(&prefix))
+                .map(|file| ForeignFile {
+                    name: file.name,
+                    vendor,
+                    owner,
+                }),
+        );
+    }
+    Ok(foreign)
+}
+
+/// Deletes a user file from the brain.
+pub async fn erase_file(conn: &mut SerialConnection, name: &str) -> Result<(), CliError> {
+    erase_vendor_file(conn, FileVendor::User, name).await
+}
+
+/// Deletes a file stored under `vendor` from the brain.
+pub async fn erase_vendor_file(
+    conn: &mut SerialConnection,
+    vendor: FileVendor,
+    name: &str,
+) -> Result<(), CliError> {
+    let reply = conn
+        .handshake::<FileEraseReplyPacket>(
+            TIMEOUT,
+            RETRIES,
+            FileErasePacket::new(FileErasePayload {
+                vendor,
+                reserved: 128,
+                file_name: FixedString::new(name.to_string())
+                    .map_err(|_| CliError::BrainFileName(name.to_string()))?,
+            }),
+        )
+        .await?;
+
+    Ok(reply.payload.map_err(SerialError::Nack)?)
+}
+
+/// Deletes every user file on the brain in one request, much faster than erasing them one at a
+/// time.
+pub async fn clear_user_files(conn: &mut SerialConnection) -> Result<(), SerialError> {
+    let reply = conn
+        .handshake::<FileClearUpReplyPacket>(
+            Duration::from_secs(30),
+            RETRIES,
+            FileClearUpPacket::new(FileClearUpPayload {
+                vendor: FileVendor::User,
+                reserved: 0,
+            }),
+        )
+        .await?;
+
+    reply.payload.map_err(SerialError::Nack)
+}
+
+/// Stops whatever program is running, as if it had been stopped from the brain's screen.
+pub async fn stop_program(conn: &mut SerialConnection) -> Result<(), SerialError> {
+    let reply = conn
+        .handshake::<FileLoadActionReplyPacket>(
+            TIMEOUT,
+            RETRIES,
+            FileLoadActionPacket::new(FileLoadActionPayload {
+                vendor: FileVendor::User,
+                action: FileLoadAction::Stop,
+                file_name: FixedString::new(String::new()).unwrap(),
+            }),
+        )
+        .await?;
+
+    reply.payload.map_err(SerialError::Nack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::vex_crc32;
+
+    #[test]
+    fn crc_matches_vexos() {
+        // CRC-32/CKSUM's check value, without its final XOR
+        assert_eq!(vex_crc32(b"123456789"), 0x765E_7680 ^ 0xFFFF_FFFF);
+    }
+}
@@ -1,14 +1,47 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
     ffi::{OsStr, OsString},
+    io::{Read, Write},
     path::{Path, PathBuf},
+    pin::Pin,
     process::Stdio,
-    time::SystemTime,
+    task::Poll,
+    thread::available_parallelism,
+    time::Instant,
 };
 
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use venice_program_table::{ProgramBuilder, ProgramFlags, VptBuilder};
 
 use crate::{
-    BUILD_DIR, MPY_CROSS_PATH, TABLE_FILE, VENDOR_ID, errors::CliError, project_dir,
+    BUILD_DIR, TABLE_FILE, VENDOR_ID,
+    assets::asset_programs,
+    auton::autons_program,
+    buildinfo::{BUILDINFO_MODULE, buildinfo_source},
+    config::cache_dir,
+    deps::{VENDOR_DIR, dependency_roots, install, pypi_roots},
+    diagnostics::{MessageFormat, compiler_error, print_message},
+    env::is_identifier,
+    errors::CliError,
+    generate::generate_modules,
+    glob,
+    imports::{CheckedSource, builtin_modules, check_imports, import_graph, unreachable_modules},
+    manifest::{BuildConfig, ProfileConfig, Project, UnreachableModules, get_project},
+    mpy::check_precompiled,
+    profile, project_dir,
+    pysource::has_code,
+    redact::{find_host_path, host_paths},
+    registry::sha256_hex,
+    runtime::{Feature, check_feature},
+    signing::{SIGNATURE_PROGRAM, load_or_create_key, signature_payload},
+    stats::record_build,
+    timings::{BuildTimings, ModuleTiming},
+    toolchain,
+    vpt::UNPACK_DIR,
+    workspace,
 };
 
 pub const SRC_EXT: &str = "py";
@@ -16,19 +49,226 @@ pub const BUILD_EXT: &str = "mpy";
 
 pub const PYTHON_MOD_SEP: u8 = b'.';
 
+/// Subdirectory of the build dir holding sources generated by the CLI.
+pub const GENERATED_DIR: &str = "generated";
+
+/// Module the program starts from, which every other has to be reachable from.
+pub const ENTRYPOINT: &str = "main";
+
+/// Generated module whose empty bytecode stands in for a namespace package's `__init__`.
+const NAMESPACE_PACKAGE: &str = "namespace_package";
+
+/// File in the build dir recording what each module's bytecode was compiled from.
+pub const CACHE_FILE: &str = ".venice-cache.json";
+
+/// Directories that aren't taken for namespace packages, though they may hold Python files: build
+/// output, vendored dependencies, `venice vpt unpack` output, and tests, which run on the host.
+const NON_PACKAGE_DIRS: &[&str] = &[BUILD_DIR, VENDOR_DIR, UNPACK_DIR, "tests"];
+
+/// Flag bit set on programs whose payload is zlib-compressed, which the runtime inflates when
+/// it loads them. Payloads that wouldn't get smaller are left as they are, without it.
+pub const COMPRESSED_FLAG: u8 = 1 << 6;
+
+/// Subdirectory of the cache dir holding bytecode shared between projects.
+pub const SHARED_CACHE_DIR: &str = "compiled";
+
+#[derive(Debug, Default, Clone)]
+pub struct BuildOptions {
+    /// Append a signature trailer program, signed with the key in the config dir
+    pub sign: bool,
+    /// Runtime the program will run on, which decides the built-in modules imports may use
+    pub runtime: Option<semver::Version>,
+    /// Print per-module compile times and write an HTML report to the build dir
+    pub timings: bool,
+    /// Modules compiled at once. Defaults to the number of CPUs.
+    pub jobs: Option<usize>,
+    /// With [`MessageFormat::Json`], a record is printed for each module compiled and the program
+    /// table written
+    pub message_format: MessageFormat,
+}
+
+impl BuildOptions {
+    fn jobs(&self) -> usize {
+        self.jobs
+            .unwrap_or_else(|| available_parallelism().map_or(1, |n| n.get()))
+            .max(1)
+    }
+}
+
+/// A front-end that turns project sources into MicroPython bytecode for the VPT.
+pub trait Compiler {
+    /// Extension of the source files this front-end compiles, without the dot.
+    fn src_ext(&self) -> &'static str;
+
+    /// Identifies the compiler's version, so bytecode from a different one is rebuilt.
+    fn version(&self) -> impl Future<Output = String>;
+
+    /// Compiles one source file to bytecode at `build_path`. `source_name` is the file name
+    /// embedded in the bytecode and shown in tracebacks.
+    fn compile(
+        &self,
+        src_path: &Path,
+        build_path: &Path,
+        source_name: &OsStr,
+    ) -> impl Future<Output = Result<(), CliError>>;
+}
+
+/// Compiles Python sources with `mpy-cross`. Made by [`Project::compiler`], which applies the
+/// project's pin, profile and flags.
+pub struct MpyCross {
+    /// [tool.venice].mpy-cross-version
+    pub pinned: Option<semver::Version>,
+    /// `-O` level
+    pub opt_level: u8,
+    /// [tool.venice.build].mpy-flags
+    pub flags: Vec<String>,
+}
+
+impl Compiler for MpyCross {
+    fn src_ext(&self) -> &'static str {
+        SRC_EXT
+    }
+
+    async fn version(&self) -> String {
+        let Ok(mpy_cross) = toolchain::mpy_cross(self.pinned.as_ref()).await else {
+            return String::new();
+        };
+        tokio::process::Command::new(mpy_cross)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map(|output| {
+                // Bytecode from another optimization level or other flags is stale too
+                let banner = String::from_utf8_lossy(&output.stdout);
+                let mut version = format!("{} -O{}", banner.trim(), self.opt_level);
+                for flag in &self.flags {
+                    version.push(' ');
+                    version.push_str(flag);
+                }
+                version
+            })
+            .unwrap_or_default()
+    }
+
+    async fn compile(
+        &self,
+        src_path: &Path,
+        build_path: &Path,
+        source_name: &OsStr,
+    ) -> Result<(), CliError> {
+        let mpy_cross = toolchain::mpy_cross(self.pinned.as_ref()).await?;
+        compile_file(
+            &mpy_cross,
+            self.opt_level,
+            &self.flags,
+            src_path,
+            build_path,
+            source_name,
+        )
+        .await
+    }
+}
+
+/// [tool.venice].language: which front-end compiles the project's sources.
+#[derive(Deserialize, JsonSchema, Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Language {
+    #[default]
+    Python,
+}
+
+/// `--profile`: which of [tool.venice.profile] a build uses.
+#[derive(clap::ValueEnum, Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BuildProfile {
+    #[default]
+    Debug,
+    Release,
+}
+
+impl BuildProfile {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Release => "release",
+        }
+    }
+}
+
+impl Project {
+    fn profile(&self, profile: BuildProfile) -> &ProfileConfig {
+        match profile {
+            BuildProfile::Debug => &self.profiles.debug,
+            BuildProfile::Release => &self.profiles.release,
+        }
+    }
+
+    /// Where `profile` builds go in the project at `dir`: its out-dir, or `build/<profile>`. A
+    /// workspace member's default is `build/<member>/<profile>` in the workspace's root.
+    pub fn out_dir(&self, dir: &Path, profile: BuildProfile) -> PathBuf {
+        match &self.profile(profile).out_dir {
+            Some(out_dir) => dir.join(out_dir),
+            None => match workspace().filter(|workspace| workspace.contains(dir)) {
+                Some(workspace) => workspace
+                    .root
+                    .join(BUILD_DIR)
+                    .join(dir.file_name().unwrap_or_default())
+                    .join(profile.name()),
+                None => dir.join(BUILD_DIR).join(profile.name()),
+            },
+        }
+    }
+
+    /// Whether `profile` builds leave debug info out of the bytecode.
+    pub fn stripped(&self, profile: BuildProfile) -> bool {
+        self.profile(profile).strip
+    }
+
+    /// The front-end for [tool.venice].language, at the version the project pins if it does.
+    /// Release builds compile out asserts unless the profile sets its own optimization level.
+    pub fn compiler(&self, profile: BuildProfile) -> impl Compiler {
+        let opt_level = self.profile(profile).opt_level.unwrap_or(match profile {
+            BuildProfile::Debug => 0,
+            BuildProfile::Release => 1,
+        });
+        // -O3 is the level at which mpy-cross drops line numbers
+        let opt_level = if self.stripped(profile) { 3 } else { opt_level };
+        match self.language {
+            Language::Python => MpyCross {
+                pinned: self.mpy_cross_version.clone(),
+                opt_level: opt_level.min(3),
+                flags: self.build.mpy_flags.clone(),
+            },
+        }
+    }
+}
+
+/// The program table the current project's last build under `--profile` wrote.
+pub async fn table_path() -> Result<PathBuf, CliError> {
+    let dir = project_dir()?;
+    Ok(get_project()
+        .await?
+        .out_dir(dir, profile())
+        .join(TABLE_FILE))
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct SrcModule {
     name: OsString,
+    src_ext: &'static str,
 }
 
 impl SrcModule {
-    fn from_path(path: &Path, src_dir: &Path) -> Self {
+    pub fn from_path(path: &Path, src_dir: &Path, src_ext: &'static str) -> Self {
         let dir_stripped = path.strip_prefix(src_dir).unwrap();
         let ext_stripped = dir_stripped
             .with_file_name(dir_stripped.file_stem().unwrap())
             .into_os_string();
 
-        Self { name: ext_stripped }
+        Self {
+            name: ext_stripped,
+            src_ext,
+        }
     }
 
     pub fn python_name(&self) -> Result<Vec<u8>, CliError> {
@@ -49,8 +289,19 @@ impl SrcModule {
         Ok(python_name)
     }
 
+    pub fn is_package(&self) -> bool {
+        self.name.as_encoded_bytes().ends_with(b"__init__")
+    }
+
+    /// Whether the module is bytecode shipped without its source, packed as it is.
+    pub fn is_precompiled(&self) -> bool {
+        self.src_ext == BUILD_EXT
+    }
+
+    /// The module's program flags. Only `IS_PACKAGE` is the table's own; the CLI's take the top
+    /// bits: [`COMPRESSED_FLAG`] and the assets' flag.
     pub fn module_flags(&self) -> ProgramFlags {
-        if self.name.as_encoded_bytes().ends_with(b"__init__") {
+        if self.is_package() {
             ProgramFlags::IS_PACKAGE
         } else {
             ProgramFlags::empty()
@@ -58,48 +309,133 @@ impl SrcModule {
     }
 
     pub fn src_path(&self, src_dir: &Path) -> PathBuf {
-        src_dir.join(&self.name).with_extension(SRC_EXT)
+        src_dir.join(&self.name).with_extension(self.src_ext)
     }
 
     pub fn build_path(&self, build_dir: &Path) -> PathBuf {
         build_dir.join(&self.name).with_extension(BUILD_EXT)
     }
 
-    pub async fn needs_rebuild(
-        &self,
-        src_dir: &Path,
-        build_dir: &Path,
-    ) -> Result<bool, std::io::Error> {
-        let src_modified = tokio::fs::metadata(self.src_path(src_dir))
-            .await?
-            .modified()
-            .unwrap_or(SystemTime::UNIX_EPOCH);
-        let build_modified = tokio::fs::metadata(self.build_path(build_dir))
+    /// Whether the bytecode is missing or was compiled from a different source than the one with
+    /// `hash`.
+    async fn needs_rebuild(&self, build_dir: &Path, cache: &BuildCache, hash: &str) -> bool {
+        let built = tokio::fs::try_exists(self.build_path(build_dir))
             .await
-            .and_then(|metadata| metadata.modified())
-            .unwrap_or(SystemTime::UNIX_EPOCH);
-        Ok(src_modified >= build_modified)
+            .unwrap_or(false);
+        !built || cache.modules.get(&self.cache_key()) != Some(&hash.to_string())
     }
+
+    fn cache_key(&self) -> String {
+        self.name
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/")
+    }
+}
+
+/// What each module's bytecode in the build dir was compiled from. Content hashes, unlike
+/// modification times, survive git checkouts, `touch` and copying the project.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct BuildCache {
+    /// [`Compiler::version`] of the compiler that built the bytecode
+    compiler: String,
+    /// SHA-256 of each module's source, by module path
+    modules: BTreeMap<String, String>,
+}
+
+impl BuildCache {
+    /// The cache for bytecode from `compiler`. A missing or unreadable cache, or one from a
+    /// different compiler, is empty, so everything is rebuilt.
+    pub async fn load(build_dir: &Path, compiler: String) -> Self {
+        let cache = tokio::fs::read_to_string(build_dir.join(CACHE_FILE))
+            .await
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).ok())
+            .filter(|cache| cache.compiler == compiler);
+        cache.unwrap_or(Self {
+            compiler,
+            modules: BTreeMap::new(),
+        })
+    }
+
+    pub async fn save(&self, build_dir: &Path) -> Result<(), CliError> {
+        tokio::fs::write(build_dir.join(CACHE_FILE), serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+}
+
+/// Whether `dir` directly holds source files. Hidden directories like `.venv` never count.
+async fn has_sources(dir: &Path, src_ext: &str) -> Result<bool, CliError> {
+    if dir
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    {
+        return Ok(false);
+    }
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        if entry.path().extension() == Some(OsStr::new(src_ext)) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The packages `modules` are in that have no `__init__` module of their own.
+fn namespace_packages(modules: &[SrcModule]) -> Result<BTreeSet<String>, CliError> {
+    let mut parents = BTreeSet::new();
+    let mut packages = BTreeSet::new();
+    for module in modules {
+        let name = String::from_utf8_lossy(&module.python_name()?).into_owned();
+        let mut prefix = name.as_str();
+        while let Some((parent, _)) = prefix.rsplit_once('.') {
+            parents.insert(parent.to_string());
+            prefix = parent;
+        }
+        if module.is_package() {
+            packages.insert(name);
+        }
+    }
+    Ok(parents.difference(&packages).cloned().collect())
 }
 
 /// Find modules starting from entrypoint directory.
 /// For root: looks for main.py first, then __init__.py.
-/// For subdirectories: only __init__.py marks a package.
+/// For subdirectories: only __init__.py marks a package, unless `namespace_packages` is set.
 async fn find_modules_inner(
     src_dir: &Path,
     dir: &Path,
+    src_ext: &'static str,
     modules: &mut Vec<SrcModule>,
     is_root: bool,
+    namespace_packages: bool,
 ) -> Result<(), CliError> {
-    let has_init = tokio::fs::try_exists(dir.join("__init__.py")).await.map_err(CliError::Io)?;
-    let has_main = tokio::fs::try_exists(dir.join("main.py")).await.map_err(CliError::Io)?;
+    let init = dir.join("__init__");
+    let has_init = tokio::fs::try_exists(init.with_extension(src_ext))
+        .await
+        .map_err(CliError::Io)?
+        || tokio::fs::try_exists(init.with_extension(BUILD_EXT))
+            .await
+            .map_err(CliError::Io)?;
+    let has_main = tokio::fs::try_exists(dir.join("main").with_extension(src_ext))
+        .await
+        .map_err(CliError::Io)?;
     if is_root && !has_main {
         // For root, we need main.py
         return Err(CliError::NoEntrypoint(dir.to_path_buf()));
-    }
-    else if !is_root && !has_init {
+    } else if !is_root && !has_init {
         // For subdirs, we need __init__.py to be a package
-        return Ok(());
+        if !could_be_namespace_package(dir) {
+            return Ok(());
+        }
+        if !namespace_packages {
+            if has_sources(dir, src_ext).await? {
+                eprintln!(
+                    "warning: skipping {}, which has no __init__.{src_ext}; add one, or set [tool.venice.build].namespace-packages",
+                    dir.strip_prefix(src_dir).unwrap_or(dir).display()
+                );
+            }
+            return Ok(());
+        }
     }
 
     let mut read_dir = tokio::fs::read_dir(dir).await.map_err(CliError::Io)?;
@@ -108,92 +444,753 @@ async fn find_modules_inner(
 
         let file_type = entry.file_type().await.map_err(CliError::Io)?;
         if file_type.is_dir() {
-            Box::pin(find_modules_inner(src_dir, &path, modules, false)).await?;
-        } else if path.extension() == Some(OsStr::new(SRC_EXT)) {
+            if is_ignored_dir(&path).await? {
+                continue;
+            }
+            Box::pin(find_modules_inner(
+                src_dir,
+                &path,
+                src_ext,
+                modules,
+                false,
+                namespace_packages,
+            ))
+            .await?;
+        } else if let Some(ext) = module_ext(&path, src_ext).await? {
             let filename = path.file_stem().and_then(|s| s.to_str());
 
             if !is_root && filename == Some("main") {
                 continue;
             }
 
-            modules.push(SrcModule::from_path(&path, src_dir));
+            modules.push(SrcModule::from_path(&path, src_dir, ext));
         }
     }
 
     Ok(())
 }
 
-pub async fn find_modules(src_dir: &Path) -> Result<Vec<SrcModule>, CliError> {
+/// Directories never searched for modules: hidden ones like `.venv` and `.venice`, bytecode
+/// caches, virtual environments, and build dirs, whose bytecode would otherwise pass for
+/// precompiled modules.
+async fn is_ignored_dir(dir: &Path) -> Result<bool, CliError> {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    Ok(name.starts_with('.')
+        || name == "__pycache__"
+        || tokio::fs::try_exists(dir.join("pyvenv.cfg")).await?
+        || tokio::fs::try_exists(dir.join(CACHE_FILE)).await?)
+}
+
+/// Whether `dir`, which has no `__init__` module, would be a namespace package: its name has to be
+/// importable, and not one of [`NON_PACKAGE_DIRS`].
+fn could_be_namespace_package(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(OsStr::to_str)
+        .is_some_and(|name| is_identifier(name) && !NON_PACKAGE_DIRS.contains(&name))
+}
+
+/// The extension `path` is a module by: `src_ext` for a source file, or [`BUILD_EXT`] for
+/// bytecode without a source file beside it, which is packed precompiled.
+async fn module_ext(path: &Path, src_ext: &'static str) -> Result<Option<&'static str>, CliError> {
+    let ext = path.extension();
+    if ext == Some(OsStr::new(src_ext)) {
+        Ok(Some(src_ext))
+    } else if ext == Some(OsStr::new(BUILD_EXT))
+        && !tokio::fs::try_exists(path.with_extension(src_ext)).await?
+    {
+        Ok(Some(BUILD_EXT))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Puts `modules` in a fixed order, since directories are read in whatever order the filesystem
+/// lists them and the program table is packed in this order.
+fn sort_modules(modules: &mut [SrcModule]) {
+    modules.sort_by_cached_key(SrcModule::cache_key);
+}
+
+/// Finds the project's modules, leaving out those [tool.venice.build].exclude matches unless
+/// `include` matches them too.
+pub async fn find_modules(
+    src_dir: &Path,
+    compiler: &impl Compiler,
+    config: &BuildConfig,
+) -> Result<Vec<SrcModule>, CliError> {
+    let mut modules = Vec::new();
+    find_modules_inner(
+        src_dir,
+        src_dir,
+        compiler.src_ext(),
+        &mut modules,
+        true,
+        config.namespace_packages,
+    )
+    .await?;
+    modules.retain(|module| is_included(config, &module.src_path(Path::new(""))));
+    sort_modules(&mut modules);
+    Ok(modules)
+}
+
+/// Whether the source at `path`, relative to the project, is compiled: when no exclude glob
+/// matches it, or an include glob does.
+fn is_included(config: &BuildConfig, path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let matches = |patterns: &[String]| patterns.iter().any(|p| glob::matches(p, &path));
+    !matches(&config.exclude) || matches(&config.include)
+}
+
+/// The project's modules, from its own directory then each of [tool.venice.build].src-dirs, by
+/// the directory they're in.
+pub async fn find_project_modules(
+    project_dir: &Path,
+    project: &Project,
+    compiler: &impl Compiler,
+) -> Result<Vec<(PathBuf, Vec<SrcModule>)>, CliError> {
+    let mut sources = vec![(
+        project_dir.to_path_buf(),
+        find_modules(project_dir, compiler, &project.build).await?,
+    )];
+    for dir in &project.build.src_dirs {
+        let mut modules = find_root_modules(&project_dir.join(dir), compiler).await?;
+        modules.retain(|module| is_included(&project.build, &module.src_path(dir)));
+        sources.push((project_dir.join(dir), modules));
+    }
+    Ok(sources)
+}
+
+/// Fails if two source files, in any of the directories built from, would be the same module.
+fn check_collisions(sources: &[(PathBuf, Vec<SrcModule>)]) -> Result<(), CliError> {
+    let mut seen = BTreeMap::new();
+    for (root, modules) in sources {
+        for module in modules {
+            let path = module.src_path(root);
+            let name = String::from_utf8_lossy(&module.python_name()?).into_owned();
+            if let Some(first) = seen.insert(name.clone(), path.clone()) {
+                return Err(CliError::ModuleCollision {
+                    module: name,
+                    first,
+                    second: path,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Find the modules of the package `package` inside `root`, which unlike a project root has no
+/// entrypoint. Used for dependencies.
+pub async fn find_package_modules(
+    root: &Path,
+    package: &str,
+    compiler: &impl Compiler,
+) -> Result<Vec<SrcModule>, CliError> {
     let mut modules = Vec::new();
-    find_modules_inner(src_dir, src_dir, &mut modules, true).await?;
+    find_modules_inner(
+        root,
+        &root.join(package),
+        compiler.src_ext(),
+        &mut modules,
+        false,
+        false,
+    )
+    .await?;
+    sort_modules(&mut modules);
+    Ok(modules)
+}
+
+/// Finds every top-level module and package in `root`, a directory of modules without an
+/// entrypoint: a [tool.venice.build].src-dirs entry, or one uv installed a PyPI package into.
+pub async fn find_root_modules(
+    root: &Path,
+    compiler: &impl Compiler,
+) -> Result<Vec<SrcModule>, CliError> {
+    let src_ext = compiler.src_ext();
+    let mut modules = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(root).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_dir() {
+            // Metadata like `*.dist-info` has no `__init__` and is skipped
+            find_modules_inner(root, &path, src_ext, &mut modules, false, false).await?;
+        } else if let Some(ext) = module_ext(&path, src_ext).await? {
+            modules.push(SrcModule::from_path(&path, root, ext));
+        }
+    }
+    sort_modules(&mut modules);
     Ok(modules)
 }
 
+/// Compiles one source file with the `mpy_cross` executable at `-O{opt_level}`, followed by any
+/// extra `flags`. `source_name` is the file name embedded in the bytecode and shown in tracebacks.
+pub async fn compile_file(
+    mpy_cross: &Path,
+    opt_level: u8,
+    flags: &[String],
+    src_path: &Path,
+    build_path: &Path,
+    source_name: &OsStr,
+) -> Result<(), CliError> {
+    // Tracebacks and error dialogs on the brain show this name, so nothing from the build machine
+    // may be in it
+    let host_path = find_host_path(source_name.as_encoded_bytes(), &host_paths())
+        .map(str::to_string)
+        .or_else(|| {
+            Path::new(source_name)
+                .is_absolute()
+                .then(|| source_name.to_string_lossy().into_owned())
+        });
+    if let Some(path) = host_path {
+        return Err(CliError::HostPathInArtifact {
+            module: src_path.display().to_string(),
+            path,
+        });
+    }
+
+    tokio::fs::create_dir_all(build_path.parent().unwrap()).await?;
+    let output = tokio::process::Command::new(mpy_cross)
+        .arg(src_path)
+        .arg("-o")
+        .arg(build_path)
+        .arg("-s")
+        .arg(source_name)
+        .arg(format!("-O{opt_level}"))
+        .args(flags)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let source = tokio::fs::read_to_string(src_path).await.ok();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        return Err(compiler_error(src_path, source, stderr));
+    }
+
+    Ok(())
+}
+
+/// Runs `futures` on this task, at most `limit` at a time, returning their outputs in order.
+async fn run_bounded<F: Future>(futures: Vec<F>, limit: usize) -> Vec<F::Output> {
+    let mut waiting: VecDeque<_> = futures.into_iter().enumerate().collect();
+    let mut running: Vec<(usize, Pin<Box<F>>)> = Vec::new();
+    let mut outputs: Vec<Option<F::Output>> = waiting.iter().map(|_| None).collect();
+
+    std::future::poll_fn(|cx| {
+        loop {
+            while running.len() < limit
+                && let Some((index, future)) = waiting.pop_front()
+            {
+                running.push((index, Box::pin(future)));
+            }
+
+            let before = running.len();
+            running.retain_mut(|(index, future)| match future.as_mut().poll(cx) {
+                Poll::Ready(output) => {
+                    outputs[*index] = Some(output);
+                    false
+                }
+                Poll::Pending => true,
+            });
+            if running.is_empty() && waiting.is_empty() {
+                return Poll::Ready(());
+            }
+            // Nothing finished, so there's no room to start more until one wakes us
+            if running.len() == before {
+                return Poll::Pending;
+            }
+        }
+    })
+    .await;
+
+    outputs.into_iter().map(Option::unwrap).collect()
+}
+
+/// Where bytecode from `compiler` for the source with `hash` is kept in the cache dir, shared by
+/// every project and branch on this machine. The source name is part of the key since it's
+/// compiled into the bytecode. `None` if the compiler's version is unknown or there's no cache
+/// dir.
+fn shared_bytecode_path(compiler: &str, source_name: &OsStr, hash: &str) -> Option<PathBuf> {
+    if compiler.is_empty() {
+        return None;
+    }
+    let key = format!("{compiler}\0{}\0{hash}", source_name.to_string_lossy());
+    let key = sha256_hex(key.as_bytes());
+    let dir = cache_dir()?.join(SHARED_CACHE_DIR).join(&key[..2]);
+    Some(dir.join(key).with_extension(BUILD_EXT))
+}
+
+/// Copies bytecode from `from` to `to` through a temporary file, so builds running at the same
+/// time never read a partly written one.
+async fn copy_bytecode(from: &Path, to: &Path) -> Result<(), CliError> {
+    tokio::fs::create_dir_all(to.parent().unwrap()).await?;
+    let partial = to.with_extension(format!("{BUILD_EXT}.{}", std::process::id()));
+    tokio::fs::copy(from, &partial).await?;
+    tokio::fs::rename(&partial, to).await?;
+    Ok(())
+}
+
+/// Compiles the modules whose source changed since their bytecode was built, timing each and
+/// recording what they were built from in `cache`. Bytecode any project already compiled from the
+/// same source with the same compiler is copied from the shared cache instead. Up to `jobs`
+/// compile at once; every module is attempted, and all failures are reported together.
 pub async fn build_modules(
     src_dir: &Path,
     build_dir: &Path,
+    compiler: &impl Compiler,
     modules: &[SrcModule],
-) -> Result<(), CliError> {
-    for module in modules.iter() {
-        let src_path = module.src_path(src_dir);
-
-        let build_path = module.build_path(build_dir);
-        tokio::fs::create_dir_all(build_path.parent().unwrap()).await?;
+    jobs: usize,
+    cache: &mut BuildCache,
+) -> Result<Vec<ModuleTiming>, CliError> {
+    let cached_modules = &*cache;
+    let compiles = modules.iter().map(|module| async move {
         let mut name = module.name.clone();
-        name.push(".py");
-        let output = std::process::Command::new(MPY_CROSS_PATH.get().unwrap())
-            .arg(&src_path)
-            .arg("-o")
-            .arg(build_path)
-            .arg("-s")
-            .arg(name)
-            .stdin(Stdio::null())
-            .output();
-
-        if let Ok(output) = output
-            && !output.status.success()
-        {
-            return Err(CliError::Compiler {
-                file: src_path,
-                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        name.push(".");
+        name.push(module.src_ext);
+
+        let start = Instant::now();
+        let hash = sha256_hex(&tokio::fs::read(module.src_path(src_dir)).await?);
+        let mut cached = !module.needs_rebuild(build_dir, cached_modules, &hash).await;
+        if !cached && module.is_precompiled() {
+            copy_bytecode(&module.src_path(src_dir), &module.build_path(build_dir)).await?;
+        } else if !cached {
+            let build_path = module.build_path(build_dir);
+            let shared = shared_bytecode_path(&cached_modules.compiler, &name, &hash);
+            if let Some(shared) = &shared {
+                cached = copy_bytecode(shared, &build_path).await.is_ok();
+            }
+            if !cached {
+                compiler
+                    .compile(&module.src_path(src_dir), &build_path, &name)
+                    .await?;
+                if let Some(shared) = &shared {
+                    // Only a missed chance for other builds to skip compiling
+                    let _ = copy_bytecode(&build_path, shared).await;
+                }
+            }
+        }
+        let timing = ModuleTiming {
+            name: name.to_string_lossy().into_owned(),
+            duration: start.elapsed(),
+            cached,
+        };
+        Ok::<_, CliError>((timing, module.cache_key(), hash))
+    });
+    let results = run_bounded(compiles.collect(), jobs).await;
+
+    let mut timings = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok((timing, key, hash)) => {
+                cache.modules.insert(key, hash);
+                timings.push(timing);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    match errors.len() {
+        0 => Ok(timings),
+        1 => Err(errors.pop().unwrap()),
+        count => Err(CliError::CompileErrors { count, errors }),
+    }
+}
+
+pub fn compressed_flags() -> ProgramFlags {
+    ProgramFlags::from_bits_retain(COMPRESSED_FLAG.into())
+}
+
+/// Compresses `program`'s payload and sets [`COMPRESSED_FLAG`] on it, if that makes it smaller.
+fn compress(program: &mut ProgramBuilder) -> Result<(), CliError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&program.payload)?;
+    let compressed = encoder.finish()?;
+    if compressed.len() < program.payload.len() {
+        program.payload = compressed;
+        program.flags |= compressed_flags();
+    }
+    Ok(())
+}
+
+/// The payload of a program from a table, inflated if it has [`COMPRESSED_FLAG`].
+pub fn decompressed(flags: ProgramFlags, payload: &[u8]) -> Result<Vec<u8>, CliError> {
+    if !flags.contains(compressed_flags()) {
+        return Ok(payload.to_vec());
+    }
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(payload).read_to_end(&mut inflated)?;
+    Ok(inflated)
+}
+
+/// Every module a build compiles, by the directory it's in: the project's, its dependencies' and
+/// generated ones.
+pub async fn all_sources(
+    project_dir: &Path,
+    project: &Project,
+    compiler: &impl Compiler,
+    build_dir: &Path,
+) -> Result<Vec<(PathBuf, Vec<SrcModule>)>, CliError> {
+    let mut sources = find_project_modules(project_dir, project, compiler).await?;
+    for (root, package) in dependency_roots(project_dir, project) {
+        let modules = find_package_modules(&root, &package, compiler).await?;
+        sources.push((root, modules));
+    }
+    for root in pypi_roots(project_dir, project) {
+        let modules = find_root_modules(&root, compiler).await?;
+        sources.push((root, modules));
+    }
+    sources.extend(generate_modules(project_dir, project, build_dir).await?);
+    check_collisions(&sources)?;
+    Ok(sources)
+}
+
+/// The source of each of `sources` for the import checks. Precompiled modules have none.
+pub async fn checked_sources(
+    sources: &[(PathBuf, Vec<SrcModule>)],
+) -> Result<Vec<CheckedSource>, CliError> {
+    let mut checked = Vec::new();
+    for (src_dir, modules) in sources.iter() {
+        // Bytecode can't be checked, but it's packed, so importing it resolves
+        for module in modules.iter().filter(|module| !module.is_precompiled()) {
+            let path = module.src_path(src_dir);
+            checked.push(CheckedSource {
+                source: tokio::fs::read_to_string(&path).await?,
+                path,
+                module: String::from_utf8_lossy(&module.python_name()?).into_owned(),
+                is_package: module.is_package(),
             });
         }
     }
+    Ok(checked)
+}
 
+/// Fails unless the entrypoint at `path` has code to run.
+async fn check_entrypoint(path: &Path) -> Result<(), CliError> {
+    if has_code(&tokio::fs::read_to_string(path).await?) {
+        Ok(())
+    } else {
+        Err(CliError::EmptyEntrypoint(path.to_path_buf()))
+    }
+}
+
+/// Warns about each module or package named like one of the runtime's `builtins`, since on the
+/// brain an import of that name only ever finds one of the two.
+fn warn_shadowed_builtins(
+    project_dir: &Path,
+    sources: &[(PathBuf, Vec<SrcModule>)],
+    builtins: &BTreeSet<String>,
+) -> Result<(), CliError> {
+    let mut warned = BTreeSet::new();
+    for (root, modules) in sources {
+        for module in modules {
+            let name = String::from_utf8_lossy(&module.python_name()?).into_owned();
+            let top_level = name.split('.').next().unwrap().to_string();
+            if builtins.contains(&top_level) && warned.insert(top_level.clone()) {
+                let path = module.src_path(root);
+                eprintln!(
+                    "warning: `{}` is named like the runtime's built-in `{top_level}` module, which it shadows or is shadowed by; rename it",
+                    path.strip_prefix(project_dir).unwrap_or(&path).display()
+                );
+            }
+        }
+    }
     Ok(())
 }
 
-pub async fn build() -> Result<Vec<u8>, CliError> {
+/// Compiles a module whose source is generated by the CLI rather than read from the project.
+async fn build_generated_module(
+    compiler: &impl Compiler,
+    build_dir: &Path,
+    name: &str,
+    source: &str,
+) -> Result<Vec<u8>, CliError> {
+    let generated_dir = build_dir.join(GENERATED_DIR);
+    let src_path = generated_dir.join(name).with_extension(SRC_EXT);
+    let build_path = generated_dir.join(name).with_extension(BUILD_EXT);
+
+    tokio::fs::create_dir_all(&generated_dir).await?;
+    tokio::fs::write(&src_path, source).await?;
+    compiler
+        .compile(
+            &src_path,
+            &build_path,
+            OsStr::new(&format!("{name}.{SRC_EXT}")),
+        )
+        .await?;
+    Ok(tokio::fs::read(&build_path).await?)
+}
+
+pub async fn build(options: &BuildOptions) -> Result<Vec<u8>, CliError> {
+    let start = Instant::now();
     let manifest_dir = project_dir()?;
 
     let src_dir = manifest_dir;
-    let build_dir = manifest_dir.join(BUILD_DIR);
+    let project = get_project().await?;
+    let build_dir = project.out_dir(manifest_dir, profile());
+    let compiler = project.compiler(profile());
+    install(&project).await?;
 
-    let modules = find_modules(&src_dir).await?;
+    let mut sources = all_sources(src_dir, &project, &compiler, &build_dir).await?;
+    check_entrypoint(&src_dir.join(ENTRYPOINT).with_extension(SRC_EXT)).await?;
+    let namespace = if project.build.namespace_packages {
+        namespace_packages(&sources[0].1)?
+    } else {
+        BTreeSet::new()
+    };
 
-    if !tokio::fs::try_exists(&build_dir).await? {
-        tokio::fs::create_dir(&build_dir).await?;
+    // Checked before anything is compiled, so a typo fails the build straight away
+    let mut packed = BTreeSet::from([BUILDINFO_MODULE.to_string()]);
+    packed.extend(namespace.iter().cloned());
+    for module in sources.iter().flat_map(|(_, modules)| modules) {
+        packed.insert(String::from_utf8_lossy(&module.python_name()?).into_owned());
     }
+    let mut builtins = builtin_modules(options.runtime.as_ref()).await;
+    warn_shadowed_builtins(manifest_dir, &sources, &builtins)?;
+    builtins.extend(project.ignore_imports.iter().cloned());
+    let mut checked = checked_sources(&sources).await?;
+
+    let mode = project.build.unreachable_modules;
+    if mode != UnreachableModules::Keep {
+        let unreachable = unreachable_modules(&import_graph(&checked, &packed), ENTRYPOINT);
+        // Whatever precompiled modules import can't be seen, so nothing is safe to leave out
+        let precompiled = sources
+            .iter()
+            .flat_map(|(_, modules)| modules)
+            .any(SrcModule::is_precompiled);
+        let exclude = mode == UnreachableModules::Exclude && !precompiled;
+        for module in &unreachable {
+            let action = if exclude { "; leaving it out" } else { "" };
+            eprintln!("warning: `{module}` isn't imported from {ENTRYPOINT}{action}");
+        }
+        if exclude {
+            for (_, modules) in sources.iter_mut() {
+                modules.retain(|module| {
+                    let name = module.python_name().unwrap_or_default();
+                    !unreachable.contains(&*String::from_utf8_lossy(&name))
+                });
+            }
+            checked.retain(|source| !unreachable.contains(&source.module));
+            packed.retain(|module| !unreachable.contains(module));
+        } else if mode == UnreachableModules::Exclude {
+            eprintln!(
+                "warning: not leaving out unreachable modules, since precompiled ones are packed"
+            );
+        }
+    }
+    check_imports(&checked, &packed, &builtins)?;
+
+    tokio::fs::create_dir_all(&build_dir).await?;
+
+    let buildinfo = buildinfo_source(&project, manifest_dir, profile()).await;
+    let buildinfo =
+        build_generated_module(&compiler, &build_dir, BUILDINFO_MODULE, &buildinfo).await?;
 
     let table_path = build_dir.join(TABLE_FILE);
-    build_modules(&src_dir, &build_dir, &modules).await?;
+    let mut timings = BuildTimings::default();
+    let mut cache = BuildCache::load(&build_dir, compiler.version().await).await;
+    for (src_dir, modules) in sources.iter() {
+        let result = build_modules(
+            src_dir,
+            &build_dir,
+            &compiler,
+            modules,
+            options.jobs(),
+            &mut cache,
+        )
+        .await;
+        // Saved even when some modules failed, so the ones that built aren't built again
+        cache.save(&build_dir).await?;
+        let module_timings = result?;
+        if options.message_format == MessageFormat::Json {
+            for (module, timing) in modules.iter().zip(&module_timings) {
+                print_message(
+                    "compiled-module",
+                    json!({
+                        "module": String::from_utf8_lossy(&module.python_name()?),
+                        "src": module.src_path(src_dir),
+                        "artifact": module.build_path(&build_dir),
+                        "cached": timing.cached,
+                        "duration-ms": timing.duration.as_millis() as u64,
+                    }),
+                );
+            }
+        }
+        timings.modules.extend(module_timings);
+    }
 
-    let mut vpt_builder = VptBuilder::new(VENDOR_ID);
+    let mut programs = Vec::new();
+    for (src_dir, modules) in sources.iter() {
+        for module in modules {
+            let build_path = module.build_path(&build_dir);
+            let bytecode = tokio::fs::read(&build_path).await?;
+            if module.is_precompiled() {
+                check_precompiled(&module.src_path(src_dir), &bytecode, &buildinfo)?;
+            }
+            let module_name = String::from_utf8_lossy(&module.python_name()?).into_owned();
 
-    for module in modules.iter() {
-        let build_path = module.build_path(&build_dir);
-        let bytecode = tokio::fs::read(&build_path).await?;
-        let module_name = String::from_utf8_lossy(&module.python_name()?).into_owned();
+            programs.push(ProgramBuilder {
+                name: module_name.into_bytes(),
+                payload: bytecode,
+                flags: module.module_flags(),
+            });
+        }
+    }
 
-        vpt_builder.add_program(ProgramBuilder {
-            name: module_name.into_bytes(),
-            payload: bytecode,
-            flags: module.module_flags()
+    if !namespace.is_empty() {
+        let payload = build_generated_module(&compiler, &build_dir, NAMESPACE_PACKAGE, "").await?;
+        for package in namespace {
+            eprintln!(
+                "warning: `{package}` has no __init__.{SRC_EXT}; compiling it as a namespace package"
+            );
+            programs.push(ProgramBuilder {
+                name: package.into_bytes(),
+                payload: payload.clone(),
+                flags: ProgramFlags::IS_PACKAGE,
+            });
+        }
+    }
+
+    programs.push(ProgramBuilder {
+        name: BUILDINFO_MODULE.as_bytes().to_vec(),
+        payload: buildinfo,
+        flags: ProgramFlags::empty(),
+    });
+
+    // A runtime that can't inflate the payloads would fail to import every module, so they're
+    // left alone for it
+    let compress_payloads = match &options.runtime {
+        Some(version) if project.build.compress => {
+            check_feature(version, Feature::Compression).await
+        }
+        _ => project.build.compress,
+    };
+    if compress_payloads {
+        for program in programs.iter_mut() {
+            compress(program)?;
+        }
+    }
+
+    programs.extend(autons_program(&project.autons));
+    programs.extend(asset_programs(&manifest_dir.join(&project.assets.dir))?);
+
+    if options.sign {
+        let key = load_or_create_key()?;
+        let payload = signature_payload(
+            &key,
+            programs
+                .iter()
+                .map(|p| (p.name.as_slice(), p.flags, p.payload.as_slice())),
+        );
+        programs.push(ProgramBuilder {
+            name: SIGNATURE_PROGRAM.to_vec(),
+            payload,
+            flags: ProgramFlags::empty(),
         });
     }
 
+    let program_count = programs.len();
+    let mut vpt_builder = VptBuilder::new(VENDOR_ID);
+    for program in programs {
+        vpt_builder.add_program(program);
+    }
+
     let vpt = vpt_builder.build();
 
     tokio::fs::write(&table_path, &vpt).await?;
+    record_build(manifest_dir, vpt.len(), program_count);
+    if options.message_format == MessageFormat::Json {
+        print_message(
+            "artifact",
+            json!({
+                "kind": "program-table",
+                "path": table_path,
+                "size": vpt.len(),
+                "programs": program_count,
+            }),
+        );
+    }
+
+    if options.timings {
+        timings.total = start.elapsed();
+        timings.print_table();
+        let report = timings.write_html(&build_dir).await?;
+        println!("Timing report written to {}", report.display());
+    }
     Ok(vpt)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SRC_EXT, check_entrypoint, compile_file, find_modules_inner};
+    use crate::errors::CliError;
+
+    #[tokio::test]
+    async fn skips_non_package_dirs() {
+        let root = std::env::temp_dir().join(format!("venice-find-modules-{}", std::process::id()));
+        for file in [
+            "main.py",
+            "robot/drive.py",
+            ".venv/lib/python3.12/site-packages/requests/__init__.py",
+            "venv/lib/python3.12/site-packages/six.py",
+            ".venice/pypi/attrs/attr/__init__.py",
+            "build/debug/main.py",
+            "vendor/lib.py",
+            "tests/test_drive.py",
+            "unpacked/main.py",
+            "__pycache__/drive.py",
+            "my-data/paths.py",
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::write(root.join("venv/pyvenv.cfg"), "").unwrap();
+
+        let mut modules = Vec::new();
+        find_modules_inner(&root, &root, SRC_EXT, &mut modules, true, true)
+            .await
+            .unwrap();
+        let mut names: Vec<String> = modules
+            .iter()
+            .map(|module| String::from_utf8(module.python_name().unwrap()).unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["main", "robot.drive"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_entrypoint_without_code() {
+        let dir = std::env::temp_dir().join(format!("venice-entrypoint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("main.py");
+
+        std::fs::write(&main, "\"\"\"Robot code.\n\nimport drive\n\"\"\"\n# TODO\n").unwrap();
+        assert!(matches!(
+            check_entrypoint(&main).await,
+            Err(CliError::EmptyEntrypoint(_))
+        ));
+
+        std::fs::write(&main, "\"\"\"Robot code.\"\"\"\nimport drive\n").unwrap();
+        check_entrypoint(&main).await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_host_path_source_names() {
+        let dir = std::env::temp_dir().join(format!("venice-source-name-{}", std::process::id()));
+        let main = dir.join("main.py");
+        // Refused before mpy-cross is run, so it needn't exist
+        let result = compile_file(
+            "mpy-cross".as_ref(),
+            0,
+            &[],
+            &main,
+            &dir.join("main.mpy"),
+            main.as_os_str(),
+        )
+        .await;
+        assert!(matches!(result, Err(CliError::HostPathInArtifact { .. })));
+    }
+}
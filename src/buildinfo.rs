@@ -0,0 +1,86 @@
+use std::{
+    path::Path,
+    process::Stdio,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{build::BuildProfile, env::python_str_literal, manifest::Project};
+
+/// Name of the generated module describing the build, so a running program can print what it
+/// is, e.g. `import _buildinfo; print(_buildinfo.COMMIT)`.
+pub const BUILDINFO_MODULE: &str = "_buildinfo";
+
+async fn git(dir: &Path, args: &[&str]) -> Option<Vec<u8>> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+/// The commit checked out in `dir`, with `-dirty` if there are uncommitted changes, or `None`
+/// outside a git repository.
+async fn git_commit(dir: &Path) -> Option<String> {
+    let head = git(dir, &["rev-parse", "HEAD"]).await?;
+    let mut commit = String::from_utf8_lossy(&head).trim().to_string();
+    if git(dir, &["status", "--porcelain"])
+        .await
+        .is_some_and(|status| !status.is_empty())
+    {
+        commit.push_str("-dirty");
+    }
+    Some(commit)
+}
+
+/// When the build happened, in seconds since the Unix epoch. So that building the same sources
+/// twice gives the same program table, this is `SOURCE_DATE_EPOCH` if it's set, then the time of
+/// `commit` if the tree is clean, and only otherwise the current time.
+async fn build_time(dir: &Path, commit: Option<&str>) -> u64 {
+    if let Some(epoch) = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+    {
+        return epoch;
+    }
+    if commit.is_some_and(|commit| !commit.ends_with("-dirty"))
+        && let Some(time) = git(dir, &["log", "-1", "--format=%ct"]).await
+        && let Ok(time) = String::from_utf8_lossy(&time).trim().parse()
+    {
+        return time;
+    }
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn optional_str(value: Option<&str>) -> String {
+    value.map_or("None".to_string(), python_str_literal)
+}
+
+fn python_bool(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+/// Source for the [`BUILDINFO_MODULE`] module of the project in `dir`.
+pub async fn buildinfo_source(project: &Project, dir: &Path, profile: BuildProfile) -> String {
+    let commit = git_commit(dir).await;
+    format!(
+        "# Generated by venice-cli for this build\n\
+         NAME = {}\n\
+         VERSION = {}\n\
+         BUILT_AT = {}\n\
+         COMMIT = {}\n\
+         PROFILE = {}\n\
+         STRIPPED = {}\n",
+        python_str_literal(&project.name),
+        optional_str(project.version.as_deref()),
+        build_time(dir, commit.as_deref()).await,
+        optional_str(commit.as_deref()),
+        python_str_literal(profile.name()),
+        python_bool(project.stripped(profile)),
+    )
+}
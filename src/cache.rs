@@ -0,0 +1,199 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    BUILD_DIR,
+    build::{BuildProfile, SHARED_CACHE_DIR},
+    config::cache_dir,
+    errors::CliError,
+    manifest::get_project,
+    project_dir,
+    stats::format_size,
+};
+
+/// Cache entries unused for this long are suggested for pruning.
+pub const STALE_AFTER_DAYS: u64 = 30;
+
+/// What the subdirectories of the cache dir hold.
+const CATEGORIES: &[(&str, &str)] = &[
+    ("runtime", "Runtimes"),
+    ("toolchain", "Toolchains"),
+    ("github", "GitHub responses"),
+    ("git", "Git dependencies"),
+    (SHARED_CACHE_DIR, "Compiled modules"),
+];
+
+/// Files under a directory, and how many of them haven't been used lately.
+#[derive(Default, Debug)]
+struct Usage {
+    files: u64,
+    bytes: u64,
+    stale_files: u64,
+    stale_bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, other: &Usage) {
+        self.files += other.files;
+        self.bytes += other.bytes;
+        self.stale_files += other.stale_files;
+        self.stale_bytes += other.stale_bytes;
+    }
+}
+
+/// When a file was last read, or written on filesystems that don't record reads.
+fn last_used(metadata: &std::fs::Metadata) -> Option<SystemTime> {
+    metadata.accessed().or_else(|_| metadata.modified()).ok()
+}
+
+fn usage(path: &Path, stale_before: SystemTime) -> Usage {
+    let mut usage = Usage::default();
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return usage;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            usage.add(&self::usage(&entry.path(), stale_before));
+            continue;
+        }
+        usage.files += 1;
+        usage.bytes += metadata.len();
+        if last_used(&metadata).is_some_and(|used| used < stale_before) {
+            usage.stale_files += 1;
+            usage.stale_bytes += metadata.len();
+        }
+    }
+    usage
+}
+
+fn stale_before(days: u64) -> SystemTime {
+    SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60)
+}
+
+/// The directories `clean` removes for the current project: the build dir, and any profile
+/// out-dir outside it.
+async fn build_dirs() -> Result<Vec<PathBuf>, CliError> {
+    let dir = project_dir()?;
+    let build_dir = dir.join(BUILD_DIR);
+    let project = get_project().await?;
+    let mut dirs = vec![build_dir.clone()];
+    for profile in [BuildProfile::Debug, BuildProfile::Release] {
+        let out_dir = project.out_dir(dir, profile);
+        if !out_dir.starts_with(&build_dir) && !dirs.contains(&out_dir) {
+            dirs.push(out_dir);
+        }
+    }
+    Ok(dirs)
+}
+
+/// `venice clean`: deletes the project's build output, reporting the space it took.
+pub async fn clean() -> Result<(), CliError> {
+    let dir = project_dir()?;
+    let mut reclaimed = 0;
+    for build_dir in build_dirs().await? {
+        if !build_dir.exists() {
+            continue;
+        }
+        let bytes = usage(&build_dir, SystemTime::UNIX_EPOCH).bytes;
+        std::fs::remove_dir_all(&build_dir)?;
+        let shown = build_dir.strip_prefix(dir).unwrap_or(&build_dir);
+        println!("✓ Removed {} ({})", shown.display(), format_size(bytes));
+        reclaimed += bytes;
+    }
+    if reclaimed == 0 {
+        println!("Nothing to clean");
+    }
+    Ok(())
+}
+
+/// `venice cache stats`: what the cache holds, by category, and how much of it has gone unused.
+pub async fn stats() -> Result<(), CliError> {
+    let cache = cache_dir().ok_or(CliError::NoConfigDir)?;
+    let stale_before = stale_before(STALE_AFTER_DAYS);
+
+    println!("Cache in {}\n", cache.display());
+    let mut total = Usage::default();
+    let mut rows = Vec::new();
+    for (dir, label) in CATEGORIES {
+        let usage = usage(&cache.join(dir), stale_before);
+        total.add(&usage);
+        rows.push((label.to_string(), usage));
+    }
+    let mut other = usage(&cache, stale_before);
+    other.files -= total.files;
+    other.bytes -= total.bytes;
+    other.stale_files -= total.stale_files;
+    other.stale_bytes -= total.stale_bytes;
+    total.add(&other);
+    rows.push(("Other".to_string(), other));
+    if project_dir().is_ok() {
+        let mut build = Usage::default();
+        for build_dir in build_dirs().await? {
+            build.add(&usage(&build_dir, stale_before));
+        }
+        rows.push(("Build cache (this project)".to_string(), build));
+    }
+
+    for (label, usage) in rows.iter() {
+        println!(
+            "{label:<28} {:>10}  {} file(s)",
+            format_size(usage.bytes),
+            usage.files
+        );
+    }
+    println!("{:<28} {:>10}", "Total cache", format_size(total.bytes));
+
+    let stale: Vec<_> = rows
+        .iter()
+        .filter(|(_, usage)| usage.stale_files > 0)
+        .collect();
+    if !stale.is_empty() {
+        println!();
+        for (label, usage) in stale {
+            println!(
+                "{label}: {} in {} file(s) unused for over {STALE_AFTER_DAYS} days",
+                format_size(usage.stale_bytes),
+                usage.stale_files
+            );
+        }
+        println!("Run `venice cache prune` to delete them.");
+    }
+    Ok(())
+}
+
+/// Deletes files under `path` last used before `stale_before`, returning the bytes freed.
+fn prune_dir(path: &Path, stale_before: SystemTime) -> Result<u64, CliError> {
+    let mut freed = 0;
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Ok(freed);
+    };
+    for entry in entries.flatten() {
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            freed += prune_dir(&entry.path(), stale_before)?;
+            // Left behind only if something in it is still in use
+            let _ = std::fs::remove_dir(entry.path());
+        } else if last_used(&metadata).is_some_and(|used| used < stale_before) {
+            std::fs::remove_file(entry.path())?;
+            freed += metadata.len();
+        }
+    }
+    Ok(freed)
+}
+
+/// `venice cache prune`: deletes cached files unused for `days` days. Anything deleted is
+/// downloaded again when next needed.
+pub fn prune(days: u64) -> Result<(), CliError> {
+    let cache = cache_dir().ok_or(CliError::NoConfigDir)?;
+    let freed = prune_dir(&cache, stale_before(days))?;
+    println!(
+        "✓ Freed {} of files unused for over {days} days",
+        format_size(freed)
+    );
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use vex_v5_serial::{
+    commands::file::USER_PROGRAM_LOAD_ADDR, protocol::cdc2::file::FileVendor,
+    serial::SerialConnection,
+};
+
+use crate::{
+    brain::{download_file, list_files},
+    device::{open_connection, open_device, system_port, usb_serial_number},
+    errors::CliError,
+    project_dir,
+    store::Store,
+    upload::upload_user_file,
+};
+
+/// Calibration constants in the project, versioned alongside the code.
+pub const CALIB_FILE: &str = "calibration.toml";
+/// Where the runtime reads calibration from. JSON, since MicroPython has no TOML parser.
+pub const BRAIN_CALIB_FILE: &str = "venice-calib.json";
+
+/// The project's calibration as the JSON the brain expects, or `None` if it has none.
+async fn read_calibration() -> Result<Option<Vec<u8>>, CliError> {
+    let path = project_dir()?.join(CALIB_FILE);
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let table: toml::Table = toml::from_str(&contents)?;
+    Ok(Some(serde_json::to_vec(&table)?))
+}
+
+/// Uploads calibration and remembers what this brain has, so uploads only push it again when it
+/// changes.
+async fn upload_calibration(
+    conn: &mut SerialConnection,
+    device_serial: &str,
+    json: &[u8],
+) -> Result<(), CliError> {
+    upload_user_file(conn, BRAIN_CALIB_FILE, json, USER_PROGRAM_LOAD_ADDR, None).await?;
+    let checksum = crc32fast::hash(json);
+    Store::update(|store| {
+        store
+            .calibration_synced
+            .insert(device_serial.to_string(), checksum);
+    })
+}
+
+/// `venice calib pull`: saves the brain's calibration to calibration.toml.
+pub async fn pull() -> Result<(), CliError> {
+    let mut conn = open_connection().await?;
+    let file = list_files(&mut conn, FileVendor::User)
+        .await?
+        .into_iter()
+        .find(|file| file.name == BRAIN_CALIB_FILE)
+        .ok_or(CliError::NoCalibration)?;
+    let json = download_file(&mut conn, &file).await?;
+
+    let table: toml::Table =
+        serde_json::from_slice(&json).map_err(|e| CliError::CalibrationFormat(e.to_string()))?;
+    let contents =
+        toml::to_string_pretty(&table).map_err(|e| CliError::CalibrationFormat(e.to_string()))?;
+    tokio::fs::write(project_dir()?.join(CALIB_FILE), contents).await?;
+    println!("✓ Saved the brain's calibration to {CALIB_FILE}");
+    Ok(())
+}
+
+/// `venice calib push`: writes calibration.toml to the brain.
+pub async fn push() -> Result<(), CliError> {
+    let json = read_calibration()
+        .await?
+        .ok_or(CliError::NoCalibrationFile)?;
+    let (mut conn, device) = open_device().await?;
+    let device_serial = usb_serial_number(system_port(&device)).unwrap_or_default();
+    upload_calibration(&mut conn, &device_serial, &json).await?;
+    println!("✓ Pushed {CALIB_FILE} to the brain");
+    Ok(())
+}
+
+/// Pushes calibration.toml during an upload if it changed since it was last pushed to this brain.
+pub async fn push_if_changed(
+    conn: &mut SerialConnection,
+    device_serial: &str,
+) -> Result<(), CliError> {
+    let Some(json) = read_calibration().await? else {
+        return Ok(());
+    };
+    let synced = Store::load()?.calibration_synced;
+    if synced.get(device_serial) == Some(&crc32fast::hash(&json)) {
+        return Ok(());
+    }
+    upload_calibration(conn, device_serial, &json).await
+}
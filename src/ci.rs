@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use tokio::{
+    io::{AsyncWriteExt, stdout},
+    select,
+    time::{sleep, timeout_at},
+};
+use vex_v5_serial::{Connection, protocol::cdc2::file::FileExitAction, serial::SerialConnection};
+
+use crate::{
+    brain,
+    device::{identity, open_connection, wait_for_device},
+    errors::CliError,
+    runtime::RuntimeSource,
+    upload::upload,
+};
+
+/// What `run --ci` waits for.
+#[derive(Clone)]
+pub struct CiOptions {
+    /// Wall-clock limit for the whole run, including waiting for the device and uploading
+    pub timeout: Duration,
+    /// Text that must each appear in a line of output for the run to pass
+    pub expect: Vec<String>,
+    /// Text that fails the run as soon as a line of output contains it
+    pub fail_on: Vec<String>,
+}
+
+/// Watches program output line by line for the expected and failing text.
+struct Matcher<'a> {
+    options: &'a CiOptions,
+    /// Expected text not seen yet
+    missing: Vec<&'a str>,
+    partial: Vec<u8>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(options: &'a CiOptions) -> Self {
+        Self {
+            options,
+            missing: options.expect.iter().map(String::as_str).collect(),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Feeds output in. `Some` once the run is decided: passed, or failed on a line.
+    fn output(&mut self, bytes: &[u8]) -> Option<Result<(), CliError>> {
+        self.partial.extend_from_slice(bytes);
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(pattern) = self
+                .options
+                .fail_on
+                .iter()
+                .find(|p| line.contains(p.as_str()))
+            {
+                return Some(Err(CliError::CiFailureOutput {
+                    pattern: pattern.clone(),
+                    line: line.trim_end().to_string(),
+                }));
+            }
+            self.missing.retain(|expected| !line.contains(expected));
+            if !self.options.expect.is_empty() && self.missing.is_empty() {
+                return Some(Ok(()));
+            }
+        }
+        None
+    }
+
+    /// The verdict at the timeout: passing only if nothing was expected.
+    fn timed_out(&self) -> Result<(), CliError> {
+        if self.options.expect.is_empty() {
+            return Ok(());
+        }
+        let missing: Vec<_> = self
+            .missing
+            .iter()
+            .map(|text| format!("`{text}`"))
+            .collect();
+        Err(CliError::CiTimeout {
+            seconds: self.options.timeout.as_secs(),
+            waiting_for: missing.join(", "),
+        })
+    }
+}
+
+async fn watch(
+    conn: &mut SerialConnection,
+    options: &CiOptions,
+    deadline: tokio::time::Instant,
+) -> Result<(), CliError> {
+    let mut matcher = Matcher::new(options);
+    let mut output = [0; 2048];
+    loop {
+        select! {
+            read = conn.read_user(&mut output) => {
+                if let Ok(size) = read {
+                    stdout().write_all(&output[..size]).await?;
+                    if let Some(verdict) = matcher.output(&output[..size]) {
+                        return verdict;
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(deadline) => return matcher.timed_out(),
+            _ = tokio::signal::ctrl_c() => return Err(CliError::Interrupted),
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// `venice run --ci`: waits for a device, uploads and runs the program without progress bars,
+/// and watches its output until the expected text shows up, failing text shows up or the
+/// timeout passes. The program is always stopped before returning, so the next job starts from
+/// a brain that isn't driving anything.
+pub async fn run(
+    options: CiOptions,
+    cold: bool,
+    runtime_source: Option<RuntimeSource>,
+) -> Result<(), CliError> {
+    let start = Instant::now();
+    let deadline = tokio::time::Instant::now() + options.timeout;
+
+    let upload = async {
+        wait_for_device(options.timeout).await?;
+        upload(Some(FileExitAction::RunProgram), runtime_source, cold).await
+    };
+    let mut conn = match timeout_at(deadline, upload).await {
+        Ok(conn) => conn?,
+        Err(_) => {
+            // The upload may have started the program before hanging
+            if let Ok(mut conn) = open_connection().await {
+                let _ = brain::stop_program(&mut conn).await;
+            }
+            return Err(CliError::CiTimeout {
+                seconds: options.timeout.as_secs(),
+                waiting_for: "the upload".to_string(),
+            });
+        }
+    };
+
+    let verdict = watch(&mut conn, &options, deadline).await;
+    if let Err(e) = brain::stop_program(&mut conn).await {
+        eprintln!("warning: couldn't stop the program: {e}");
+    }
+    let device = identity().cloned().unwrap_or_default();
+    match &verdict {
+        Ok(()) => eprintln!(
+            "✓ Passed on {device} in {:.1}s",
+            start.elapsed().as_secs_f64()
+        ),
+        Err(_) => eprintln!("✗ Failed on {device}"),
+    }
+    verdict
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{CiOptions, Matcher};
+
+    #[test]
+    fn passes_once_every_expected_line_is_seen() {
+        let options = CiOptions {
+            timeout: Duration::from_secs(10),
+            expect: vec!["imu ready".to_string(), "auton done".to_string()],
+            fail_on: vec!["Traceback".to_string()],
+        };
+        let mut matcher = Matcher::new(&options);
+        assert!(matcher.output(b"imu re").is_none());
+        assert!(matcher.output(b"ady\nauton done").is_none());
+        assert!(matcher.timed_out().is_err());
+        assert!(matches!(matcher.output(b"\n"), Some(Ok(()))));
+    }
+
+    #[test]
+    fn fails_on_failing_text() {
+        let options = CiOptions {
+            timeout: Duration::from_secs(10),
+            expect: Vec::new(),
+            fail_on: vec!["Traceback".to_string()],
+        };
+        let mut matcher = Matcher::new(&options);
+        assert!(matches!(
+            matcher.output(b"Traceback (most recent call last):\n"),
+            Some(Err(_))
+        ));
+        assert!(Matcher::new(&options).timed_out().is_ok());
+    }
+}
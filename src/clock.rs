@@ -0,0 +1,218 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::time::{timeout, timeout_at};
+use vex_v5_serial::{Connection, serial::SerialConnection};
+
+use crate::{
+    config,
+    errors::CliError,
+    runtime::{Feature, RuntimeSource, capabilities},
+    timesync::{REPLY_END, ReplyFilter},
+};
+
+/// Written to the program's input, followed by [`REPLY_END`], to ask the runtime for the brain's
+/// date and time. It takes it out of the input and answers on its output with [`REPLY_PREFIX`],
+/// the brain's clock in Unix seconds and [`REPLY_END`].
+const REQUEST_PREFIX: &[u8] = b"\x1b]venice;date?";
+/// Like [`REQUEST_PREFIX`], followed by Unix seconds to set the brain's clock to. The runtime
+/// answers the same way, with the clock as set.
+const SET_PREFIX: &[u8] = b"\x1b]venice;date!";
+const REPLY_PREFIX: &[u8] = b"\x1b]venice;date=";
+
+/// How long to wait for the runtime to answer.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How far the brain's clock can be from the host's before it's worth a warning: beyond any
+/// drift, so only a clock that was never set or was lost.
+const MAX_OFFSET: u64 = 5 * 60;
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn request(prefix: &[u8], value: Option<i64>) -> Vec<u8> {
+    let mut request = prefix.to_vec();
+    if let Some(value) = value {
+        request.extend_from_slice(value.to_string().as_bytes());
+    }
+    request.push(REPLY_END);
+    request
+}
+
+/// `3 hours behind this computer's`, for how far the brain's clock at `brain` is from the host's
+/// at `host`.
+pub fn describe_offset(brain: i64, host: i64) -> String {
+    let offset = brain - host;
+    let secs = offset.unsigned_abs();
+    let (amount, unit) = match secs {
+        0 => return "in step with this computer's".to_string(),
+        ..120 => (secs, "second"),
+        ..7_200 => (secs / 60, "minute"),
+        ..172_800 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!(
+        "{amount} {unit}{} {} this computer's",
+        if amount == 1 { "" } else { "s" },
+        if offset < 0 { "behind" } else { "ahead of" }
+    )
+}
+
+/// Checks the brain's clock against the host's while the program runs, warning if it's far off,
+/// or setting it with `[clock] sync`. The runtime's answers are taken out of the program's
+/// output so they never reach the screen.
+pub struct ClockCheck {
+    replies: ReplyFilter,
+    /// When the unanswered request went out
+    sent: Option<Instant>,
+    /// Whether the unanswered request sets the clock
+    setting: bool,
+}
+
+impl ClockCheck {
+    /// Starts a check, with the request to write to the program.
+    pub fn start(now: Instant) -> (Self, Vec<u8>) {
+        let check = Self {
+            replies: ReplyFilter::new(REPLY_PREFIX),
+            sent: Some(now),
+            setting: false,
+        };
+        (check, request(REQUEST_PREFIX, None))
+    }
+
+    /// Takes the runtime's answers out of the program's output, returning what's left to show
+    /// and the request to set the clock, if it's off and should be set.
+    pub fn filter(&mut self, output: &[u8], now: Instant) -> (Vec<u8>, Option<Vec<u8>>) {
+        let (shown, replies) = self.replies.filter(output);
+        let mut set_request = None;
+        for brain in replies.iter().filter_map(|reply| reply.parse::<i64>().ok()) {
+            if self.sent.take().is_none() {
+                continue;
+            }
+            let host = unix_now();
+            if self.setting {
+                eprintln!("[set the brain's clock to this computer's]");
+            } else if brain.abs_diff(host) > MAX_OFFSET {
+                if config().clock.sync {
+                    set_request = Some(request(SET_PREFIX, Some(host)));
+                    self.sent = Some(now);
+                    self.setting = true;
+                } else {
+                    eprintln!(
+                        "[the brain's clock is {}, so file times and times in the program's \
+                         logs are off; `venice clock set` sets it]",
+                        describe_offset(brain, host)
+                    );
+                }
+            }
+        }
+        (shown, set_request)
+    }
+
+    /// Whether it's over: answered, or given up on after [`REPLY_TIMEOUT`].
+    pub fn finished(&self, now: Instant) -> bool {
+        self.sent
+            .is_none_or(|sent| now.duration_since(sent) >= REPLY_TIMEOUT)
+    }
+
+    /// Output still held back, for showing once it's over.
+    pub fn into_pending(self) -> Vec<u8> {
+        self.replies.into_pending()
+    }
+}
+
+/// Whether the runtime in `runtime_source` answers clock requests.
+pub async fn supported(runtime_source: Option<&RuntimeSource>) -> bool {
+    match runtime_source {
+        Some(source) => capabilities(&source.version)
+            .await
+            .is_some_and(|capabilities| capabilities.supports(Feature::Clock)),
+        None => false,
+    }
+}
+
+/// Checks the brain's clock right after an upload started the program, when nothing is showing
+/// its output. Gives up quietly if the program doesn't answer.
+pub async fn check_after_upload(conn: &mut SerialConnection) {
+    let (mut check, request) = ClockCheck::start(Instant::now());
+    if conn.write_user(&request).await.is_err() {
+        return;
+    }
+    let mut output = [0; 2048];
+    while !check.finished(Instant::now()) {
+        let Ok(Ok(size)) = timeout(REPLY_TIMEOUT, conn.read_user(&mut output)).await else {
+            return;
+        };
+        if let (_, Some(request)) = check.filter(&output[..size], Instant::now())
+            && conn.write_user(&request).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Sends `request` to the running program and waits for the runtime's answer, the brain's clock
+/// in Unix seconds. The program's other output is dropped meanwhile.
+async fn exchange(conn: &mut SerialConnection, request: &[u8]) -> Result<i64, CliError> {
+    conn.write_user(request).await?;
+    let mut replies = ReplyFilter::new(REPLY_PREFIX);
+    let mut output = [0; 2048];
+    let deadline = tokio::time::Instant::now() + REPLY_TIMEOUT;
+    loop {
+        let size = timeout_at(deadline, conn.read_user(&mut output))
+            .await
+            .map_err(|_| CliError::NoClockAnswer)??;
+        let (_, answers) = replies.filter(&output[..size]);
+        if let Some(brain) = answers.iter().find_map(|answer| answer.parse().ok()) {
+            return Ok(brain);
+        }
+    }
+}
+
+/// `venice clock get`: how far the brain's clock is from this computer's.
+pub async fn get(conn: &mut SerialConnection) -> Result<(), CliError> {
+    let brain = exchange(conn, &request(REQUEST_PREFIX, None)).await?;
+    println!(
+        "The brain's clock is {}",
+        describe_offset(brain, unix_now())
+    );
+    Ok(())
+}
+
+/// `venice clock set`: sets the brain's clock to this computer's.
+pub async fn set(conn: &mut SerialConnection) -> Result<(), CliError> {
+    let brain = exchange(conn, &request(SET_PREFIX, Some(unix_now()))).await?;
+    let host = unix_now();
+    if brain.abs_diff(host) > 1 {
+        println!("The brain kept its clock, {}", describe_offset(brain, host));
+    } else {
+        println!("✓ Set the brain's clock to this computer's");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::describe_offset;
+
+    #[test]
+    fn describes_offsets() {
+        assert_eq!(describe_offset(100, 100), "in step with this computer's");
+        assert_eq!(describe_offset(99, 100), "1 second behind this computer's");
+        assert_eq!(
+            describe_offset(100 + 600, 100),
+            "10 minutes ahead of this computer's"
+        );
+        assert_eq!(
+            describe_offset(0, 3 * 3_600),
+            "3 hours behind this computer's"
+        );
+        assert_eq!(
+            describe_offset(0, 800_000_000),
+            "9259 days behind this computer's"
+        );
+    }
+}
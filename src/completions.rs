@@ -0,0 +1,86 @@
+use std::io::Write;
+
+use clap::CommandFactory;
+use clap_complete::{
+    Shell,
+    engine::CompletionCandidate,
+    env::{CompleteEnv, Shells},
+};
+use vex_v5_serial::serial;
+
+use crate::{
+    Venice,
+    config::load_config,
+    device::{DeviceName, system_port, usb_serial_number},
+    errors::CliError,
+    store::Store,
+};
+
+/// Environment variable the dynamic completion scripts set when asking venice for candidates.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Answers a completion request from a `--dynamic` script, if this invocation is one. Returns
+/// whether it was, in which case there's nothing else to do.
+pub fn try_complete(args: &[String]) -> Result<bool, CliError> {
+    Ok(CompleteEnv::with_factory(Venice::command)
+        .var(COMPLETE_VAR)
+        .try_complete(args, None)?)
+}
+
+/// Attached devices, by USB serial number. Enumerating USB is cheap; nothing is opened.
+pub fn device_candidates() -> Vec<CompletionCandidate> {
+    serial::find_devices()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|device| {
+            let serial = usb_serial_number(system_port(device))?;
+            Some(CompletionCandidate::new(serial).help(Some(DeviceName(device).to_string().into())))
+        })
+        .collect()
+}
+
+/// Slots the cached state says are empty on the default brain, or the first attached one. With
+/// nothing cached, every slot is offered.
+pub fn slot_candidates() -> Vec<CompletionCandidate> {
+    let brain = load_config()
+        .ok()
+        .and_then(|config| config.default_device)
+        .or_else(|| {
+            serial::find_devices()
+                .ok()?
+                .first()
+                .and_then(|device| usb_serial_number(system_port(device)))
+        });
+    let taken = brain
+        .zip(Store::load().ok())
+        .and_then(|(brain, mut store)| store.brain_slots.remove(&brain))
+        .unwrap_or_default();
+
+    (1..=8u8)
+        .filter(|slot| !taken.contains_key(slot))
+        .map(|slot| CompletionCandidate::new(slot.to_string()))
+        .collect()
+}
+
+/// `venice completions`: prints a completion script for `shell`. The `--dynamic` script calls
+/// back into venice, so device and slot values can be completed too.
+pub fn completions(shell: Shell, dynamic: bool, bin_path: &str) -> Result<(), CliError> {
+    let name = std::path::Path::new(bin_path)
+        .file_name()
+        .map_or("venice".to_string(), |name| {
+            name.to_string_lossy().into_owned()
+        });
+    let mut stdout = std::io::stdout();
+
+    if !dynamic {
+        clap_complete::generate(shell, &mut Venice::command(), &name, &mut stdout);
+        return Ok(());
+    }
+
+    let completer = Shells::builtins()
+        .completer(&shell.to_string())
+        .ok_or_else(|| CliError::UnsupportedShell(shell.to_string()))?;
+    completer.write_registration(COMPLETE_VAR, &name, &name, bin_path, &mut stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
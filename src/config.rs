@@ -0,0 +1,267 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{errors::CliError, theme::Theme};
+
+pub const CONFIG_NAME: &str = "config.toml";
+
+const APP_DIR: &str = "venice";
+
+/// Global, per-user configuration stored in the venice config directory.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub github: GithubConfig,
+    pub update_check: UpdateCheck,
+    /// USB serial number of the device to connect to when several are plugged in. Port names
+    /// aren't stable (macOS renames them after sleep), serial numbers are.
+    pub default_device: Option<String>,
+    pub signing: SigningConfig,
+    pub registry: RegistryConfig,
+    pub notify: NotifyConfig,
+    pub battery: BatteryConfig,
+    pub terminal: TerminalConfig,
+    /// Where `terminal` also sends the program's output, as JSON events, e.g. for a team's
+    /// scouting or analytics pipeline
+    pub sinks: Vec<SinkConfig>,
+    /// Language for messages, like `es`. `--lang` overrides it; without either, the system locale
+    /// is used.
+    pub lang: Option<String>,
+    /// Always use `--screen-reader` output
+    pub screen_reader: bool,
+    /// Colors for progress bars and diagnostics: `default`, `high-contrast`, `color-blind` or
+    /// `monochrome`
+    pub theme: Theme,
+    pub usage: UsageConfig,
+    pub clock: ClockConfig,
+}
+
+/// [github] section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GithubConfig {
+    /// Personal access token used to raise the API rate limit
+    pub token: Option<String>,
+}
+
+/// [signing] section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SigningConfig {
+    /// Hex-encoded public keys. When non-empty, `upload` refuses tables not signed by one of them.
+    pub trusted_keys: Vec<String>,
+}
+
+/// [registry] section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RegistryConfig {
+    /// Base URL of the package registry used by `add`, `publish` and `yank`
+    pub url: Option<String>,
+    /// API token sent when publishing or yanking. `VENICE_REGISTRY_TOKEN` takes precedence.
+    pub token: Option<String>,
+}
+
+/// [notify] section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NotifyConfig {
+    /// URL the `notify` after-upload step posts to, e.g. a team chat webhook
+    pub webhook: Option<String>,
+}
+
+/// [terminal] section
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TerminalConfig {
+    /// Marker texts typed as `!1`, `!2`, ... in `terminal --log`, e.g.
+    /// `["auton start", "ring jam"]`
+    pub markers: Vec<String>,
+    /// Seconds of quiet after which the terminal pings the device, so USB autosuspend and radio
+    /// idling don't drop the link. 0 turns keepalives off.
+    pub keepalive: u64,
+    /// Seconds without any answer after which the device is considered gone
+    pub peer_timeout: u64,
+    /// Lines of program output shown per second; past it, lines are dropped with a notice.
+    /// 0 shows everything.
+    pub max_lines_per_second: u32,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            markers: Vec::new(),
+            keepalive: 5,
+            peer_timeout: 30,
+            max_lines_per_second: 200,
+        }
+    }
+}
+
+/// A [[sinks]] entry. Each line of program output is sent as a JSON object with `time` (Unix
+/// seconds), `device` (the brain's name and serial) and `text`, or stored as a row with them for
+/// SQLite.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum SinkConfig {
+    /// Append events to a file, one per line
+    File { path: PathBuf },
+    /// Send each event as a UDP datagram to `address`, like `192.168.1.20:9000`
+    Udp { address: String },
+    /// Publish each event to `topic` on an MQTT broker at `broker`, like `localhost:1883`
+    Mqtt { broker: String, topic: String },
+    /// Insert each event into the `events` table of the SQLite database at `path`, creating it
+    /// if needed
+    Sqlite { path: PathBuf },
+    /// Send each event as a text message on a WebSocket to `url`, like
+    /// `ws://192.168.1.20:8080/telemetry`
+    Websocket { url: String },
+}
+
+/// [battery] section
+#[derive(Deserialize, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BatteryConfig {
+    /// `upload` and `run` warn when the brain's charge is below this percentage
+    pub warn_below: u8,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self { warn_below: 30 }
+    }
+}
+
+/// [clock] section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ClockConfig {
+    /// Set the brain's clock when an upload or the terminal finds it's off, instead of only
+    /// warning
+    pub sync: bool,
+}
+
+/// [usage] section
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct UsageConfig {
+    /// Whether venice keeps command counts, durations and error codes for `venice stats usage`
+    pub collect: UsageCollection,
+    /// Where `venice stats usage --submit` sends them, when `collect` is `share`
+    pub submit_url: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UsageCollection {
+    #[default]
+    Off,
+    /// Kept on this machine only
+    Local,
+    /// Kept on this machine, and sent anonymously when asked with `--submit`
+    Share,
+}
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateCheck {
+    /// Check for a newer CLI release at most once a day
+    #[default]
+    Daily,
+    Never,
+}
+
+/// `<platform config dir>/venice`, e.g. `~/.config/venice` on Linux.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// `<platform data dir>/venice`, e.g. `~/.local/share/venice` on Linux. Holds history that
+/// shouldn't disappear when the cache is cleared.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// `<platform cache dir>/venice`, e.g. `~/.cache/venice` on Linux.
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// Creates a file for a key at `path`, and its directory if need be. On Unix, only its owner can
+/// read or write it; elsewhere it gets the user's default permissions.
+pub fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), CliError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents)?;
+    Ok(())
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_NAME))
+}
+
+pub fn load_config() -> Result<Config, CliError> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(CliError::Io(e)),
+    };
+
+    toml::from_str(&contents).map_err(|source| CliError::Config { path, source })
+}
+
+/// Sets a top-level key in the config file, preserving the rest of its formatting.
+pub fn set_config_value(key: &str, value: toml_edit::Item) -> Result<(), CliError> {
+    let path = config_path().ok_or(CliError::NoConfigDir)?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(CliError::Io(e)),
+    };
+
+    let mut doc = contents
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| CliError::ConfigEdit(e.to_string()))?;
+    doc.insert(key, value);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, doc.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_private_file;
+
+    #[test]
+    fn writes_private_files() {
+        let dir = std::env::temp_dir().join(format!("venice-private-{}", std::process::id()));
+        let path = dir.join("keys").join("signing-key");
+        write_private_file(&path, b"secret").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"secret");
+        // Never replaces a key that's already there
+        assert!(write_private_file(&path, b"other").is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+use std::{path::PathBuf, process::Command};
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{errors::CliError, manifest::get_project, project_dir};
+
+/// [tool.venice.copro] section: a co-processor (Raspberry Pi, Jetson, ...) whose files are
+/// deployed alongside the brain program.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct CoproConfig {
+    /// Directory, relative to the project, whose contents are pushed
+    #[serde(default = "default_dir")]
+    pub dir: PathBuf,
+    pub transport: Transport,
+}
+
+fn default_dir() -> PathBuf {
+    PathBuf::from("copro")
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Transport {
+    /// Copy over SSH with `scp`, e.g. `{ kind = "ssh", host = "pi@robot.local", path = "app" }`
+    Ssh { host: String, path: String },
+    /// Run an arbitrary command from the project directory. The directory to push is passed in
+    /// the `VENICE_COPRO_DIR` environment variable.
+    Command { command: Vec<String> },
+}
+
+pub async fn push() -> Result<(), CliError> {
+    let config = get_project().await?.copro.ok_or(CliError::NoCopro)?;
+    let project_dir = project_dir()?;
+    let dir = project_dir.join(&config.dir);
+
+    let mut command = match &config.transport {
+        Transport::Ssh { host, path } => {
+            let mut command = Command::new("scp");
+            command
+                .arg("-r")
+                .arg(dir.join("."))
+                .arg(format!("{host}:{path}"));
+            command
+        }
+        Transport::Command { command: args } => {
+            let (program, args) = args.split_first().ok_or(CliError::NoCopro)?;
+            let mut command = Command::new(program);
+            command.args(args).env("VENICE_COPRO_DIR", &dir);
+            command
+        }
+    };
+
+    println!("Pushing {} to the co-processor...", config.dir.display());
+    let status = tokio::task::spawn_blocking(move || command.current_dir(project_dir).status())
+        .await
+        .unwrap()?;
+    if !status.success() {
+        return Err(CliError::CoproFailed(status.code().unwrap_or(-1)));
+    }
+
+    println!("✓ Co-processor updated");
+    Ok(())
+}
+
+pub async fn show() -> Result<(), CliError> {
+    match get_project().await?.copro {
+        Some(config) => {
+            println!("directory: {}", config.dir.display());
+            match config.transport {
+                Transport::Ssh { host, path } => println!("transport: ssh to {host}:{path}"),
+                Transport::Command { command } => {
+                    println!("transport: command `{}`", command.join(" "))
+                }
+            }
+        }
+        None => println!("No co-processor configured in [tool.venice.copro]"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use vex_v5_serial::{
+    Connection,
+    commands::file::{USER_PROGRAM_LOAD_ADDR, UploadFile, j2000_timestamp},
+    protocol::{
+        FixedString, Version,
+        cdc2::file::{ExtensionType, FileExitAction, FileMetadata, FileTransferTarget, FileVendor},
+    },
+    serial::{SerialConnection, SerialError},
+};
+
+use crate::{BUILD_DIR, errors::CliError, manifest::DataConfig, project_dir};
+
+/// Records which data files are already on each brain, keyed by USB serial number.
+pub const SYNC_FILE: &str = "data-sync.json";
+
+/// Longest file name the brain accepts.
+const MAX_FILE_NAME: usize = 23;
+
+#[derive(
+    Deserialize, Serialize, JsonSchema, clap::ValueEnum, Debug, Clone, Copy, Eq, PartialEq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataTarget {
+    Sd,
+    Flash,
+}
+
+impl DataTarget {
+    pub fn transfer_target(self) -> FileTransferTarget {
+        match self {
+            Self::Sd => FileTransferTarget::A1,
+            Self::Flash => FileTransferTarget::Qspi,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DataFile {
+    /// Path relative to the data directory, with `/` separators. Also the name on the brain.
+    pub name: String,
+    pub path: PathBuf,
+    pub target: DataTarget,
+}
+
+type SyncState = BTreeMap<String, BTreeMap<String, u32>>;
+
+pub async fn find_data_files(config: &DataConfig) -> Result<Vec<DataFile>, CliError> {
+    let data_dir = project_dir()?.join(&config.dir);
+    let mut files = Vec::new();
+    if tokio::fs::try_exists(&data_dir).await? {
+        find_data_files_inner(config, &data_dir, &data_dir, &mut files).await?;
+    }
+    Ok(files)
+}
+
+async fn find_data_files_inner(
+    config: &DataConfig,
+    data_dir: &Path,
+    dir: &Path,
+    files: &mut Vec<DataFile>,
+) -> Result<(), CliError> {
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_dir() {
+            Box::pin(find_data_files_inner(config, data_dir, &path, files)).await?;
+            continue;
+        }
+
+        let name = path
+            .strip_prefix(data_dir)
+            .unwrap()
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        if name.len() > MAX_FILE_NAME {
+            return Err(CliError::DataFileName(name));
+        }
+
+        let target = if config.flash.contains(&name) {
+            DataTarget::Flash
+        } else if config.sd.contains(&name) {
+            DataTarget::Sd
+        } else {
+            config.default_target
+        };
+        files.push(DataFile { name, path, target });
+    }
+
+    Ok(())
+}
+
+async fn read_sync_state(path: &Path) -> SyncState {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_default(),
+        Err(_) => SyncState::default(),
+    }
+}
+
+/// Uploads every data file whose checksum differs from what was last uploaded to this brain.
+/// Files meant for the SD card are skipped with a warning if the brain has no card inserted.
+pub async fn sync_data_files(
+    conn: &mut SerialConnection,
+    device_serial: &str,
+    config: &DataConfig,
+) -> Result<(), CliError> {
+    let files = find_data_files(config).await?;
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let state_path = project_dir()?.join(BUILD_DIR).join(SYNC_FILE);
+    let mut state = read_sync_state(&state_path).await;
+    let synced = state.entry(device_serial.to_string()).or_default();
+
+    let mut uploaded = 0;
+    let mut skipped_sd = 0;
+    for file in files.iter() {
+        let contents = tokio::fs::read(&file.path).await?;
+        let checksum = crc32fast::hash(&contents);
+        if synced.get(&file.name) == Some(&checksum) {
+            continue;
+        }
+
+        let extension = Path::new(&file.name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().chars().take(3).collect::<String>())
+            .unwrap_or_else(|| String::from("bin"));
+
+        let result = conn
+            .execute_command(UploadFile {
+                file_name: FixedString::new(file.name.clone()).unwrap(),
+                metadata: FileMetadata {
+                    extension: FixedString::new(extension).unwrap(),
+                    extension_type: ExtensionType::Binary,
+                    timestamp: j2000_timestamp(),
+                    version: Version {
+                        major: 0,
+                        minor: 1,
+                        build: 0,
+                        beta: 0,
+                    },
+                },
+                vendor: FileVendor::User,
+                data: &contents,
+                target: file.target.transfer_target(),
+                load_address: USER_PROGRAM_LOAD_ADDR,
+                linked_file: None,
+                after_upload: FileExitAction::DoNothing,
+                progress_callback: None,
+            })
+            .await;
+
+        match result {
+            Ok(_) => {
+                synced.insert(file.name.clone(), checksum);
+                uploaded += 1;
+            }
+            // The brain refuses SD transfers when there's no card
+            Err(SerialError::Nack(_)) if file.target == DataTarget::Sd => skipped_sd += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if uploaded > 0 {
+        println!("✓ Synced {uploaded} data file(s)");
+    }
+    if skipped_sd > 0 {
+        eprintln!(
+            "warning: skipped {skipped_sd} SD card data file(s) - is there a card in the brain?"
+        );
+    }
+
+    tokio::fs::create_dir_all(state_path.parent().unwrap()).await?;
+    tokio::fs::write(&state_path, serde_json::to_string_pretty(&state)?).await?;
+    Ok(())
+}
@@ -0,0 +1,1022 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    build::Compiler,
+    config,
+    config::cache_dir,
+    errors::CliError,
+    frozen,
+    manifest::{
+        GitDependency, MANIFEST_NAME, Project, get_project, get_project_at,
+        remove_venice_table_entry, set_venice_table_entry,
+    },
+    profile, project_dir,
+    registry::{RegistryClient, sha256_hex},
+    tr, uv_path,
+    workspace::{deps_root, shared_requirements, sharing_projects},
+};
+
+/// Records the exact version and checksum every dependency resolved to. A workspace member's, like
+/// its directories below, is in the workspace's root instead; see [`deps_root`].
+pub const LOCK_FILE: &str = "venice.lock";
+/// Where installed packages are unpacked, relative to the project.
+pub const PACKAGES_DIR: &str = ".venice/packages";
+/// Where git dependencies are checked out, relative to the project.
+pub const GIT_CHECKOUTS_DIR: &str = ".venice/git";
+/// Where PyPI dependencies are installed, relative to the project.
+pub const PYPI_DIR: &str = ".venice/pypi";
+/// Ref a git dependency's default branch is fetched into, in its clone in the venice cache.
+const DEFAULT_BRANCH_REF: &str = "refs/venice/default";
+/// Where `venice vendor` keeps package tarballs, relative to the project. Meant to be committed.
+pub const VENDOR_DIR: &str = "vendor";
+
+/// File inside each unpacked package recording which version it holds.
+const INSTALLED_MARKER: &str = ".venice-version";
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+    #[serde(default, rename = "git")]
+    pub git: Vec<LockedGit>,
+    #[serde(default, rename = "pypi")]
+    pub pypi: Vec<LockedPypi>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: semver::Version,
+    pub sha256: String,
+}
+
+/// The commit a git dependency resolved to.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LockedGit {
+    pub name: String,
+    pub url: String,
+    /// What the manifest asked for, so changing it resolves again
+    pub reference: String,
+    pub commit: String,
+}
+
+/// The version a PyPI dependency resolved to.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LockedPypi {
+    pub name: String,
+    /// What the manifest asked for, so changing it resolves again
+    pub specifier: String,
+    pub version: String,
+}
+
+impl Lockfile {
+    pub async fn read(project_dir: &Path) -> Result<Self, CliError> {
+        match tokio::fs::read_to_string(deps_root(project_dir).join(LOCK_FILE)).await {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| CliError::Lockfile(e.to_string()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(CliError::Io(e)),
+        }
+    }
+
+    pub async fn write(&self, project_dir: &Path) -> Result<(), CliError> {
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| CliError::Lockfile(e.to_string()))?;
+        tokio::fs::write(deps_root(project_dir).join(LOCK_FILE), contents).await?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    fn insert(&mut self, package: LockedPackage) {
+        self.packages.retain(|p| p.name != package.name);
+        self.packages.push(package);
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    fn insert_git(&mut self, locked: LockedGit) {
+        self.git.retain(|g| g.name != locked.name);
+        self.git.push(locked);
+        self.git.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    fn insert_pypi(&mut self, locked: LockedPypi) {
+        self.pypi.retain(|p| p.name != locked.name);
+        self.pypi.push(locked);
+        self.pypi.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Forgets whatever `name` was locked to.
+    fn remove(&mut self, name: &str) {
+        self.packages.retain(|p| p.name != name);
+        self.git.retain(|g| g.name != name);
+        self.pypi.retain(|p| p.name != name);
+    }
+}
+
+/// The Python package name a library publishes its modules under.
+pub fn import_name(project_name: &str) -> String {
+    project_name.replace('-', "_")
+}
+
+fn package_dir(project_dir: &Path, name: &str) -> PathBuf {
+    deps_root(project_dir).join(PACKAGES_DIR).join(name)
+}
+
+fn git_checkout_dir(project_dir: &Path, name: &str) -> PathBuf {
+    deps_root(project_dir).join(GIT_CHECKOUTS_DIR).join(name)
+}
+
+fn pypi_dir(project_dir: &Path, name: &str) -> PathBuf {
+    deps_root(project_dir).join(PYPI_DIR).join(name)
+}
+
+/// The directory and package name of every dependency, registry and path alike, for the build
+/// to compile.
+pub fn dependency_roots(project_dir: &Path, project: &Project) -> Vec<(PathBuf, String)> {
+    let registry = project
+        .dependencies
+        .keys()
+        .map(|name| (package_dir(project_dir, name), import_name(name)));
+    let path = project
+        .path_dependencies
+        .iter()
+        .chain(&project.dev_dependencies)
+        .map(|(name, dep)| (project_dir.join(&dep.path), import_name(name)));
+    let git = project
+        .git_dependencies
+        .keys()
+        .map(|name| (git_checkout_dir(project_dir, name), import_name(name)));
+    registry.chain(path).chain(git).collect()
+}
+
+/// The directory each PyPI dependency is installed in. Unlike [`dependency_roots`], every
+/// top-level module and package in it is compiled, since a PyPI package's modules needn't share
+/// its name.
+pub fn pypi_roots(project_dir: &Path, project: &Project) -> Vec<PathBuf> {
+    project
+        .pypi_dependencies
+        .keys()
+        .map(|name| pypi_dir(project_dir, name))
+        .collect()
+}
+
+async fn installed_version(project_dir: &Path, name: &str) -> Option<semver::Version> {
+    let marker = package_dir(project_dir, name).join(INSTALLED_MARKER);
+    tokio::fs::read_to_string(marker)
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+async fn unpack(
+    project_dir: &Path,
+    package: &LockedPackage,
+    tarball: Vec<u8>,
+) -> Result<(), CliError> {
+    let dest = package_dir(project_dir, &package.name);
+    if tokio::fs::try_exists(&dest).await? {
+        tokio::fs::remove_dir_all(&dest).await?;
+    }
+
+    let unpack_dest = dest.clone();
+    tokio::task::spawn_blocking(move || {
+        tar::Archive::new(GzDecoder::new(&tarball[..])).unpack(unpack_dest)
+    })
+    .await
+    .unwrap()?;
+    tokio::fs::write(dest.join(INSTALLED_MARKER), package.version.to_string()).await?;
+    Ok(())
+}
+
+fn vendored_path(project_dir: &Path, package: &LockedPackage) -> PathBuf {
+    deps_root(project_dir)
+        .join(VENDOR_DIR)
+        .join(format!("{}-{}.tar.gz", package.name, package.version))
+}
+
+/// Where `venice vendor` keeps a git dependency's tree at `commit`, as a gzipped tarball.
+fn vendored_git_path(project_dir: &Path, name: &str, commit: &str) -> PathBuf {
+    deps_root(project_dir)
+        .join(VENDOR_DIR)
+        .join(format!("{name}-{commit}.tar.gz"))
+}
+
+/// Where `venice vendor` keeps what uv installed for a PyPI dependency at `version`, as a gzipped
+/// tarball.
+fn vendored_pypi_path(project_dir: &Path, name: &str, version: &str) -> PathBuf {
+    deps_root(project_dir)
+        .join(VENDOR_DIR)
+        .join(format!("{name}-{version}.tar.gz"))
+}
+
+/// Reads a package's tarball from the vendor directory, if it's been vendored.
+async fn read_vendored(
+    project_dir: &Path,
+    package: &LockedPackage,
+) -> Result<Option<Vec<u8>>, CliError> {
+    let tarball = match tokio::fs::read(vendored_path(project_dir, package)).await {
+        Ok(tarball) => tarball,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(CliError::Io(e)),
+    };
+
+    if sha256_hex(&tarball) != package.sha256 {
+        return Err(CliError::ChecksumMismatch(package.name.clone()));
+    }
+    Ok(Some(tarball))
+}
+
+fn registry(client: &mut Option<RegistryClient>) -> Result<&RegistryClient, CliError> {
+    if client.is_none() {
+        *client = Some(RegistryClient::new(config())?);
+    }
+    Ok(client.as_ref().unwrap())
+}
+
+/// Makes sure every dependency in the manifest is unpacked at its locked version, resolving and
+/// locking any that aren't locked yet (or whose lock no longer satisfies the manifest). Vendored
+/// tarballs are used instead of the registry when present; with `--frozen` they're required.
+/// Git and PyPI dependencies are installed at their locked commit or version; see
+/// [`install_git`] and [`install_pypi`]. In a workspace, registry packages resolve to a version
+/// every member accepts.
+pub async fn install(project: &Project) -> Result<(), CliError> {
+    if project.dependencies.is_empty()
+        && project.git_dependencies.is_empty()
+        && project.pypi_dependencies.is_empty()
+    {
+        return Ok(());
+    }
+
+    let project_dir = project_dir()?;
+    let sharing = sharing_projects(project_dir).await?;
+    check_shared_pins(&sharing)?;
+    let mut lock = Lockfile::read(project_dir).await?;
+    let mut client = None;
+    let mut lock_changed = install_git(project_dir, project, &mut lock).await?;
+    lock_changed |= install_pypi(project_dir, project, &mut lock).await?;
+
+    for (name, req) in shared_requirements(project, &sharing).iter() {
+        let locked = lock.get(name).filter(|p| req.matches(&p.version)).cloned();
+        if let Some(locked) = &locked
+            && installed_version(project_dir, name).await.as_ref() == Some(&locked.version)
+        {
+            continue;
+        }
+
+        let package = match locked {
+            Some(locked) => locked,
+            None if frozen() => return Err(CliError::FrozenUnlocked(name.clone())),
+            None => {
+                let index = registry(&mut client)?.index(name).await?;
+                let version = index.resolve(req)?;
+                let package = LockedPackage {
+                    name: name.clone(),
+                    version: version.version.clone(),
+                    sha256: version.sha256.clone(),
+                };
+                lock.insert(package.clone());
+                lock_changed = true;
+                package
+            }
+        };
+
+        let tarball = match read_vendored(project_dir, &package).await? {
+            Some(tarball) => tarball,
+            None if frozen() => return Err(CliError::FrozenNotVendored(name.clone())),
+            None => {
+                registry(&mut client)?
+                    .download(name, &package.version, &package.sha256)
+                    .await?
+            }
+        };
+        unpack(project_dir, &package, tarball).await?;
+        println!(
+            "{}",
+            tr!(
+                "deps-installed",
+                name = name,
+                version = package.version.to_string()
+            )
+        );
+    }
+
+    if lock_changed {
+        lock.write(project_dir).await?;
+    }
+    Ok(())
+}
+
+/// Fails if two of the projects sharing installed dependencies ask for a git or PyPI dependency of
+/// the same name differently. Each name has one checkout and one lock entry, which they'd
+/// otherwise take turns resolving again.
+fn check_shared_pins(sharing: &[Project]) -> Result<(), CliError> {
+    let mut pins: BTreeMap<&String, (&String, String)> = BTreeMap::new();
+    for project in sharing {
+        let git = project
+            .git_dependencies
+            .iter()
+            .map(|(name, dep)| (name, format!("{} {}", dep.git, git_reference(dep))));
+        let pypi = project
+            .pypi_dependencies
+            .iter()
+            .map(|(name, dep)| (name, format!("pypi {}", dep.pypi)));
+        for (name, pin) in git.chain(pypi) {
+            match pins.get(name) {
+                Some((first, first_pin)) if *first_pin != pin => {
+                    return Err(CliError::ConflictingPins {
+                        name: name.clone(),
+                        first: first.to_string(),
+                        second: project.name.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    pins.insert(name, (&project.name, pin));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs git in `db`, the venice cache's clone of `url`, returning what it printed.
+async fn git(db: &Path, url: &str, args: &[&str]) -> Result<Vec<u8>, CliError> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(db)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(CliError::GitFailed {
+            url: url.to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// The revision a git dependency asks for, as `git rev-parse` takes it.
+fn git_reference(dep: &GitDependency) -> String {
+    match (&dep.rev, &dep.tag, &dep.branch) {
+        (Some(rev), _, _) => rev.clone(),
+        (None, Some(tag), _) => format!("refs/tags/{tag}"),
+        (None, None, Some(branch)) => format!("refs/heads/{branch}"),
+        (None, None, None) => DEFAULT_BRANCH_REF.to_string(),
+    }
+}
+
+/// The venice cache's clone of `url`, shared by every project depending on it.
+fn git_db(url: &str) -> Result<PathBuf, CliError> {
+    Ok(cache_dir()
+        .ok_or(CliError::NoConfigDir)?
+        .join("git")
+        .join(&sha256_hex(url.as_bytes())[..16]))
+}
+
+/// The tree of `commit` as a tar archive, fetching `url` into its clone first if the commit
+/// hasn't been fetched yet.
+async fn git_archive(url: &str, commit: &str) -> Result<Vec<u8>, CliError> {
+    let db = git_db(url)?;
+    let object = format!("{commit}^{{commit}}");
+    if git(&db, url, &["cat-file", "-e", &object]).await.is_err() {
+        if frozen() {
+            return Err(CliError::FrozenNetwork);
+        }
+        fetch(&db, url).await?;
+    }
+    git(&db, url, &["archive", "--format=tar", commit]).await
+}
+
+/// Fetches every branch and tag of `url`, and its default branch as [`DEFAULT_BRANCH_REF`], into
+/// the bare clone at `db`, creating it if needed.
+async fn fetch(db: &Path, url: &str) -> Result<(), CliError> {
+    if !db.exists() {
+        tokio::fs::create_dir_all(db).await?;
+        git(db, url, &["init", "--bare", "--quiet"]).await?;
+    }
+    let default_branch = format!("+HEAD:{DEFAULT_BRANCH_REF}");
+    git(
+        db,
+        url,
+        &[
+            "fetch",
+            "--quiet",
+            "--force",
+            "--tags",
+            url,
+            &default_branch,
+            "+refs/heads/*:refs/heads/*",
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Checks out each git dependency into [`GIT_CHECKOUTS_DIR`] at its locked commit, or at the
+/// commit its `rev`, `tag` or `branch` resolves to now if it isn't locked. Repositories are
+/// cloned once into the venice cache and shared by every project, so a locked commit that's
+/// already been fetched, or vendored, needs no network. Returns whether the lock changed.
+async fn install_git(
+    project_dir: &Path,
+    project: &Project,
+    lock: &mut Lockfile,
+) -> Result<bool, CliError> {
+    let mut lock_changed = false;
+    for (name, dep) in &project.git_dependencies {
+        let reference = git_reference(dep);
+        let locked = lock
+            .git
+            .iter()
+            .find(|g| g.name == *name && g.url == dep.git && g.reference == reference)
+            .cloned();
+        let checkout = git_checkout_dir(project_dir, name);
+        if let Some(locked) = &locked
+            && tokio::fs::read_to_string(checkout.join(INSTALLED_MARKER))
+                .await
+                .is_ok_and(|commit| commit == locked.commit)
+        {
+            continue;
+        }
+
+        let commit = match locked {
+            // Named in git's arguments and the vendored archive's file name
+            Some(locked) if !locked.commit.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                return Err(CliError::Lockfile(format!(
+                    "`{name}` is locked to `{}`, which isn't a commit hash",
+                    locked.commit
+                )));
+            }
+            Some(locked) => locked.commit,
+            None if frozen() => return Err(CliError::FrozenUnlocked(name.clone())),
+            None => {
+                let db = git_db(&dep.git)?;
+                fetch(&db, &dep.git).await?;
+                let object = format!("{reference}^{{commit}}");
+                let output = git(&db, &dep.git, &["rev-parse", "--verify", &object]).await?;
+                let commit = String::from_utf8_lossy(&output).trim().to_string();
+                lock.insert_git(LockedGit {
+                    name: name.clone(),
+                    url: dep.git.clone(),
+                    reference,
+                    commit: commit.clone(),
+                });
+                lock_changed = true;
+                commit
+            }
+        };
+
+        let vendored = match tokio::fs::read(vendored_git_path(project_dir, name, &commit)).await {
+            Ok(tarball) => Some(tarball),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(CliError::Io(e)),
+        };
+        if tokio::fs::try_exists(&checkout).await? {
+            tokio::fs::remove_dir_all(&checkout).await?;
+        }
+        let unpack_dest = checkout.clone();
+        match vendored {
+            Some(tarball) => tokio::task::spawn_blocking(move || {
+                tar::Archive::new(GzDecoder::new(&tarball[..])).unpack(unpack_dest)
+            }),
+            None => {
+                let archive = git_archive(&dep.git, &commit).await?;
+                tokio::task::spawn_blocking(move || {
+                    tar::Archive::new(&archive[..]).unpack(unpack_dest)
+                })
+            }
+        }
+        .await
+        .unwrap()?;
+        tokio::fs::write(checkout.join(INSTALLED_MARKER), &commit).await?;
+        println!(
+            "{}",
+            tr!(
+                "deps-installed",
+                name = name,
+                version = commit[..commit.len().min(12)].to_string()
+            )
+        );
+    }
+    Ok(lock_changed)
+}
+
+/// The version in the `<name>-<version>.dist-info` directory uv leaves next to what it installed.
+async fn dist_info_version(dir: &Path) -> Result<Option<String>, CliError> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if let Some((_, version)) = file_name
+            .strip_suffix(".dist-info")
+            .and_then(|stem| stem.rsplit_once('-'))
+        {
+            return Ok(Some(version.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// A compiled extension anywhere under `dir`, which the brain couldn't load.
+fn find_extension(dir: &Path) -> Result<Option<PathBuf>, CliError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(found) = find_extension(&path)? {
+                return Ok(Some(found));
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ["so", "pyd", "dylib", "dll"].iter().any(|e| ext == *e))
+        {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Installs each PyPI dependency into [`PYPI_DIR`] with uv, at its locked version, or the newest
+/// its specifier allows if it isn't locked. A locked version that's been vendored is unpacked
+/// instead, without uv or the network. Dependencies of the package aren't installed: they
+/// have to be added themselves, so nothing reaches the program table unasked. Returns whether
+/// the lock changed.
+async fn install_pypi(
+    project_dir: &Path,
+    project: &Project,
+    lock: &mut Lockfile,
+) -> Result<bool, CliError> {
+    let mut lock_changed = false;
+    for (name, dep) in &project.pypi_dependencies {
+        let locked = lock
+            .pypi
+            .iter()
+            .find(|p| p.name == *name && p.specifier == dep.pypi)
+            .cloned();
+        let dir = pypi_dir(project_dir, name);
+        if let Some(locked) = &locked
+            && tokio::fs::read_to_string(dir.join(INSTALLED_MARKER))
+                .await
+                .is_ok_and(|version| version == locked.version)
+        {
+            continue;
+        }
+        if let Some(locked) = &locked {
+            let vendored = vendored_pypi_path(project_dir, name, &locked.version);
+            match tokio::fs::read(&vendored).await {
+                Ok(tarball) => {
+                    if tokio::fs::try_exists(&dir).await? {
+                        tokio::fs::remove_dir_all(&dir).await?;
+                    }
+                    let unpack_dest = dir.clone();
+                    tokio::task::spawn_blocking(move || {
+                        tar::Archive::new(GzDecoder::new(&tarball[..])).unpack(unpack_dest)
+                    })
+                    .await
+                    .unwrap()?;
+                    tokio::fs::write(dir.join(INSTALLED_MARKER), &locked.version).await?;
+                    println!(
+                        "{}",
+                        tr!(
+                            "deps-installed",
+                            name = name,
+                            version = locked.version.as_str()
+                        )
+                    );
+                    continue;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(CliError::Io(e)),
+            }
+        }
+        if frozen() {
+            return Err(match locked {
+                Some(_) => CliError::FrozenNetwork,
+                None => CliError::FrozenUnlocked(name.clone()),
+            });
+        }
+
+        let requirement = match (&locked, dep.pypi.as_str()) {
+            (Some(locked), _) => format!("{name}=={}", locked.version),
+            (None, "*") => name.clone(),
+            (None, specifier) => format!("{name}{specifier}"),
+        };
+        if tokio::fs::try_exists(&dir).await? {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+        let output = tokio::process::Command::new(uv_path()?)
+            .args(["pip", "install", "--quiet", "--no-deps", "--target"])
+            .arg(&dir)
+            .arg(&requirement)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(CliError::UvFailed {
+                status: output.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+
+        if let Some(extension) = find_extension(&dir)? {
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            return Err(CliError::NotPurePython {
+                name: name.clone(),
+                extension,
+            });
+        }
+        let version = match locked {
+            Some(locked) => locked.version,
+            None => {
+                let version = dist_info_version(&dir)
+                    .await?
+                    .unwrap_or_else(|| "0".to_string());
+                lock.insert_pypi(LockedPypi {
+                    name: name.clone(),
+                    specifier: dep.pypi.clone(),
+                    version: version.clone(),
+                });
+                lock_changed = true;
+                version
+            }
+        };
+        tokio::fs::write(dir.join(INSTALLED_MARKER), &version).await?;
+        println!(
+            "{}",
+            tr!("deps-installed", name = name, version = version.as_str())
+        );
+    }
+    Ok(lock_changed)
+}
+
+/// Packs what uv installed into `dir` for a PyPI dependency into a gzipped tarball, leaving out
+/// venice's own marker.
+fn pypi_tarball(dir: &Path) -> Result<Vec<u8>, CliError> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_name() == INSTALLED_MARKER {
+            continue;
+        } else if path.is_dir() {
+            builder.append_dir_all(entry.file_name(), &path)?;
+        } else {
+            builder.append_path_with_name(&path, entry.file_name())?;
+        }
+    }
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// `venice add --pypi`: adds a pure-Python PyPI package to [tool.venice.dependencies] and
+/// installs it. Without a specifier, it's pinned to the installed version's compatible releases.
+pub async fn add_pypi(name: &str, specifier: Option<String>) -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let manifest_path = project_dir.join(MANIFEST_NAME);
+    let specifier = specifier.unwrap_or_else(|| "*".to_string());
+    let mut table = toml_edit::InlineTable::new();
+    table.insert("pypi", specifier.as_str().into());
+    set_venice_table_entry(&manifest_path, "dependencies", name, table.into()).await?;
+
+    let project = get_project().await?;
+    let mut lock = Lockfile::read(project_dir).await?;
+    lock.remove(name);
+    if let Err(e) = install_pypi(project_dir, &project, &mut lock).await {
+        remove_venice_table_entry(&manifest_path, "dependencies", name).await?;
+        return Err(e);
+    }
+
+    // Locked by the install above, since the old lock entry was dropped
+    let version = lock
+        .pypi
+        .iter()
+        .find(|p| p.name == name)
+        .map(|p| p.version.clone())
+        .unwrap_or_default();
+    let mut parts = version.split('.').map(str::parse::<u64>);
+    if specifier == "*"
+        && let (Some(Ok(major)), Some(Ok(minor))) = (parts.next(), parts.next())
+    {
+        // `~=1.2` allows 1.2 and up, but not 2.0, like a caret requirement
+        let specifier = format!("~={major}.{minor}");
+        let mut table = toml_edit::InlineTable::new();
+        table.insert("pypi", specifier.as_str().into());
+        set_venice_table_entry(&manifest_path, "dependencies", name, table.into()).await?;
+        lock.insert_pypi(LockedPypi {
+            name: name.to_string(),
+            specifier,
+            version: version.clone(),
+        });
+    }
+    lock.write(project_dir).await?;
+    println!(
+        "{}",
+        tr!("deps-added", name = name, version = version.as_str())
+    );
+    Ok(())
+}
+
+/// `venice remove`: takes a dependency of any kind out of the manifest and the lock, and deletes
+/// what was installed for it.
+pub async fn remove(name: &str) -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let manifest_path = project_dir.join(MANIFEST_NAME);
+    let mut removed = false;
+    for table in ["dependencies", "dev-dependencies"] {
+        removed |= remove_venice_table_entry(&manifest_path, table, name).await?;
+    }
+    if !removed {
+        return Err(CliError::NotADependency(name.to_string()));
+    }
+
+    for dir in [
+        package_dir(project_dir, name),
+        git_checkout_dir(project_dir, name),
+        pypi_dir(project_dir, name),
+    ] {
+        if tokio::fs::try_exists(&dir).await? {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+    }
+    let mut lock = Lockfile::read(project_dir).await?;
+    lock.remove(name);
+    lock.write(project_dir).await?;
+    println!("{}", tr!("deps-removed", name = name));
+    Ok(())
+}
+
+/// `venice vendor`: copies the tarball of every locked dependency into `vendor/`, so the project
+/// can be built with `--frozen` and no network access. Git dependencies are archived at their
+/// locked commit, and PyPI dependencies as uv installed them. In a workspace, that's every
+/// member's dependencies, since they share the directory.
+pub async fn vendor() -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let mut projects = sharing_projects(project_dir).await?;
+    if projects.is_empty() {
+        projects.push(get_project().await?);
+    }
+    for project in &projects {
+        install(project).await?;
+    }
+    let lock = Lockfile::read(project_dir).await?;
+
+    let vendor_dir = deps_root(project_dir).join(VENDOR_DIR);
+    tokio::fs::create_dir_all(&vendor_dir).await?;
+
+    let mut client = None;
+    let mut vendored = Vec::new();
+    let names: BTreeSet<&String> = projects
+        .iter()
+        .flat_map(|project| project.dependencies.keys())
+        .collect();
+    for name in names {
+        let package = lock
+            .get(name)
+            .ok_or_else(|| CliError::Lockfile(format!("`{name}` isn't locked")))?;
+        let path = vendored_path(project_dir, package);
+        if read_vendored(project_dir, package).await?.is_none() {
+            let tarball = registry(&mut client)?
+                .download(name, &package.version, &package.sha256)
+                .await?;
+            tokio::fs::write(&path, tarball).await?;
+        }
+        vendored.push(path);
+    }
+
+    let git_deps: BTreeMap<&String, &GitDependency> = projects
+        .iter()
+        .flat_map(|project| &project.git_dependencies)
+        .collect();
+    for (name, dep) in git_deps {
+        let locked = lock
+            .git
+            .iter()
+            .find(|g| g.name == *name)
+            .ok_or_else(|| CliError::Lockfile(format!("`{name}` isn't locked")))?;
+        let path = vendored_git_path(project_dir, name, &locked.commit);
+        if !tokio::fs::try_exists(&path).await? {
+            let archive = git_archive(&dep.git, &locked.commit).await?;
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&archive)?;
+            tokio::fs::write(&path, encoder.finish()?).await?;
+        }
+        vendored.push(path);
+    }
+
+    let pypi_names: BTreeSet<&String> = projects
+        .iter()
+        .flat_map(|project| project.pypi_dependencies.keys())
+        .collect();
+    for name in pypi_names {
+        let locked = lock
+            .pypi
+            .iter()
+            .find(|p| p.name == *name)
+            .ok_or_else(|| CliError::Lockfile(format!("`{name}` isn't locked")))?;
+        let path = vendored_pypi_path(project_dir, name, &locked.version);
+        if !tokio::fs::try_exists(&path).await? {
+            tokio::fs::write(&path, pypi_tarball(&pypi_dir(project_dir, name))?).await?;
+        }
+        vendored.push(path);
+    }
+
+    // Drop tarballs of versions that are no longer locked
+    let mut entries = tokio::fs::read_dir(&vendor_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.to_string_lossy().ends_with(".tar.gz") && !vendored.contains(&path) {
+            tokio::fs::remove_file(&path).await?;
+        }
+    }
+
+    println!(
+        "{}",
+        tr!("deps-vendored", count = vendored.len(), dir = VENDOR_DIR)
+    );
+    Ok(())
+}
+
+/// `venice add`: adds a registry package to [tool.venice.dependencies] and installs it.
+pub async fn add(name: &str, req: Option<semver::VersionReq>) -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let client = RegistryClient::new(config())?;
+    let index = client.index(name).await?;
+    let version = index.resolve(&req.clone().unwrap_or(semver::VersionReq::STAR))?;
+
+    let package = LockedPackage {
+        name: name.to_string(),
+        version: version.version.clone(),
+        sha256: version.sha256.clone(),
+    };
+    unpack(
+        project_dir,
+        &package,
+        client
+            .download(name, &package.version, &package.sha256)
+            .await?,
+    )
+    .await?;
+
+    let req =
+        req.unwrap_or_else(|| semver::VersionReq::parse(&format!("^{}", package.version)).unwrap());
+    set_venice_table_entry(
+        &project_dir.join(MANIFEST_NAME),
+        "dependencies",
+        name,
+        req.to_string().into(),
+    )
+    .await?;
+
+    let mut lock = Lockfile::read(project_dir).await?;
+    println!(
+        "{}",
+        tr!(
+            "deps-added",
+            name = name,
+            version = package.version.to_string()
+        )
+    );
+    lock.insert(package);
+    lock.write(project_dir).await?;
+    Ok(())
+}
+
+/// `venice add --path`: adds a local library to [tool.venice.dependencies], or with `dev` to
+/// [tool.venice.dev-dependencies]. Its sources are compiled straight into the program table, so
+/// edits show up on the next build.
+pub async fn add_path(path: &Path, dev: bool) -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let library = get_project_at(path).await?;
+
+    let init = path
+        .join(import_name(&library.name))
+        .join("__init__")
+        .with_extension(library.compiler(profile()).src_ext());
+    if !tokio::fs::try_exists(&init).await? {
+        return Err(CliError::NoLibraryPackage(import_name(&library.name)));
+    }
+
+    let mut table = toml_edit::InlineTable::new();
+    table.insert("path", path.to_string_lossy().replace('\\', "/").into());
+    set_venice_table_entry(
+        &project_dir.join(MANIFEST_NAME),
+        if dev {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        },
+        &library.name,
+        table.into(),
+    )
+    .await?;
+
+    println!(
+        "{}",
+        tr!(
+            "deps-added-path",
+            name = library.name.as_str(),
+            path = path.display().to_string()
+        )
+    );
+    Ok(())
+}
+
+/// Packs the library's pyproject.toml and its `<import name>/` package into a gzipped tarball.
+fn package_tarball(
+    project_dir: &Path,
+    import_name: &str,
+    src_ext: &str,
+) -> Result<Vec<u8>, CliError> {
+    fn append_dir(
+        builder: &mut tar::Builder<GzEncoder<Vec<u8>>>,
+        project_dir: &Path,
+        dir: &Path,
+        src_ext: &str,
+    ) -> Result<(), CliError> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                append_dir(builder, project_dir, &path, src_ext)?;
+            } else if path.extension().is_some_and(|ext| ext == src_ext) {
+                builder.append_path_with_name(&path, path.strip_prefix(project_dir).unwrap())?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    builder.append_path_with_name(project_dir.join(MANIFEST_NAME), MANIFEST_NAME)?;
+    append_dir(
+        &mut builder,
+        project_dir,
+        &project_dir.join(import_name),
+        src_ext,
+    )?;
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// `venice publish`: uploads the current project to the registry as a library.
+pub async fn publish() -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let project = get_project().await?;
+    let version: semver::Version = project
+        .version
+        .as_deref()
+        .ok_or(CliError::NoProjectVersion)?
+        .parse()?;
+
+    let import_name = import_name(&project.name);
+    let src_ext = project.compiler(profile()).src_ext();
+    let init = project_dir
+        .join(&import_name)
+        .join("__init__")
+        .with_extension(src_ext);
+    if !tokio::fs::try_exists(&init).await? {
+        return Err(CliError::NoLibraryPackage(import_name));
+    }
+
+    let tarball = package_tarball(project_dir, &import_name, src_ext)?;
+    let client = RegistryClient::new(config())?;
+    client.publish(&project.name, &version, tarball).await?;
+
+    println!(
+        "{}",
+        tr!(
+            "deps-published",
+            name = project.name.as_str(),
+            version = version.to_string()
+        )
+    );
+    Ok(())
+}
+
+/// `venice yank`: hides a published version from new resolutions, or undoes that.
+pub async fn yank(name: &str, version: &semver::Version, undo: bool) -> Result<(), CliError> {
+    RegistryClient::new(config())?
+        .set_yanked(name, version, !undo)
+        .await?;
+
+    if undo {
+        println!(
+            "{}",
+            tr!("deps-unyanked", name = name, version = version.to_string())
+        );
+    } else {
+        println!(
+            "{}",
+            tr!("deps-yanked", name = name, version = version.to_string())
+        );
+    }
+    Ok(())
+}
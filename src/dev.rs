@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, stdin, stdout},
+    select,
+    time::sleep,
+};
+use vex_v5_serial::protocol::cdc2::file::FileExitAction;
+
+use crate::{
+    assets::asset_paths,
+    brain,
+    build::{BuildOptions, build, find_package_modules, find_project_modules},
+    deps::dependency_roots,
+    device::open_connection,
+    diagnostics::{MessageFormat, print_build_messages, print_build_result},
+    errors::CliError,
+    generate::generator_inputs,
+    manifest::{MANIFEST_NAME, get_project},
+    palette::{self, CLEAR_SCREEN, DEV_HELP, Input, PaletteCommand},
+    profile, project_dir,
+    runtime::RuntimeSource,
+    stats::format_size,
+    tr,
+    upload::upload,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+/// Modification times of the manifest and every source file that goes into the program table,
+/// including those of path dependencies.
+async fn snapshot() -> Result<Snapshot, CliError> {
+    let project_dir = project_dir()?;
+    let project = get_project().await?;
+    let compiler = project.compiler(profile());
+
+    let mut sources = vec![project_dir.join(MANIFEST_NAME)];
+    for (root, modules) in find_project_modules(project_dir, &project, &compiler).await? {
+        for module in modules {
+            sources.push(module.src_path(&root));
+        }
+    }
+    for (root, package) in dependency_roots(project_dir, &project) {
+        for module in find_package_modules(&root, &package, &compiler).await? {
+            sources.push(module.src_path(&root));
+        }
+    }
+    sources.extend(asset_paths(&project_dir.join(&project.assets.dir))?);
+    sources.extend(generator_inputs(project_dir, &project));
+
+    let mut snapshot = Snapshot::new();
+    for path in sources {
+        let modified = tokio::fs::metadata(&path).await?.modified()?;
+        snapshot.insert(path, modified);
+    }
+    Ok(snapshot)
+}
+
+async fn stop_program() -> Result<(), CliError> {
+    brain::stop_program(&mut open_connection().await?).await?;
+    Ok(())
+}
+
+/// `venice dev`: uploads and runs the program, then does it again whenever a project source or a
+/// path dependency changes. Build errors are printed rather than ending the session. Lines
+/// starting with `:` are commands, like in `venice terminal`.
+pub async fn dev(runtime_source: Option<RuntimeSource>) -> Result<(), CliError> {
+    let mut lines = BufReader::new(stdin()).lines();
+    let mut stdin_open = true;
+    let mut last = None;
+    loop {
+        let current = match snapshot().await {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                eprintln!("{:?}", miette::Report::new(e));
+                None
+            }
+        };
+
+        if current.is_some() && current != last {
+            if last.is_some() {
+                println!("\n{}", tr!("dev-rebuilding"));
+            }
+
+            match upload(
+                Some(FileExitAction::RunProgram),
+                runtime_source.clone(),
+                false,
+            )
+            .await
+            {
+                Ok(_) => println!("{}", tr!("dev-running")),
+                Err(e) => eprintln!("{:?}", miette::Report::new(e)),
+            }
+            last = current;
+        }
+
+        let line = select! {
+            _ = sleep(POLL_INTERVAL) => continue,
+            line = lines.next_line(), if stdin_open => match line? {
+                Some(line) => line,
+                None => {
+                    stdin_open = false;
+                    continue;
+                }
+            },
+        };
+        match palette::parse(line.as_bytes()) {
+            Input::Command(PaletteCommand::Stop) => match stop_program().await {
+                Ok(()) => eprintln!("[program stopped]"),
+                Err(e) => eprintln!("{:?}", miette::Report::new(e)),
+            },
+            // Forgetting the snapshot makes the next poll upload
+            Input::Command(PaletteCommand::Upload) => last = None,
+            Input::Command(PaletteCommand::Clear) => stdout().write_all(CLEAR_SCREEN).await?,
+            Input::Command(PaletteCommand::Help) => eprintln!("{DEV_HELP}"),
+            Input::Command(PaletteCommand::Timestamps | PaletteCommand::Log(_)) => {
+                eprintln!("[only `venice terminal` shows the program's output]")
+            }
+            Input::Command(PaletteCommand::Unknown(name)) => {
+                eprintln!("[unknown command `:{name}`, type `:help` for the list]")
+            }
+            Input::Program(_) => {}
+        }
+    }
+}
+
+/// `venice build --watch`: builds the project, then again whenever a project source or a path
+/// dependency changes, until Ctrl+C. Only changed modules are recompiled. Build errors are printed
+/// rather than ending the session; with `json`, each build's outcome is printed as a line of JSON,
+/// and with `--message-format json` as a stream of records.
+pub async fn watch_build(options: &BuildOptions, json: bool) -> Result<(), CliError> {
+    let mut last = None;
+    loop {
+        let current = match snapshot().await {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                eprintln!("{:?}", miette::Report::new(e));
+                None
+            }
+        };
+
+        if current.is_some() && current != last {
+            let messages = options.message_format == MessageFormat::Json;
+            if last.is_some() && !json && !messages {
+                println!("\n{}", tr!("dev-rebuilding"));
+            }
+
+            let result = build(options).await;
+            if json {
+                print_build_result(result.as_ref().err());
+            } else if messages {
+                print_build_messages(result.as_ref().err());
+            } else {
+                match result {
+                    Ok(vpt) => {
+                        println!("{}", tr!("dev-built", size = format_size(vpt.len() as u64)))
+                    }
+                    Err(e) => eprintln!("{:?}", miette::Report::new(e)),
+                }
+            }
+            last = current;
+        }
+
+        select! {
+            _ = sleep(POLL_INTERVAL) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    fmt::Display,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use serialport::{SerialPortType, UsbPortInfo};
+use tokio::{task::spawn_blocking, time::sleep};
+use vex_v5_serial::{
+    Connection,
+    protocol::cdc2::system::{SystemFlagsPacket, SystemFlagsReplyPacket},
+    serial::{self, SerialConnection, SerialDevice, SerialError},
+};
+
+use crate::{
+    battery,
+    brain::{ROBOT_NAME_KEY, read_key},
+    config,
+    config::set_config_value,
+    errors::CliError,
+    jobs, steal, tr,
+};
+
+/// USB vendor ID assigned to VEX Robotics.
+pub const VEX_USB_VID: u16 = 0x2888;
+
+static IDENTITY: OnceLock<DeviceIdentity> = OnceLock::new();
+
+/// Which brain a command talked to, so output and logs from rooms full of them can be told apart.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviceIdentity {
+    /// Robot name shown on the brain's home screen
+    pub name: Option<String>,
+    pub serial: Option<String>,
+}
+
+impl DeviceIdentity {
+    async fn query(conn: &mut SerialConnection, device: &SerialDevice) -> Self {
+        Self {
+            name: read_key(conn, ROBOT_NAME_KEY).await.ok().flatten(),
+            serial: usb_serial_number(system_port(device)),
+        }
+    }
+}
+
+impl Display for DeviceIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.name, &self.serial) {
+            (Some(name), Some(serial)) => write!(f, "'{name}' (serial {serial})"),
+            (Some(name), None) => write!(f, "'{name}'"),
+            (None, Some(serial)) => write!(f, "the brain with serial {serial}"),
+            (None, None) => write!(f, "the brain"),
+        }
+    }
+}
+
+/// The device this command first connected to, once it has.
+pub fn identity() -> Option<&'static DeviceIdentity> {
+    IDENTITY.get()
+}
+
+/// How hard to try when enumerating and opening a device.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectStrategy {
+    /// Time to wait before re-enumerating when no devices were found
+    pub settle_time: Duration,
+    /// Number of times to try opening a port that is held by another program
+    pub attempts: u32,
+    pub retry_delay: Duration,
+    pub timeout: Duration,
+}
+
+impl ConnectStrategy {
+    /// Windows takes noticeably longer to expose COM ports after a brain is plugged in or
+    /// rebooted, and VEXcode holds ports open for a few seconds after it loses focus.
+    pub const fn for_platform() -> Self {
+        if cfg!(windows) {
+            Self {
+                settle_time: Duration::from_millis(1500),
+                attempts: 5,
+                retry_delay: Duration::from_secs(1),
+                timeout: Duration::from_secs(8),
+            }
+        } else {
+            Self {
+                settle_time: Duration::from_millis(500),
+                attempts: 2,
+                retry_delay: Duration::from_millis(500),
+                timeout: Duration::from_secs(5),
+            }
+        }
+    }
+}
+
+/// Human-readable description of a device, e.g. `V5 Brain (COM3, COM4)`.
+pub struct DeviceName<'a>(pub &'a SerialDevice);
+
+impl Display for DeviceName<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            SerialDevice::Brain {
+                system_port,
+                user_port,
+            } => write!(f, "V5 Brain (system: {system_port}, user: {user_port})"),
+            SerialDevice::Controller { system_port } => {
+                write!(f, "V5 Controller (system: {system_port})")
+            }
+            SerialDevice::Unknown { system_port } => {
+                write!(f, "unknown VEX device (system: {system_port})")
+            }
+        }
+    }
+}
+
+pub fn system_port(device: &SerialDevice) -> &str {
+    match device {
+        SerialDevice::Brain { system_port, .. }
+        | SerialDevice::Controller { system_port }
+        | SerialDevice::Unknown { system_port } => system_port,
+    }
+}
+
+/// USB serial number of the device a port belongs to. Unlike port names, this survives replugs
+/// and macOS renaming `/dev/cu.usbmodem*` ports after sleep.
+pub fn usb_serial_number(port: &str) -> Option<String> {
+    serialport::available_ports()
+        .ok()?
+        .into_iter()
+        .find(|info| info.port_name == port)
+        .and_then(|info| match info.port_type {
+            SerialPortType::UsbPort(usb) => usb.serial_number,
+            _ => None,
+        })
+}
+
+/// Picks the configured default device if it's connected, otherwise the first one found.
+fn select_device(devices: Vec<SerialDevice>) -> SerialDevice {
+    if let Some(serial) = &config().default_device
+        && let Some(index) = devices
+            .iter()
+            .position(|d| usb_serial_number(system_port(d)).as_ref() == Some(serial))
+    {
+        return devices.into_iter().nth(index).unwrap();
+    }
+
+    devices.into_iter().next().unwrap()
+}
+
+/// Enumerates devices, giving the OS one chance to finish exposing ports if none were found.
+pub async fn find_devices(strategy: &ConnectStrategy) -> Result<Vec<SerialDevice>, CliError> {
+    let devices = serial::find_devices()?;
+    if !devices.is_empty() {
+        return Ok(devices);
+    }
+
+    sleep(strategy.settle_time).await;
+    let devices = serial::find_devices()?;
+    if devices.is_empty() {
+        return Err(no_device_error());
+    }
+
+    Ok(devices)
+}
+
+/// Waits for any VEX device to be plugged in, e.g. a CI runner's test brain that's still booting.
+pub async fn wait_for_device(timeout: Duration) -> Result<(), CliError> {
+    let start = Instant::now();
+    loop {
+        if !serial::find_devices()?.is_empty() {
+            return Ok(());
+        }
+        if start.elapsed() >= timeout {
+            return Err(no_device_error());
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+pub async fn open_connection() -> Result<SerialConnection, CliError> {
+    Ok(open_device().await?.0)
+}
+
+/// Like [`open_connection`], but also returns which device was picked.
+pub async fn open_device() -> Result<(SerialConnection, SerialDevice), CliError> {
+    let strategy = ConnectStrategy::for_platform();
+    let devices = find_devices(&strategy).await?;
+    let device = select_device(devices);
+    jobs::wait_turn(system_port(&device)).await?;
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let result = {
+            let device = device.clone();
+            spawn_blocking(move || device.connect(strategy.timeout))
+                .await
+                .unwrap()
+        };
+
+        match result {
+            Ok(mut conn) => {
+                battery::record(&mut conn, &device).await;
+                if IDENTITY.get().is_none() {
+                    let _ = IDENTITY.set(DeviceIdentity::query(&mut conn, &device).await);
+                }
+                return Ok((conn, device));
+            }
+            // Linux reports missing udev permissions as EACCES; retrying won't help
+            Err(err) if cfg!(target_os = "linux") && is_permission_denied(&err) => {
+                return Err(CliError::PortPermissionDenied(
+                    system_port(&device).to_string(),
+                ));
+            }
+            Err(err) if is_port_busy(&err) => {
+                let port = system_port(&device).to_string();
+                if attempt < strategy.attempts {
+                    eprintln!(
+                        "{}",
+                        tr!(
+                            "device-port-busy-retry",
+                            port = port,
+                            attempt = attempt,
+                            attempts = strategy.attempts
+                        )
+                    );
+                } else if steal() {
+                    if attempt == strategy.attempts {
+                        let holder = port_holder(&port);
+                        eprintln!(
+                            "{}",
+                            tr!(
+                                "device-port-busy-waiting",
+                                holder = holder.as_deref().unwrap_or("the other program"),
+                                port = port
+                            )
+                        );
+                    }
+                } else {
+                    let holder = port_holder(&port);
+                    return Err(CliError::PortBusy { port, holder });
+                }
+                sleep(strategy.retry_delay).await;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+fn is_permission_denied(err: &SerialError) -> bool {
+    match err {
+        SerialError::SerialportError(err) => {
+            err.kind() == serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied)
+        }
+        SerialError::IoError(err) => err.kind() == std::io::ErrorKind::PermissionDenied,
+        _ => false,
+    }
+}
+
+/// Waits for a device that dropped off the bus to come back. Ports are re-resolved from scratch
+/// since the OS may have given the device a new name.
+pub async fn reconnect(timeout: Duration) -> Result<SerialConnection, CliError> {
+    let start = Instant::now();
+    loop {
+        match open_connection().await {
+            Ok(conn) => return Ok(conn),
+            Err(_) if start.elapsed() < timeout => sleep(Duration::from_secs(1)).await,
+            Err(_) => return Err(CliError::ReconnectTimeout),
+        }
+    }
+}
+
+/// Sends the cheapest request every device answers, to check it's still there.
+pub async fn ping(conn: &mut SerialConnection) -> Result<(), SerialError> {
+    let reply = conn
+        .handshake::<SystemFlagsReplyPacket>(Duration::from_secs(1), 2, SystemFlagsPacket::new(()))
+        .await?;
+    reply.payload.map_err(SerialError::Nack)?;
+    Ok(())
+}
+
+/// Keeps a long-lived connection from idling out, and notices when the device stops answering
+/// even though its port is still open (a radio link that died, a brain that was turned off
+/// while plugged into a hub).
+pub struct Keepalive {
+    interval: Duration,
+    peer_timeout: Duration,
+    last_heard: Instant,
+}
+
+impl Keepalive {
+    /// Uses the intervals in [terminal] of the venice config. `None` when keepalives are off.
+    pub fn from_config() -> Option<Self> {
+        let terminal = &config().terminal;
+        (terminal.keepalive != 0).then(|| Self {
+            interval: Duration::from_secs(terminal.keepalive),
+            peer_timeout: Duration::from_secs(terminal.peer_timeout.max(terminal.keepalive)),
+            last_heard: Instant::now(),
+        })
+    }
+
+    /// Records that the device said something, so there's no need to ping it for a while.
+    pub fn heard(&mut self) {
+        self.last_heard = Instant::now();
+    }
+
+    /// Pings the device if it's been quiet for the keepalive interval. Fails once it hasn't
+    /// answered for the peer timeout.
+    pub async fn check(&mut self, conn: &mut SerialConnection) -> Result<(), CliError> {
+        if self.last_heard.elapsed() < self.interval {
+            return Ok(());
+        }
+        match ping(conn).await {
+            Ok(()) => self.heard(),
+            Err(_) if self.last_heard.elapsed() >= self.peer_timeout => {
+                return Err(CliError::PeerUnresponsive(
+                    self.last_heard.elapsed().as_secs(),
+                ));
+            }
+            Err(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Whether opening a port failed because some other program (usually VEXcode) holds it.
+/// Windows reports this as access denied.
+fn is_port_busy(err: &SerialError) -> bool {
+    let no_device = matches!(
+        err,
+        SerialError::SerialportError(err) if err.kind() == serialport::ErrorKind::NoDevice
+    );
+    no_device || is_permission_denied(err)
+}
+
+/// The program holding `port` open, e.g. `vexcode (pid 1234)`. Linux exposes every process's open
+/// files in /proc, and macOS has lsof; Windows doesn't let us see other processes' handles. Only
+/// processes we're allowed to inspect are found.
+pub fn port_holder(port: &str) -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let port = std::fs::canonicalize(port).ok()?;
+        for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+            let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+                continue;
+            };
+            if pid == std::process::id() {
+                continue;
+            }
+            let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+                continue;
+            };
+            let holds_port = fds
+                .flatten()
+                .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == port));
+            if holds_port {
+                let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+                return Some(format!("{} (pid {pid})", name.trim()));
+            }
+        }
+        None
+    } else if cfg!(target_os = "macos") {
+        let output = std::process::Command::new("lsof")
+            .args(["-t", "--", port])
+            .output()
+            .ok()?;
+        let pid = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()?
+            .trim()
+            .to_string();
+        let name = std::process::Command::new("ps")
+            .args(["-p", &pid, "-o", "comm="])
+            .output()
+            .ok()?;
+        let name = String::from_utf8_lossy(&name.stdout);
+        // ps prints the executable's full path
+        let name = name.trim().rsplit('/').next().unwrap_or_default();
+        Some(format!("{name} (pid {pid})"))
+    } else {
+        None
+    }
+}
+
+/// Without the VEX driver, Windows binds brains to the generic `usbser` driver, so the ports
+/// exist but don't carry VEX's product strings and never show up in [`serial::find_devices`].
+fn no_device_error() -> CliError {
+    if cfg!(windows) {
+        let driverless = serialport::available_ports()
+            .unwrap_or_default()
+            .into_iter()
+            .any(|port| match port.port_type {
+                SerialPortType::UsbPort(UsbPortInfo {
+                    vid, ref product, ..
+                }) => vid == VEX_USB_VID && !product.as_deref().is_some_and(|p| p.contains("VEX")),
+                _ => false,
+            });
+
+        if driverless {
+            return CliError::MissingVexDriver;
+        }
+    }
+
+    CliError::NoDevice
+}
+
+pub async fn devices(set_default: Option<usize>) -> Result<(), CliError> {
+    let devices = find_devices(&ConnectStrategy::for_platform()).await?;
+
+    if let Some(index) = set_default {
+        let device = devices
+            .get(index)
+            .ok_or(CliError::DeviceIndexOutOfRange(index))?;
+        let serial = usb_serial_number(system_port(device)).ok_or(CliError::NoDevice)?;
+        set_config_value("default-device", toml_edit::value(serial.as_str()))?;
+        println!(
+            "{}",
+            tr!(
+                "device-default-set",
+                device = DeviceName(device).to_string()
+            )
+        );
+        return Ok(());
+    }
+
+    for (index, device) in devices.iter().enumerate() {
+        let serial = usb_serial_number(system_port(device));
+        let default = serial.is_some() && serial == config().default_device;
+        println!(
+            "{index}: {}{}{}",
+            DeviceName(device),
+            serial.map(|s| format!(" [serial {s}]")).unwrap_or_default(),
+            if default { " (default)" } else { "" }
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use serde_json::{Value, json};
+
+use crate::errors::CliError;
+
+const SEVERITY_ERROR: u8 = 1;
+
+/// `venice build --message-format`: how the build reports what it did.
+#[derive(clap::ValueEnum, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Progress and errors for people to read
+    #[default]
+    Human,
+    /// One JSON object per line on stdout, each with a `reason` saying what it reports
+    Json,
+}
+
+/// 1-based line number of an mpy-cross error, from the `File "main.py", line 3` line of a
+/// traceback or a `main.py:3: message` line.
+fn compiler_error_line(stderr: &str) -> Option<usize> {
+    stderr.lines().find_map(|line| {
+        let line = line.trim();
+        let number = match line.strip_prefix("File ") {
+            Some(rest) => rest.rsplit_once(", line ")?.1,
+            None => line.split(':').nth(1)?,
+        };
+        number
+            .split(|c: char| !c.is_ascii_digit())
+            .next()?
+            .parse()
+            .ok()
+    })
+}
+
+/// The span of 1-based `line` in `source`, without its line ending.
+fn line_span(source: &str, line: usize) -> Option<SourceSpan> {
+    let start: usize = source
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    let text = source[start..].lines().next()?;
+    Some((start, text.len()).into())
+}
+
+/// [`CliError::Compiler`] for mpy-cross failing on `file`, whose contents are `source`, with
+/// `stderr`. The error is the last line mpy-cross printed, and points at the line it names.
+pub fn compiler_error(file: &Path, source: Option<String>, stderr: String) -> CliError {
+    let message = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .map_or_else(|| stderr.trim().to_string(), |line| line.trim().to_string());
+    let span = compiler_error_line(&stderr)
+        .zip(source.as_deref())
+        .and_then(|(line, source)| line_span(source, line));
+    let source_code = span
+        .and(source)
+        .map(|source| NamedSource::new(file.display().to_string(), source));
+    CliError::Compiler {
+        file: file.to_path_buf(),
+        stderr,
+        message,
+        source_code,
+        span,
+    }
+}
+
+fn editor_diagnostic(
+    error: &CliError,
+    file: Option<&Path>,
+    range: Option<Value>,
+    message: String,
+) -> Value {
+    json!({
+        "file": file,
+        "range": range,
+        "severity": SEVERITY_ERROR,
+        "code": error.code().map(|code| code.to_string()),
+        "source": "venice",
+        "message": message,
+    })
+}
+
+/// Converts a build error into LSP-style diagnostics (0-based lines, UTF-16 columns). Errors
+/// that don't point into a file get a `null` file and range.
+pub fn editor_diagnostics(error: &CliError) -> Vec<Value> {
+    match error {
+        CliError::UnresolvedImports { imports, .. } => {
+            imports.iter().flat_map(editor_diagnostics).collect()
+        }
+        CliError::CompileErrors { errors, .. } => {
+            errors.iter().flat_map(editor_diagnostics).collect()
+        }
+        CliError::UnresolvedImport {
+            file,
+            line,
+            columns,
+            ..
+        } => {
+            let range = json!({
+                "start": { "line": line - 1, "character": columns.start },
+                "end": { "line": line - 1, "character": columns.end },
+            });
+            vec![editor_diagnostic(
+                error,
+                Some(file),
+                Some(range),
+                error.to_string(),
+            )]
+        }
+        CliError::Compiler {
+            file,
+            stderr,
+            message,
+            ..
+        } => {
+            // mpy-cross doesn't report columns, so the whole line is marked
+            let range = compiler_error_line(stderr).map(|line| {
+                json!({
+                    "start": { "line": line.saturating_sub(1), "character": 0 },
+                    "end": { "line": line, "character": 0 },
+                })
+            });
+            vec![editor_diagnostic(error, Some(file), range, message.clone())]
+        }
+        _ => vec![editor_diagnostic(error, None, None, error.to_string())],
+    }
+}
+
+/// `venice build --json`: prints the build's outcome as a single JSON object on stdout.
+pub fn print_build_result(error: Option<&CliError>) {
+    let diagnostics = error.map(editor_diagnostics).unwrap_or_default();
+    println!(
+        "{}",
+        json!({ "success": error.is_none(), "diagnostics": diagnostics })
+    );
+}
+
+/// Prints one `--message-format json` record: `fields` with `reason` added.
+pub fn print_message(reason: &str, mut fields: Value) {
+    fields["reason"] = json!(reason);
+    println!("{fields}");
+}
+
+/// `venice build --message-format json`: prints a `diagnostic` record for each of the build's
+/// diagnostics, then a `build-finished` one.
+pub fn print_build_messages(error: Option<&CliError>) {
+    for diagnostic in error.map(editor_diagnostics).unwrap_or_default() {
+        print_message("diagnostic", diagnostic);
+    }
+    print_message("build-finished", json!({ "success": error.is_none() }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_line_in_compiler_traceback() {
+        let stderr = concat!(
+            "Traceback (most recent call last):\n",
+            "  File \"drive/pid.py\", line 12\n",
+            "SyntaxError: invalid syntax\n",
+        );
+        assert_eq!(compiler_error_line(stderr), Some(12));
+        assert_eq!(compiler_error_line("main.py:4: SyntaxError\n"), Some(4));
+        assert_eq!(compiler_error_line("MemoryError\n"), None);
+    }
+
+    #[test]
+    fn spans_whole_line() {
+        let source = "import venice\r\nx = (\nprint(x)";
+        assert_eq!(line_span(source, 2), Some((15, 5).into()));
+        assert_eq!(line_span(source, 3), Some((21, 8).into()));
+        assert_eq!(line_span(source, 4), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    io::Write,
+    process::{Command, ExitStatus, Stdio},
+};
+
+use inquire::Confirm;
+
+use crate::{
+    device::{ConnectStrategy, DeviceName, find_devices, open_connection},
+    errors::CliError,
+    screen_reader,
+    toolchain::{MPY_CROSS_VERSION, find},
+    uv_path,
+};
+
+pub const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/99-venice-vex.rules";
+pub const UDEV_RULE: &str =
+    "SUBSYSTEMS==\"usb\", ATTRS{idVendor}==\"2888\", MODE=\"0666\", TAG+=\"uaccess\"\n";
+
+fn report(ok: bool, message: impl AsRef<str>) {
+    let mark = match (ok, screen_reader()) {
+        (true, false) => "✓",
+        (false, false) => "✗",
+        (true, true) => "ok:",
+        (false, true) => "problem:",
+    };
+    println!("{mark} {}", message.as_ref());
+}
+
+/// First line of `program arg`'s output, e.g. a `--version` banner.
+pub fn tool_version(program: &str, arg: &str) -> Option<String> {
+    let output = Command::new(program).arg(arg).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(|line| line.trim().to_string())
+}
+
+/// The outcome of one environment check.
+pub struct Check {
+    pub ok: bool,
+    pub message: String,
+    /// What to do about a failed check
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn new(ok: bool, message: impl Into<String>) -> Self {
+        Self {
+            ok,
+            message: message.into(),
+            hint: None,
+        }
+    }
+}
+
+/// Runs the environment checks `venice doctor` reports on.
+pub async fn run_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let mpy_cross = find().and_then(|path| tool_version(&path.to_string_lossy(), "--version"));
+    checks.push(match mpy_cross {
+        Some(version) => Check::new(true, format!("mpy-cross: {version}")),
+        None => Check {
+            hint: Some(format!(
+                "venice downloads mpy-cross {MPY_CROSS_VERSION} on the next build"
+            )),
+            ..Check::new(true, "mpy-cross: not installed")
+        },
+    });
+
+    let uv_version = uv_path().ok().and_then(|uv| tool_version(uv, "--version"));
+    checks.push(match uv_version {
+        Some(version) => Check::new(true, format!("uv: {version}")),
+        None => Check::new(false, "uv: not found"),
+    });
+
+    let devices = match find_devices(&ConnectStrategy::for_platform()).await {
+        Ok(devices) => devices,
+        Err(e) => {
+            checks.push(Check::new(false, format!("devices: {e}")));
+            return checks;
+        }
+    };
+    for device in devices.iter() {
+        checks.push(Check::new(true, format!("found {}", DeviceName(device))));
+    }
+
+    checks.push(match open_connection().await {
+        Ok(_) => Check::new(true, "opened a connection to the first device"),
+        Err(CliError::PortPermissionDenied(port)) => Check {
+            hint: Some("run `venice doctor --fix-permissions` to install a udev rule".to_string()),
+            ..Check::new(false, format!("no permission to open `{port}`"))
+        },
+        Err(e) => Check::new(false, format!("couldn't connect: {e}")),
+    });
+
+    checks
+}
+
+pub async fn doctor(fix_permissions: bool) -> Result<(), CliError> {
+    if fix_permissions {
+        return fix_udev_permissions();
+    }
+
+    println!("venice-cli {}", env!("CARGO_PKG_VERSION"));
+    for check in run_checks().await {
+        report(check.ok, &check.message);
+        if let Some(hint) = &check.hint {
+            println!("  {hint}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Installs a udev rule granting the active user access to VEX devices, falling back to printing
+/// the rule if the user declines or `sudo` isn't available.
+fn fix_udev_permissions() -> Result<(), CliError> {
+    if !cfg!(target_os = "linux") {
+        println!("Device permissions only need fixing on Linux.");
+        return Ok(());
+    }
+
+    let install = Confirm::new(&format!(
+        "Install a udev rule to {UDEV_RULE_PATH} using sudo?"
+    ))
+    .with_default(true)
+    .prompt()
+    .unwrap_or(false);
+
+    if install && install_udev_rule().is_ok() {
+        println!("✓ Installed {UDEV_RULE_PATH}. Replug the brain to apply it.");
+        return Ok(());
+    }
+
+    println!("\nAdd the following line to {UDEV_RULE_PATH}:\n\n  {UDEV_RULE}");
+    println!(
+        "Then run `sudo udevadm control --reload-rules && sudo udevadm trigger` and replug the brain."
+    );
+    Ok(())
+}
+
+fn install_udev_rule() -> Result<(), CliError> {
+    // Piped straight to a root `tee`, so there's no file in a shared directory another user could
+    // swap out while sudo waits for the password
+    let mut tee = Command::new("sudo")
+        .args(["tee", UDEV_RULE_PATH])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    tee.stdin.take().unwrap().write_all(UDEV_RULE.as_bytes())?;
+    check_sudo(&["tee", UDEV_RULE_PATH], tee.wait()?)?;
+
+    let commands: [&[&str]; 3] = [
+        &["chmod", "644", UDEV_RULE_PATH],
+        &["udevadm", "control", "--reload-rules"],
+        &["udevadm", "trigger"],
+    ];
+    for args in commands {
+        check_sudo(args, Command::new("sudo").args(args).status()?)?;
+    }
+    Ok(())
+}
+
+fn check_sudo(args: &[&str], status: ExitStatus) -> Result<(), CliError> {
+    if status.success() {
+        return Ok(());
+    }
+    Err(CliError::Io(std::io::Error::other(format!(
+        "`sudo {}` failed",
+        args.join(" ")
+    ))))
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::{errors::CliError, secrets::get_secret};
+
+/// Name of the generated module exposing [tool.venice.env] to the program.
+pub const ENV_MODULE: &str = "venice_env";
+
+/// A value in [tool.venice.env]: either a literal or a reference to a secret set with
+/// `venice secret set`, resolved at build time.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum EnvValue {
+    Plain(String),
+    Secret { secret: String },
+}
+
+impl EnvValue {
+    fn resolve(&self) -> Result<String, CliError> {
+        match self {
+            Self::Plain(value) => Ok(value.clone()),
+            Self::Secret { secret } => get_secret(secret),
+        }
+    }
+}
+
+/// Quotes a string as a Python string literal.
+pub fn python_str_literal(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Source for the [`ENV_MODULE`] module, or `None` if the project has no env vars.
+pub fn env_module_source(env: &BTreeMap<String, EnvValue>) -> Result<Option<String>, CliError> {
+    if env.is_empty() {
+        return Ok(None);
+    }
+
+    let mut source = String::from("# Generated by venice-cli from [tool.venice.env]\n");
+    for (name, value) in env.iter() {
+        if !is_identifier(name) {
+            return Err(CliError::InvalidEnvName(name.clone()));
+        }
+        source.push_str(&format!(
+            "{name} = {}\n",
+            python_str_literal(&value.resolve()?)
+        ));
+    }
+
+    Ok(Some(source))
+}
+
+/// Whether `name` can be a Python name: a variable, or a module in an import.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
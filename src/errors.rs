@@ -1,6 +1,6 @@
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf};
 
-use miette::Diagnostic;
+use miette::{Diagnostic, NamedSource, SourceSpan};
 use thiserror::Error;
 
 use crate::manifest::MANIFEST_NAME;
@@ -8,56 +8,664 @@ use crate::manifest::MANIFEST_NAME;
 #[derive(Debug, Error, Diagnostic)]
 pub enum CliError {
     #[error(transparent)]
+    #[diagnostic(code(VE0001))]
     Io(#[from] std::io::Error),
 
     #[error("slot must be between 1 and 8")]
+    #[diagnostic(code(VE0002))]
     SlotOutOfRange,
 
     #[error(transparent)]
+    #[diagnostic(code(VE0003))]
     Serial(#[from] vex_v5_serial::serial::SerialError),
 
     #[error("no devices found")]
+    #[diagnostic(code(VE0004))]
     NoDevice,
 
+    #[error("a VEX device is connected but the VEX USB driver isn't installed")]
+    #[diagnostic(
+        code(VE0005),
+        help("install VEXcode, or the standalone VEX V5 driver, then replug the brain")
+    )]
+    MissingVexDriver,
+
+    #[error("`{port}` is in use by {}", .holder.as_deref().unwrap_or("another program"))]
+    #[diagnostic(
+        code(VE0006),
+        help(
+            "close VEXcode or any other program connected to the brain, or pass --steal to wait until it lets go"
+        )
+    )]
+    PortBusy {
+        port: String,
+        /// The program holding the port, e.g. `vexcode (pid 1234)`, if the OS says
+        holder: Option<String>,
+    },
+
+    #[error("permission denied opening `{0}`")]
+    #[diagnostic(
+        code(VE0007),
+        help("run `venice doctor --fix-permissions` to install a udev rule for VEX devices")
+    )]
+    PortPermissionDenied(String),
+
+    #[error("no device at index {0} - run `venice devices` to list connected devices")]
+    #[diagnostic(code(VE0008))]
+    DeviceIndexOutOfRange(usize),
+
+    #[error("lost connection to the brain and it didn't come back")]
+    #[diagnostic(code(VE0009))]
+    ReconnectTimeout,
+
+    #[error("the controller isn't linked to a brain")]
+    #[diagnostic(code(VE0010))]
+    NoRadioLink,
+
     #[error("radio channel disconnect timeout")]
+    #[diagnostic(code(VE0011))]
     RadioChannelDisconnectTimeout,
 
     #[error("radio channel reconnect timeout")]
+    #[diagnostic(code(VE0012))]
     RadioChannelReconnectTimeout,
 
     #[error("invalid version: {0}")]
+    #[diagnostic(code(VE0013))]
     InvalidVersion(#[from] semver::Error),
 
     #[error("couldn't parse {MANIFEST_NAME}")]
+    #[diagnostic(code(VE0014))]
     Manifest(#[from] toml::de::Error),
 
     #[error("couldn't parse {MANIFEST_NAME}: {0}")]
+    #[diagnostic(code(VE0015))]
     ManifestEdit(String),
 
-    #[error("couldn't build `{file}` with `mpy-cross`: {stderr}")]
-    Compiler { file: PathBuf, stderr: String },
+    #[error("couldn't build `{}` with `mpy-cross`: {message}", .file.display())]
+    #[diagnostic(code(VE0016))]
+    Compiler {
+        file: PathBuf,
+        stderr: String,
+        /// The error in `stderr`, without the traceback before it
+        message: String,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+        #[label("mpy-cross stopped on this line")]
+        span: Option<SourceSpan>,
+    },
 
     #[error("couldn't find {MANIFEST_NAME} in current directory or any parent directories")]
+    #[diagnostic(code(VE0017))]
     NoManifest,
 
     #[error("no project name found - set [project].name or [tool.venice].name in {MANIFEST_NAME}")]
+    #[diagnostic(code(VE0018))]
     NoProjectName,
 
     #[error("no entrypoint found in `{0}` - expected main.py")]
+    #[diagnostic(
+        code(VE0019),
+        help(
+            "create main.py at the root of the project; the brain runs it when the program starts"
+        )
+    )]
     NoEntrypoint(PathBuf),
 
-    #[error("found top-level __init__.py in source root. the device root is not a package, so this file will never execute; please move initialization code to main.py")]
+    #[error(
+        "found top-level __init__.py in source root. the device root is not a package, so this file will never execute; please move initialization code to main.py"
+    )]
+    #[diagnostic(code(VE0020))]
     TopLevelInit,
 
+    #[error("runtime v{0} doesn't publish a description of its built-in modules")]
+    #[diagnostic(code(VE0021))]
+    NoRuntimeDescription(semver::Version),
+
     #[error("no runtime source provided - ensure the 'venice' package is installed")]
+    #[diagnostic(code(VE0022))]
     NoRuntimeSource,
 
-    #[error("uv not found - ensure the 'uv' package is installed in the same environment as venice-cli")]
+    #[error(
+        "uv not found - ensure the 'uv' package is installed in the same environment as venice-cli"
+    )]
+    #[diagnostic(code(VE0023))]
     NoUv,
 
+    #[error("data file name `{0}` is longer than the brain's 23 character limit")]
+    #[diagnostic(code(VE0024))]
+    DataFileName(String),
+
+    #[error("`{0}` isn't a valid file name on the brain (at most 23 characters)")]
+    #[diagnostic(code(VE0025))]
+    BrainFileName(String),
+
+    #[error("couldn't read or write brain-state.toml: {0}")]
+    #[diagnostic(code(VE0026))]
+    StateFile(String),
+
+    #[error("invalid program table: {0}")]
+    #[diagnostic(code(VE0027))]
+    InvalidVpt(String),
+
+    #[error("signing key at `{0}` is corrupt")]
+    #[diagnostic(code(VE0028))]
+    InvalidSigningKey(PathBuf),
+
+    #[error("program table signature doesn't match its contents")]
+    #[diagnostic(code(VE0029))]
+    BadSignature,
+
+    #[error("program table isn't signed, but [signing].trusted-keys is set")]
+    #[diagnostic(code(VE0030))]
+    Unsigned,
+
+    #[error("program table was signed by untrusted key {0}")]
+    #[diagnostic(code(VE0031))]
+    UntrustedSigner(String),
+
+    #[error("secrets key at `{0}` is corrupt")]
+    #[diagnostic(code(VE0032))]
+    InvalidSecretsKey(PathBuf),
+
+    #[error("couldn't decrypt secret `{0}` - it may have been set by another user")]
+    #[diagnostic(code(VE0033))]
+    UndecryptableSecret(String),
+
+    #[error("secret `{0}` isn't set")]
+    #[diagnostic(code(VE0034), help("set it with `venice secret set {0}`"))]
+    MissingSecret(String),
+
+    #[error(transparent)]
+    #[diagnostic(code(VE0035))]
+    Keychain(#[from] keyring::Error),
+
+    #[error("`{0}` in [tool.venice.env] isn't a valid Python identifier")]
+    #[diagnostic(code(VE0036))]
+    InvalidEnvName(String),
+
+    #[error("no co-processor configured - add a [tool.venice.copro] section to {MANIFEST_NAME}")]
+    #[diagnostic(code(VE0037))]
+    NoCopro,
+
+    #[error("co-processor push exited with status {0}")]
+    #[diagnostic(code(VE0038))]
+    CoproFailed(i32),
+
+    #[error("`{module}` isn't in the program table or built into the runtime ({}:{line})", file.display())]
+    #[diagnostic(code(VE0039))]
+    UnresolvedImport {
+        module: String,
+        file: PathBuf,
+        line: usize,
+        /// 0-based UTF-16 columns of the module name on `line`, for editors
+        columns: Range<usize>,
+        #[source_code]
+        source_code: NamedSource<String>,
+        #[label("not a project module, dependency or runtime built-in")]
+        span: SourceSpan,
+    },
+
+    #[error("{count} import(s) won't resolve on the brain")]
+    #[diagnostic(
+        code(VE0040),
+        help(
+            "add the missing modules to the project or [tool.venice.dependencies], or list them in [tool.venice].ignore-imports"
+        )
+    )]
+    UnresolvedImports {
+        count: usize,
+        #[related]
+        imports: Vec<CliError>,
+    },
+
+    #[error("no module named `{0}` in this project")]
+    #[diagnostic(code(VE0041))]
+    UnknownModule(String),
+
+    #[error("module `{0}` already exists")]
+    #[diagnostic(code(VE0042))]
+    ModuleExists(String),
+
+    #[error("`{0}` isn't a valid module name")]
+    #[diagnostic(code(VE0043))]
+    InvalidModuleName(String),
+
     #[error("directory `{0}` already exists")]
+    #[diagnostic(code(VE0044))]
     ProjectExists(PathBuf),
 
     #[error("uv exited with status {status}:\n{stderr}")]
+    #[diagnostic(code(VE0045))]
     UvFailed { status: i32, stderr: String },
+
+    #[error("couldn't parse `{path}`")]
+    #[diagnostic(code(VE0046))]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("couldn't edit the venice config: {0}")]
+    #[diagnostic(code(VE0047))]
+    ConfigEdit(String),
+
+    #[error("couldn't determine the venice config directory")]
+    #[diagnostic(code(VE0048))]
+    NoConfigDir,
+
+    #[error(transparent)]
+    #[diagnostic(code(VE0049))]
+    Http(#[from] ureq::Error),
+
+    #[error(transparent)]
+    #[diagnostic(code(VE0050))]
+    Json(#[from] serde_json::Error),
+
+    #[error("GitHub returned status {status} for {url}")]
+    #[diagnostic(code(VE0051))]
+    GithubStatus { status: u16, url: String },
+
+    #[error("no package registry configured")]
+    #[diagnostic(code(VE0052), help("set [registry].url in the venice config"))]
+    NoRegistry,
+
+    #[error("publishing requires a registry token")]
+    #[diagnostic(
+        code(VE0053),
+        help("set [registry].token in the venice config or VENICE_REGISTRY_TOKEN")
+    )]
+    NoRegistryToken,
+
+    #[error("registry returned status {status} for {url}")]
+    #[diagnostic(code(VE0054))]
+    RegistryStatus { status: u16, url: String },
+
+    #[error("package `{0}` isn't in the registry")]
+    #[diagnostic(code(VE0055))]
+    PackageNotFound(String),
+
+    #[error("no published version of `{name}` matches `{req}`")]
+    #[diagnostic(code(VE0056))]
+    NoMatchingVersion {
+        name: String,
+        req: semver::VersionReq,
+    },
+
+    #[error("checksum of the downloaded `{0}` package doesn't match the registry index")]
+    #[diagnostic(code(VE0057))]
+    ChecksumMismatch(String),
+
+    #[error(
+        "no `{0}` package directory to publish - libraries keep their modules in a package named after the project"
+    )]
+    #[diagnostic(code(VE0058))]
+    NoLibraryPackage(String),
+
+    #[error("no version to publish - set [project].version in {MANIFEST_NAME}")]
+    #[diagnostic(code(VE0059))]
+    NoProjectVersion,
+
+    #[error("network access is disabled by --frozen")]
+    #[diagnostic(code(VE0060))]
+    FrozenNetwork,
+
+    #[error(
+        "`{0}` isn't in venice.lock (or its locked version no longer matches), and --frozen forbids resolving it"
+    )]
+    #[diagnostic(
+        code(VE0061),
+        help("run `venice vendor` with network access and commit venice.lock and vendor/")
+    )]
+    FrozenUnlocked(String),
+
+    #[error("`{0}` isn't vendored, and --frozen forbids downloading it")]
+    #[diagnostic(
+        code(VE0062),
+        help("run `venice vendor` with network access and commit vendor/")
+    )]
+    FrozenNotVendored(String),
+
+    #[error("couldn't read or write venice.lock: {0}")]
+    #[diagnostic(code(VE0063))]
+    Lockfile(String),
+
+    #[error(
+        "GitHub API rate limit exceeded (resets at unix time {reset}) - set [github].token in the venice config to raise it"
+    )]
+    #[diagnostic(code(VE0064))]
+    GithubRateLimited { reset: u64 },
+
+    #[error("`{0}` isn't a venice error code")]
+    #[diagnostic(code(VE0065))]
+    UnknownErrorCode(String),
+
+    #[error("slot {slot} on the connected brain holds `{program}`, which uploading will replace")]
+    #[diagnostic(code(VE0066))]
+    SlotTaken { slot: u8, program: String },
+
+    #[error("no artifact metadata at {} - build with `venice build --artifact-metadata`", .0.display())]
+    #[diagnostic(code(VE0067))]
+    NoArtifactMetadata(PathBuf),
+
+    #[error("the program table doesn't match its metadata: {0}")]
+    #[diagnostic(code(VE0068))]
+    ArtifactMismatch(String),
+
+    #[error(
+        "the program table was built for runtime {built}, but runtime {installed} is installed"
+    )]
+    #[diagnostic(
+        code(VE0069),
+        help("install venice {built} in this environment, or rebuild the table")
+    )]
+    ArtifactRuntimeMismatch {
+        built: semver::Version,
+        installed: semver::Version,
+    },
+
+    #[error("after-upload command `{command}` failed with status {status}")]
+    #[diagnostic(code(VE0070))]
+    HookFailed { command: String, status: i32 },
+
+    #[error("{0} device(s) run older firmware than others of the same type")]
+    #[diagnostic(
+        code(VE0071),
+        help("unplug and replug them, or restart the brain, so VEXos updates them")
+    )]
+    OutdatedFirmware(usize),
+
+    #[error("the venice state store is version {0}, newer than this CLI understands")]
+    #[diagnostic(
+        code(VE0072),
+        help(
+            "update venice, or run `venice restore-state` to go back to a snapshot from an older version"
+        )
+    )]
+    StoreTooNew(u32),
+
+    #[error("no dynamic completions for {0}")]
+    #[diagnostic(code(VE0073), help("run `venice completions {0}` without --dynamic"))]
+    UnsupportedShell(String),
+
+    #[error(
+        "`{module}` would be compiled under the host path `{path}`, which would show up on the brain"
+    )]
+    #[diagnostic(
+        code(VE0074),
+        help("modules are compiled under their names relative to the project")
+    )]
+    HostPathInArtifact { module: String, path: String },
+
+    #[error("the brain has no calibration file")]
+    #[diagnostic(
+        code(VE0075),
+        help("write calibration.toml in the project and run `venice calib push`")
+    )]
+    NoCalibration,
+
+    #[error("the project has no calibration.toml")]
+    #[diagnostic(
+        code(VE0076),
+        help("run `venice calib pull` to save the brain's calibration, or write one by hand")
+    )]
+    NoCalibrationFile,
+
+    #[error("the brain's calibration file can't be converted to TOML: {0}")]
+    #[diagnostic(code(VE0077))]
+    CalibrationFormat(String),
+
+    #[error("the project hasn't been built")]
+    #[diagnostic(code(VE0078), help("run `venice build` first"))]
+    NotBuilt,
+
+    #[error("no autonomous routine named `{name}`; the program has: {available}")]
+    #[diagnostic(
+        code(VE0079),
+        help("list routines in [tool.venice].autons and rebuild")
+    )]
+    UnknownAuton { name: String, available: String },
+
+    #[error("the device stopped answering {0} seconds ago")]
+    #[diagnostic(
+        code(VE0080),
+        help("check the device is still on, then run the command again")
+    )]
+    PeerUnresponsive(u64),
+
+    #[error("timed out after {seconds} seconds waiting for {waiting_for}")]
+    #[diagnostic(code(VE0081), help("raise --timeout if the program needs longer"))]
+    CiTimeout { seconds: u64, waiting_for: String },
+
+    #[error("the program printed `{pattern}`: {line}")]
+    #[diagnostic(code(VE0082))]
+    CiFailureOutput { pattern: String, line: String },
+
+    #[error("interrupted")]
+    #[diagnostic(code(VE0083))]
+    Interrupted,
+
+    #[error("{0} isn't a VEXcode project")]
+    #[diagnostic(
+        code(VE0084),
+        help("pass a .v5python file, or a folder made by the VEXcode extension for VS Code")
+    )]
+    NotVexcodeProject(PathBuf),
+
+    #[error("{0} is already a venice project")]
+    #[diagnostic(code(VE0085))]
+    AlreadyVeniceProject(PathBuf),
+
+    #[error("there's no example named `{name}`; available: {available}")]
+    #[diagnostic(
+        code(VE0086),
+        help("run `venice example list` to see what each one shows")
+    )]
+    UnknownExample { name: String, available: String },
+
+    #[error("runtime v{0} has no published binary")]
+    #[diagnostic(
+        code(VE0087),
+        help("`venice runtime list --available` shows the published versions")
+    )]
+    NoRuntimeBinary(semver::Version),
+
+    #[error("--good v{good} must be an older release than --bad v{bad}")]
+    #[diagnostic(code(VE0088))]
+    BisectRange {
+        good: semver::Version,
+        bad: semver::Version,
+    },
+
+    #[error("there's no snapshot of the config and state from venice v{0}")]
+    #[diagnostic(code(VE0089), help("run `venice restore-state` to list the snapshots"))]
+    NoSnapshot(semver::Version),
+
+    #[error("there's no slot preset named `{name}`; available: {available}")]
+    #[diagnostic(
+        code(VE0090),
+        help("presets are the [presets.<name>] tables in slots.toml")
+    )]
+    UnknownPreset { name: String, available: String },
+
+    #[error("slot preset `{preset}` has an entry for slot `{slot}`; slots are 1 to 8")]
+    #[diagnostic(code(VE0091))]
+    PresetSlot { preset: String, slot: String },
+
+    #[error("{count} file(s) couldn't be built with `mpy-cross`")]
+    #[diagnostic(code(VE0092))]
+    CompileErrors {
+        count: usize,
+        #[related]
+        errors: Vec<CliError>,
+    },
+
+    #[error("mpy-cross was installed to `{}` but doesn't run", .0.display())]
+    #[diagnostic(
+        code(VE0093),
+        help(
+            "install it yourself with `uv pip install mpy-cross` in the environment venice-cli is installed in"
+        )
+    )]
+    MpyCrossMissing(PathBuf),
+
+    #[error("can't patch: {0}")]
+    #[diagnostic(code(VE0094), help("run `venice upload` to upload the whole program"))]
+    NoPatch(String),
+
+    #[error("the project pins mpy-cross {pinned}, but the one installed reports `{found}`")]
+    #[diagnostic(
+        code(VE0095),
+        help(
+            "check that [tool.venice].mpy-cross-version is a published release of the mpy-cross package"
+        )
+    )]
+    MpyCrossVersion {
+        pinned: semver::Version,
+        found: String,
+    },
+
+    #[error("usage metrics can only be submitted when sharing is turned on")]
+    #[diagnostic(
+        code(VE0096),
+        help("set `collect = \"share\"` and `submit-url` under [usage] in the venice config")
+    )]
+    UsageNotShared,
+
+    #[error("git failed on `{url}`:\n{stderr}")]
+    #[diagnostic(
+        code(VE0097),
+        help("check that git is installed and the repository and its rev, tag or branch exist")
+    )]
+    GitFailed { url: String, stderr: String },
+
+    #[error("`{name}` from PyPI has a compiled extension, `{}`, which can't run on the brain", .extension.display())]
+    #[diagnostic(
+        code(VE0098),
+        help(
+            "only pure-Python packages can be added from PyPI; look for a pure-Python alternative"
+        )
+    )]
+    NotPurePython { name: String, extension: PathBuf },
+
+    #[error("`{0}` isn't in [tool.venice.dependencies] or [tool.venice.dev-dependencies]")]
+    #[diagnostic(code(VE0099))]
+    NotADependency(String),
+
+    #[error("module `{module}` is defined twice, by `{}` and `{}`", .first.display(), .second.display())]
+    #[diagnostic(
+        code(VE0100),
+        help("rename one of them, or leave one out with [tool.venice.build].exclude")
+    )]
+    ModuleCollision {
+        module: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+
+    #[error("`{}` isn't MicroPython bytecode", .0.display())]
+    #[diagnostic(
+        code(VE0101),
+        help("precompiled modules must be .mpy files from mpy-cross")
+    )]
+    NotBytecode(PathBuf),
+
+    #[error("`{}` is bytecode format {found}, but the runtime loads format {expected}", .file.display())]
+    #[diagnostic(
+        code(VE0102),
+        help("compile it again with the project's mpy-cross version, or ship its source instead")
+    )]
+    BytecodeVersion {
+        file: PathBuf,
+        found: String,
+        expected: String,
+    },
+
+    #[error("`{}` has native code for {found}, but the brain runs {expected}", .file.display())]
+    #[diagnostic(
+        code(VE0103),
+        help("compile it again with `mpy-cross -march={expected}`")
+    )]
+    NativeArch {
+        file: PathBuf,
+        found: String,
+        expected: String,
+    },
+
+    #[error("`{}` has no code, so the program would do nothing when run", .0.display())]
+    #[diagnostic(
+        code(VE0104),
+        help("start the robot's code from main.py, e.g. by importing and calling it")
+    )]
+    EmptyEntrypoint(PathBuf),
+
+    #[error(
+        "`--package` picks a workspace member, but there's no [tool.venice.workspace] here or above"
+    )]
+    #[diagnostic(
+        code(VE0105),
+        help("run it from inside the workspace, or leave out `-p`")
+    )]
+    NotAWorkspace,
+
+    #[error("the workspace has no member called `{name}`")]
+    #[diagnostic(code(VE0106), help("its members are: {members}"))]
+    UnknownMember { name: String, members: String },
+
+    #[error("this is a workspace's root, not one of its projects")]
+    #[diagnostic(
+        code(VE0107),
+        help(
+            "pick a member with `-p <member>`, or run from its directory; the members are: {members}"
+        )
+    )]
+    WorkspaceRoot { members: String },
+
+    #[error("workspace members `{first}` and `{second}` ask for `{name}` differently")]
+    #[diagnostic(
+        code(VE0108),
+        help(
+            "members share one install of each git and PyPI dependency; give both the same entry"
+        )
+    )]
+    ConflictingPins {
+        name: String,
+        first: String,
+        second: String,
+    },
+
+    #[error("`{value}` is too long for the brain's `{key}` setting")]
+    #[diagnostic(code(VE0109))]
+    BrainKeyValue { key: String, value: String },
+
+    #[error("git dependency `{name}` has `{value}`, which git would take for an option")]
+    #[diagnostic(
+        code(VE0110),
+        help("a git dependency's url, rev, tag and branch can't start with `-`")
+    )]
+    GitOptionLike { name: String, value: String },
+
+    #[error("the program on the brain didn't answer about the brain's clock")]
+    #[diagnostic(
+        code(VE0111),
+        help(
+            "`venice clock` asks the running program's runtime; start a program built with a runtime that supports it, e.g. with `venice run`"
+        )
+    )]
+    NoClockAnswer,
+
+    #[error("the brain has a file named `{0}`, which isn't a plain file name")]
+    #[diagnostic(
+        code(VE0112),
+        help("rename or remove the file on the brain, then pull again")
+    )]
+    UnsafeBrainFileName(String),
+
+    #[error("moving the sources out of src/ would overwrite {0}")]
+    #[diagnostic(
+        code(VE0113),
+        help("venice builds from the project root; rename or remove the file, then migrate again")
+    )]
+    MigrateOverwrite(PathBuf),
 }
@@ -0,0 +1,162 @@
+use std::{
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use crate::{
+    config,
+    errors::CliError,
+    frozen,
+    github::{EXAMPLES_REPO, GithubClient},
+    new::{VENICE_VERSION, add_dependencies},
+    uv_path,
+};
+
+/// Lists the examples in the examples repo; each one lives in a folder named after it.
+const INDEX_FILE: &str = "examples.json";
+
+#[derive(Deserialize, Debug, Clone)]
+struct Example {
+    name: String,
+    description: String,
+}
+
+async fn index(client: &GithubClient) -> Result<Vec<Example>, CliError> {
+    let contents = client.download_file(EXAMPLES_REPO, INDEX_FILE).await?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+/// `venice example list`
+pub async fn list() -> Result<(), CliError> {
+    if frozen() {
+        return Err(CliError::FrozenNetwork);
+    }
+
+    let examples = index(&GithubClient::new(config())).await?;
+    let width = examples.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    for example in &examples {
+        println!("{:width$}  {}", example.name, example.description);
+    }
+    println!("\nCreate one with `venice example new <name>`.");
+    Ok(())
+}
+
+/// `venice example new`: copies an example into a folder of the same name and pins it to the
+/// runtime this version of venice ships with, so it builds even if the example was written
+/// against another one.
+pub async fn new(
+    name: &str,
+    venice_wheel: Option<&Path>,
+    cli_wheel: Option<&Path>,
+) -> miette::Result<()> {
+    let uv = uv_path()?;
+    if frozen() {
+        return Err(CliError::FrozenNetwork.into());
+    }
+    let dest = PathBuf::from(name);
+    if dest.exists() {
+        return Err(CliError::ProjectExists(dest).into());
+    }
+
+    let client = GithubClient::new(config());
+    let examples = index(&client).await?;
+    if !examples.iter().any(|example| example.name == name) {
+        return Err(CliError::UnknownExample {
+            name: name.to_string(),
+            available: examples
+                .iter()
+                .map(|example| example.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+        .into());
+    }
+
+    let tarball = client.download_tarball(EXAMPLES_REPO).await?;
+    let files = extract(&tarball, name, &dest)?;
+    add_dependencies(uv, &dest, venice_wheel, cli_wheel)?;
+
+    println!("✓ Created `{name}` ({files} file(s)), pinned to venice {VENICE_VERSION}");
+    println!("Run `cd {name}` and `venice run` to try it.");
+    Ok(())
+}
+
+/// Unpacks the files under `name/` in the repo tarball into `dest`, returning how many there were.
+fn extract(tarball: &[u8], name: &str, dest: &Path) -> Result<usize, CliError> {
+    let mut archive = tar::Archive::new(GzDecoder::new(tarball));
+    let mut files = 0;
+    for entry in archive.entries().map_err(CliError::Io)? {
+        let mut entry = entry.map_err(CliError::Io)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(CliError::Io)?.into_owned();
+        // GitHub puts everything under a `<owner>-<repo>-<commit>/` folder
+        let mut components = path.components().skip(1);
+        if components.next() != Some(Component::Normal(OsStr::new(name))) {
+            continue;
+        }
+        let relative: PathBuf = components.collect();
+        if relative.as_os_str().is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            continue;
+        }
+
+        let target = dest.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(CliError::Io)?;
+        }
+        entry.unpack(&target).map_err(CliError::Io)?;
+        files += 1;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::extract;
+
+    #[test]
+    fn extracts_only_the_named_example() {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, contents) in [
+            ("venice-v5-venice-examples-abc123/pid-tuning/main.py", "pid"),
+            (
+                "venice-v5-venice-examples-abc123/pid-tuning/lib/pid.py",
+                "lib",
+            ),
+            (
+                "venice-v5-venice-examples-abc123/drivetrain/main.py",
+                "drive",
+            ),
+            ("venice-v5-venice-examples-abc123/examples.json", "[]"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let tarball = builder.into_inner().unwrap().finish().unwrap();
+
+        let dest = std::env::temp_dir().join(format!("venice-example-{}", std::process::id()));
+        let files = extract(&tarball, "pid-tuning", &dest).unwrap();
+        assert_eq!(files, 2);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("lib/pid.py")).unwrap(),
+            "lib"
+        );
+        assert!(!dest.join("drivetrain").exists());
+        std::fs::remove_dir_all(dest).unwrap();
+    }
+}
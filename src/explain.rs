@@ -0,0 +1,990 @@
+use crate::errors::CliError;
+
+/// Longer descriptions of every error code, shown by `venice explain`. Codes are stable: never
+/// renumber one, and give new errors the next unused code.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "VE0001",
+        "\
+A file or directory couldn't be read or written.
+
+The message after the code comes from the operating system and usually says what went wrong:
+a file that doesn't exist, a directory you don't have permission to write to, or a full disk.
+Check the path in the message, and that no other program has the file locked.",
+    ),
+    (
+        "VE0002",
+        "\
+The program slot isn't between 1 and 8.
+
+The brain has eight program slots. Set [tool.venice].slot in pyproject.toml to a number from 1
+to 8, or delete it and venice will ask for one.",
+    ),
+    (
+        "VE0003",
+        "\
+Communication with the brain or controller failed.
+
+Common causes:
+- The cable was unplugged, or is a charge-only cable. Try a different cable and USB port.
+- The brain is busy: close VEXcode or any other program talking to it.
+- The brain refused a request (a NACK). The message names the reason:
+  - `NackProgramFile`: the file doesn't exist on the brain.
+  - `NackFileStorageFull` / `NackMaxUserFiles`: the brain is out of storage. Remove old
+    programs from it.
+  - `NackPacketCrc` / `NackProgramCrc` / `NackTransferSize`: the transfer was corrupted.
+    Retry, ideally over USB rather than the radio.
+  - `NackFileAlreadyExists`: a file with that name is on the brain and couldn't be replaced.
+If it keeps happening, restart the brain.",
+    ),
+    (
+        "VE0004",
+        "\
+No VEX brain or controller was found.
+
+Plug the brain in over USB and turn it on, then run `venice devices` to check that it shows
+up. Charge-only USB cables don't carry data. On Linux, run `venice doctor` to check
+permissions.",
+    ),
+    (
+        "VE0005",
+        "\
+A VEX device is plugged in, but Windows has no driver for it.
+
+Install VEXcode or the standalone VEX V5 USB driver, then unplug and replug the brain.",
+    ),
+    (
+        "VE0006",
+        "\
+The serial port is already open in another program.
+
+Only one program can talk to the brain at a time. Close VEXcode, PROS, other terminals running
+`venice terminal` or `venice upload`, and serial monitors, then try again. On Linux and macOS the
+error names the program holding the port.
+
+To wait for the other program to finish instead, for example an upload started in another
+terminal, pass `--steal`: venice keeps retrying until the port is released.",
+    ),
+    (
+        "VE0007",
+        "\
+Your user isn't allowed to open the serial port (Linux).
+
+Run `venice doctor --fix-permissions` to install a udev rule giving your user access to VEX
+devices, then replug the brain. Running venice with sudo also works, but isn't recommended.",
+    ),
+    (
+        "VE0008",
+        "\
+`venice devices --set-default` was given an index that isn't in the device list.
+
+Run `venice devices` to see the connected devices and their indices.",
+    ),
+    (
+        "VE0009",
+        "\
+The connection to the brain dropped and didn't come back within a minute.
+
+The brain may have been turned off or unplugged, or the radio link lost. Reconnect it and run
+the command again.",
+    ),
+    (
+        "VE0010",
+        "\
+The controller isn't linked to a brain over the radio.
+
+Turn the brain on and wait for the controller's link light to turn green, or plug the brain in
+directly over USB.",
+    ),
+    (
+        "VE0011",
+        "\
+The controller was asked to change radio channel but never dropped the old one.
+
+Retry the command. If it keeps failing, power cycle the controller and brain.",
+    ),
+    (
+        "VE0012",
+        "\
+The controller changed radio channel but didn't relink to the brain.
+
+Radio interference (at competitions especially) can stop the link from coming back. Move closer
+to the brain, retry, or connect over USB.",
+    ),
+    (
+        "VE0013",
+        "\
+A version number couldn't be parsed.
+
+Versions follow semantic versioning, like `1.2.3`. Check the version you typed, or the one in
+pyproject.toml.",
+    ),
+    (
+        "VE0014",
+        "\
+pyproject.toml isn't valid TOML, or [tool.venice] has a setting of the wrong type.
+
+The message points at the problem. Common mistakes are missing quotes around strings and
+writing a number where venice expects a string, or the other way round.",
+    ),
+    (
+        "VE0015",
+        "\
+venice needed to edit pyproject.toml but couldn't parse it.
+
+Fix the syntax error named in the message and run the command again.",
+    ),
+    (
+        "VE0016",
+        "\
+mpy-cross couldn't compile one of your files.
+
+This is almost always a syntax error in your code: the message shows mpy-cross's output with
+the file and line. Fix it and build again. Note that MicroPython doesn't support every
+CPython feature.",
+    ),
+    (
+        "VE0017",
+        "\
+No pyproject.toml was found in this directory or any parent.
+
+Run venice from inside a project directory, use `-C <dir>` to point at one, or create a new
+project with `venice new <name>`.",
+    ),
+    (
+        "VE0018",
+        "\
+The project has no name.
+
+Set [project].name (or [tool.venice].name) in pyproject.toml.",
+    ),
+    (
+        "VE0019",
+        "\
+The project has no main.py.
+
+The brain runs main.py when the program starts. Create one at the root of the project.",
+    ),
+    (
+        "VE0020",
+        "\
+There's an __init__.py at the root of the project.
+
+The project root isn't a package, so this file would never run. Move its code into main.py
+and delete it.",
+    ),
+    (
+        "VE0021",
+        "\
+The runtime release has no published description of its built-in modules.
+
+Older runtime releases predate descriptions. venice falls back to a built-in list of modules
+for import checking, so this only affects `venice runtime describe`.",
+    ),
+    (
+        "VE0022",
+        "\
+No runtime was found to upload.
+
+The runtime comes from the `venice` Python package. Make sure it's installed in the project's
+environment: `uv add venice`, then run venice through `uv run`.",
+    ),
+    (
+        "VE0023",
+        "\
+uv wasn't found.
+
+venice uses uv to manage Python packages. Install venice-cli with its dependencies, e.g.
+`uv tool install venice-cli` or `pip install venice-cli`.",
+    ),
+    (
+        "VE0024",
+        "\
+A file in the data directory has a name the brain can't store.
+
+File names on the brain, including their path inside the data directory, are limited to 23
+characters. Rename the file or move it up a directory.",
+    ),
+    (
+        "VE0025",
+        "\
+A file name is too long for the brain.
+
+File names on the brain are limited to 23 characters.",
+    ),
+    (
+        "VE0026",
+        "\
+brain-state.toml couldn't be read or written.
+
+If you edited it by hand, check its syntax. Otherwise run `venice sync pull` again to
+regenerate it.",
+    ),
+    (
+        "VE0027",
+        "\
+The file isn't a valid program table.
+
+Program tables (.vpt files) are produced by `venice build`. The file may be truncated or from
+an incompatible venice version; rebuild it.",
+    ),
+    (
+        "VE0028",
+        "\
+Your signing key is corrupt.
+
+The key lives in the venice config directory as `signing-key`. Delete it to generate a new one,
+then give the new public key to anyone who trusts your old one.",
+    ),
+    (
+        "VE0029",
+        "\
+The program table's signature doesn't match its contents.
+
+The table was modified after it was signed. Rebuild it with `venice build --sign`.",
+    ),
+    (
+        "VE0030",
+        "\
+The program table isn't signed, but your config requires a trusted signature.
+
+[signing].trusted-keys is set in the venice config. Build with `venice build --sign`, or remove
+the setting.",
+    ),
+    (
+        "VE0031",
+        "\
+The program table was signed by a key that isn't trusted.
+
+Add the signer's public key to [signing].trusted-keys in the venice config if you trust it.",
+    ),
+    (
+        "VE0032",
+        "\
+Your secrets key is corrupt.
+
+The key lives in the venice config directory as `secrets-key`. Deleting it makes every secret
+you've set unreadable, so set them again afterwards with `venice secret set`.",
+    ),
+    (
+        "VE0033",
+        "\
+A secret in .venice-secrets couldn't be decrypted.
+
+Secrets are encrypted with a per-user key, so they can only be read by whoever set them. Set it
+again yourself with `venice secret set <name>`.",
+    ),
+    (
+        "VE0034",
+        "\
+[tool.venice.env] refers to a secret that hasn't been set.
+
+Set it with `venice secret set <name>`.",
+    ),
+    (
+        "VE0035",
+        "\
+The OS keychain couldn't be accessed.
+
+On Linux a Secret Service provider (GNOME Keyring or KWallet) must be running. Alternatively,
+store the secret in .venice-secrets by leaving out --keychain.",
+    ),
+    (
+        "VE0036",
+        "\
+A name in [tool.venice.env] isn't a valid Python identifier.
+
+Names become attributes of the generated `venice_env` module, so they may only contain letters,
+digits and underscores, and can't start with a digit.",
+    ),
+    (
+        "VE0037",
+        "\
+No co-processor is configured.
+
+Add a [tool.venice.copro] section to pyproject.toml describing how to reach it.",
+    ),
+    (
+        "VE0038",
+        "\
+The command pushing files to the co-processor failed.
+
+Its output is shown above. Check that the co-processor is reachable (for ssh, that you can
+`ssh` to the host without a password prompt).",
+    ),
+    (
+        "VE0039",
+        "\
+An import won't resolve on the brain.
+
+The imported module isn't in the project, its dependencies, or built into the runtime. Check
+the spelling; add the module to the project or [tool.venice.dependencies]; or, if you know
+it'll be there, list it in [tool.venice].ignore-imports. Imports inside try blocks aren't
+checked.",
+    ),
+    (
+        "VE0040",
+        "\
+Some imports won't resolve on the brain. See VE0039.",
+    ),
+    (
+        "VE0041",
+        "\
+The project has no module by that name.
+
+Module names are dotted, like `drive.pid` for drive/pid.py.",
+    ),
+    (
+        "VE0042",
+        "\
+A module by that name already exists. Pick a different name, or rename the existing module
+first.",
+    ),
+    (
+        "VE0043",
+        "\
+That isn't a valid module name.
+
+Each part of a dotted module name may only contain letters, digits and underscores, and can't
+start with a digit.",
+    ),
+    (
+        "VE0044",
+        "\
+A directory with the project's name already exists. Choose another name, or delete it.",
+    ),
+    (
+        "VE0045",
+        "\
+uv failed while setting up the project.
+
+Its output is shown above. Network problems are the usual cause: uv downloads packages from
+PyPI.",
+    ),
+    (
+        "VE0046",
+        "\
+The venice config file isn't valid.
+
+The message names the file and the problem. Fix it, or delete the file to go back to the
+defaults.",
+    ),
+    (
+        "VE0047",
+        "\
+venice couldn't update its config file. Check it's valid TOML.",
+    ),
+    (
+        "VE0048",
+        "\
+venice couldn't work out where your config directory is.
+
+This happens when HOME (or APPDATA on Windows) isn't set.",
+    ),
+    (
+        "VE0049",
+        "\
+A network request failed.
+
+Check your internet connection. Behind a proxy, set HTTPS_PROXY. For offline builds, vendor
+dependencies with `venice vendor` and build with --frozen.",
+    ),
+    (
+        "VE0050",
+        "\
+A response or cache file wasn't valid JSON.
+
+Cached files live in the venice cache directory and are safe to delete.",
+    ),
+    (
+        "VE0051",
+        "\
+GitHub returned an error.
+
+A 404 usually means the release doesn't exist. Other errors are usually temporary; try
+again later.",
+    ),
+    (
+        "VE0052",
+        "\
+No package registry is configured.
+
+Set [registry].url in the venice config.",
+    ),
+    (
+        "VE0053",
+        "\
+Publishing and yanking need a registry API token.
+
+Set [registry].token in the venice config, or VENICE_REGISTRY_TOKEN in the environment.",
+    ),
+    (
+        "VE0054",
+        "\
+The package registry returned an error.
+
+401 or 403 means your token is missing or doesn't own the package; 409 when publishing means
+that version already exists. Bump [project].version and publish again.",
+    ),
+    (
+        "VE0055",
+        "\
+The registry has no package by that name. Check the spelling.",
+    ),
+    (
+        "VE0056",
+        "\
+No published version of the package satisfies the version requirement.
+
+Loosen the requirement in [tool.venice.dependencies]. Yanked versions are never picked for new
+installs.",
+    ),
+    (
+        "VE0057",
+        "\
+A downloaded package doesn't match the checksum it was published with.
+
+The download may have been corrupted, so try again. If it keeps happening, the package may
+have been tampered with; tell its author.",
+    ),
+    (
+        "VE0058",
+        "\
+The library has no package to publish.
+
+A library's modules must live in a package named after the project, with dashes replaced by
+underscores: a project named `my-lib` needs my_lib/__init__.py.",
+    ),
+    (
+        "VE0059",
+        "\
+Publishing needs a version. Set [project].version in pyproject.toml.",
+    ),
+    (
+        "VE0060",
+        "\
+--frozen forbids network access, but the command needed it.
+
+Run it once without --frozen to fetch what's missing.",
+    ),
+    (
+        "VE0061",
+        "\
+--frozen requires every dependency to be locked.
+
+venice.lock is missing the dependency, or locks a version that no longer matches
+pyproject.toml. Run `venice vendor` with network access and commit venice.lock and vendor/.",
+    ),
+    (
+        "VE0062",
+        "\
+--frozen requires every dependency to be vendored.
+
+Run `venice vendor` with network access and commit vendor/.",
+    ),
+    (
+        "VE0063",
+        "\
+venice.lock couldn't be read or written.
+
+If it was edited by hand or mangled by a merge conflict, delete it and build again to
+regenerate it.",
+    ),
+    (
+        "VE0064",
+        "\
+GitHub's API rate limit was exceeded.
+
+Anonymous requests are limited per IP address, which a whole classroom shares. Create a
+personal access token (no scopes needed) and set [github].token in the venice config.",
+    ),
+    (
+        "VE0065",
+        "\
+That isn't a venice error code. Codes look like VE0001.",
+    ),
+    (
+        "VE0066",
+        "\
+Another program already occupies the project's slot on the connected brain.
+
+Uploading replaces it. If you want to keep it, set [tool.venice].slot to a free slot; `venice
+sync pull` lists what's in each one.",
+    ),
+    (
+        "VE0067",
+        "\
+The program table has no metadata file next to it.
+
+`upload --vpt` and `venice artifact verify` need the .json file that `venice build
+--artifact-metadata` writes alongside the table. Copy both files together.",
+    ),
+    (
+        "VE0068",
+        "\
+The program table doesn't match its metadata file.
+
+One of them was modified or replaced after the build, or they come from different builds. Rebuild
+with --artifact-metadata and copy both files again.",
+    ),
+    (
+        "VE0069",
+        "\
+The program table was built against a different runtime than the one installed.
+
+Its imports were checked against that runtime, so it may not run on this one. Install the
+matching `venice` package, or rebuild the table.",
+    ),
+    (
+        "VE0070",
+        "\
+A command in [tool.venice].after-upload failed.
+
+The program was uploaded; only the steps after the failing command were skipped. The command's
+own output above says what went wrong.",
+    ),
+    (
+        "VE0071",
+        "\
+Some smart devices run older firmware than others of the same type.
+
+VEXos updates a device's firmware when the device is plugged in, so a motor that stayed unplugged
+through a VEXos update keeps the old version. Mixed versions cause intermittent disconnects.
+Unplug and replug the flagged devices, or restart the brain with them connected.",
+    ),
+    (
+        "VE0072",
+        "\
+The state store in the venice data directory was written by a newer version of venice.
+
+venice keeps what it remembers between runs, such as battery history, in `state.json` and upgrades
+older stores automatically, but it can't read a store from the future. Update venice, or delete
+`state.json` to start with an empty store.
+
+The first time a different version of venice runs, it saves a copy of the config and state store
+as the previous version left them. If you went back to an older venice on purpose, `venice
+restore-state <version>` puts back the copy that version wrote.",
+    ),
+    (
+        "VE0073",
+        "\
+There's no dynamic completion script for this shell.
+
+Dynamic completions call back into venice to suggest attached devices and empty slots, which needs
+a script written for each shell. Use the static script from `venice completions <shell>` instead;
+it completes commands and flags but not their values.",
+    ),
+    (
+        "VE0074",
+        "\
+A module was about to be compiled under a path from the machine it was built on.
+
+Tracebacks and error dialogs on the brain show the file name compiled into a module, and paths like
+`/home/<you>/robot/main.py` leak the builder's user name and directory layout to anyone who sees
+the brain's screen. venice compiles modules under project-relative names, so this is a bug in
+venice or in a compiler front-end; please report it.",
+    ),
+    (
+        "VE0075",
+        "\
+The connected brain has no calibration file to pull.
+
+Calibration constants live in `calibration.toml` in the project and are copied to the brain as
+`venice-calib.json` by `venice calib push`, or by `venice upload` whenever the file changes. Write
+`calibration.toml` first, then push it.",
+    ),
+    (
+        "VE0076",
+        "\
+The project has no `calibration.toml`.
+
+`venice calib push` copies the project's calibration to the brain. Pull the constants from a brain
+that has them with `venice calib pull`, or create the file by hand, e.g.:
+
+    [imu]
+    heading-offset = 1.8",
+    ),
+    (
+        "VE0077",
+        "\
+The calibration file on the brain isn't a JSON object TOML can represent.
+
+TOML has no null and needs a table at the top level. The file was most likely written by something
+other than venice; fix or delete `venice-calib.json` on the brain and push again.",
+    ),
+    (
+        "VE0078",
+        "\
+The command needs the project's program table, but the project hasn't been built.
+
+Run `venice build` (or `venice upload`, which builds) and try again.",
+    ),
+    (
+        "VE0079",
+        "\
+The program doesn't have an autonomous routine with that name.
+
+Routines are listed in pyproject.toml and built into the program table, which the runtime reads to
+check the selection:
+
+    [tool.venice]
+    autons = [\"left\", \"right\", \"skills\"]
+
+Add the routine there, rebuild, and upload before selecting it. `venice auton list` shows the
+routines in the last build.",
+    ),
+    (
+        "VE0080",
+        "\
+The device's port stayed open, but it stopped answering the terminal's keepalive pings.
+
+This usually means the device was turned off while its USB cable stayed connected to a hub, which
+keeps the port around. Turn it back on and run the command again. How often the terminal pings and
+how long it waits are set in the venice config:
+
+    [terminal]
+    keepalive = 5
+    peer-timeout = 30",
+    ),
+    (
+        "VE0081",
+        "\
+`venice run --ci` hit its timeout before the program printed everything passed with --expect, or
+before the upload finished.
+
+The timeout covers waiting for the device, building, uploading and running. The program was
+stopped. Raise --timeout if the program needs longer, or check the output above for how far it
+got.",
+    ),
+    (
+        "VE0082",
+        "\
+`venice run --ci` stopped the program because a line of its output contained text passed with
+--fail-on. By default that's the start of a Python traceback, meaning the program crashed.
+
+The line is shown with the error, and the output above it usually explains why.",
+    ),
+    (
+        "VE0083",
+        "\
+The command was interrupted with Ctrl+C before it finished. Anything it started on the brain was
+stopped.",
+    ),
+    (
+        "VE0084",
+        "\
+`venice migrate vexcode` didn't recognize the path as a VEXcode project.
+
+It reads two kinds of project: a `.v5python` file saved by VEXcode V5, and a folder made by the
+VEXcode extension for VS Code, which has `.vscode/vex_project_settings.json` and its sources in
+`src/`. Blocks projects can't be migrated; export them to Python in VEXcode first.",
+    ),
+    (
+        "VE0085",
+        "\
+The folder already has a pyproject.toml, so it's already set up as a venice (or other Python)
+project and migrating it would overwrite that.
+
+Move pyproject.toml out of the way if it's left over from something else, then run the migration
+again.",
+    ),
+    (
+        "VE0086",
+        "\
+`venice example new` only knows the examples listed in the venice-examples repository. Names are
+the folder names there, like `pid-tuning`, not the titles.
+
+Run `venice example list` for the current list.",
+    ),
+    (
+        "VE0087",
+        "\
+`--rt-version` downloads the runtime from its GitHub release, but that release has no
+`venice-v<version>.bin` asset. The version may not exist, or may be a pre-release published
+without a binary.
+
+Pick a version from `venice runtime list --available`.",
+    ),
+    (
+        "VE0088",
+        "\
+`venice bisect` searches forwards from the release the program worked on to the one it broke on,
+so --good has to be the older of the two.
+
+If the program broke when going back to an older release, swap them and read the result as the
+first release it works on.",
+    ),
+    (
+        "VE0089",
+        "\
+The first time a different version of venice runs, it saves the config and state store as the
+previous version left them, keeping the last five. `venice restore-state` can only restore a
+version it has a snapshot for.
+
+Run `venice restore-state` with no version to list them.",
+    ),
+    (
+        "VE0090",
+        "\
+`venice slots apply` looks the preset up in slots.toml, which holds one [presets.<name>] table per
+preset, mapping slot numbers to projects or program tables:
+
+    [presets.competition]
+    1 = \"robot\"
+    2 = \"skills/build/release/out.vpt\"
+
+Check the preset's name, or run `venice slots list` to see them all.",
+    ),
+    (
+        "VE0091",
+        "\
+Each key in a slot preset is the number of the brain's slot the program goes in, from 1 to 8.
+Quote it like any TOML key with digits: `1 = \"robot\"`.",
+    ),
+    (
+        "VE0092",
+        "\
+mpy-cross couldn't compile several of your files. Modules compile in parallel, and every one is
+tried before the build stops, so each failure is listed below with its file and line.
+
+See VE0016 for what an individual failure usually means.",
+    ),
+    (
+        "VE0093",
+        "\
+venice compiles with the mpy-cross installed alongside it. When that one can't run, venice
+downloads a pinned release into its cache with uv and uses that instead. This error means the
+download finished but the compiler it installed still doesn't run, usually because no build of
+mpy-cross is published for this platform.
+
+Install mpy-cross yourself, in the same environment as venice-cli, and check it with
+`venice doctor`.",
+    ),
+    (
+        "VE0094",
+        "\
+`venice patch` uploads only the modules that changed since the last `venice upload`, and the
+runtime loads them over the uploaded program when it next starts. That only works when:
+
+- the runtime supports module patches (`venice runtime describe` lists them)
+- the project was uploaded from this machine, to the slot in the manifest, and that upload is
+  still what the brain has in the slot
+- every change is to a module: no modules were removed, the autonomous routines are the same,
+  and the program table isn't signed
+
+Otherwise, run `venice upload`. Later patches build on that upload again.",
+    ),
+    (
+        "VE0095",
+        "\
+[tool.venice].mpy-cross-version pins the mpy-cross release the project compiles with, because
+bytecode from one mpy-cross version doesn't always load on a runtime built for another. When
+the installed mpy-cross is a different version, venice downloads the pinned one into its cache;
+this error means what it downloaded still reports a different version.
+
+Check that the pinned version is a release of the mpy-cross package on PyPI, e.g.
+
+    [tool.venice]
+    mpy-cross-version = \"1.25.0\"",
+    ),
+    (
+        "VE0096",
+        "\
+Usage metrics are off unless the venice config turns them on, and even then they're only sent
+somewhere when sharing is on and a destination is set. `venice stats usage` always shows exactly
+what would be sent: command names, how often they ran, how long they took and the error codes
+they failed with, never arguments, paths or code.
+
+    [usage]
+    collect = \"share\"
+    submit-url = \"https://example.com/venice-metrics\"",
+    ),
+    (
+        "VE0097",
+        "\
+Git dependencies are cloned into the venice cache with the `git` command, so it has to be
+installed and able to reach the repository, with whatever credentials that repository needs.
+The error shows what git printed. Check the URL, and that the `rev`, `tag` or `branch` exists:
+
+    [tool.venice.dependencies]
+    mylib = { git = \"https://github.com/team/mylib\", tag = \"v1.0\" }
+
+Once a dependency is locked in venice.lock, builds use the locked commit and only reach the
+network if it isn't in the cache yet.",
+    ),
+    (
+        "VE0098",
+        "\
+PyPI packages are installed with uv and compiled with mpy-cross like the rest of the program, so
+only their Python sources reach the brain. A package that ships compiled extensions (`.so`,
+`.pyd` and the like) is built for a desktop Python and can't be loaded by the runtime, so it's
+refused and removed again.
+
+Many such packages have a pure-Python fallback or alternative published separately.",
+    ),
+    (
+        "VE0099",
+        "\
+`venice remove` takes a dependency out of [tool.venice.dependencies] or
+[tool.venice.dev-dependencies] in pyproject.toml by the name it's listed under there. Check the
+spelling against the manifest.",
+    ),
+    (
+        "VE0100",
+        "\
+Every module in the program table has a single name, so two source files that would be imported
+the same way can't both be compiled. This happens when the project and one of
+[tool.venice.build].src-dirs, two source directories, or a source directory and a dependency
+each have a module or package of that name:
+
+    [tool.venice.build]
+    src-dirs = [\"generated\", \"vendor\"]    # generated/util.py and vendor/util.py collide
+
+Rename one of them, or leave one out with [tool.venice.build].exclude.",
+    ),
+    (
+        "VE0101",
+        "\
+An .mpy file with no .py beside it is packed into the program table as a precompiled module, but
+this one doesn't start with MicroPython's bytecode header. Only files written by mpy-cross can be
+shipped this way; if it's something else, move it out of the source tree or into the assets
+directory.",
+    ),
+    (
+        "VE0102",
+        "\
+Precompiled modules are packed as they are, so they have to be in the bytecode format the runtime
+loads, which is the one the project's own modules were just compiled to. Recompile the module
+with an mpy-cross of the same release as [tool.venice].mpy-cross-version, e.g.
+
+    mpy-cross robot/fast_math.py
+
+or ship the module's source instead and let the build compile it.",
+    ),
+    (
+        "VE0103",
+        "\
+A precompiled module holds machine code, from @micropython.native or @micropython.viper
+functions, for a different processor than the V5 brain's. Loading it would fail on the brain when
+it's imported, so the build stops instead. Recompile it for the brain:
+
+    mpy-cross -march=armv7emdp robot/fast_math.py
+
+Modules with only bytecode, compiled without -march, load anywhere.",
+    ),
+    (
+        "VE0104",
+        "\
+The project's main.py has nothing but blank lines, comments and docstrings.
+
+The brain runs main.py when the program starts, and nothing else unless main.py imports it, so
+the program would start and immediately end. Put the program's code in main.py, or import and
+start it from there:
+
+    from robot import competition
+
+    competition.run()",
+    ),
+    (
+        "VE0105",
+        "\
+`-p`/`--package` was given outside a workspace.
+
+`-p` picks one project of a workspace, a directory whose pyproject.toml lists its member projects:
+
+    [tool.venice.workspace]
+    members = [\"competition\", \"skills\"]
+
+Run the command from inside the workspace, or leave `-p` out to use the project in the current
+directory.",
+    ),
+    (
+        "VE0106",
+        "\
+`-p`/`--package` named a project that isn't in the workspace.
+
+Members are named by their directory or by their project's name. Check the spelling, or add the
+project's directory to [tool.venice.workspace].members.",
+    ),
+    (
+        "VE0107",
+        "\
+The command needs a project, but ran in a workspace's root directory.
+
+A workspace's root lists its members rather than being a program itself. Pick the member to work
+on with `-p <member>`, or run the command from the member's directory. To make the root a member
+too, add \".\" to [tool.venice.workspace].members.",
+    ),
+    (
+        "VE0108",
+        "\
+Two workspace members have different entries for the same git or PyPI dependency.
+
+Members share the workspace's venice.lock and install each dependency once, by name, so they
+can't use different repositories, revisions or version specifiers for it. Make the two members'
+entries for it in [tool.venice.dependencies] the same.",
+    ),
+    (
+        "VE0109",
+        "\
+A setting is too long for the brain to store.
+
+The brain keeps settings such as the team number and robot name in fixed-size fields. Shorten the
+value in the snapshot's brain-state.toml and push it again.",
+    ),
+    (
+        "VE0110",
+        "\
+A git dependency's url, rev, tag or branch starts with `-`.
+
+venice passes these to git as arguments, where a leading `-` would make git read the value as an
+option instead. Fix the entry in [tool.venice.dependencies].",
+    ),
+    (
+        "VE0111",
+        "\
+`venice clock` got no answer from the brain.
+
+The brain's clock is read and set by the venice runtime in the running program, since the V5's
+serial protocol has no way to reach it. Start a program built with a runtime that supports the
+brain clock (`venice runtime describe` lists it), for example with `venice run`, and try again
+while it's running.",
+    ),
+    (
+        "VE0112",
+        "\
+`venice sync pull` found a file on the brain whose name isn't a plain file name.
+
+Pulled files are saved under brain-state/ by the names the brain reports. A name with a `/`, or one
+like `..`, would put the file somewhere else on this computer, so the pull stops instead. Rename or
+remove the file on the brain, for example with VEXcode, and pull again.",
+    ),
+    (
+        "VE0113",
+        "\
+`venice migrate vexcode` would overwrite a file at the root of the project.
+
+The VEXcode extension keeps sources in src/, but venice builds from the project root, so the
+migration moves them up a level. A file already at the root with the same name as one of them would
+be lost, so nothing is changed instead. Rename or remove it, then migrate again.",
+    ),
+];
+
+/// `venice explain`: accepts `VE0004`, `ve4` or just `4`.
+pub fn explain(code: &str) -> Result<(), CliError> {
+    let digits = code
+        .trim()
+        .trim_start_matches(['V', 'v'])
+        .trim_start_matches(['E', 'e']);
+    let normalized = digits
+        .parse::<u16>()
+        .map(|n| format!("VE{n:04}"))
+        .map_err(|_| CliError::UnknownErrorCode(code.to_string()))?;
+
+    let (_, text) = EXPLANATIONS
+        .iter()
+        .find(|(c, _)| *c == normalized)
+        .ok_or_else(|| CliError::UnknownErrorCode(code.to_string()))?;
+    println!("{normalized}: {text}");
+    Ok(())
+}
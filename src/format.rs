@@ -0,0 +1,85 @@
+use inquire::Confirm;
+use vex_v5_serial::protocol::cdc2::file::FileVendor;
+
+use crate::{
+    auton::SELECTION_FILE,
+    brain::{clear_user_files, erase_file, list_files},
+    calib::BRAIN_CALIB_FILE,
+    device::open_connection,
+    errors::CliError,
+    runtime::RtBin,
+};
+
+/// Files venice writes to the brain: every slot's program and its ini, runtimes, and the
+/// calibration and auton selection files.
+fn is_venice_file(name: &str) -> bool {
+    let is_slot_file = name
+        .strip_prefix("slot_")
+        .and_then(|rest| {
+            rest.strip_suffix(".bin")
+                .or_else(|| rest.strip_suffix(".ini"))
+        })
+        .and_then(|slot| slot.parse::<u8>().ok())
+        .is_some_and(|slot| (1..=8).contains(&slot));
+    is_slot_file
+        || name.parse::<RtBin>().is_ok()
+        || name == BRAIN_CALIB_FILE
+        || name == SELECTION_FILE
+}
+
+/// `venice format --user-files`: deletes everything venice put on the brain. When that's every
+/// user file, the brain clears them in one request; otherwise other files (like data files) are
+/// kept and venice's are erased one by one.
+pub async fn format_user_files(yes: bool) -> Result<(), CliError> {
+    let mut conn = open_connection().await?;
+    let files = list_files(&mut conn, FileVendor::User).await?;
+    let (ours, others): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|file| is_venice_file(&file.name));
+    if ours.is_empty() {
+        println!("No venice files on the brain");
+        return Ok(());
+    }
+
+    if !yes {
+        println!("This deletes from the brain:");
+        for file in ours.iter() {
+            println!("  {}", file.name);
+        }
+        let confirmed = Confirm::new("Continue?")
+            .with_default(false)
+            .prompt()
+            .unwrap_or(false);
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    if others.is_empty() {
+        clear_user_files(&mut conn).await?;
+    } else {
+        for file in ours.iter() {
+            erase_file(&mut conn, &file.name).await?;
+        }
+    }
+    println!(
+        "✓ Deleted {} file(s), kept {} other file(s)",
+        ours.len(),
+        others.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_venice_file;
+
+    #[test]
+    fn venice_files() {
+        assert!(is_venice_file("slot_3.bin"));
+        assert!(is_venice_file("slot_8.ini"));
+        assert!(is_venice_file("venice-v0.1.0.bin"));
+        assert!(!is_venice_file("slot_9.bin"));
+        assert!(!is_venice_file("auton-path.csv"));
+    }
+}
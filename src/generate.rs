@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    build::{GENERATED_DIR, SRC_EXT, SrcModule},
+    env::{ENV_MODULE, env_module_source},
+    errors::CliError,
+    manifest::Project,
+};
+
+/// A module whose source is written by a [`Generator`] at build time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedModule {
+    /// Dotted module name. Packages in it need an `__init__` module of their own.
+    pub name: String,
+    pub source: String,
+}
+
+/// Contributes modules generated from the project's config or files to every build. They're
+/// compiled, cached, checked for unresolved imports and packed like the project's own sources.
+pub trait Generator {
+    /// Names the generator's directory of sources, under the build dir's `generated` one.
+    fn name(&self) -> &'static str;
+
+    /// The modules to generate for `project`, whose directory is `project_dir`.
+    fn generate(
+        &self,
+        project_dir: &Path,
+        project: &Project,
+    ) -> Result<Vec<GeneratedModule>, CliError>;
+
+    /// Files the modules are generated from, so `venice dev` and `build --watch` rebuild when one
+    /// changes. The manifest is always watched.
+    fn inputs(&self, _project_dir: &Path, _project: &Project) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// The [`ENV_MODULE`] module, from [tool.venice.env].
+struct EnvGenerator;
+
+impl Generator for EnvGenerator {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn generate(
+        &self,
+        _project_dir: &Path,
+        project: &Project,
+    ) -> Result<Vec<GeneratedModule>, CliError> {
+        Ok(env_module_source(&project.env)?
+            .map(|source| GeneratedModule {
+                name: ENV_MODULE.to_string(),
+                source,
+            })
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Every generator a build runs.
+pub fn generators() -> Vec<Box<dyn Generator>> {
+    vec![Box::new(EnvGenerator)]
+}
+
+/// Writes each generator's modules under `build_dir`, returning the directories they're in with
+/// their modules, to be built alongside the project's sources.
+pub async fn generate_modules(
+    project_dir: &Path,
+    project: &Project,
+    build_dir: &Path,
+) -> Result<Vec<(PathBuf, Vec<SrcModule>)>, CliError> {
+    let mut sources = Vec::new();
+    for generator in generators() {
+        let root = build_dir.join(GENERATED_DIR).join(generator.name());
+        let mut modules = Vec::new();
+        for module in generator.generate(project_dir, project)? {
+            let path = root
+                .join(module.name.replace('.', "/"))
+                .with_extension(SRC_EXT);
+            tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+            tokio::fs::write(&path, &module.source).await?;
+            modules.push(SrcModule::from_path(&path, &root, SRC_EXT));
+        }
+        sources.push((root, modules));
+    }
+    Ok(sources)
+}
+
+/// The files every generator reads, for [`Generator::inputs`].
+pub fn generator_inputs(project_dir: &Path, project: &Project) -> Vec<PathBuf> {
+    generators()
+        .iter()
+        .flat_map(|generator| generator.inputs(project_dir, project))
+        .collect()
+}
@@ -0,0 +1,346 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    config::{self, Config},
+    errors::CliError,
+    releases::PER_PAGE,
+};
+
+pub const API_URL: &str = "https://api.github.com";
+pub const CLI_REPO: &str = "venice-v5/venice-cli";
+pub const RUNTIME_REPO: &str = "venice-v5/venice";
+pub const EXAMPLES_REPO: &str = "venice-v5/venice-examples";
+
+const MAX_ATTEMPTS: u32 = 4;
+/// Timeout for a [`GithubClient::quick`] request, as a whole.
+const QUICK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Rate limit resets further out than this are reported instead of waited on.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Release {
+    pub tag_name: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    pub html_url: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Parses the tag name as a version, ignoring a leading `v`.
+    pub fn version(&self) -> Result<semver::Version, CliError> {
+        Ok(self.tag_name.trim_start_matches('v').parse()?)
+    }
+}
+
+/// A cached API response, keyed by request path.
+#[derive(Deserialize, Serialize)]
+struct CacheEntry {
+    etag: Option<String>,
+    /// Seconds since the unix epoch
+    fetched_at: u64,
+    body: String,
+}
+
+impl CacheEntry {
+    fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.fetched_at))
+    }
+}
+
+enum Fetched {
+    Modified { etag: Option<String>, body: String },
+    NotModified,
+    RateLimited { reset: u64 },
+}
+
+/// GitHub REST client that revalidates responses with `If-None-Match`, keeps them on disk, and
+/// backs off when rate limited. Conditional requests answered with 304 don't count against the
+/// rate limit, which matters when a whole classroom shares one public IP.
+pub struct GithubClient {
+    agent: ureq::Agent,
+    token: Option<String>,
+    cache_dir: Option<PathBuf>,
+    max_attempts: u32,
+}
+
+impl GithubClient {
+    pub fn new(config: &Config) -> Self {
+        Self::with_limits(config, Duration::from_secs(10), MAX_ATTEMPTS)
+    }
+
+    /// A client for requests that mustn't hold up the command they run alongside, like the
+    /// update check: it tries once, with a short timeout, and never waits on the rate limit.
+    pub fn quick(config: &Config) -> Self {
+        Self::with_limits(config, QUICK_TIMEOUT, 1)
+    }
+
+    fn with_limits(config: &Config, timeout: Duration, max_attempts: u32) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(timeout))
+            .user_agent(concat!("venice-cli/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .into();
+
+        Self {
+            agent,
+            token: config.github.token.clone(),
+            cache_dir: config::cache_dir().map(|dir| dir.join("github")),
+            max_attempts,
+        }
+    }
+
+    /// One page of `repo`'s releases, newest first, answered from the disk cache if it's younger
+    /// than `max_age`. See
+    /// [`ReleaseIndex`](crate::releases::ReleaseIndex) for all of them.
+    pub async fn releases_page(
+        &self,
+        repo: &str,
+        page: usize,
+        max_age: Duration,
+    ) -> Result<Vec<Release>, CliError> {
+        self.get(
+            &format!("/repos/{repo}/releases?per_page={PER_PAGE}&page={page}"),
+            Some(max_age),
+        )
+        .await
+    }
+
+    pub async fn latest_release(&self, repo: &str) -> Result<Release, CliError> {
+        self.get(&format!("/repos/{repo}/releases/latest"), None)
+            .await
+    }
+
+    pub async fn release_by_tag(&self, repo: &str, tag: &str) -> Result<Release, CliError> {
+        self.get(&format!("/repos/{repo}/releases/tags/{tag}"), None)
+            .await
+    }
+
+    /// Downloads a release asset. Asset downloads aren't API calls, so they skip the response
+    /// cache and don't count against the rate limit.
+    pub async fn download_asset(&self, asset: &ReleaseAsset) -> Result<Vec<u8>, CliError> {
+        self.download(&asset.browser_download_url).await
+    }
+
+    /// Downloads a file from the default branch of `repo`, from the raw file host rather than
+    /// the API.
+    pub async fn download_file(&self, repo: &str, path: &str) -> Result<Vec<u8>, CliError> {
+        self.download(&format!(
+            "https://raw.githubusercontent.com/{repo}/HEAD/{path}"
+        ))
+        .await
+    }
+
+    /// Downloads the default branch of `repo` as a gzipped tarball, whose entries are all under
+    /// one top-level directory.
+    pub async fn download_tarball(&self, repo: &str) -> Result<Vec<u8>, CliError> {
+        self.download(&format!("{API_URL}/repos/{repo}/tarball"))
+            .await
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>, CliError> {
+        let url = url.to_string();
+        let agent = self.agent.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<Vec<u8>, CliError> {
+            let mut response = agent
+                .get(&url)
+                .header("Accept", "application/octet-stream")
+                .call()?;
+            match response.status().as_u16() {
+                200..=299 => Ok(response.body_mut().read_to_vec()?),
+                status => Err(CliError::GithubStatus { status, url }),
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        max_age: Option<Duration>,
+    ) -> Result<T, CliError> {
+        let cached = self.read_cache(path).await;
+
+        if let (Some(entry), Some(max_age)) = (&cached, max_age)
+            && entry.age() < max_age
+        {
+            return Ok(serde_json::from_str(&entry.body)?);
+        }
+
+        let etag = cached.as_ref().and_then(|entry| entry.etag.clone());
+        let mut attempt = 0;
+        let body = loop {
+            attempt += 1;
+            match self.fetch(path, etag.clone()).await {
+                Ok(Fetched::Modified { etag, body }) => {
+                    let entry = CacheEntry {
+                        etag,
+                        fetched_at: unix_now(),
+                        body: body.clone(),
+                    };
+                    self.write_cache(path, &entry).await;
+                    break body;
+                }
+                Ok(Fetched::NotModified) => {
+                    // Only sent an etag if there was a cache entry to begin with
+                    let mut entry = cached.unwrap();
+                    entry.fetched_at = unix_now();
+                    self.write_cache(path, &entry).await;
+                    break entry.body;
+                }
+                Ok(Fetched::RateLimited { reset }) => {
+                    // Stale data beats waiting on the rate limit
+                    if let Some(entry) = cached {
+                        break entry.body;
+                    }
+
+                    let wait = Duration::from_secs(reset.saturating_sub(unix_now()));
+                    if attempt < self.max_attempts && wait <= MAX_RATE_LIMIT_WAIT {
+                        tokio::time::sleep(wait).await;
+                        continue;
+                    }
+                    return Err(CliError::GithubRateLimited { reset });
+                }
+                Err(CliError::Http(_)) if attempt < self.max_attempts => {
+                    tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+                }
+                Err(e) => match cached {
+                    Some(entry) => break entry.body,
+                    None => return Err(e),
+                },
+            }
+        };
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    async fn fetch(&self, path: &str, etag: Option<String>) -> Result<Fetched, CliError> {
+        let url = format!("{API_URL}{path}");
+        let agent = self.agent.clone();
+        let token = self.token.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<Fetched, CliError> {
+            let mut request = agent
+                .get(&url)
+                .header("Accept", "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", "2022-11-28");
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {token}"));
+            }
+            if let Some(etag) = etag {
+                request = request.header("If-None-Match", etag);
+            }
+
+            let mut response = request.call()?;
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+
+            match response.status().as_u16() {
+                304 => Ok(Fetched::NotModified),
+                403 | 429 if header("x-ratelimit-remaining").as_deref() == Some("0") => {
+                    let reset = header("x-ratelimit-reset")
+                        .and_then(|reset| reset.parse().ok())
+                        .unwrap_or_else(unix_now);
+                    Ok(Fetched::RateLimited { reset })
+                }
+                // Secondary rate limits only send `retry-after`
+                403 | 429 if header("retry-after").is_some() => {
+                    let retry_after: u64 = header("retry-after")
+                        .and_then(|secs| secs.parse().ok())
+                        .unwrap_or(60);
+                    Ok(Fetched::RateLimited {
+                        reset: unix_now() + retry_after,
+                    })
+                }
+                200..=299 => {
+                    let etag = header("etag");
+                    let body = response.body_mut().read_to_string()?;
+                    Ok(Fetched::Modified { etag, body })
+                }
+                status => Err(CliError::GithubStatus { status, url }),
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    fn cache_path(&self, path: &str) -> Option<PathBuf> {
+        self.cache_dir
+            .as_ref()
+            .map(|dir| dir.join(cache_file_name(path)))
+    }
+
+    async fn read_cache(&self, path: &str) -> Option<CacheEntry> {
+        let contents = tokio::fs::read_to_string(self.cache_path(path)?)
+            .await
+            .ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// The cache is best-effort; failing to write it must never fail the request.
+    async fn write_cache(&self, path: &str, entry: &CacheEntry) {
+        let Some(cache_path) = self.cache_path(path) else {
+            return;
+        };
+        let Ok(contents) = serde_json::to_string(entry) else {
+            return;
+        };
+
+        if let Some(parent) = cache_path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        let _ = tokio::fs::write(cache_path, contents).await;
+    }
+}
+
+/// The file a response to the API `path` is cached in. `.json` is appended rather than set as the
+/// extension, which would replace everything after a dot in the path, like a tag's patch version.
+fn cache_file_name(path: &str) -> String {
+    let name = path
+        .trim_start_matches('/')
+        .replace(['/', '?', '&', '='], "-");
+    format!("{name}.json")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cache_file_name;
+
+    #[test]
+    fn caches_each_tag_separately() {
+        let first = cache_file_name("/repos/venice-v5/venice/releases/tags/v0.3.0");
+        let second = cache_file_name("/repos/venice-v5/venice/releases/tags/v0.3.1");
+        assert_eq!(first, "repos-venice-v5-venice-releases-tags-v0.3.0.json");
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,45 @@
+/// Whether `path`, with `/` between its components, matches the glob `pattern`. `*` matches any
+/// run of characters within a component, `?` any one character, and a `**` component any number
+/// of components, including none.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_components(&pattern, &path)
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_components(rest, &path[skip..])),
+        Some((component, rest)) => path.split_first().is_some_and(|(name, path)| {
+            let pattern: Vec<char> = component.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            match_component(&pattern, &name) && match_components(rest, path)
+        }),
+    }
+}
+
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_component(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_component(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_component(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn matches_globs() {
+        assert!(matches("**/tests/**", "tests/test_drive.py"));
+        assert!(matches("**/tests/**", "robot/tests/unit/test_arm.py"));
+        assert!(!matches("**/tests/**", "robot/testing.py"));
+        assert!(matches("experiments/*.py", "experiments/odom.py"));
+        assert!(!matches("experiments/*.py", "experiments/old/odom.py"));
+        assert!(matches("scratch?.py", "scratch1.py"));
+        assert!(!matches("*.py", "robot/main.py"));
+    }
+}
@@ -0,0 +1,202 @@
+use std::{fmt::Display, time::Duration};
+
+use tokio::{
+    select,
+    time::{MissedTickBehavior, interval},
+};
+use vex_v5_serial::{
+    Connection,
+    protocol::cdc2::{
+        file::FileVendor,
+        system::{SystemFlagsPacket, SystemFlagsReplyPacket},
+    },
+    serial::{SerialConnection, SerialError},
+};
+
+use crate::{
+    artifact::ProgramInfo,
+    brain::{list_files, vex_crc32},
+    build::table_path,
+    device::{identity, open_connection, reconnect},
+    errors::CliError,
+    manifest::get_project,
+    screen_reader,
+};
+
+/// Field control state changes within a few hundred milliseconds of the match starting, so poll
+/// quickly enough that the alert comes before the robot has moved far.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Field control bits of the system flags
+const FIELD_CONNECTED: u32 = 1 << 13;
+const FIELD_AUTONOMOUS: u32 = 1 << 14;
+const FIELD_DISABLED: u32 = 1 << 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldState {
+    /// No field controller or competition switch
+    Unplugged,
+    Disabled,
+    Autonomous,
+    Driver,
+}
+
+impl FieldState {
+    fn from_flags(flags: u32) -> Self {
+        if flags & FIELD_CONNECTED == 0 {
+            Self::Unplugged
+        } else if flags & FIELD_DISABLED != 0 {
+            Self::Disabled
+        } else if flags & FIELD_AUTONOMOUS != 0 {
+            Self::Autonomous
+        } else {
+            Self::Driver
+        }
+    }
+
+    fn enabled(self) -> bool {
+        matches!(self, Self::Autonomous | Self::Driver)
+    }
+}
+
+impl Display for FieldState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Unplugged => "no field control",
+            Self::Disabled => "disabled",
+            Self::Autonomous => "autonomous",
+            Self::Driver => "driver control",
+        })
+    }
+}
+
+/// The program the brain should be running: the last build of the project, in its slot.
+struct Expected {
+    slot: u8,
+    size: u32,
+    crc32: u32,
+}
+
+async fn read_state(conn: &mut SerialConnection) -> Result<(FieldState, u8), SerialError> {
+    let reply = conn
+        .handshake::<SystemFlagsReplyPacket>(Duration::from_secs(1), 2, SystemFlagsPacket::new(()))
+        .await?;
+    let flags = reply.payload.map_err(SerialError::Nack)?;
+    Ok((FieldState::from_flags(flags.flags), flags.current_program))
+}
+
+/// What's wrong with the program the brain is about to run. `running` is the slot that's
+/// running, if it matters yet: before the match is enabled, nothing needs to be.
+async fn check(
+    conn: &mut SerialConnection,
+    expected: &Expected,
+    running: Option<u8>,
+) -> Result<Vec<String>, CliError> {
+    let mut problems = Vec::new();
+    let file_name = format!("slot_{}.bin", expected.slot);
+    let file = list_files(conn, FileVendor::User)
+        .await?
+        .into_iter()
+        .find(|file| file.name == file_name);
+    match file {
+        None => problems.push(format!(
+            "slot {} is empty; upload the program",
+            expected.slot
+        )),
+        Some(file) if file.size != expected.size || file.crc32 != expected.crc32 => {
+            problems.push(format!(
+                "slot {} holds a different build than the last one here; upload again",
+                expected.slot
+            ));
+        }
+        Some(_) => {}
+    }
+    match running {
+        Some(0) => problems.push("no program is running; start it from the brain".to_string()),
+        Some(slot) if slot != expected.slot => problems.push(format!(
+            "slot {slot} is running, not slot {}; select the right program on the brain",
+            expected.slot
+        )),
+        _ => {}
+    }
+    Ok(problems)
+}
+
+fn alert(problems: &[String]) {
+    let bell = if screen_reader() { "" } else { "\x07\x07\x07" };
+    eprintln!("{bell}");
+    eprintln!("!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!");
+    eprintln!("!!  WRONG PROGRAM ON THE BRAIN");
+    for problem in problems {
+        eprintln!("!!  {problem}");
+    }
+    eprintln!("!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!");
+}
+
+/// `venice guard`: stays connected and watches field control. When a field controller is
+/// plugged in, and again the moment the match is enabled, checks the brain has the last build
+/// in the project's slot and is running it, and alerts loudly if not.
+pub async fn guard() -> Result<(), CliError> {
+    let slot = ProgramInfo::from_project(&get_project().await?)?.slot;
+    let vpt = match tokio::fs::read(table_path().await?).await {
+        Ok(vpt) => vpt,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(CliError::NotBuilt),
+        Err(e) => return Err(e.into()),
+    };
+    let expected = Expected {
+        slot,
+        size: vpt.len() as u32,
+        crc32: vex_crc32(&vpt),
+    };
+
+    let mut conn = open_connection().await?;
+    let brain = identity().cloned().unwrap_or_default();
+    println!("Guarding slot {slot} on {brain}. Press Ctrl+C to stop.");
+    let problems = check(&mut conn, &expected, None).await?;
+    if !problems.is_empty() {
+        alert(&problems);
+    }
+
+    let mut poll = interval(POLL_INTERVAL);
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last: Option<FieldState> = None;
+    loop {
+        select! {
+            _ = poll.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+
+        let (state, running) = match read_state(&mut conn).await {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("warning: lost the brain ({e}); reconnecting");
+                conn = reconnect(RECONNECT_TIMEOUT).await?;
+                continue;
+            }
+        };
+        if last == Some(state) {
+            continue;
+        }
+        println!("Field: {state}");
+
+        let plugged_in =
+            state == FieldState::Disabled && last.is_none_or(|s| s == FieldState::Unplugged);
+        let enabled = state.enabled() && !last.is_some_and(FieldState::enabled);
+        last = Some(state);
+        if !(plugged_in || enabled) {
+            continue;
+        }
+
+        match check(&mut conn, &expected, enabled.then_some(running)).await {
+            Ok(problems) if problems.is_empty() => println!(
+                "✓ Slot {slot} has the last build{}",
+                if enabled { " and is running" } else { "" }
+            ),
+            Ok(problems) => alert(&problems),
+            // Keep watching; the next state change checks again
+            Err(e) => eprintln!("warning: couldn't check the brain: {e}"),
+        }
+    }
+}
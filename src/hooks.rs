@@ -0,0 +1,128 @@
+use std::process::Command;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use vex_v5_serial::{protocol::cdc2::file::FileExitAction, serial::SerialConnection};
+
+use crate::{
+    config, copro, device::identity, errors::CliError, frozen, manifest::Project, project_dir,
+    runtime::RuntimeSource, terminal::terminal,
+};
+
+/// A step of [tool.venice].after-upload.
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum AfterUploadStep {
+    Builtin(BuiltinStep),
+    /// Run a command from the project directory, e.g. `{ command = ["./notify.sh"] }`. The
+    /// program's name and slot are passed in `VENICE_PROGRAM` and `VENICE_SLOT`.
+    Command {
+        command: Vec<String>,
+    },
+    /// POST a JSON description of the upload to a URL, e.g. `{ webhook = "https://..." }`
+    Webhook {
+        webhook: String,
+    },
+}
+
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuiltinStep {
+    /// Start the program as soon as it's uploaded
+    Run,
+    /// Open the terminal. Always runs last, since it doesn't return until Ctrl+C.
+    Terminal,
+    /// POST to the webhook in [notify].webhook of the venice config
+    Notify,
+    /// Push to the co-processor configured in [tool.venice.copro]
+    Copro,
+}
+
+/// What the brain should do once the upload finishes, if `steps` decides it.
+pub fn exit_action(steps: &[AfterUploadStep]) -> Option<FileExitAction> {
+    steps
+        .contains(&AfterUploadStep::Builtin(BuiltinStep::Run))
+        .then_some(FileExitAction::RunProgram)
+}
+
+fn run_command(command: &[String], project: &Project) -> Result<(), CliError> {
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(project_dir()?)
+        .env("VENICE_PROGRAM", &project.name)
+        .env("VENICE_SLOT", project.slot.unwrap_or(1).to_string())
+        .status()?;
+
+    if !status.success() {
+        return Err(CliError::HookFailed {
+            command: command.join(" "),
+            status: status.code().unwrap_or(-1),
+        });
+    }
+    Ok(())
+}
+
+/// Posts to a webhook. A notification that doesn't arrive isn't worth failing the upload over,
+/// so problems are only warned about.
+async fn post_webhook(url: &str, project: &Project) {
+    if frozen() {
+        eprintln!("warning: skipping webhook, --frozen forbids network access");
+        return;
+    }
+
+    let body = serde_json::json!({
+        "event": "uploaded",
+        "program": project.name,
+        "slot": project.slot,
+        "version": project.version,
+        "device": identity(),
+    })
+    .to_string();
+    let url = url.to_string();
+    let result = tokio::task::spawn_blocking(move || {
+        ureq::post(&url)
+            .header("Content-Type", "application/json")
+            .send(body)
+            .map(|response| response.status().as_u16())
+    })
+    .await
+    .unwrap();
+
+    match result {
+        Ok(200..=299) => {}
+        Ok(status) => eprintln!("warning: webhook returned status {status}"),
+        Err(e) => eprintln!("warning: couldn't reach webhook: {e}"),
+    }
+}
+
+/// Runs [tool.venice].after-upload once the program is on the brain. `run` has already been
+/// applied as the upload's exit action; `terminal` is left until the end.
+pub async fn run_after_upload(
+    steps: &[AfterUploadStep],
+    project: &Project,
+    conn: SerialConnection,
+    runtime_source: Option<RuntimeSource>,
+) -> Result<(), CliError> {
+    for step in steps {
+        match step {
+            AfterUploadStep::Builtin(BuiltinStep::Run | BuiltinStep::Terminal) => {}
+            AfterUploadStep::Builtin(BuiltinStep::Copro) => copro::push().await?,
+            AfterUploadStep::Builtin(BuiltinStep::Notify) => match &config().notify.webhook {
+                Some(url) => post_webhook(url, project).await,
+                None => eprintln!(
+                    "warning: `notify` needs [notify].webhook to be set in the venice config"
+                ),
+            },
+            AfterUploadStep::Command { command } => run_command(command, project)?,
+            AfterUploadStep::Webhook { webhook } => post_webhook(webhook, project).await,
+        }
+    }
+
+    if steps.contains(&AfterUploadStep::Builtin(BuiltinStep::Terminal)) {
+        terminal(conn, runtime_source).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::sync::OnceLock;
+
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle};
+use unic_langid::LanguageIdentifier;
+
+use crate::config::config_dir;
+
+const LOCALES_DIR: &str = "locales";
+const FALLBACK_LANG: &str = "en";
+
+/// Translations shipped with venice. English is complete; others may miss messages, which then
+/// fall back to English.
+const BUNDLED: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+
+/// Bundles to look messages up in, most preferred first.
+struct Localizer {
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+/// A bundle for `lang` from the bundled translation and `<config dir>/locales/<lang>.ftl`, whose
+/// messages take precedence. `None` if neither exists.
+fn load_bundle(lang: &str) -> Option<FluentBundle<FluentResource>> {
+    let bundled = BUNDLED
+        .iter()
+        .find(|(name, _)| *name == lang)
+        .map(|(_, source)| source.to_string());
+    let user = config_dir().and_then(|dir| {
+        std::fs::read_to_string(dir.join(LOCALES_DIR).join(format!("{lang}.ftl"))).ok()
+    });
+    if bundled.is_none() && user.is_none() {
+        return None;
+    }
+
+    let langid: LanguageIdentifier = lang.parse().ok()?;
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Unicode isolation marks show up as garbage in most terminals
+    bundle.set_use_isolating(false);
+    for source in [bundled, user].into_iter().flatten() {
+        // A broken user file shouldn't stop venice; parse what we can
+        let resource = FluentResource::try_new(source).unwrap_or_else(|(resource, _)| resource);
+        bundle.add_resource_overriding(resource);
+    }
+    Some(bundle)
+}
+
+impl Localizer {
+    fn new(lang: &str) -> Self {
+        let mut bundles = Vec::new();
+        // `es-MX` uses `es-MX.ftl` if there is one, then `es.ftl`
+        let mut candidates = vec![lang.to_string()];
+        if let Ok(langid) = lang.parse::<LanguageIdentifier>() {
+            candidates.push(langid.language.to_string());
+        }
+        candidates.push(FALLBACK_LANG.to_string());
+        candidates.dedup();
+
+        for candidate in candidates {
+            if let Some(bundle) = load_bundle(&candidate) {
+                bundles.push(bundle);
+            }
+        }
+        Self { bundles }
+    }
+
+    fn format(&self, id: &str, args: &FluentArgs) -> Option<String> {
+        self.bundles.iter().find_map(|bundle| {
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = Vec::new();
+            Some(
+                bundle
+                    .format_pattern(pattern, Some(args), &mut errors)
+                    .into_owned(),
+            )
+        })
+    }
+}
+
+/// Selects the output language: `lang` if given (from `--lang` or the config), otherwise the
+/// system locale. Messages formatted before this is called are in English.
+pub fn init(lang: Option<&str>) {
+    let lang = lang
+        .map(str::to_string)
+        .or_else(sys_locale::get_locale)
+        .unwrap_or_else(|| FALLBACK_LANG.to_string());
+    let _ = LOCALIZER.set(Localizer::new(&lang));
+}
+
+/// Formats the message `id`. Use the [`tr!`](crate::tr) macro rather than calling this directly.
+pub fn translate(id: &str, args: Vec<(&str, FluentValue<'_>)>) -> String {
+    let localizer = LOCALIZER.get_or_init(|| Localizer::new(FALLBACK_LANG));
+    let args = FluentArgs::from_iter(args);
+    localizer
+        .format(id, &args)
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Formats a localized message: `tr!("deps-added", name = name, version = version.to_string())`.
+#[macro_export]
+macro_rules! tr {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::translate(
+            $id,
+            vec![$((stringify!($name), ::fluent_bundle::FluentValue::from($value))),*],
+        )
+    };
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::PathBuf,
+};
+
+use miette::NamedSource;
+
+use crate::{
+    errors::CliError,
+    pysource::{Import, column_of, line_of, resolve_relative, scan},
+    runtime::describe,
+};
+
+/// Modules compiled into the runtime, with the first runtime release that has them. Used when the
+/// runtime's published description isn't available.
+const BUILTIN_MODULES: &[(&str, &str)] = &[
+    ("array", "0.1.0"),
+    ("binascii", "0.1.0"),
+    ("builtins", "0.1.0"),
+    ("cmath", "0.1.0"),
+    ("collections", "0.1.0"),
+    ("errno", "0.1.0"),
+    ("gc", "0.1.0"),
+    ("hashlib", "0.1.0"),
+    ("heapq", "0.1.0"),
+    ("io", "0.1.0"),
+    ("json", "0.1.0"),
+    ("math", "0.1.0"),
+    ("micropython", "0.1.0"),
+    ("random", "0.1.0"),
+    ("re", "0.1.0"),
+    ("struct", "0.1.0"),
+    ("sys", "0.1.0"),
+    ("time", "0.1.0"),
+    // Injected as bytecode rather than compiled in, but importable all the same
+    ("typing", "0.1.0"),
+    ("vasyncio", "0.1.0"),
+    ("venice", "0.1.0"),
+];
+
+/// The runtime's built-in modules, from its published description if it can be fetched. Without a
+/// known runtime version, assumes the newest.
+pub async fn builtin_modules(runtime: Option<&semver::Version>) -> BTreeSet<String> {
+    if let Some(version) = runtime
+        && let Ok(description) = describe(version).await
+    {
+        return description.modules.into_iter().map(|m| m.name).collect();
+    }
+
+    fallback_builtin_modules(runtime)
+}
+
+fn fallback_builtin_modules(runtime: Option<&semver::Version>) -> BTreeSet<String> {
+    BUILTIN_MODULES
+        .iter()
+        .filter(|(_, since)| {
+            runtime.is_none_or(|runtime| *runtime >= semver::Version::parse(since).unwrap())
+        })
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// A compiled source file, as seen by the import check.
+pub struct CheckedSource {
+    pub path: PathBuf,
+    pub module: String,
+    pub is_package: bool,
+    pub source: String,
+}
+
+fn resolves(module: &str, packed: &BTreeSet<String>, builtins: &BTreeSet<String>) -> bool {
+    let top_level = module.split('.').next().unwrap();
+    packed.contains(module) || builtins.contains(module) || builtins.contains(top_level)
+}
+
+/// Checks that every import in `sources` names a module that will exist on the brain: one in
+/// the program table, or one built into the runtime. Imports inside `try` blocks are skipped, since
+/// the program is presumably ready for them to fail.
+pub fn check_imports(
+    sources: &[CheckedSource],
+    packed: &BTreeSet<String>,
+    builtins: &BTreeSet<String>,
+) -> Result<(), CliError> {
+    let mut unresolved = Vec::new();
+
+    for file in sources {
+        for import in scan(&file.source).imports {
+            if import.guarded() {
+                continue;
+            }
+
+            let modules: Vec<(String, Range<usize>)> = match &import {
+                Import::Import { modules, .. } => modules
+                    .iter()
+                    .map(|alias| (alias.name.text.clone(), alias.name.span.clone()))
+                    .collect(),
+                Import::From {
+                    level,
+                    module,
+                    span,
+                    ..
+                } => {
+                    let name = module.as_ref().map(|m| m.text.as_str());
+                    // A relative import climbing out of the project is always unresolved
+                    vec![(
+                        resolve_relative(&file.module, file.is_package, *level, name)
+                            .unwrap_or_else(|| {
+                                format!("{}{}", ".".repeat(*level), name.unwrap_or(""))
+                            }),
+                        module.as_ref().map_or(span.clone(), |m| m.span.clone()),
+                    )]
+                }
+            };
+
+            for (module, span) in modules {
+                if !resolves(&module, packed, builtins) {
+                    unresolved.push(CliError::UnresolvedImport {
+                        module,
+                        file: file.path.clone(),
+                        line: line_of(&file.source, span.start),
+                        columns: column_of(&file.source, span.start)
+                            ..column_of(&file.source, span.end),
+                        source_code: NamedSource::new(
+                            file.path.display().to_string(),
+                            file.source.clone(),
+                        ),
+                        span: span.into(),
+                    });
+                }
+            }
+        }
+    }
+
+    if unresolved.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::UnresolvedImports {
+            count: unresolved.len(),
+            imports: unresolved,
+        })
+    }
+}
+
+/// The packed modules each of `sources` imports, guarded imports included. Importing `a.b` runs
+/// package `a` first, and `from a import b` imports submodule `a.b` if there is one, so both
+/// count.
+pub fn import_graph(
+    sources: &[CheckedSource],
+    packed: &BTreeSet<String>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut graph = BTreeMap::new();
+    for file in sources {
+        let mut imported = BTreeSet::new();
+        for import in scan(&file.source).imports {
+            let targets: Vec<String> = match &import {
+                Import::Import { modules, .. } => modules
+                    .iter()
+                    .map(|alias| alias.name.text.clone())
+                    .collect(),
+                Import::From {
+                    level,
+                    module,
+                    names,
+                    ..
+                } => {
+                    let name = module.as_ref().map(|m| m.text.as_str());
+                    let Some(base) = resolve_relative(&file.module, file.is_package, *level, name)
+                    else {
+                        continue;
+                    };
+                    let submodules = names
+                        .iter()
+                        .map(|alias| format!("{base}.{}", alias.name.text));
+                    std::iter::once(base.clone()).chain(submodules).collect()
+                }
+            };
+
+            for target in targets {
+                let parts: Vec<&str> = target.split('.').collect();
+                for end in 1..=parts.len() {
+                    let prefix = parts[..end].join(".");
+                    if packed.contains(&prefix) && prefix != file.module {
+                        imported.insert(prefix);
+                    }
+                }
+            }
+        }
+        graph.insert(file.module.clone(), imported);
+    }
+    graph
+}
+
+/// The modules in `graph` that nothing imported from `root`, directly or not, imports.
+pub fn unreachable_modules(
+    graph: &BTreeMap<String, BTreeSet<String>>,
+    root: &str,
+) -> BTreeSet<String> {
+    let mut reached = BTreeSet::from([root.to_string()]);
+    let mut queue = vec![root];
+    while let Some(module) = queue.pop() {
+        for imported in graph.get(module).into_iter().flatten() {
+            if reached.insert(imported.clone()) {
+                queue.push(imported);
+            }
+        }
+    }
+    graph
+        .keys()
+        .filter(|module| !reached.contains(*module))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        path::{Path, PathBuf},
+    };
+
+    use super::{
+        CheckedSource, check_imports, fallback_builtin_modules, import_graph, unreachable_modules,
+    };
+
+    fn source(module: &str, is_package: bool, source: &str) -> CheckedSource {
+        CheckedSource {
+            path: PathBuf::from(format!("{module}.py")),
+            module: module.to_string(),
+            is_package,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_unreachable_modules() {
+        let sources = [
+            source("main", false, "import robot.drive\nfrom robot import arm\n"),
+            source("robot", true, ""),
+            source("robot.drive", false, "from .pid import PID\nimport math\n"),
+            source("robot.pid", false, ""),
+            source("robot.arm", false, ""),
+            source("experiments.odom", false, "import robot.drive\n"),
+        ];
+        let packed: BTreeSet<String> = sources.iter().map(|s| s.module.clone()).collect();
+        let graph = import_graph(&sources, &packed);
+
+        assert_eq!(
+            graph["main"],
+            BTreeSet::from(["robot".into(), "robot.drive".into(), "robot.arm".into()])
+        );
+        assert_eq!(
+            unreachable_modules(&graph, "main"),
+            BTreeSet::from(["experiments.odom".to_string()])
+        );
+    }
+
+    /// Every `.py` file under `dir`, as the module it builds.
+    fn fixture_sources(root: &Path, dir: &Path, sources: &mut Vec<CheckedSource>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                fixture_sources(root, &path, sources);
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "py") {
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap().with_extension("");
+            let is_package = relative.ends_with("__init__");
+            let parts: Vec<_> = relative
+                .iter()
+                .map(|part| part.to_string_lossy().into_owned())
+                .filter(|part| part != "__init__")
+                .collect();
+            sources.push(CheckedSource {
+                source: std::fs::read_to_string(&path).unwrap(),
+                path,
+                module: parts.join("."),
+                is_package,
+            });
+        }
+    }
+
+    #[test]
+    fn fixtures_import_only_known_modules() {
+        // Without a runtime version, as a plain `venice build` checks them
+        let builtins = fallback_builtin_modules(None);
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+        for fixture in std::fs::read_dir(fixtures).unwrap() {
+            let root = fixture.unwrap().path();
+            let mut sources = Vec::new();
+            fixture_sources(&root, &root, &mut sources);
+            let packed: BTreeSet<String> = sources.iter().map(|s| s.module.clone()).collect();
+            assert!(
+                check_imports(&sources, &packed, &builtins).is_ok(),
+                "{} has unresolved imports",
+                root.display()
+            );
+        }
+    }
+}
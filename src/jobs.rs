@@ -0,0 +1,364 @@
+use std::{
+    fmt,
+    fs::{self, File, TryLockError},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::{config::cache_dir, errors::CliError, registry::sha256_hex, steal, tr};
+
+/// How often a queued command checks whether it's its turn for the device.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Locked by the command whose turn it is, in each device's queue directory. The queue's order
+/// comes from a snapshot that can change before the front job marks itself active, so only the
+/// lock decides whose turn it is.
+const TURN_LOCK: &str = "turn.lock";
+
+/// How urgently a command needs the device. Commands queued for the same device get it in this
+/// order, and in the order they queued within each.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum JobPriority {
+    /// Someone is watching it, like `terminal`, `run` and `dev`
+    Interactive,
+    #[default]
+    Normal,
+    /// Polling nobody is waiting on, like the language server's slot checks
+    Background,
+}
+
+impl fmt::Display for JobPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Interactive => "interactive",
+            Self::Normal => "normal",
+            Self::Background => "background",
+        })
+    }
+}
+
+/// A command's place in a device's queue, as `venice jobs` shows it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct JobInfo {
+    pub port: String,
+    /// The top-level subcommand, e.g. `upload`
+    pub command: String,
+    pub pid: u32,
+    pub priority: JobPriority,
+    /// Milliseconds since the Unix epoch
+    pub queued_at: u64,
+    /// Whether it's its turn, so it has the device or is opening it
+    pub active: bool,
+}
+
+impl JobInfo {
+    /// Where it goes in the queue: the command with the device first, then by priority, then
+    /// first come first served.
+    fn order(&self) -> (bool, JobPriority, u64, u32) {
+        (!self.active, self.priority, self.queued_at, self.pid)
+    }
+}
+
+/// A queued command. It's a `.lock` file this process holds a lock on, so that it leaves the
+/// queue when the process exits however it exits, and a `.json` file with its [`JobInfo`].
+struct Job {
+    dir: PathBuf,
+    id: String,
+    info: JobInfo,
+    _lock: File,
+    /// [`TURN_LOCK`], once it's this job's turn
+    turn: Option<File>,
+}
+
+impl Job {
+    fn create(dir: &Path, info: JobInfo) -> Result<Self, CliError> {
+        fs::create_dir_all(dir)?;
+        let id = format!("{}-{}", info.queued_at, info.pid);
+        // Locked before the info is written, so an info file without a locked `.lock` beside it
+        // is always one whose process is gone
+        let lock = File::create(dir.join(format!("{id}.lock")))?;
+        lock.try_lock().map_err(std::io::Error::from)?;
+        let job = Self {
+            dir: dir.to_path_buf(),
+            id,
+            info,
+            _lock: lock,
+            turn: None,
+        };
+        job.write_info()?;
+        Ok(job)
+    }
+
+    /// Takes the turn if this job is at the front of the queue and nobody else has it.
+    fn take_turn(&mut self) -> Result<bool, CliError> {
+        let queue = live_jobs(&self.dir);
+        if !matches!(
+            queue.iter().position(|(id, _)| *id == self.id),
+            Some(0) | None
+        ) {
+            return Ok(false);
+        }
+        let turn = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join(TURN_LOCK))?;
+        match turn.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(false),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        self.turn = Some(turn);
+        self.info.active = true;
+        self.write_info()?;
+        Ok(true)
+    }
+
+    fn write_info(&self) -> Result<(), CliError> {
+        fs::write(
+            self.dir.join(format!("{}.json", self.id)),
+            serde_json::to_vec(&self.info).unwrap(),
+        )?;
+        Ok(())
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.dir.join(format!("{}.json", self.id)));
+        let _ = fs::remove_file(self.dir.join(format!("{}.lock", self.id)));
+    }
+}
+
+/// The subcommand this process is running and its priority, set once at startup.
+static CURRENT: OnceLock<(String, JobPriority)> = OnceLock::new();
+
+/// The jobs whose turn it is, kept until [`release`] or until the process exits.
+static HELD: Mutex<Vec<Job>> = Mutex::new(Vec::new());
+
+/// Records what this process is running, for its place in device queues.
+pub fn set_current(command: String, priority: JobPriority) {
+    let _ = CURRENT.set((command, priority));
+}
+
+fn jobs_dir() -> PathBuf {
+    cache_dir().unwrap_or_else(std::env::temp_dir).join("jobs")
+}
+
+fn queue_dir(port: &str) -> PathBuf {
+    jobs_dir().join(&sha256_hex(port.as_bytes())[..16])
+}
+
+/// The live jobs in a device's queue directory, in queue order. Those whose process has exited
+/// are removed along the way.
+fn live_jobs(dir: &Path) -> Vec<(String, JobInfo)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut jobs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(id) = path.file_stem().map(|id| id.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let lock_path = dir.join(format!("{id}.lock"));
+        let live = match File::open(&lock_path).map(|lock| lock.try_lock()) {
+            Ok(Err(TryLockError::WouldBlock)) => true,
+            Ok(Err(TryLockError::Error(_))) => continue,
+            // Nobody holds it, or it's gone, so its process has exited
+            Ok(Ok(())) | Err(_) => false,
+        };
+        if !live {
+            let _ = fs::remove_file(&path);
+            let _ = fs::remove_file(&lock_path);
+            continue;
+        }
+        if let Some(info) = fs::read(&path)
+            .ok()
+            .and_then(|info| serde_json::from_slice::<JobInfo>(&info).ok())
+        {
+            jobs.push((id, info));
+        }
+    }
+    jobs.sort_by_key(|(_, info)| info.order());
+    jobs
+}
+
+/// Waits until it's this process's turn for the device on `port`: until every venice command
+/// ahead of it in the queue is done with the device. It keeps its turn until [`release`] or until
+/// the process exits. `--steal` skips the queue.
+pub async fn wait_turn(port: &str) -> Result<(), CliError> {
+    if steal() || HELD.lock().unwrap().iter().any(|job| job.info.port == port) {
+        return Ok(());
+    }
+
+    let (command, priority) = CURRENT
+        .get()
+        .cloned()
+        .unwrap_or_else(|| ("venice".to_string(), JobPriority::default()));
+    let dir = queue_dir(port);
+    let mut job = Job::create(
+        &dir,
+        JobInfo {
+            port: port.to_string(),
+            command,
+            pid: std::process::id(),
+            priority,
+            queued_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            active: false,
+        },
+    )?;
+
+    let mut waiting_on = None;
+    while !job.take_turn()? {
+        if let Some((ahead_id, ahead)) = live_jobs(&dir).first()
+            && *ahead_id != job.id
+            && waiting_on.as_ref() != Some(ahead_id)
+        {
+            eprintln!(
+                "{}",
+                tr!(
+                    "device-queue-waiting",
+                    command = ahead.command.as_str(),
+                    pid = ahead.pid,
+                    port = port
+                )
+            );
+            waiting_on = Some(ahead_id.clone());
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+    HELD.lock().unwrap().push(job);
+    Ok(())
+}
+
+/// Gives up this process's turn for every device it has one for, for the next command in their
+/// queues.
+pub fn release() {
+    HELD.lock().unwrap().clear();
+}
+
+/// Every device's queue, in order, for devices with anything in it.
+pub fn queues() -> Vec<Vec<JobInfo>> {
+    let Ok(entries) = fs::read_dir(jobs_dir()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            live_jobs(&entry.path())
+                .into_iter()
+                .map(|(_, info)| info)
+                .collect::<Vec<_>>()
+        })
+        .filter(|queue| !queue.is_empty())
+        .collect()
+}
+
+/// `venice jobs`: which venice commands have or are waiting for each device.
+pub fn list() {
+    let queues = queues();
+    if queues.is_empty() {
+        println!("No venice commands are using or waiting for a device");
+        return;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    for queue in queues {
+        println!("{}", queue[0].port);
+        for (place, job) in queue.iter().enumerate() {
+            let state = if job.active {
+                "using".to_string()
+            } else {
+                format!("waiting #{place}")
+            };
+            println!(
+                "  {state:<10} venice {:<10} pid {:<7} {:<11} {}s",
+                job.command,
+                job.pid,
+                job.priority,
+                now.saturating_sub(job.queued_at) / 1000
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Job, JobInfo, JobPriority, live_jobs};
+
+    fn info(pid: u32, priority: JobPriority, queued_at: u64, active: bool) -> JobInfo {
+        JobInfo {
+            port: "/dev/ttyACM0".to_string(),
+            command: "upload".to_string(),
+            pid,
+            priority,
+            queued_at,
+            active,
+        }
+    }
+
+    #[test]
+    fn queues_by_turn_priority_and_age() {
+        let dir = std::env::temp_dir().join(format!("venice-jobs-{}", std::process::id()));
+        let _background = Job::create(&dir, info(1, JobPriority::Background, 1, true)).unwrap();
+        let _late = Job::create(&dir, info(2, JobPriority::Normal, 3, false)).unwrap();
+        let _early = Job::create(&dir, info(3, JobPriority::Normal, 2, false)).unwrap();
+        let interactive = Job::create(&dir, info(4, JobPriority::Interactive, 4, false)).unwrap();
+        let pids = |dir| {
+            live_jobs(dir)
+                .into_iter()
+                .map(|(_, info)| info.pid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pids(&dir), [1, 4, 3, 2]);
+
+        // A job leaves the queue when it's dropped, and one whose process is gone is cleaned up
+        drop(interactive);
+        std::fs::write(
+            dir.join("0-5.json"),
+            serde_json::to_vec(&info(5, JobPriority::Interactive, 0, true)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(pids(&dir), [1, 3, 2]);
+        assert!(!dir.join("0-5.json").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn one_job_has_the_turn() {
+        let dir = std::env::temp_dir().join(format!("venice-jobs-turn-{}", std::process::id()));
+        let mut normal = Job::create(&dir, info(1, JobPriority::Normal, 1, false)).unwrap();
+        assert!(normal.take_turn().unwrap());
+
+        // As if it hadn't marked itself active yet when a more urgent job came along and found
+        // itself at the front
+        normal.info.active = false;
+        normal.write_info().unwrap();
+        let mut interactive =
+            Job::create(&dir, info(2, JobPriority::Interactive, 2, false)).unwrap();
+        assert_eq!(live_jobs(&dir)[0].1.pid, 2);
+        assert!(!interactive.take_turn().unwrap());
+
+        drop(normal);
+        assert!(interactive.take_turn().unwrap());
+
+        drop(interactive);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use inquire::{Confirm, Text};
+use vex_v5_serial::protocol::cdc2::file::FileExitAction;
+
+use crate::{
+    PROJECT_DIR,
+    build::{BuildOptions, build},
+    device::{open_device, wait_for_device},
+    errors::CliError,
+    manifest::{get_project, get_project_at},
+    new::new,
+    project_dir,
+    runtime::RuntimeSource,
+    stats::format_size,
+    terminal::terminal,
+    upload::upload,
+};
+
+const STEPS: usize = 5;
+
+/// How long to wait for a brain to be plugged in before offering to retry.
+const DEVICE_WAIT: Duration = Duration::from_secs(60);
+
+fn step(number: usize, title: &str, explanation: &str) {
+    println!("\n── Step {number} of {STEPS}: {title} ──\n");
+    println!("{explanation}\n");
+}
+
+/// Runs `action` until it succeeds, showing what went wrong and offering another go after each
+/// failure. Declining ends the tutorial with the last error.
+async fn checkpoint<T>(mut action: impl AsyncFnMut() -> miette::Result<T>) -> miette::Result<T> {
+    loop {
+        match action().await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                eprintln!("{err:?}");
+                let retry = Confirm::new("Fix that and try this step again?")
+                    .with_default(true)
+                    .prompt()
+                    .unwrap_or(false);
+                if !retry {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// `venice learn`: a guided first session that creates a project, builds it, finds the brain,
+/// uploads the program and opens the terminal. Every step is checked with the same code the
+/// individual commands use, so the tutorial only moves on once the step really worked.
+pub async fn learn(runtime_source: Option<RuntimeSource>) -> miette::Result<()> {
+    println!("Welcome to venice! This walks you through getting a first program onto a V5 brain.");
+    println!("You'll need a brain, a USB cable and about five minutes.");
+
+    step(
+        1,
+        "create a project",
+        "A project is a folder with a pyproject.toml describing the program and a main.py it \
+         starts from. `venice new <name>` makes one.",
+    );
+    match project_dir() {
+        Ok(dir) => {
+            let project = get_project().await?;
+            println!(
+                "✓ You're already in the project `{}`, so we'll use that.",
+                project.name
+            );
+            println!("  ({})", dir.display());
+        }
+        Err(_) => {
+            let dir = checkpoint(async || {
+                let name = Text::new("What should the project be called?")
+                    .with_default("my-robot")
+                    .prompt()
+                    .map_err(|_| CliError::Interrupted)?;
+                new(&name, None, None)?;
+                let dir = std::fs::canonicalize(&name).map_err(CliError::Io)?;
+                // Checks the manifest `new` wrote is one the other commands can read
+                get_project_at(&dir).await?;
+                Ok(dir)
+            })
+            .await?;
+            println!("✓ Created {}", dir.display());
+            println!("  Later, run venice commands from inside this folder.");
+            PROJECT_DIR.set(dir).unwrap();
+        }
+    }
+
+    step(
+        2,
+        "build it",
+        "Building compiles the project's Python into a program table, the file the brain runs. \
+         `venice build` does this; `venice run` and `venice upload` do it for you.",
+    );
+    let runtime = runtime_source.as_ref().map(|source| source.version.clone());
+    let vpt = checkpoint(async || {
+        Ok(build(&BuildOptions {
+            runtime: runtime.clone(),
+            ..Default::default()
+        })
+        .await?)
+    })
+    .await?;
+    println!("✓ Built a {} program table", format_size(vpt.len() as u64));
+
+    step(
+        3,
+        "connect the brain",
+        "Plug the brain into this computer with a USB cable and turn it on. A controller paired \
+         with the brain works too. `venice devices` lists what's connected.",
+    );
+    checkpoint(async || {
+        println!("Waiting for a brain...");
+        wait_for_device(DEVICE_WAIT).await?;
+        // Finding the port isn't enough: make sure the brain answers on it
+        open_device().await?;
+        Ok(())
+    })
+    .await?;
+    println!("✓ The brain is connected and answering");
+
+    step(
+        4,
+        "upload and run",
+        "Uploading sends the program table to a slot on the brain. The first upload also sends \
+         the venice runtime, so it takes a little longer. `venice run` uploads and starts the \
+         program, like this step does.",
+    );
+    let slot = get_project().await?.slot;
+    let connection = checkpoint(async || {
+        Ok(upload(
+            Some(FileExitAction::RunProgram),
+            runtime_source.clone(),
+            false,
+        )
+        .await?)
+    })
+    .await?;
+    match slot {
+        Some(slot) => println!("✓ Uploaded to slot {slot}; the brain's screen shows it running"),
+        None => println!("✓ Uploaded; the brain's screen shows it running"),
+    }
+
+    step(
+        5,
+        "see its output",
+        "The terminal shows what the program prints, and sends what you type to it. You should \
+         see \"Hello, Venice!\" from main.py. Press Ctrl+C to leave.",
+    );
+    terminal(connection, runtime_source).await?;
+
+    println!(
+        "\nThat's everything! To keep going, edit main.py in {}",
+        project_dir()?.display()
+    );
+    println!("and run `venice run` from that folder to upload it and watch its output.");
+    println!("`venice example list` has bigger programs to learn from.");
+    Ok(())
+}
@@ -3,32 +3,110 @@ pub const SRC_DIR: &str = "src";
 pub const BUILD_DIR: &str = "build";
 pub const TABLE_FILE: &str = "out.vpt";
 
+pub mod artifact;
+pub mod assets;
+pub mod auton;
+pub mod battery;
+pub mod bisect;
+pub mod brain;
 pub mod build;
+pub mod buildinfo;
+pub mod cache;
+pub mod calib;
+pub mod ci;
+pub mod clock;
+pub mod completions;
+pub mod config;
+pub mod copro;
+pub mod data;
+pub mod deps;
+pub mod dev;
+pub mod device;
+pub mod diagnostics;
+pub mod doctor;
+pub mod env;
 pub mod errors;
+pub mod example;
+pub mod explain;
+pub mod format;
+pub mod generate;
+pub mod github;
+pub mod glob;
+pub mod guard;
+pub mod hooks;
+pub mod i18n;
+pub mod imports;
+pub mod jobs;
+pub mod learn;
+pub mod log;
+pub mod lsp;
 pub mod manifest;
+pub mod migrate;
+pub mod mpy;
 pub mod new;
+pub mod palette;
+pub mod patch;
+pub mod ports;
+pub mod purge;
+pub mod push;
+pub mod pysource;
+pub mod radio;
+pub mod redact;
+pub mod registry;
+pub mod releases;
+pub mod rename;
+pub mod report;
 pub mod runtime;
+pub mod secrets;
+pub mod signing;
+pub mod sink;
+pub mod slots;
+pub mod stats;
+pub mod store;
+pub mod sync;
 pub mod terminal;
+pub mod theme;
+pub mod throttle;
+pub mod timesync;
+pub mod timings;
+pub mod toolchain;
+pub mod upgrade;
 pub mod upload;
+pub mod usage;
+pub mod vpt;
+pub mod workspace;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::engine::ArgValueCandidates;
 use pyo3::prelude::*;
 use tokio::runtime::Runtime;
 
 use std::{
     path::{Path, PathBuf},
     sync::OnceLock,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use build::build;
+use build::{BuildOptions, BuildProfile, build};
+use config::{Config, UpdateCheck, load_config};
+use device::{devices, open_connection, open_device};
+use diagnostics::MessageFormat;
+use doctor::doctor;
 use errors::CliError;
-use manifest::{get_project, prompt_for_slot, resolve_project_dir, update_missing_config, MANIFEST_NAME};
+use github::{CLI_REPO, GithubClient};
+use jobs::JobPriority;
+use manifest::{
+    MANIFEST_NAME, get_project, prompt_for_slot, resolve_project_dir, update_missing_config,
+};
 use new::new;
+use releases::{ReleaseIndex, ReleaseQuery};
 use runtime::RuntimeSource;
-use terminal::terminal;
-use upload::{open_connection, upload};
+use store::Store;
+use terminal::{TerminalLog, TerminalOptions, terminal, terminal_with};
+use upload::upload;
+use workspace::{Workspace, find_workspace};
 
-use vex_v5_serial::protocol::cdc2::file::FileExitAction;
+use vex_v5_serial::{protocol::cdc2::file::FileExitAction, serial::SerialDevice};
 
 #[derive(Debug, Clone, clap::ValueEnum)]
 enum AfterUpload {
@@ -49,11 +127,250 @@ impl From<AfterUpload> for FileExitAction {
     }
 }
 
+#[derive(Clone, clap::Subcommand)]
+enum SyncAction {
+    /// Save the connected brain's settings and programs to brain-state.toml
+    Pull,
+    /// Apply brain-state.toml to the connected brain
+    Push,
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum ClockAction {
+    /// Show how far the brain's clock is from this computer's
+    Get,
+    /// Set the brain's clock to this computer's
+    Set,
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum AutonAction {
+    /// List the autonomous routines in the last build
+    List,
+    /// Make the runtime run this routine in autonomous
+    Select { name: String },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum LogAction {
+    /// Add a marker to a log written by `terminal --log`
+    Annotate {
+        path: PathBuf,
+        /// Seconds since the log started
+        at: f64,
+        text: String,
+    },
+    /// Print the lines of a log containing some text
+    Search {
+        path: PathBuf,
+        #[arg(default_value = "")]
+        query: String,
+        /// Only search markers. Without a query, lists every marker.
+        #[arg(long)]
+        markers: bool,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum CalibAction {
+    /// Save the connected brain's calibration to calibration.toml
+    Pull,
+    /// Write calibration.toml to the connected brain
+    Push,
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum RadioAction {
+    /// Show the controller's radio link type, channel and signal quality
+    Status,
+    /// Switch the controller's radio channel
+    Channel { channel: radio::Channel },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum VptAction {
+    /// Check a program table's signature
+    Verify { path: PathBuf },
+    /// Write every program in a program table back to disk: modules as .mpy files, assets and
+    /// the CLI's own programs as they were packed
+    Unpack {
+        path: PathBuf,
+        /// Directory to write them into
+        #[arg(long, short, default_value = vpt::UNPACK_DIR)]
+        out: PathBuf,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum ArtifactAction {
+    /// Check that a program table matches the metadata written by `build --artifact-metadata`
+    Verify { path: PathBuf },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum BatteryAction {
+    /// Show the battery charge recorded each time venice connected to a brain
+    History {
+        /// Number of readings to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum MigrateAction {
+    /// Convert a .v5python file, or a project folder from the VEXcode extension for VS Code
+    Vexcode {
+        path: PathBuf,
+        #[arg(long)]
+        venice_wheel: Option<PathBuf>,
+        #[arg(long)]
+        cli_wheel: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum ExampleAction {
+    /// List the examples with what each one shows
+    List,
+    /// Copy an example into a new folder, pinned to this version's runtime
+    New {
+        name: String,
+        #[arg(long)]
+        venice_wheel: Option<PathBuf>,
+        #[arg(long)]
+        cli_wheel: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum SlotsAction {
+    /// List the presets and the program each puts in each slot
+    List {
+        #[arg(long, default_value = slots::SLOTS_FILE)]
+        file: PathBuf,
+    },
+    /// Upload each program in a preset to its slot, skipping the ones already on the brain
+    Apply {
+        preset: String,
+        #[arg(long, default_value = slots::SLOTS_FILE)]
+        file: PathBuf,
+        /// Upload every program, even ones the brain already has
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum CacheAction {
+    /// How much space runtimes, toolchains and build output take, and how much has gone unused
+    Stats,
+    /// Delete cached files that haven't been used in a while. They're downloaded again when
+    /// next needed.
+    Prune {
+        /// Delete files unused for this many days
+        #[arg(long, default_value_t = cache::STALE_AFTER_DAYS)]
+        days: u64,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum StatsAction {
+    /// Lines of source, modules, dependencies, largest modules and how the program table's size
+    /// has changed over recent builds
+    Project,
+    /// Which modules each module imports, and which ones main never reaches
+    Imports {
+        /// Print the graph in Graphviz's dot language
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Command counts, durations and error codes recorded on this machine, when [usage] in the
+    /// venice config turns them on
+    Usage {
+        /// Send them anonymously to [usage].submit-url
+        #[arg(long)]
+        submit: bool,
+        /// Forget them
+        #[arg(long)]
+        reset: bool,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum SecretAction {
+    /// Set a secret, prompting for its value
+    Set {
+        name: String,
+        /// Store the secret in the OS keychain instead of .venice-secrets
+        #[arg(long)]
+        keychain: bool,
+    },
+    /// Print a secret's value
+    Get { name: String },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum RuntimeAction {
+    /// Show a runtime release's built-in modules and their members
+    Describe { version: semver::Version },
+    /// List the runtime releases downloaded for `venice bisect`, newest first
+    List {
+        /// List the published releases instead
+        #[arg(long)]
+        available: bool,
+        /// Include prereleases
+        #[arg(long, requires = "available")]
+        prerelease: bool,
+        /// Only releases at or after this version
+        #[arg(
+            long,
+            value_name = "VERSION",
+            requires = "available",
+            value_parser = bisect::parse_version
+        )]
+        since: Option<semver::Version>,
+    },
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum ManifestAction {
+    /// Print a JSON Schema for pyproject.toml. Point your editor at it for validation and
+    /// completion, e.g. with a `#:schema ./pyproject.schema.json` comment for taplo
+    Schema,
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum CoproAction {
+    /// Push the co-processor directory using the configured transport
+    Push,
+    /// Show the co-processor configuration
+    Show,
+}
+
 #[derive(clap::Parser)]
 #[command(version)]
 struct Venice {
     #[arg(long = "directory", short = 'C')]
     dir: Option<PathBuf>,
+    /// Workspace member to work on, by its directory's or project's name
+    #[arg(long = "package", short = 'p', global = true, value_name = "MEMBER")]
+    package: Option<String>,
+    /// Forbid network access; dependencies must be locked and vendored
+    #[arg(long, global = true)]
+    frozen: bool,
+    /// Language for messages, like `es` or `pt-BR`. Defaults to the system locale.
+    #[arg(long, global = true)]
+    lang: Option<String>,
+    /// Plain-text output for screen readers: no progress bars, colors or box drawing
+    #[arg(long, global = true)]
+    screen_reader: bool,
+    /// USB serial number of the device to connect to, instead of the default device
+    #[arg(long, global = true, value_name = "SERIAL", add = ArgValueCandidates::new(completions::device_candidates))]
+    device: Option<String>,
+    /// Wait for another program holding the brain's port to release it instead of giving up
+    #[arg(long, global = true)]
+    steal: bool,
     /// Path to a raw runtime binary (dev builds only)
     #[cfg(debug_assertions)]
     #[arg(long = "raw-binary")]
@@ -71,24 +388,351 @@ enum Subcommand {
         #[arg(long)]
         cli_wheel: Option<PathBuf>,
     },
-    Build,
+    /// Turn a project from another toolchain into a venice project
+    #[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
+    Migrate {
+        #[command(subcommand)]
+        action: Option<MigrateAction>,
+        /// Copy the program's name, slot, icon and description from this toolchain's project at
+        /// PATH into this project, so it keeps its place on the brain
+        #[arg(long, value_name = "TOOLCHAIN", requires = "path")]
+        from: Option<migrate::MetadataSource>,
+        #[arg(requires = "from")]
+        path: Option<PathBuf>,
+    },
+    /// Walk through creating, building, uploading and watching a first program
+    Learn,
+    /// Start from one of the curated example projects
+    Example {
+        #[command(subcommand)]
+        action: ExampleAction,
+    },
+    /// Fill the brain's slots from a named preset in slots.toml
+    Slots {
+        #[command(subcommand)]
+        action: SlotsAction,
+    },
+    /// Inspect and prune the venice cache shared by every project
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    Build {
+        /// Sign the program table with the key in the venice config directory
+        #[arg(long)]
+        sign: bool,
+        /// Print the outcome and any diagnostics as JSON, for editor integrations
+        #[arg(long)]
+        json: bool,
+        /// Write a JSON sidecar next to the program table describing it, for flashing it later
+        /// with `upload --vpt`
+        #[arg(long)]
+        artifact_metadata: bool,
+        /// Print per-module compile times and cache hits, and write an HTML report to the build
+        /// directory
+        #[arg(long)]
+        timings: bool,
+        /// Build again whenever a source file changes, until Ctrl+C
+        #[arg(long, conflicts_with = "artifact_metadata")]
+        watch: bool,
+        /// Modules to compile at once. Defaults to the number of CPUs.
+        #[arg(long, short)]
+        jobs: Option<usize>,
+        /// Build with this [tool.venice.profile], into its own directory
+        #[arg(long, value_enum, default_value_t)]
+        profile: BuildProfile,
+        /// How to report the build: `json` prints a JSON record per line on stdout for each
+        /// module compiled, diagnostic and artifact written, for editors and CI
+        #[arg(long, value_enum, default_value_t, conflicts_with_all = ["json", "timings"])]
+        message_format: MessageFormat,
+    },
     Clean,
     Upload {
         after_upload: Option<AfterUpload>,
         #[arg(long, short, action = clap::ArgAction::SetTrue)]
         cold: bool,
+        /// Also push to the co-processor configured in [tool.venice.copro]
+        #[arg(long, conflicts_with = "vpt")]
+        all_targets: bool,
+        /// Upload a program table built with --artifact-metadata instead of building the project
+        #[arg(long, value_name = "PATH")]
+        vpt: Option<PathBuf>,
+        /// Upload to this slot instead of the one in the manifest or artifact metadata
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=8), add = ArgValueCandidates::new(completions::slot_candidates))]
+        slot: Option<u8>,
+        /// Build for and link against this runtime release instead of the venice package's,
+        /// without changing the manifest. It's uploaded alongside the usual one.
+        #[arg(long, value_name = "VERSION")]
+        rt_version: Option<semver::Version>,
+        /// Build with this [tool.venice.profile], into its own directory
+        #[arg(long, value_enum, default_value_t)]
+        profile: BuildProfile,
+    },
+    /// Experimental: upload only the modules changed since the last upload, for the runtime to
+    /// load over it the next time the program starts. Much faster than a full upload over the
+    /// radio.
+    Patch {
+        /// Build with this [tool.venice.profile], which has to be the one the last upload used
+        #[arg(long, value_enum, default_value_t)]
+        profile: BuildProfile,
+    },
+    /// Open the program's terminal. Type `:help` for commands run by venice, like `:upload`.
+    /// Through a controller, its radio status is shown too, and lines starting with
+    /// `@controller` are sent to it.
+    Terminal {
+        /// Also write the program's output to this file, with timestamps. Type `!! text` to add
+        /// a marker, or `!1`-`!9` for the presets in [terminal].markers of the venice config.
+        #[arg(long, value_name = "PATH")]
+        log: Option<PathBuf>,
+    },
+    /// Annotate and search logs written by `terminal --log`
+    Log {
+        #[command(subcommand)]
+        action: LogAction,
+    },
+    /// List connected VEX devices
+    Devices {
+        /// Remember the device at this index as the one to connect to
+        #[arg(long, value_name = "INDEX")]
+        set_default: Option<usize>,
+    },
+    /// List the smart devices plugged into the brain
+    Ports {
+        /// Flag devices running older firmware than others of the same type
+        #[arg(long)]
+        check_firmware: bool,
+    },
+    /// List the venice commands using or waiting for each device, in the order they get it
+    Jobs,
+    /// Check the environment for common problems
+    Doctor {
+        /// Install a udev rule so VEX devices can be opened without root (Linux only)
+        #[arg(long)]
+        fix_permissions: bool,
     },
-    Terminal,
     Run {
         #[arg(long, short, action = clap::ArgAction::SetTrue)]
         cold: bool,
+        /// Build with this [tool.venice.profile], into its own directory
+        #[arg(long, value_enum, default_value_t)]
+        profile: BuildProfile,
+        /// Upload and restart the program whenever a source file changes, like `venice dev`
+        #[arg(long, conflicts_with_all = ["ci", "auton"])]
+        watch: bool,
+        /// Select this autonomous routine before the program starts
+        #[arg(long, value_name = "NAME")]
+        auton: Option<String>,
+        /// Run a hardware smoke test: wait for the device, hide progress bars, watch the output
+        /// for --expect and --fail-on until --timeout, then stop the program. Exits nonzero if
+        /// the test failed.
+        #[arg(long)]
+        ci: bool,
+        /// Seconds the whole run may take, including the upload
+        #[arg(long, value_name = "SECONDS", default_value_t = 120, requires = "ci")]
+        timeout: u64,
+        /// Pass once a line of output contains this text. Repeat to require several.
+        #[arg(long, value_name = "TEXT", requires = "ci")]
+        expect: Vec<String>,
+        /// Fail as soon as a line of output contains this text
+        #[arg(
+            long,
+            value_name = "TEXT",
+            default_value = "Traceback (most recent call last)",
+            requires = "ci"
+        )]
+        fail_on: Vec<String>,
+    },
+    /// Find the runtime release that broke the program, by running it against releases between
+    /// a good and a bad one the way `run --ci` does
+    Bisect {
+        /// A release the program works on
+        #[arg(long, value_name = "VERSION", value_parser = bisect::parse_version)]
+        good: semver::Version,
+        /// A later release it doesn't
+        #[arg(long, value_name = "VERSION", value_parser = bisect::parse_version)]
+        bad: semver::Version,
+        /// A release is good once a line of output contains this text. Repeat to require several.
+        #[arg(long, value_name = "TEXT", required = true)]
+        expect: Vec<String>,
+        /// A release is bad as soon as a line of output contains this text
+        #[arg(
+            long,
+            value_name = "TEXT",
+            default_value = "Traceback (most recent call last)"
+        )]
+        fail_on: Vec<String>,
+        /// Seconds each run may take, including the upload
+        #[arg(long, value_name = "SECONDS", default_value_t = 120)]
+        timeout: u64,
+    },
+    /// Stay connected during a competition and alert loudly if, when the field enables the
+    /// robot, the brain isn't running the project's last build from its slot
+    Guard,
+    /// Copy settings and programs between a brain and the local directory
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+    /// Choose the autonomous routine the program runs
+    Auton {
+        #[command(subcommand)]
+        action: AutonAction,
+    },
+    /// Copy calibration constants between a brain and calibration.toml
+    Calib {
+        #[command(subcommand)]
+        action: CalibAction,
+    },
+    /// Inspect program tables
+    Vpt {
+        #[command(subcommand)]
+        action: VptAction,
+    },
+    /// Inspect the brain's battery history
+    Battery {
+        #[command(subcommand)]
+        action: BatteryAction,
+    },
+    /// Summarize the project's size and shape
+    Stats {
+        #[command(subcommand)]
+        action: StatsAction,
+    },
+    /// Inspect CI-built program tables and their metadata
+    Artifact {
+        #[command(subcommand)]
+        action: ArtifactAction,
+    },
+    /// Manage secrets referenced from [tool.venice.env]
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+    /// Deploy files to a co-processor
+    Copro {
+        #[command(subcommand)]
+        action: CoproAction,
+    },
+    /// Inspect or change the controller's radio
+    Radio {
+        #[command(subcommand)]
+        action: RadioAction,
+    },
+    /// Explain an error code, e.g. `venice explain VE0004`
+    Explain {
+        code: String,
+    },
+    /// Inspect the project manifest
+    Manifest {
+        #[command(subcommand)]
+        action: ManifestAction,
+    },
+    /// Read or set the brain's clock, through the running program's runtime
+    Clock {
+        #[command(subcommand)]
+        action: ClockAction,
+    },
+    /// Inspect runtime releases
+    Runtime {
+        #[command(subcommand)]
+        action: RuntimeAction,
+    },
+    /// Add a package from the registry or PyPI, or a local library with --path, to
+    /// [tool.venice.dependencies]
+    Add {
+        #[arg(required_unless_present = "path")]
+        name: Option<String>,
+        /// Version requirement, e.g. `^1.2`, or with --pypi a PEP 440 specifier like `>=1.2`.
+        /// Defaults to the newest version, pinned to its compatible releases
+        #[arg(long, conflicts_with = "path")]
+        version: Option<String>,
+        /// Add to [tool.venice.dev-dependencies] instead
+        #[arg(long, requires = "path")]
+        dev: bool,
+        /// Local library to compile from source instead of installing from the registry
+        #[arg(long, conflicts_with = "name")]
+        path: Option<PathBuf>,
+        /// Install a pure-Python package from PyPI instead of the registry
+        #[arg(long, conflicts_with = "path")]
+        pypi: bool,
+    },
+    /// Remove a dependency of any kind, and whatever was installed for it
+    Remove {
+        name: String,
+    },
+    /// Upload and run, then re-upload whenever a source file or path dependency changes. Type
+    /// `:help` for commands like `:stop`.
+    Dev,
+    /// Rename a module, rewriting every import of it
+    Rename {
+        old: String,
+        new: String,
+    },
+    /// Copy every locked dependency into vendor/ for offline builds
+    Vendor,
+    /// Publish this project to the registry as a library
+    Publish,
+    /// Stop a published version from being resolved by new installs
+    Yank {
+        name: String,
+        version: semver::Version,
+        /// Make a yanked version resolvable again
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Run a language server for pyproject.toml over stdio
+    Lsp,
+    /// Delete everything venice stores on this machine: cache, config, keys and state
+    #[command(alias = "uninstall")]
+    Purge {
+        /// Also delete the venice runtimes uploaded to the connected brain
+        #[arg(long)]
+        brain: bool,
+        /// Don't ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Put back the config and state store saved when a different version of venice first ran,
+    /// e.g. after going back to an older venice. Lists the saved versions if none is given.
+    RestoreState {
+        /// The venice version whose config and state to restore
+        version: Option<semver::Version>,
+    },
+    /// Bundle versions, `venice doctor` output, the manifest and the last error into a tarball to
+    /// attach to bug reports. Host paths are redacted.
+    Report {
+        /// Where to write the tarball. Defaults to venice-report-<time>.tar.gz here.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Wipe files from the brain
+    Format {
+        /// Delete every program slot, runtime and other file venice wrote
+        #[arg(long, required = true)]
+        user_files: bool,
+        /// Don't ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Upload arbitrary files to the brain, e.g. calibration data or test blobs
+    Push {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value_t)]
+        vendor: push::Vendor,
+        #[arg(long, value_enum, default_value = "flash")]
+        target: data::DataTarget,
+        /// Where the file is loaded, in hex or decimal. Defaults to the user program address.
+        #[arg(long, value_parser = push::parse_address)]
+        load_address: Option<u32>,
+    },
+    /// Print a shell completion script
+    Completions {
+        shell: clap_complete::Shell,
+        /// Complete device serial numbers and empty slots by calling back into venice
+        #[arg(long)]
+        dynamic: bool,
     },
-}
-
-fn clean() -> miette::Result<()> {
-    std::fs::remove_dir_all(project_dir()?.join(BUILD_DIR)).map_err(CliError::Io)?;
-
-    Ok(())
 }
 
 async fn ensure_project_config() -> Result<(PathBuf, PathBuf), CliError> {
@@ -115,15 +759,86 @@ async fn ensure_project_config() -> Result<(PathBuf, PathBuf), CliError> {
     Ok((manifest_path.to_path_buf(), project_dir.to_path_buf()))
 }
 
+/// Prints a notice to stderr if a newer CLI release is available. Never fails: an update check
+/// that breaks the command the user actually asked for is worse than no update check. It makes
+/// one quick attempt, and after a failed one waits out [`UPDATE_CHECK_INTERVAL`] before trying
+/// again, so working offline isn't slowed down.
+async fn check_for_update() {
+    if config().update_check == UpdateCheck::Never || frozen() {
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let failed_at = Store::load()
+        .ok()
+        .and_then(|store| store.update_check_failed_at);
+    if failed_at
+        .is_some_and(|failed_at| now.saturating_sub(failed_at) < UPDATE_CHECK_INTERVAL.as_secs())
+    {
+        return;
+    }
+
+    let client = GithubClient::quick(config());
+    let index = match ReleaseIndex::fetch(&client, CLI_REPO, UPDATE_CHECK_INTERVAL).await {
+        Ok(index) => index,
+        Err(_) => {
+            let _ = Store::update(|store| store.update_check_failed_at = Some(now));
+            return;
+        }
+    };
+    if failed_at.is_some() {
+        let _ = Store::update(|store| store.update_check_failed_at = None);
+    }
+    let Some((latest, release)) = index.latest(&ReleaseQuery::stable()) else {
+        return;
+    };
+
+    let current = semver::Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+    if *latest > current {
+        eprintln!(
+            "{}",
+            tr!(
+                "update-available",
+                latest = latest.to_string(),
+                current = current.to_string(),
+                url = release.html_url.as_str()
+            )
+        );
+    }
+}
+
+/// How long the CLI's release list is cached for, and how long the update check waits after
+/// failing to fetch it.
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
 static PROJECT_DIR: OnceLock<PathBuf> = OnceLock::new();
 static MPY_CROSS_PATH: OnceLock<String> = OnceLock::new();
 static UV_PATH: OnceLock<String> = OnceLock::new();
+static CONFIG: OnceLock<Config> = OnceLock::new();
+static FROZEN: OnceLock<bool> = OnceLock::new();
+static SCREEN_READER: OnceLock<bool> = OnceLock::new();
+static STEAL: OnceLock<bool> = OnceLock::new();
+static QUIET: OnceLock<bool> = OnceLock::new();
+static PROFILE: OnceLock<BuildProfile> = OnceLock::new();
+static WORKSPACE: OnceLock<Workspace> = OnceLock::new();
 
 pub fn project_dir() -> Result<&'static Path, CliError> {
     PROJECT_DIR
         .get()
         .map(PathBuf::as_path)
-        .ok_or(CliError::NoManifest)
+        .ok_or_else(|| match workspace() {
+            Some(workspace) => CliError::WorkspaceRoot {
+                members: workspace.member_names().join(", "),
+            },
+            None => CliError::NoManifest,
+        })
+}
+
+/// The workspace the current project, or the current directory, is in.
+pub fn workspace() -> Option<&'static Workspace> {
+    WORKSPACE.get()
 }
 
 pub fn uv_path() -> Result<&'static str, CliError> {
@@ -133,6 +848,45 @@ pub fn uv_path() -> Result<&'static str, CliError> {
         .ok_or(CliError::NoUv)
 }
 
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Whether `--frozen` was passed, forbidding network access.
+pub fn frozen() -> bool {
+    FROZEN.get().copied().unwrap_or(false)
+}
+
+/// Whether output should suit a screen reader, from `--screen-reader` or the config.
+pub fn screen_reader() -> bool {
+    SCREEN_READER.get().copied().unwrap_or(false)
+}
+
+/// Whether to wait for a busy port rather than fail, from `--steal`.
+pub fn steal() -> bool {
+    STEAL.get().copied().unwrap_or(false)
+}
+
+/// Whether to leave out progress bars, for `run --ci` logs.
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+/// The `--profile` builds use.
+pub fn profile() -> BuildProfile {
+    PROFILE.get().copied().unwrap_or_default()
+}
+
+/// The top-level subcommand in `args`, e.g. `runtime` for `venice runtime list`, by its full name
+/// even when given as an alias.
+fn command_name(args: &[String]) -> Option<String> {
+    let command = Venice::command();
+    args.iter()
+        .skip(1)
+        .find_map(|arg| command.find_subcommand(arg))
+        .map(|subcommand| subcommand.get_name().to_string())
+}
+
 #[pyfunction]
 #[pyo3(signature = (args, binary_path, version, mpy_cross, uv_path=None))]
 fn call(
@@ -142,6 +896,8 @@ fn call(
     mpy_cross: Option<String>,
     uv_path: Option<String>,
 ) -> PyResult<()> {
+    let command_line = args.clone();
+    let start = Instant::now();
     let rt = Runtime::new().unwrap();
     let result: miette::Result<()> = rt.block_on(async {
         MPY_CROSS_PATH
@@ -151,6 +907,11 @@ fn call(
             UV_PATH.set(path).unwrap();
         }
 
+        if completions::try_complete(&args)? {
+            return Ok(());
+        }
+        let bin_path = args.first().cloned().unwrap_or_default();
+
         let cmd = Venice::try_parse_from(args);
         let cmd = match cmd {
             Ok(cmd) => cmd,
@@ -159,12 +920,76 @@ fn call(
             }
         };
 
+        upgrade::run()?;
+        let mut config = load_config()?;
+        if let Some(device) = cmd.device.clone() {
+            config.default_device = Some(device);
+        }
+        CONFIG.set(config).unwrap();
+        FROZEN.set(cmd.frozen).unwrap();
+        STEAL.set(cmd.steal).unwrap();
+        PROFILE
+            .set(match &cmd.subcmd {
+                Subcommand::Build { profile, .. }
+                | Subcommand::Upload { profile, .. }
+                | Subcommand::Run { profile, .. }
+                | Subcommand::Patch { profile } => *profile,
+                _ => BuildProfile::default(),
+            })
+            .unwrap();
+        jobs::set_current(
+            command_name(&command_line).unwrap_or_default(),
+            match &cmd.subcmd {
+                Subcommand::Terminal { .. } | Subcommand::Run { .. } | Subcommand::Dev => {
+                    JobPriority::Interactive
+                }
+                Subcommand::Lsp => JobPriority::Background,
+                _ => JobPriority::Normal,
+            },
+        );
+        QUIET
+            .set(matches!(
+                cmd.subcmd,
+                Subcommand::Run { ci: true, .. } | Subcommand::Bisect { .. }
+            ))
+            .unwrap();
+        i18n::init(cmd.lang.as_deref().or(config().lang.as_deref()));
+        SCREEN_READER
+            .set(cmd.screen_reader || config().screen_reader)
+            .unwrap();
+        if screen_reader() {
+            // Reads diagnostics out as sentences instead of drawing them with colors and arrows
+            let _ = miette::set_hook(Box::new(|_| {
+                Box::new(miette::NarratableReportHandler::new())
+            }));
+        } else {
+            theme::install_report_hook();
+        }
+        check_for_update().await;
+
         let start_dir = match cmd.dir.clone() {
             Some(dir) => dir,
             None => std::env::current_dir().map_err(CliError::Io)?,
         };
-        if let Ok(project_dir) = resolve_project_dir(&start_dir) {
-            PROJECT_DIR.set(project_dir).unwrap();
+        let workspace = find_workspace(&start_dir);
+        match (&cmd.package, &workspace) {
+            (Some(package), Some(workspace)) => {
+                PROJECT_DIR.set(workspace.member(package).await?).unwrap();
+            }
+            (Some(_), None) => return Err(CliError::NotAWorkspace.into()),
+            (None, _) => {
+                if let Ok(project_dir) = resolve_project_dir(&start_dir)
+                    // A workspace's root is only a project if it's one of its own members
+                    && !workspace.as_ref().is_some_and(|workspace| {
+                        workspace.root == project_dir && !workspace.contains(&project_dir)
+                    })
+                {
+                    PROJECT_DIR.set(project_dir).unwrap();
+                }
+            }
+        }
+        if let Some(workspace) = workspace {
+            WORKSPACE.set(workspace).unwrap();
         }
 
         // Determine the runtime source
@@ -197,26 +1022,369 @@ fn call(
             Subcommand::New { name, venice_wheel, cli_wheel } => {
                 new(&name, venice_wheel.as_deref(), cli_wheel.as_deref())?;
             }
-            Subcommand::Build => {
+            Subcommand::Migrate { action, from, path } => match (action, from.zip(path)) {
+                (
+                    Some(MigrateAction::Vexcode {
+                        path,
+                        venice_wheel,
+                        cli_wheel,
+                    }),
+                    _,
+                ) => migrate::vexcode(&path, venice_wheel.as_deref(), cli_wheel.as_deref())?,
+                (None, Some((from, path))) => migrate::import_metadata(from, &path).await?,
+                (None, None) => unreachable!("clap requires a subcommand or --from"),
+            },
+            Subcommand::Learn => learn::learn(runtime_source).await?,
+            Subcommand::Patch { .. } => {
+                let _ = ensure_project_config().await?;
+                patch::patch(runtime_source.ok_or(CliError::NoRuntimeSource)?).await?
+            }
+            Subcommand::Slots { action } => match action {
+                SlotsAction::List { file } => slots::list(&file).await?,
+                SlotsAction::Apply {
+                    preset,
+                    file,
+                    force,
+                } => slots::apply(&file, &preset, force, runtime_source).await?,
+            },
+            Subcommand::Cache { action } => match action {
+                CacheAction::Stats => cache::stats().await?,
+                CacheAction::Prune { days } => cache::prune(days)?,
+            },
+            Subcommand::Example { action } => match action {
+                ExampleAction::List => example::list().await?,
+                ExampleAction::New {
+                    name,
+                    venice_wheel,
+                    cli_wheel,
+                } => example::new(&name, venice_wheel.as_deref(), cli_wheel.as_deref()).await?,
+            },
+            Subcommand::Build {
+                sign,
+                json,
+                artifact_metadata,
+                timings,
+                watch,
+                jobs,
+                profile: _,
+                message_format,
+            } => {
                 let _ = ensure_project_config().await?;
-                let _ = build().await?;
+                let runtime = runtime_source.map(|source| source.version);
+                let options = BuildOptions {
+                    sign,
+                    runtime: runtime.clone(),
+                    timings,
+                    jobs,
+                    message_format,
+                };
+                if watch {
+                    dev::watch_build(&options, json).await?;
+                } else {
+                    let result = build(&options).await;
+                    if json {
+                        diagnostics::print_build_result(result.as_ref().err());
+                    } else if message_format == MessageFormat::Json {
+                        diagnostics::print_build_messages(result.as_ref().err());
+                    }
+                    let vpt = result?;
+                    if artifact_metadata {
+                        let path = artifact::write_metadata(&vpt, runtime).await?;
+                        match message_format {
+                            MessageFormat::Human => println!("✓ Wrote {}", path.display()),
+                            MessageFormat::Json => diagnostics::print_message(
+                                "artifact",
+                                serde_json::json!({ "kind": "metadata", "path": path }),
+                            ),
+                        }
+                    }
+                }
             }
-            Subcommand::Clean => clean()?,
-            Subcommand::Upload { after_upload, cold } => {
+            Subcommand::Clean => cache::clean().await?,
+            Subcommand::Upload {
+                after_upload,
+                cold,
+                all_targets,
+                vpt,
+                slot,
+                rt_version,
+                profile: _,
+            } => {
+                let runtime_source = match rt_version {
+                    Some(version) => Some(runtime::fetch_source(&version).await?),
+                    None => runtime_source,
+                };
+                // After-upload steps belong to the project, so they don't apply to artifacts
+                let (source, project) = match vpt {
+                    Some(path) => (upload::UploadSource::Artifact(path), None),
+                    None => {
+                        let _ = ensure_project_config().await?;
+                        (upload::UploadSource::Project, Some(get_project().await?))
+                    }
+                };
+                let steps = project
+                    .as_ref()
+                    .map(|p| p.after_upload.as_slice())
+                    .unwrap_or_default();
+                let exit_action = after_upload
+                    .map(|a| a.into())
+                    .or_else(|| hooks::exit_action(steps));
+                let started = matches!(exit_action, Some(FileExitAction::RunProgram));
+                let mut conn =
+                    upload::upload_from(&source, slot, exit_action, runtime_source.clone(), cold)
+                        .await?;
+                // A terminal step checks the brain's clock itself once it opens
+                let terminal_step = hooks::AfterUploadStep::Builtin(hooks::BuiltinStep::Terminal);
+                if started
+                    && !steps.contains(&terminal_step)
+                    && clock::supported(runtime_source.as_ref()).await
+                {
+                    clock::check_after_upload(&mut conn).await;
+                }
+                if all_targets {
+                    copro::push().await?;
+                }
+                if let Some(project) = &project {
+                    hooks::run_after_upload(steps, project, conn, runtime_source).await?;
+                }
+            }
+            Subcommand::Terminal { log } => {
+                let log = log.as_deref().map(TerminalLog::create).transpose()?;
+                let (conn, device) = open_device().await?;
+                let controller = matches!(device, SerialDevice::Controller { .. });
+                terminal_with(
+                    conn,
+                    TerminalOptions {
+                        log,
+                        controller,
+                        runtime_source,
+                    },
+                )
+                .await?
+            }
+            Subcommand::Log { action } => match action {
+                LogAction::Annotate { path, at, text } => log::annotate(&path, at, &text).await?,
+                LogAction::Search {
+                    path,
+                    query,
+                    markers,
+                } => log::search(&path, &query, markers).await?,
+            },
+            Subcommand::Devices { set_default } => devices(set_default).await?,
+            Subcommand::Ports { check_firmware } => ports::ports(check_firmware).await?,
+            Subcommand::Jobs => jobs::list(),
+            Subcommand::Doctor { fix_permissions } => doctor(fix_permissions).await?,
+            Subcommand::Run {
+                cold,
+                watch,
+                auton,
+                ci,
+                timeout,
+                expect,
+                fail_on,
+                profile: _,
+            } => {
+                let _ = ensure_project_config().await?;
+                // Checked against the manifest, since the upload rebuilds from it
+                if let Some(name) = auton {
+                    let supported = match &runtime_source {
+                        Some(source) => {
+                            runtime::check_feature(&source.version, runtime::Feature::Autons).await
+                        }
+                        None => true,
+                    };
+                    if supported {
+                        auton::select(&name, &get_project().await?.autons).await?;
+                    }
+                }
+                if watch {
+                    dev::dev(runtime_source).await?;
+                } else if ci {
+                    let options = ci::CiOptions {
+                        timeout: Duration::from_secs(timeout),
+                        expect,
+                        fail_on,
+                    };
+                    ci::run(options, cold, runtime_source).await?;
+                } else {
+                    let conn = upload(
+                        Some(FileExitAction::RunProgram),
+                        runtime_source.clone(),
+                        cold,
+                    )
+                    .await?;
+                    terminal(conn, runtime_source).await?;
+                }
+            }
+            Subcommand::Bisect {
+                good,
+                bad,
+                expect,
+                fail_on,
+                timeout,
+            } => {
+                let _ = ensure_project_config().await?;
+                let options = ci::CiOptions {
+                    timeout: Duration::from_secs(timeout),
+                    expect,
+                    fail_on,
+                };
+                bisect::bisect(&good, &bad, options).await?;
+            }
+            Subcommand::Guard => {
                 let _ = ensure_project_config().await?;
-                let _ = upload(after_upload.map(|a| a.into()), runtime_source, cold).await?;
+                guard::guard().await?;
             }
-            Subcommand::Terminal => terminal(&mut open_connection().await?).await?,
-            Subcommand::Run { cold } => {
+            Subcommand::Sync { action } => match action {
+                SyncAction::Pull => sync::pull(&start_dir).await?,
+                SyncAction::Push => sync::push(&start_dir).await?,
+            },
+            Subcommand::Auton { action } => match action {
+                AutonAction::List => auton::list().await?,
+                AutonAction::Select { name } => {
+                    auton::select(&name, &auton::built_routines().await?).await?
+                }
+            },
+            Subcommand::Calib { action } => match action {
+                CalibAction::Pull => calib::pull().await?,
+                CalibAction::Push => calib::push().await?,
+            },
+            Subcommand::Vpt { action } => match action {
+                VptAction::Verify { path } => {
+                    let vpt = tokio::fs::read(&path).await.map_err(CliError::Io)?;
+                    match signing::verify(&vpt::read_programs(&vpt)?)? {
+                        Some(key) => println!("✓ Signed by {}", hex::encode(key.to_bytes())),
+                        None => println!("Not signed"),
+                    }
+                }
+                VptAction::Unpack { path, out } => vpt::unpack(&path, &out).await?,
+            },
+            Subcommand::Battery { action } => match action {
+                BatteryAction::History { limit } => battery::history(limit)?,
+            },
+            Subcommand::Stats { action } => match action {
+                StatsAction::Project => stats::project().await?,
+                StatsAction::Imports { dot } => stats::imports(dot).await?,
+                StatsAction::Usage { submit, reset } => usage::usage(submit, reset).await?,
+            },
+            Subcommand::Artifact { action } => match action {
+                ArtifactAction::Verify { path } => artifact::verify_artifact(&path).await?,
+            },
+            Subcommand::Secret { action } => match action {
+                SecretAction::Set { name, keychain } => {
+                    secrets::prompt_and_set_secret(&name, keychain)?
+                }
+                SecretAction::Get { name } => println!("{}", secrets::get_secret(&name)?),
+            },
+            Subcommand::Copro { action } => match action {
+                CoproAction::Push => copro::push().await?,
+                CoproAction::Show => copro::show().await?,
+            },
+            Subcommand::Radio { action } => {
+                let mut conn = open_connection().await?;
+                match action {
+                    RadioAction::Status => radio::status(&mut conn).await?,
+                    RadioAction::Channel { channel } => {
+                        radio::switch_channel(&mut conn, channel).await?
+                    }
+                }
+            }
+            Subcommand::Add {
+                name,
+                version,
+                dev,
+                path,
+                pypi,
+            } => match (name, path) {
+                (_, Some(path)) => deps::add_path(&path, dev).await?,
+                (Some(name), None) if pypi => deps::add_pypi(&name, version).await?,
+                (Some(name), None) => {
+                    let version = version
+                        .as_deref()
+                        .map(semver::VersionReq::parse)
+                        .transpose()
+                        .map_err(CliError::from)?;
+                    deps::add(&name, version).await?
+                }
+                (None, None) => unreachable!("clap requires a name or --path"),
+            },
+            Subcommand::Remove { name } => deps::remove(&name).await?,
+            Subcommand::Dev => {
                 let _ = ensure_project_config().await?;
-                let mut conn = upload(Some(FileExitAction::RunProgram), runtime_source, cold).await?;
-                terminal(&mut conn).await?;
+                dev::dev(runtime_source).await?;
+            }
+            Subcommand::Explain { code } => explain::explain(&code)?,
+            Subcommand::Manifest { action } => match action {
+                ManifestAction::Schema => println!("{}", manifest::schema()),
+            },
+            Subcommand::Clock { action } => {
+                let mut conn = open_connection().await?;
+                match action {
+                    ClockAction::Get => clock::get(&mut conn).await?,
+                    ClockAction::Set => clock::set(&mut conn).await?,
+                }
+            }
+            Subcommand::Runtime { action } => match action {
+                RuntimeAction::Describe { version } => runtime::print_description(&version).await?,
+                RuntimeAction::List {
+                    available,
+                    prerelease,
+                    since,
+                } => {
+                    let versions = if available {
+                        let query = ReleaseQuery {
+                            prereleases: prerelease,
+                            since,
+                        };
+                        runtime::available_versions(&query).await?
+                    } else {
+                        runtime::downloaded_versions().await?
+                    };
+                    for version in versions {
+                        println!("{version}");
+                    }
+                }
+            },
+            Subcommand::Rename { old, new } => rename::rename(&old, &new).await?,
+            Subcommand::Vendor => deps::vendor().await?,
+            Subcommand::Publish => deps::publish().await?,
+            Subcommand::Yank {
+                name,
+                version,
+                undo,
+            } => deps::yank(&name, &version, undo).await?,
+            Subcommand::Lsp => lsp::lsp().await?,
+            Subcommand::Purge { brain, yes } => purge::purge(brain, yes).await?,
+            Subcommand::RestoreState { version } => upgrade::restore(version.as_ref())?,
+            Subcommand::Format { user_files: _, yes } => format::format_user_files(yes).await?,
+            Subcommand::Push {
+                files,
+                vendor,
+                target,
+                load_address,
+            } => push::push(&files, vendor, target, load_address).await?,
+            Subcommand::Report { output } => {
+                report::report(
+                    output,
+                    runtime_source.as_ref().map(|source| &source.version),
+                )
+                .await?
+            }
+            Subcommand::Completions { shell, dynamic } => {
+                completions::completions(shell, dynamic, &bin_path)?
             }
         };
         Ok(())
     });
+    if let Some(command) = command_name(&command_line) {
+        usage::record(&command, start.elapsed(), result.as_ref().err());
+    }
     let _ = result.map_err(|e| {
         eprint!("{:?}", e);
+        if let Some(code) = e.code() {
+            eprintln!("\n{}", tr!("explain-hint", code = code.to_string()));
+        }
+        report::record_failure(&command_line, &e);
         std::process::exit(1);
     });
     Ok(())
@@ -0,0 +1,72 @@
+use std::{path::Path, time::Duration};
+
+use crate::errors::CliError;
+
+/// Prefix, after the timestamp, of a marker line.
+pub const MARKER_PREFIX: &str = "## ";
+
+/// `[   12.345] text`: a log line stamped with the time since the terminal opened.
+pub fn format_line(elapsed: Duration, text: &str) -> String {
+    format!("[{:9.3}] {text}", elapsed.as_secs_f64())
+}
+
+/// The timestamp and text of a log line, if it has a timestamp.
+fn parse_line(line: &str) -> Option<(f64, &str)> {
+    let (time, text) = line.strip_prefix('[')?.split_once("] ")?;
+    Some((time.trim().parse().ok()?, text))
+}
+
+/// Inserts a marker before the first line logged after `at` seconds.
+fn insert_marker(log: &str, at: f64, text: &str) -> String {
+    let marker = format_line(
+        Duration::from_secs_f64(at),
+        &format!("{MARKER_PREFIX}{text}"),
+    );
+    let mut lines: Vec<&str> = log.lines().collect();
+    let index = lines
+        .iter()
+        .position(|line| parse_line(line).is_some_and(|(time, _)| time > at))
+        .unwrap_or(lines.len());
+    lines.insert(index, &marker);
+
+    let mut log = lines.join("\n");
+    log.push('\n');
+    log
+}
+
+/// `venice log annotate`: adds a marker to a log written by `terminal --log`.
+pub async fn annotate(path: &Path, at: f64, text: &str) -> Result<(), CliError> {
+    let log = tokio::fs::read_to_string(path).await?;
+    tokio::fs::write(path, insert_marker(&log, at, text)).await?;
+    println!("✓ Marked {at:.3}s: {text}");
+    Ok(())
+}
+
+/// `venice log search`: prints the lines of a log containing `query`, ignoring case. With
+/// `markers_only`, only markers are searched, so an empty query lists them all.
+pub async fn search(path: &Path, query: &str, markers_only: bool) -> Result<(), CliError> {
+    let log = tokio::fs::read_to_string(path).await?;
+    let query = query.to_lowercase();
+    for line in log.lines() {
+        let is_marker = parse_line(line).is_some_and(|(_, text)| text.starts_with(MARKER_PREFIX));
+        if (is_marker || !markers_only) && line.to_lowercase().contains(&query) {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::insert_marker;
+
+    #[test]
+    fn markers_go_in_time_order() {
+        let log = "[    1.000] starting\n[    5.500] intake on\n";
+        assert_eq!(
+            insert_marker(log, 3.25, "ring jam"),
+            "[    1.000] starting\n[    3.250] ## ring jam\n[    5.500] intake on\n"
+        );
+        assert!(insert_marker(log, 9.0, "end").ends_with("[    9.000] ## end\n"));
+    }
+}
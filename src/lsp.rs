@@ -0,0 +1,490 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+};
+
+use miette::Diagnostic;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{
+    brain::slot_programs,
+    device::{open_device, system_port, usb_serial_number},
+    errors::CliError,
+    jobs,
+    manifest::{MANIFEST_NAME, PyProjectToml},
+    releases::ReleaseQuery,
+    runtime::available_versions,
+    store::Store,
+};
+
+/// The Python package that provides the runtime, as named in [project].dependencies.
+const RUNTIME_PACKAGE: &str = "venice";
+
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+const SEVERITY_HINT: u8 = 4;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The parts of pyproject.toml the server points diagnostics and edits at, with their spans.
+#[derive(Deserialize)]
+struct SpannedManifest {
+    project: Option<SpannedProject>,
+    tool: Option<SpannedTool>,
+}
+
+#[derive(Deserialize)]
+struct SpannedProject {
+    name: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<toml::Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+struct SpannedTool {
+    venice: Option<SpannedVenice>,
+}
+
+#[derive(Deserialize)]
+struct SpannedVenice {
+    name: Option<String>,
+    slot: Option<toml::Spanned<u8>>,
+}
+
+impl SpannedManifest {
+    fn name(&self) -> Option<&str> {
+        let venice = self.tool.as_ref().and_then(|tool| tool.venice.as_ref());
+        venice
+            .and_then(|venice| venice.name.as_deref())
+            .or_else(|| self.project.as_ref()?.name.as_deref())
+    }
+
+    fn slot(&self) -> Option<&toml::Spanned<u8>> {
+        self.tool.as_ref()?.venice.as_ref()?.slot.as_ref()
+    }
+
+    /// Byte range of the version in the runtime requirement, e.g. `1.2.3` in `"venice==1.2.3"`.
+    fn runtime_version(&self, text: &str) -> Option<Range<usize>> {
+        self.project
+            .as_ref()?
+            .dependencies
+            .iter()
+            .find_map(|dependency| runtime_version(text, dependency))
+    }
+}
+
+fn runtime_version(text: &str, dependency: &toml::Spanned<String>) -> Option<Range<usize>> {
+    let span = dependency.span();
+    let inner = &text[span.start + 1..span.end - 1];
+    // Strings with escapes don't map byte-for-byte onto the source
+    if inner != dependency.get_ref() {
+        return None;
+    }
+
+    let name_end = inner
+        .find(|c: char| !(c.is_alphanumeric() || "-_.".contains(c)))
+        .unwrap_or(inner.len());
+    if !inner[..name_end].eq_ignore_ascii_case(RUNTIME_PACKAGE) {
+        return None;
+    }
+
+    let rest = &inner[name_end..];
+    let operator = rest.len() - rest.trim_start_matches(|c| "=<>~! ".contains(c)).len();
+    if operator == 0 {
+        return None;
+    }
+    let version = &rest[operator..];
+    let version_len = version
+        .find(|c: char| !(c.is_alphanumeric() || "._-+".contains(c)))
+        .unwrap_or(version.len());
+
+    let start = span.start + 1 + name_end + operator;
+    Some(start..start + version_len)
+}
+
+/// LSP position (line, UTF-16 column) of a byte offset.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+fn range(text: &str, span: &Range<usize>) -> Value {
+    json!({ "start": position(text, span.start), "end": position(text, span.end) })
+}
+
+/// Byte offset of an LSP position, clamped to the end of its line.
+fn offset(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+
+    let line_start: usize = text.split_inclusive('\n').take(line).map(str::len).sum();
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn diagnostic(
+    text: &str,
+    span: &Range<usize>,
+    severity: u8,
+    message: String,
+    error: Option<&CliError>,
+) -> Value {
+    let mut diagnostic = json!({
+        "range": range(text, span),
+        "severity": severity,
+        "source": "venice",
+        "message": message,
+    });
+    if let Some(code) = error.and_then(|e| e.code()) {
+        diagnostic["code"] = code.to_string().into();
+    }
+    diagnostic
+}
+
+struct Server {
+    documents: HashMap<String, String>,
+    /// Programs on the connected brain, by slot. `None` when no brain is connected.
+    slots: Option<BTreeMap<u8, String>>,
+    /// Published runtime versions, newest first. Fetched on first use.
+    versions: Option<Vec<semver::Version>>,
+    shutdown: bool,
+}
+
+impl Server {
+    fn new() -> Self {
+        Self {
+            documents: HashMap::new(),
+            slots: None,
+            versions: None,
+            shutdown: false,
+        }
+    }
+
+    /// Reads the brain's slots again. Failing to reach a brain just turns slot checks off.
+    async fn refresh_slots(&mut self) {
+        let Ok((mut conn, device)) = open_device().await else {
+            jobs::release();
+            self.slots = None;
+            return;
+        };
+        self.slots = slot_programs(&mut conn).await.ok();
+        // Let other commands at the device until the next check
+        drop(conn);
+        jobs::release();
+
+        // Cached for completions, which can't afford to connect on every tab press
+        if let Some(slots) = &self.slots
+            && let Some(device_serial) = usb_serial_number(system_port(&device))
+        {
+            let _ = Store::update(|store| {
+                store.brain_slots.insert(device_serial, slots.clone());
+            });
+        }
+    }
+
+    async fn versions(&mut self) -> &[semver::Version] {
+        if self.versions.is_none() {
+            self.versions = Some(
+                available_versions(&ReleaseQuery::stable())
+                    .await
+                    .unwrap_or_default(),
+            );
+        }
+        self.versions.as_deref().unwrap()
+    }
+
+    async fn diagnostics(&mut self, text: &str) -> Vec<Value> {
+        if let Err(e) = toml::from_str::<PyProjectToml>(text) {
+            let span = e.span().unwrap_or(0..0);
+            let message = e.message().to_string();
+            return vec![diagnostic(
+                text,
+                &span,
+                SEVERITY_ERROR,
+                message,
+                Some(&CliError::Manifest(e)),
+            )];
+        }
+        let Ok(manifest) = toml::from_str::<SpannedManifest>(text) else {
+            return Vec::new();
+        };
+
+        let mut diagnostics = Vec::new();
+        if let Some(slot) = manifest.slot() {
+            let error = if !(1..=8).contains(slot.get_ref()) {
+                Some((SEVERITY_ERROR, CliError::SlotOutOfRange))
+            } else if let Some(program) = self.slots.as_ref().and_then(|s| s.get(slot.get_ref()))
+                && Some(program.as_str()) != manifest.name()
+            {
+                Some((
+                    SEVERITY_WARNING,
+                    CliError::SlotTaken {
+                        slot: *slot.get_ref(),
+                        program: program.clone(),
+                    },
+                ))
+            } else {
+                None
+            };
+            if let Some((severity, error)) = error {
+                diagnostics.push(diagnostic(
+                    text,
+                    &slot.span(),
+                    severity,
+                    error.to_string(),
+                    Some(&error),
+                ));
+            }
+        }
+
+        if let Some(span) = manifest.runtime_version(text)
+            && let Ok(current) = semver::Version::parse(&text[span.clone()])
+            && let Some(latest) = self.versions().await.first()
+            && *latest > current
+        {
+            diagnostics.push(diagnostic(
+                text,
+                &span,
+                SEVERITY_HINT,
+                format!("runtime {latest} is available"),
+                None,
+            ));
+        }
+        diagnostics
+    }
+
+    async fn publish_diagnostics(&mut self, uri: &str) -> Value {
+        let diagnostics = match self.documents.get(uri).cloned() {
+            Some(text) => self.diagnostics(&text).await,
+            None => Vec::new(),
+        };
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        })
+    }
+
+    async fn completion(&mut self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(text) = self.documents.get(uri).cloned() else {
+            return Value::Null;
+        };
+        let cursor = offset(&text, &params["position"]);
+        let Some(span) = toml::from_str::<SpannedManifest>(&text)
+            .ok()
+            .and_then(|manifest| manifest.runtime_version(&text))
+            .filter(|span| span.contains(&cursor) || span.end == cursor)
+        else {
+            return Value::Null;
+        };
+
+        let items: Vec<Value> = self
+            .versions()
+            .await
+            .iter()
+            .enumerate()
+            .map(|(i, version)| {
+                json!({
+                    "label": version.to_string(),
+                    "kind": 12,
+                    "sortText": format!("{i:04}"),
+                    "preselect": i == 0,
+                    "textEdit": { "range": range(&text, &span), "newText": version.to_string() },
+                })
+            })
+            .collect();
+        json!({ "isIncomplete": false, "items": items })
+    }
+
+    async fn code_actions(&mut self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(text) = self.documents.get(uri).cloned() else {
+            return json!([]);
+        };
+        let Some(span) = toml::from_str::<SpannedManifest>(&text)
+            .ok()
+            .and_then(|manifest| manifest.runtime_version(&text))
+        else {
+            return json!([]);
+        };
+
+        // Offer the bump anywhere on the requirement's line
+        let line_start = text[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[span.end..]
+            .find('\n')
+            .map_or(text.len(), |i| span.end + i);
+        let start = offset(&text, &params["range"]["start"]);
+        let end = offset(&text, &params["range"]["end"]);
+        if end < line_start || start > line_end {
+            return json!([]);
+        }
+
+        let current = semver::Version::parse(&text[span.clone()]).ok();
+        match self.versions().await.first() {
+            Some(latest) if current.as_ref().is_none_or(|current| latest > current) => json!([{
+                "title": format!("Bump {RUNTIME_PACKAGE} to {latest}"),
+                "kind": "quickfix",
+                "isPreferred": true,
+                "edit": {
+                    "changes": {
+                        uri: [{ "range": range(&text, &span), "newText": latest.to_string() }],
+                    },
+                },
+            }]),
+            _ => json!([]),
+        }
+    }
+
+    /// Handles one message, returning the messages to send back.
+    async fn handle(&mut self, message: Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                    "completionProvider": { "triggerCharacters": ["=", "~", ">", "<"] },
+                    "codeActionProvider": true,
+                },
+                "serverInfo": { "name": "venice", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "initialized" => {
+                self.refresh_slots().await;
+                return Vec::new();
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/didOpen" if uri.ends_with(MANIFEST_NAME) => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                return vec![self.publish_diagnostics(&uri).await];
+            }
+            "textDocument/didChange" if self.documents.contains_key(&uri) => {
+                // Full sync: the last change holds the whole document
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                return vec![self.publish_diagnostics(&uri).await];
+            }
+            "textDocument/didSave" if self.documents.contains_key(&uri) => {
+                self.refresh_slots().await;
+                return vec![self.publish_diagnostics(&uri).await];
+            }
+            "textDocument/didClose" if self.documents.contains_key(&uri) => {
+                self.documents.remove(&uri);
+                return vec![self.publish_diagnostics(&uri).await];
+            }
+            "textDocument/completion" => self.completion(params).await,
+            "textDocument/codeAction" => self.code_actions(params).await,
+            _ => {
+                // Unknown notifications are ignored; unknown requests get an error
+                if message.get("id").is_none() {
+                    return Vec::new();
+                }
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": {
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("unknown method {method}"),
+                    },
+                })];
+            }
+        };
+
+        match message.get("id") {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Reads one `Content-Length`-framed message. `None` at end of input.
+async fn read_message(
+    reader: &mut (impl AsyncBufReadExt + Unpin),
+) -> Result<Option<Vec<u8>>, CliError> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or(0)];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+async fn write_message(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &Value,
+) -> Result<(), CliError> {
+    let body = serde_json::to_vec(message)?;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// `venice lsp`: a language server for pyproject.toml over stdio. It validates the manifest,
+/// warns when the project's slot holds another program on the connected brain, completes runtime
+/// versions and offers to bump the runtime to the latest release.
+pub async fn lsp() -> Result<(), CliError> {
+    let mut stdin = BufReader::new(tokio::io::stdin());
+    let mut stdout = tokio::io::stdout();
+    let mut server = Server::new();
+
+    while let Some(body) = read_message(&mut stdin).await? {
+        let Ok(message) = serde_json::from_slice::<Value>(&body) else {
+            continue;
+        };
+        if message["method"] == "exit" {
+            break;
+        }
+        for reply in server.handle(message).await {
+            write_message(&mut stdout, &reply).await?;
+        }
+    }
+
+    if !server.shutdown {
+        // Exiting without a shutdown request means the client crashed
+        std::process::exit(1);
+    }
+    Ok(())
+}
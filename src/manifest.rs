@@ -1,53 +1,278 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use inquire::CustomType;
 use inquire::validator::Validation;
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-use crate::{errors::CliError, project_dir};
+use crate::{
+    build::Language, copro::CoproConfig, data::DataTarget, env::EnvValue, errors::CliError,
+    hooks::AfterUploadStep, project_dir,
+};
 
 pub const MANIFEST_NAME: &str = "pyproject.toml";
 
 /// The parsed pyproject.toml structure
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct PyProjectToml {
     project: Option<PyProject>,
     tool: Option<Tool>,
 }
 
 /// Standard [project] section
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct PyProject {
     name: Option<String>,
+    version: Option<String>,
     description: Option<String>,
 }
 
 /// [tool] section containing venice config
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
 pub struct Tool {
     venice: Option<VeniceConfig>,
 }
 
 /// [tool.venice] section
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "kebab-case")]
 pub struct VeniceConfig {
     pub slot: Option<u8>,
     pub name: Option<String>,
     pub description: Option<String>,
     #[serde(default)]
     pub icon: ProgramIcon,
+    /// Source language, which decides the compiler front-end used by `venice build`
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub data: DataConfig,
+    #[serde(default)]
+    pub assets: AssetsConfig,
+    /// Values exposed to the program through the generated `venice_env` module
+    #[serde(default)]
+    pub env: BTreeMap<String, EnvValue>,
+    pub copro: Option<CoproConfig>,
+    /// Packages the program imports, by name: registry packages with the versions they're
+    /// allowed to resolve to, or local libraries as `{ path = "../mylib" }`
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, DependencySpec>,
+    /// Local libraries, by name, compiled from source and watched by `venice dev`
+    #[serde(default)]
+    pub dev_dependencies: BTreeMap<String, PathDependency>,
+    /// Modules the build's import check should assume exist on the brain
+    #[serde(default)]
+    pub ignore_imports: Vec<String>,
+    /// Steps `venice upload` runs once the program is on the brain
+    #[serde(default)]
+    pub after_upload: Vec<AfterUploadStep>,
+    /// Names of the autonomous routines the program offers, for `venice auton select`
+    #[serde(default)]
+    pub autons: Vec<String>,
+    /// Exact mpy-cross release to compile with, downloaded if the installed one differs. Its
+    /// bytecode has to be a version the runtime loads.
+    #[schemars(with = "Option<String>")]
+    pub mpy_cross_version: Option<semver::Version>,
+    /// Settings for `--profile debug` and `--profile release`
+    #[serde(default)]
+    pub profile: Profiles,
+    #[serde(default)]
+    pub build: BuildConfig,
+    pub workspace: Option<WorkspaceConfig>,
+}
+
+/// [tool.venice.workspace] section
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct WorkspaceConfig {
+    /// Project directories in the workspace, relative to it. `-p <member>` picks one by its
+    /// directory's or project's name.
+    pub members: Vec<PathBuf>,
+}
+
+/// [tool.venice.build] section
+#[derive(Deserialize, JsonSchema, Default, Debug, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BuildConfig {
+    /// Extra arguments passed to mpy-cross for every module, after the profile's `-O` level so
+    /// they can override it, e.g. `["-O2", "-X", "emit=native"]`. Bytecode the runtime can't
+    /// load, such as from a different `-march`, fails when the program starts.
+    pub mpy_flags: Vec<String>,
+    /// Globs of the project's source files to leave out of the program table, relative to the
+    /// project, like `["**/tests/**", "experiments/*.py"]`
+    pub exclude: Vec<String>,
+    /// Globs of source files to compile even though they match `exclude`
+    pub include: Vec<String>,
+    /// More directories of modules to compile, relative to the project, e.g. for generated code
+    /// or vendored libraries. Their top-level modules and packages are importable by name.
+    pub src_dirs: Vec<PathBuf>,
+    /// Treat directories without an `__init__.py` as packages, like Python 3's namespace
+    /// packages, instead of skipping them. Each one is warned about.
+    pub namespace_packages: bool,
+    /// zlib-compress each module's bytecode in the program table, for a smaller upload and less
+    /// flash used. The runtime inflates modules as they're imported.
+    pub compress: bool,
+    /// What to do with modules `main` never imports, directly or through other modules
+    pub unreachable_modules: UnreachableModules,
+}
+
+/// [tool.venice.build].unreachable-modules. Imports by a computed name, like `__import__(name)`,
+/// aren't seen, so the modules they load look unreachable.
+#[derive(Deserialize, JsonSchema, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnreachableModules {
+    /// Pack them without checking
+    #[default]
+    Keep,
+    /// Pack them, warning about each one
+    Warn,
+    /// Warn about them and leave them out of the program table
+    Exclude,
+}
+
+/// [tool.venice.profile] section
+#[derive(Deserialize, JsonSchema, Default, Debug, Clone)]
+pub struct Profiles {
+    #[serde(default)]
+    pub debug: ProfileConfig,
+    #[serde(default)]
+    pub release: ProfileConfig,
+}
+
+/// [tool.venice.profile.debug] or [tool.venice.profile.release]
+#[derive(Deserialize, JsonSchema, Default, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProfileConfig {
+    /// mpy-cross optimization level, 0 to 3. 1 and up compile out `assert`s and make
+    /// `__debug__` false, and 3 also drops line numbers from tracebacks.
+    #[schemars(range(max = 3))]
+    pub opt_level: Option<u8>,
+    /// Where the program table and bytecode go, relative to the project. Defaults to
+    /// `build/debug` or `build/release`.
+    pub out_dir: Option<PathBuf>,
+    /// Leave line numbers and other debug info out of the bytecode for a smaller table, by
+    /// compiling at `-O3` whatever `opt-level` says. Tracebacks then show no line numbers.
+    #[serde(default)]
+    pub strip: bool,
+}
+
+/// An entry in [tool.venice.dependencies]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum DependencySpec {
+    /// A version requirement on a registry package, like `"^1.2"`
+    Registry(#[schemars(with = "String")] semver::VersionReq),
+    /// A library project on disk, compiled from source with this one
+    Path(PathDependency),
+    /// A library project in a git repository, cloned into the venice cache
+    Git(GitDependency),
+    /// A pure-Python package from PyPI
+    Pypi(PypiDependency),
+}
+
+/// A PyPI entry in [tool.venice.dependencies], like `{ pypi = ">=1.2,<2" }`. Installed with uv, so
+/// packages with compiled extensions are refused.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PypiDependency {
+    /// PEP 440 version specifier, or `*` for any version
+    pub pypi: String,
+}
+
+/// A git entry in [tool.venice.dependencies], like
+/// `{ git = "https://github.com/team/lib", tag = "v1.0" }`. Without `rev`, `tag` or `branch`, the
+/// repository's default branch is used. Whichever commit it resolves to is locked in venice.lock.
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GitDependency {
+    pub git: String,
+    pub rev: Option<String>,
+    pub tag: Option<String>,
+    pub branch: Option<String>,
+}
+
+/// An entry in [tool.venice.dev-dependencies], or a path entry in [tool.venice.dependencies]
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+pub struct PathDependency {
+    /// Library project directory, relative to this project
+    pub path: PathBuf,
+}
+
+/// [tool.venice.assets] section
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AssetsConfig {
+    /// Directory, relative to the project, whose files are packed into the program table for the
+    /// program to read, e.g. lookup tables and paths. Unlike [tool.venice.data], they're part of
+    /// the program, so they're always in step with the code.
+    pub dir: PathBuf,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("assets"),
+        }
+    }
+}
+
+/// [tool.venice.data] section
+#[derive(Deserialize, JsonSchema, Debug, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DataConfig {
+    /// Directory, relative to the project, whose files are uploaded alongside the program
+    pub dir: PathBuf,
+    pub default_target: DataTarget,
+    /// Files (relative to `dir`) to store in flash regardless of `default-target`
+    pub flash: Vec<String>,
+    /// Files (relative to `dir`) to store on the SD card regardless of `default-target`
+    pub sd: Vec<String>,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("data"),
+            default_target: DataTarget::Sd,
+            flash: Vec::new(),
+            sd: Vec::new(),
+        }
+    }
 }
 
 /// The resolved project configuration (after merging [project] and [tool.venice])
 #[derive(Debug)]
 pub struct Project {
     pub name: String,
+    pub version: Option<String>,
     pub slot: Option<u8>,
     pub description: Option<String>,
     pub icon: ProgramIcon,
+    pub language: Language,
+    pub data: DataConfig,
+    pub assets: AssetsConfig,
+    pub env: BTreeMap<String, EnvValue>,
+    pub copro: Option<CoproConfig>,
+    /// Registry packages from [tool.venice.dependencies]
+    pub dependencies: BTreeMap<String, semver::VersionReq>,
+    /// Local libraries from [tool.venice.dependencies]
+    pub path_dependencies: BTreeMap<String, PathDependency>,
+    /// Git libraries from [tool.venice.dependencies]
+    pub git_dependencies: BTreeMap<String, GitDependency>,
+    /// PyPI packages from [tool.venice.dependencies]
+    pub pypi_dependencies: BTreeMap<String, PypiDependency>,
+    pub dev_dependencies: BTreeMap<String, PathDependency>,
+    pub ignore_imports: Vec<String>,
+    pub after_upload: Vec<AfterUploadStep>,
+    pub autons: Vec<String>,
+    pub mpy_cross_version: Option<semver::Version>,
+    pub profiles: Profiles,
+    pub build: BuildConfig,
 }
 
-#[derive(Deserialize, Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Deserialize, JsonSchema, Default, Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u16)]
 pub enum ProgramIcon {
     VexCodingStudio = 0,
@@ -81,6 +306,14 @@ pub enum ProgramIcon {
     VexcodeCpp = 926,
 }
 
+/// JSON Schema for pyproject.toml, covering [project] fields venice reads and all of
+/// [tool.venice]. Generated from the types above, so it can't drift from what venice accepts.
+pub fn schema() -> String {
+    let mut schema = schemars::schema_for!(PyProjectToml);
+    schema.insert("title".into(), "pyproject.toml (venice)".into());
+    serde_json::to_string_pretty(&schema).unwrap()
+}
+
 pub fn resolve_project_dir(start_dir: &Path) -> Result<PathBuf, CliError> {
     let mut search_dir = start_dir.to_path_buf();
 
@@ -96,8 +329,23 @@ pub fn resolve_project_dir(start_dir: &Path) -> Result<PathBuf, CliError> {
     }
 }
 
+/// The [tool.venice.workspace] section of the manifest in `dir`, if it has one. Only that section
+/// is read, and a manifest that can't be read or parsed has none, so a broken `pyproject.toml`
+/// in some parent directory doesn't stop venice from working on the project below it.
+pub fn read_workspace_config(dir: &Path) -> Option<WorkspaceConfig> {
+    let file_string = std::fs::read_to_string(dir.join(MANIFEST_NAME)).ok()?;
+    let pyproject: toml::Table = toml::from_str(&file_string).ok()?;
+    let workspace = pyproject.get("tool")?.get("venice")?.get("workspace")?;
+    WorkspaceConfig::deserialize(workspace.clone()).ok()
+}
+
 pub async fn get_project() -> Result<Project, CliError> {
-    let manifest_path = project_dir()?.join(MANIFEST_NAME);
+    get_project_at(project_dir()?).await
+}
+
+/// Like [`get_project`], for the project in `dir` rather than the current one.
+pub async fn get_project_at(dir: &Path) -> Result<Project, CliError> {
+    let manifest_path = dir.join(MANIFEST_NAME);
     let file_string = tokio::fs::read_to_string(manifest_path).await?;
     let pyproject: PyProjectToml = toml::from_str(&file_string).map_err(CliError::Manifest)?;
 
@@ -119,11 +367,93 @@ pub async fn get_project() -> Result<Project, CliError> {
         .and_then(|v| v.description.clone())
         .or(project_description);
 
+    let mut dependencies = BTreeMap::new();
+    let mut path_dependencies = BTreeMap::new();
+    let mut git_dependencies = BTreeMap::new();
+    let mut pypi_dependencies = BTreeMap::new();
+    for (name, spec) in venice_config
+        .as_ref()
+        .map(|v| v.dependencies.clone())
+        .unwrap_or_default()
+    {
+        match spec {
+            DependencySpec::Registry(req) => {
+                dependencies.insert(name, req);
+            }
+            DependencySpec::Path(dep) => {
+                path_dependencies.insert(name, dep);
+            }
+            DependencySpec::Git(dep) => {
+                // They're passed to git as arguments, where these would be read as options
+                let values = [&dep.rev, &dep.tag, &dep.branch].into_iter().flatten();
+                if let Some(value) = values.chain([&dep.git]).find(|v| v.starts_with('-')) {
+                    return Err(CliError::GitOptionLike {
+                        name,
+                        value: value.clone(),
+                    });
+                }
+                git_dependencies.insert(name, dep);
+            }
+            DependencySpec::Pypi(dep) => {
+                pypi_dependencies.insert(name, dep);
+            }
+        }
+    }
+
     Ok(Project {
         name,
+        version: pyproject.project.as_ref().and_then(|p| p.version.clone()),
         slot: venice_config.as_ref().and_then(|v| v.slot),
         description,
         icon: venice_config.as_ref().map(|v| v.icon).unwrap_or_default(),
+        language: venice_config
+            .as_ref()
+            .map(|v| v.language)
+            .unwrap_or_default(),
+        data: venice_config
+            .as_ref()
+            .map(|v| v.data.clone())
+            .unwrap_or_default(),
+        assets: venice_config
+            .as_ref()
+            .map(|v| v.assets.clone())
+            .unwrap_or_default(),
+        env: venice_config
+            .as_ref()
+            .map(|v| v.env.clone())
+            .unwrap_or_default(),
+        dependencies,
+        path_dependencies,
+        git_dependencies,
+        pypi_dependencies,
+        dev_dependencies: venice_config
+            .as_ref()
+            .map(|v| v.dev_dependencies.clone())
+            .unwrap_or_default(),
+        ignore_imports: venice_config
+            .as_ref()
+            .map(|v| v.ignore_imports.clone())
+            .unwrap_or_default(),
+        after_upload: venice_config
+            .as_ref()
+            .map(|v| v.after_upload.clone())
+            .unwrap_or_default(),
+        autons: venice_config
+            .as_ref()
+            .map(|v| v.autons.clone())
+            .unwrap_or_default(),
+        mpy_cross_version: venice_config
+            .as_ref()
+            .and_then(|v| v.mpy_cross_version.clone()),
+        profiles: venice_config
+            .as_ref()
+            .map(|v| v.profile.clone())
+            .unwrap_or_default(),
+        build: venice_config
+            .as_ref()
+            .map(|v| v.build.clone())
+            .unwrap_or_default(),
+        copro: venice_config.and_then(|v| v.copro),
     })
 }
 
@@ -192,3 +522,59 @@ pub async fn update_missing_config(manifest_path: &Path, slot: Option<u8>) -> Re
         .map_err(CliError::Io)?;
     Ok(())
 }
+
+/// Sets `[tool.venice.<table>].<key>` in pyproject.toml, preserving the rest of its formatting.
+pub async fn set_venice_table_entry(
+    manifest_path: &Path,
+    table: &str,
+    key: &str,
+    value: toml_edit::Value,
+) -> Result<(), CliError> {
+    let content = tokio::fs::read_to_string(manifest_path).await?;
+    let mut doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| CliError::ManifestEdit(e.to_string()))?;
+
+    let entries = doc
+        .entry("tool")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .and_then(|tool| {
+            let venice = tool.entry("venice").or_insert(toml_edit::table());
+            venice.as_table_mut()
+        })
+        .and_then(|venice| {
+            let entries = venice.entry(table).or_insert(toml_edit::table());
+            entries.as_table_mut()
+        })
+        .ok_or_else(|| CliError::ManifestEdit(format!("[tool.venice.{table}] isn't a table")))?;
+    entries.insert(key, toml_edit::Item::Value(value));
+
+    tokio::fs::write(manifest_path, doc.to_string()).await?;
+    Ok(())
+}
+
+/// Removes `[tool.venice.<table>].<key>` from pyproject.toml, preserving the rest of its
+/// formatting. Returns whether it was there.
+pub async fn remove_venice_table_entry(
+    manifest_path: &Path,
+    table: &str,
+    key: &str,
+) -> Result<bool, CliError> {
+    let content = tokio::fs::read_to_string(manifest_path).await?;
+    let mut doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| CliError::ManifestEdit(e.to_string()))?;
+
+    let removed = doc
+        .get_mut("tool")
+        .and_then(|tool| tool.get_mut("venice"))
+        .and_then(|venice| venice.get_mut(table))
+        .and_then(|entries| entries.as_table_like_mut())
+        .and_then(|entries| entries.remove(key))
+        .is_some();
+    if removed {
+        tokio::fs::write(manifest_path, doc.to_string()).await?;
+    }
+    Ok(removed)
+}
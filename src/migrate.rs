@@ -0,0 +1,512 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    errors::CliError,
+    manifest::{MANIFEST_NAME, ProgramIcon},
+    new::add_dependencies,
+    project_dir, uv_path,
+};
+
+/// Settings file of projects made with the VEXcode extension for VS Code.
+const VSCODE_SETTINGS: &str = ".vscode/vex_project_settings.json";
+
+/// Marks the start of the device setup VEXcode generates at the top of every program.
+const GENERATED_CONFIG: &str = "#region VEXcode Generated Robot Configuration";
+
+/// Import lines with a direct venice equivalent.
+const IMPORT_REWRITES: &[(&str, &str)] = &[
+    ("from vex import *", "from venice import *"),
+    ("import vex", "import venice as vex"),
+];
+
+/// VEXcode-only code that needs rewriting by hand, with how to do it in venice.
+const MANUAL_STEPS: &[(&str, &str)] = &[
+    (
+        "wait(",
+        "VEXcode's blocking `wait`: inside an `async def`, \
+         `await vasyncio.Sleep(ms, venice.TimeUnit.MILLIS)`",
+    ),
+    (
+        "Thread(",
+        "VEXcode threads: start a task with `vasyncio.spawn(...)`",
+    ),
+    (
+        "Competition(",
+        "VEXcode's `Competition(driver, auton)`: create `Competition()`, decorate the routines \
+         with `@comp.driver` and `@comp.autonomous`, and `await comp.run()`",
+    ),
+    ("import urandom", "`urandom` is VEXcode's name for `random`"),
+];
+
+/// A `.v5python` file saved by VEXcode V5, holding the whole program.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct V5PythonFile {
+    text_content: String,
+    /// Counted from 0
+    #[serde(default)]
+    slot: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct VscodeSettings {
+    project: VscodeProject,
+}
+
+#[derive(Deserialize)]
+struct VscodeProject {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    /// Counted from 1
+    #[serde(default)]
+    slot: Option<u8>,
+}
+
+/// A VEXcode project, read into what the venice project needs.
+struct VexcodeProject {
+    name: String,
+    description: Option<String>,
+    slot: u8,
+    /// Where the venice project goes
+    dir: PathBuf,
+    /// Sources, by path relative to the project
+    sources: Vec<(PathBuf, String)>,
+    /// Source files to delete once they've been moved to the project root
+    moved: Vec<PathBuf>,
+}
+
+fn read_v5python(path: &Path) -> Result<VexcodeProject, CliError> {
+    let file: V5PythonFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let name = path.file_stem().map_or("robot".to_string(), |stem| {
+        stem.to_string_lossy().into_owned()
+    });
+    let dir = PathBuf::from(package_name(&name));
+    if dir.exists() {
+        return Err(CliError::ProjectExists(dir));
+    }
+
+    Ok(VexcodeProject {
+        name,
+        description: None,
+        slot: v5python_slot(file.slot)?,
+        dir,
+        sources: vec![(PathBuf::from("main.py"), file.text_content)],
+        moved: Vec::new(),
+    })
+}
+
+/// A `.v5python` file's slot, counted from 0, as the slot from 1 to 8 it means.
+fn v5python_slot(slot: Option<u8>) -> Result<u8, CliError> {
+    match slot {
+        None => Ok(1),
+        Some(slot) => slot
+            .checked_add(1)
+            .filter(|slot| (1..=8).contains(slot))
+            .ok_or(CliError::SlotOutOfRange),
+    }
+}
+
+/// Python files under `dir`, by path relative to `root`.
+fn find_sources(root: &Path, dir: &Path, sources: &mut Vec<PathBuf>) -> Result<(), CliError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_sources(root, &path, sources)?;
+        } else if path.extension().is_some_and(|ext| ext == "py") {
+            sources.push(path.strip_prefix(root).unwrap().to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+fn read_vscode_project(dir: &Path) -> Result<VexcodeProject, CliError> {
+    let settings: VscodeSettings =
+        serde_json::from_str(&std::fs::read_to_string(dir.join(VSCODE_SETTINGS))?)?;
+    if dir.join(MANIFEST_NAME).exists() {
+        return Err(CliError::AlreadyVeniceProject(dir.to_path_buf()));
+    }
+
+    // The extension keeps sources in src/; venice builds from the project root
+    let src_dir = dir.join("src");
+    if !src_dir.is_dir() {
+        return Err(CliError::NotVexcodeProject(dir.to_path_buf()));
+    }
+    let mut paths = Vec::new();
+    find_sources(&src_dir, &src_dir, &mut paths)?;
+    let mut sources = Vec::new();
+    let mut moved = Vec::new();
+    for path in paths {
+        // Checked before anything is written, so a refused migration leaves the project as it was
+        let to = dir.join(&path);
+        if to.exists() {
+            return Err(CliError::MigrateOverwrite(to));
+        }
+        let from = src_dir.join(&path);
+        sources.push((path, std::fs::read_to_string(&from)?));
+        moved.push(from);
+    }
+
+    Ok(VexcodeProject {
+        name: settings.project.name,
+        description: settings.project.description,
+        slot: match settings.project.slot {
+            Some(slot @ 1..=8) => slot,
+            Some(_) => return Err(CliError::SlotOutOfRange),
+            None => 1,
+        },
+        dir: dir.to_path_buf(),
+        sources,
+        moved,
+    })
+}
+
+/// A Python package name for a VEXcode project name, which may have spaces and capitals.
+fn package_name(name: &str) -> String {
+    let words: Vec<_> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        "robot".to_string()
+    } else {
+        words.join("-").to_lowercase()
+    }
+}
+
+const MANIFEST_TEMPLATE: &str = r#"[project]
+name = {package}
+version = "0.1.0"
+requires-python = ">=3.14"
+
+[tool.venice]
+name = {name}
+slot = {slot}
+"#;
+
+fn manifest(project: &VexcodeProject) -> String {
+    let quote = |text: &str| toml::Value::String(text.to_string()).to_string();
+    let mut manifest = MANIFEST_TEMPLATE
+        .replace("{package}", &quote(&package_name(&project.name)))
+        .replace("{name}", &quote(&project.name))
+        .replace("{slot}", &project.slot.to_string());
+    if let Some(description) = &project.description {
+        manifest.push_str(&format!("description = {}\n", quote(description)));
+    }
+    manifest
+}
+
+/// Rewrites imports with a venice equivalent, returning the new source and the manual steps
+/// left, as `line: step`.
+fn convert_source(source: &str) -> (String, Vec<String>) {
+    let mut lines = Vec::new();
+    let mut steps = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let number = number + 1;
+        let rewrite = IMPORT_REWRITES
+            .iter()
+            .find(|(from, _)| line.trim_end() == *from);
+        lines.push(rewrite.map_or(line, |(_, to)| *to));
+
+        if line.trim() == GENERATED_CONFIG {
+            steps.push(format!(
+                "{number}: VEXcode's generated robot configuration uses VEXcode's device API; \
+                 set the devices up again with venice's"
+            ));
+        }
+        for (pattern, step) in MANUAL_STEPS {
+            if line.contains(pattern) && !line.trim_start().starts_with('#') {
+                steps.push(format!("{number}: {step}"));
+            }
+        }
+    }
+
+    let mut converted = lines.join("\n");
+    if source.ends_with('\n') {
+        converted.push('\n');
+    }
+    (converted, steps)
+}
+
+/// `venice migrate vexcode`: turns a `.v5python` file, or a project folder from the VEXcode
+/// extension for VS Code, into a venice project. Imports are rewritten where there's a direct
+/// equivalent; everything else is listed for doing by hand.
+pub fn vexcode(
+    path: &Path,
+    venice_wheel: Option<&Path>,
+    cli_wheel: Option<&Path>,
+) -> miette::Result<()> {
+    let uv = uv_path()?;
+    let project = if path.is_dir() {
+        if !path.join(VSCODE_SETTINGS).exists() {
+            return Err(CliError::NotVexcodeProject(path.to_path_buf()).into());
+        }
+        read_vscode_project(path)?
+    } else if path.extension().is_some_and(|ext| ext == "v5python") {
+        read_v5python(path)?
+    } else {
+        return Err(CliError::NotVexcodeProject(path.to_path_buf()).into());
+    };
+
+    std::fs::create_dir_all(&project.dir).map_err(CliError::Io)?;
+    let mut manual_steps = Vec::new();
+    for (source_path, source) in &project.sources {
+        let dest = project.dir.join(source_path);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).map_err(CliError::Io)?;
+        }
+        let (converted, steps) = convert_source(source);
+        std::fs::write(&dest, converted).map_err(CliError::Io)?;
+        manual_steps.extend(
+            steps
+                .into_iter()
+                .map(|step| format!("{}:{step}", source_path.display())),
+        );
+    }
+    for path in &project.moved {
+        std::fs::remove_file(path).map_err(CliError::Io)?;
+    }
+    std::fs::write(project.dir.join(MANIFEST_NAME), manifest(&project)).map_err(CliError::Io)?;
+    add_dependencies(uv, &project.dir, venice_wheel, cli_wheel)?;
+
+    println!("✓ Migrated `{}` to {}", project.name, project.dir.display());
+    if manual_steps.is_empty() {
+        println!("Nothing VEXcode-specific was found; try `venice build`.");
+    } else {
+        println!("\nStill to do by hand:");
+        for step in manual_steps {
+            println!("  {step}");
+        }
+    }
+    Ok(())
+}
+
+/// Toolchains whose project metadata `migrate --from` imports.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum MetadataSource {
+    /// `project.pros` of a PROS project
+    Pros,
+    /// [package.metadata.v5] of a vexide project's Cargo.toml
+    Vexide,
+}
+
+/// PROS icon names, from `pros upload --icon`, and the icons they stand for.
+const PROS_ICONS: &[(&str, &str)] = &[
+    ("pros", "Pros"),
+    ("pizza", "Pizza"),
+    ("planet", "Planets"),
+    ("alien", "Alien"),
+    ("ufo", "AlienInUfo"),
+    ("robot", "Robot"),
+    ("clawbot", "Clawbot"),
+    ("question", "QuestionMark"),
+    ("X", "CoolX"),
+    ("power", "PowerButton"),
+];
+
+/// What a brain shows for a program, as another toolchain recorded it.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ProgramMetadata {
+    name: Option<String>,
+    slot: Option<u8>,
+    /// A [`ProgramIcon`] variant name
+    icon: Option<String>,
+    description: Option<String>,
+}
+
+/// The [`ProgramIcon`] variant `name` refers to, if venice knows it.
+fn known_icon(name: &str) -> Option<String> {
+    serde_json::from_value::<ProgramIcon>(Value::String(name.to_string()))
+        .ok()
+        .map(|_| name.to_string())
+}
+
+fn pros_metadata(project: &str) -> Result<ProgramMetadata, CliError> {
+    let project: Value = serde_json::from_str(project)?;
+    let state = &project["py/state"];
+    let options = &state["upload_options"];
+    let text = |value: &Value| value.as_str().map(str::to_string);
+    Ok(ProgramMetadata {
+        name: text(&options["remote_name"]).or_else(|| text(&state["project_name"])),
+        slot: options["slot"]
+            .as_u64()
+            .and_then(|slot| u8::try_from(slot).ok()),
+        icon: options["icon"].as_str().and_then(|icon| {
+            PROS_ICONS
+                .iter()
+                .find(|(pros, _)| *pros == icon)
+                .map(|(_, venice)| venice.to_string())
+        }),
+        description: text(&options["description"]),
+    })
+}
+
+fn vexide_metadata(cargo_toml: &str) -> Result<ProgramMetadata, CliError> {
+    let manifest: toml::Table = toml::from_str(cargo_toml)?;
+    let package = manifest.get("package");
+    let field = |key: &str| package?.get(key)?.as_str().map(str::to_string);
+    let v5 = package
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("v5"));
+    Ok(ProgramMetadata {
+        name: field("name"),
+        slot: v5
+            .and_then(|v5| v5.get("slot")?.as_integer())
+            .and_then(|slot| u8::try_from(slot).ok()),
+        // vexide names icons in kebab case, like `cool-x`
+        icon: v5.and_then(|v5| v5.get("icon")?.as_str()).and_then(|icon| {
+            let pascal: String = icon
+                .split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or(String::new(), |first| {
+                        first.to_uppercase().chain(chars).collect()
+                    })
+                })
+                .collect();
+            known_icon(&pascal)
+        }),
+        description: field("description"),
+    })
+}
+
+/// `venice migrate --from`: copies the program's name, slot, icon and description from another
+/// toolchain's project into [tool.venice], so the rewrite lands in the same place on the brain.
+pub async fn import_metadata(source: MetadataSource, path: &Path) -> Result<(), CliError> {
+    let metadata = match source {
+        MetadataSource::Pros => {
+            pros_metadata(&tokio::fs::read_to_string(path.join("project.pros")).await?)?
+        }
+        MetadataSource::Vexide => {
+            vexide_metadata(&tokio::fs::read_to_string(path.join("Cargo.toml")).await?)?
+        }
+    };
+
+    let manifest_path = project_dir()?.join(MANIFEST_NAME);
+    let mut doc = tokio::fs::read_to_string(&manifest_path)
+        .await?
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| CliError::ManifestEdit(e.to_string()))?;
+    let venice = doc
+        .entry("tool")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .and_then(|tool| {
+            // Only [tool.venice] is written, not an empty [tool] above it
+            tool.set_implicit(true);
+            tool.entry("venice")
+                .or_insert(toml_edit::table())
+                .as_table_mut()
+        })
+        .ok_or_else(|| CliError::ManifestEdit("[tool.venice] isn't a table".to_string()))?;
+    let mut imported = Vec::new();
+    let mut missing = Vec::new();
+    let fields = [
+        ("name", metadata.name.map(toml_edit::value)),
+        (
+            "slot",
+            metadata.slot.map(|slot| toml_edit::value(i64::from(slot))),
+        ),
+        ("icon", metadata.icon.map(toml_edit::value)),
+        ("description", metadata.description.map(toml_edit::value)),
+    ];
+    for (key, value) in fields {
+        match value {
+            Some(value) => {
+                imported.push(format!("{key} = {}", value.to_string().trim()));
+                venice.insert(key, value);
+            }
+            None => missing.push(key),
+        }
+    }
+    tokio::fs::write(&manifest_path, doc.to_string()).await?;
+
+    for line in &imported {
+        println!("✓ {line}");
+    }
+    if !missing.is_empty() {
+        println!("Not set in the {source:?} project: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ProgramMetadata, convert_source, package_name, pros_metadata, v5python_slot,
+        vexide_metadata,
+    };
+    use crate::errors::CliError;
+
+    #[test]
+    fn rewrites_imports_and_lists_manual_steps() {
+        let (converted, steps) = convert_source("from vex import *\n\n# wait(1)\nwait(20, MSEC)\n");
+        assert_eq!(
+            converted,
+            "from venice import *\n\n# wait(1)\nwait(20, MSEC)\n"
+        );
+        assert_eq!(steps.len(), 1);
+        assert!(steps[0].starts_with("4: "));
+    }
+
+    #[test]
+    fn counts_v5python_slots_from_one() {
+        assert_eq!(v5python_slot(None).unwrap(), 1);
+        assert_eq!(v5python_slot(Some(0)).unwrap(), 1);
+        assert_eq!(v5python_slot(Some(7)).unwrap(), 8);
+        assert!(matches!(
+            v5python_slot(Some(8)),
+            Err(CliError::SlotOutOfRange)
+        ));
+        assert!(matches!(
+            v5python_slot(Some(255)),
+            Err(CliError::SlotOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn makes_package_names() {
+        assert_eq!(package_name("Clawbot Drive (v2)"), "clawbot-drive-v2");
+        assert_eq!(package_name("!!"), "robot");
+    }
+
+    #[test]
+    fn reads_pros_metadata() {
+        let project = r#"{"py/object": "pros.conductor.project.Project", "py/state": {
+            "project_name": "spin-up", "target": "v5",
+            "upload_options": {"slot": 3, "icon": "ufo", "description": "worlds"}}}"#;
+        assert_eq!(
+            pros_metadata(project).unwrap(),
+            ProgramMetadata {
+                name: Some("spin-up".to_string()),
+                slot: Some(3),
+                icon: Some("AlienInUfo".to_string()),
+                description: Some("worlds".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn reads_vexide_metadata() {
+        let cargo_toml = r#"
+            [package]
+            name = "over-under"
+
+            [package.metadata.v5]
+            slot = 2
+            icon = "cool-x"
+        "#;
+        assert_eq!(
+            vexide_metadata(cargo_toml).unwrap(),
+            ProgramMetadata {
+                name: Some("over-under".to_string()),
+                slot: Some(2),
+                icon: Some("CoolX".to_string()),
+                description: None,
+            }
+        );
+    }
+}
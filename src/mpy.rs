@@ -0,0 +1,116 @@
+use std::path::Path;
+
+use crate::errors::CliError;
+
+/// Native code architectures an `.mpy` header can name, by their number in it, as mpy-cross's
+/// `-march` spells them.
+const NATIVE_ARCHS: &[&str] = &[
+    "none",
+    "x86",
+    "x64",
+    "armv6",
+    "armv6m",
+    "armv7m",
+    "armv7em",
+    "armv7emsp",
+    "armv7emdp",
+    "xtensa",
+    "xtensawin",
+    "rv32imc",
+];
+
+/// Native code architecture the Venice runtime loads: Thumb-2 with a double-precision FPU, which
+/// the brain's Cortex-A9 runs.
+const RUNTIME_NATIVE_ARCH: u8 = 8;
+
+/// What the first bytes of an `.mpy` file say about the code in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MpyHeader {
+    version: u8,
+    sub_version: u8,
+    /// Index into [`NATIVE_ARCHS`]; 0 if the file is only bytecode
+    arch: u8,
+}
+
+impl MpyHeader {
+    /// Parses the header: `M`, the format version, then the native architecture in the upper six
+    /// bits of a byte whose lower two hold the sub-version.
+    fn parse(mpy: &[u8]) -> Option<Self> {
+        match mpy {
+            [b'M', version, flags, ..] => Some(Self {
+                version: *version,
+                sub_version: flags & 0b11,
+                arch: flags >> 2,
+            }),
+            _ => None,
+        }
+    }
+
+    fn format(&self) -> String {
+        format!("{}.{}", self.version, self.sub_version)
+    }
+}
+
+fn arch_name(arch: u8) -> String {
+    NATIVE_ARCHS
+        .get(arch as usize)
+        .map_or_else(|| format!("unknown ({arch})"), |name| name.to_string())
+}
+
+/// Fails unless the precompiled module at `path` would load on the runtime: its bytecode format
+/// has to be that of `reference`, which was just compiled for the program, and any native code in
+/// it built for the brain.
+pub fn check_precompiled(path: &Path, mpy: &[u8], reference: &[u8]) -> Result<(), CliError> {
+    let header = MpyHeader::parse(mpy).ok_or_else(|| CliError::NotBytecode(path.to_path_buf()))?;
+    if let Some(expected) = MpyHeader::parse(reference)
+        && (header.version, header.sub_version) != (expected.version, expected.sub_version)
+    {
+        return Err(CliError::BytecodeVersion {
+            file: path.to_path_buf(),
+            found: header.format(),
+            expected: expected.format(),
+        });
+    }
+    if header.arch != 0 && header.arch != RUNTIME_NATIVE_ARCH {
+        return Err(CliError::NativeArch {
+            file: path.to_path_buf(),
+            found: arch_name(header.arch),
+            expected: arch_name(RUNTIME_NATIVE_ARCH),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{MpyHeader, check_precompiled};
+    use crate::errors::CliError;
+
+    #[test]
+    fn checks_precompiled_headers() {
+        let reference = [b'M', 6, 3, 31];
+        let check = |mpy: &[u8]| check_precompiled(Path::new("fast.mpy"), mpy, &reference);
+
+        assert_eq!(
+            MpyHeader::parse(&[b'M', 6, (8 << 2) | 3, 31]),
+            Some(MpyHeader {
+                version: 6,
+                sub_version: 3,
+                arch: 8,
+            })
+        );
+        assert!(check(&[b'M', 6, 3, 31]).is_ok());
+        assert!(check(&[b'M', 6, (8 << 2) | 3, 31]).is_ok());
+        assert!(matches!(check(b"print(1)"), Err(CliError::NotBytecode(_))));
+        assert!(matches!(
+            check(&[b'M', 5, 0, 31]),
+            Err(CliError::BytecodeVersion { .. })
+        ));
+        assert!(matches!(
+            check(&[b'M', 6, (10 << 2) | 3, 31]),
+            Err(CliError::NativeArch { .. })
+        ));
+    }
+}
@@ -6,7 +6,7 @@ use crate::uv_path;
 
 /// The venice runtime version paired with this CLI release.
 /// Bump in tandem with CARGO_PKG_VERSION when cutting a release.
-const VENICE_VERSION: &str = "0.1.0";
+pub const VENICE_VERSION: &str = "0.1.0";
 
 const PYPROJECT_TEMPLATE: &str = r#"[project]
 name = "{name}"
@@ -26,6 +26,11 @@ async def main():
 vasyncio.run(main())
 "#;
 
+const GITIGNORE_TEMPLATE: &str = r#"build/
+.venv/
+__pycache__/
+"#;
+
 pub fn new(name: &str, venice_wheel: Option<&Path>, cli_wheel: Option<&Path>) -> miette::Result<()> {
     let uv = uv_path()?;
 
@@ -39,7 +44,25 @@ pub fn new(name: &str, venice_wheel: Option<&Path>, cli_wheel: Option<&Path>) ->
     let pyproject = PYPROJECT_TEMPLATE.replace("{name}", name);
     std::fs::write(project_dir.join("pyproject.toml"), pyproject).map_err(CliError::Io)?;
     std::fs::write(project_dir.join("main.py"), MAIN_TEMPLATE).map_err(CliError::Io)?;
+    std::fs::write(project_dir.join(".gitignore"), GITIGNORE_TEMPLATE).map_err(CliError::Io)?;
+
+    add_dependencies(uv, &project_dir, venice_wheel, cli_wheel)?;
+
+    println!(
+        "\nCreated project `{name}`. To get started:\n\n  cd {name}\n  uv run venice-cli build\n"
+    );
 
+    Ok(())
+}
+
+/// Adds the venice runtime and, as a dev dependency, this CLI to the project with uv. Wheels
+/// replace the released versions, for testing unreleased builds.
+pub fn add_dependencies(
+    uv: &str,
+    project_dir: &Path,
+    venice_wheel: Option<&Path>,
+    cli_wheel: Option<&Path>,
+) -> miette::Result<()> {
     let venice_spec = match venice_wheel {
         Some(path) => path.to_string_lossy().into_owned(),
         None => format!("venice=={VENICE_VERSION}"),
@@ -49,13 +72,8 @@ pub fn new(name: &str, venice_wheel: Option<&Path>, cli_wheel: Option<&Path>) ->
         None => format!("venice-cli=={}", env!("CARGO_PKG_VERSION")),
     };
 
-    run_uv(uv, &project_dir, &["add", &venice_spec])?;
-    run_uv(uv, &project_dir, &["add", "--dev", &cli_spec])?;
-
-    println!(
-        "\nCreated project `{name}`. To get started:\n\n  cd {name}\n  uv run venice-cli build\n"
-    );
-
+    run_uv(uv, project_dir, &["add", &venice_spec])?;
+    run_uv(uv, project_dir, &["add", "--dev", &cli_spec])?;
     Ok(())
 }
 
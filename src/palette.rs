@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+/// Shown by `:help` in `venice terminal`.
+pub const TERMINAL_HELP: &str = "\
+:ts            toggle timestamps on the program's output
+:log <path>    start writing the program's output to a file
+:log off       stop writing it
+:stop          stop the program
+:upload        rebuild, upload and run the program again
+:clear         clear the screen
+::text         send `:text` to the program";
+
+/// Shown by `:help` in `venice dev`.
+pub const DEV_HELP: &str = "\
+:stop          stop the program
+:upload        rebuild, upload and run the program again, even if nothing changed
+:clear         clear the screen";
+
+/// Clears the screen and moves the cursor to the top left.
+pub const CLEAR_SCREEN: &[u8] = b"\x1b[2J\x1b[H";
+
+/// A local command typed as a line starting with `:` in `terminal` or `dev`, run by venice instead
+/// of being sent to the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteCommand {
+    Timestamps,
+    /// `None` stops logging
+    Log(Option<PathBuf>),
+    Stop,
+    Upload,
+    Clear,
+    Help,
+    Unknown(String),
+}
+
+/// What to do with a line of input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input<'a> {
+    Command(PaletteCommand),
+    /// Input for the program, with a `::` escape already undone
+    Program(&'a [u8]),
+}
+
+pub fn parse(input: &[u8]) -> Input<'_> {
+    if input.starts_with(b"::") {
+        return Input::Program(&input[1..]);
+    }
+    let Some(line) = input.strip_prefix(b":") else {
+        return Input::Program(input);
+    };
+
+    let line = String::from_utf8_lossy(line);
+    let (name, arg) = line
+        .trim()
+        .split_once(char::is_whitespace)
+        .map_or((line.trim(), ""), |(name, arg)| (name, arg.trim()));
+    Input::Command(match (name, arg) {
+        ("ts" | "timestamps", "") => PaletteCommand::Timestamps,
+        ("log", "off") => PaletteCommand::Log(None),
+        ("log", path) if !path.is_empty() => PaletteCommand::Log(Some(PathBuf::from(path))),
+        ("stop", "") => PaletteCommand::Stop,
+        ("upload", "") => PaletteCommand::Upload,
+        ("clear", "") => PaletteCommand::Clear,
+        ("help" | "?", "") => PaletteCommand::Help,
+        _ => PaletteCommand::Unknown(line.trim().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Input, PaletteCommand, parse};
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse(b":ts\n"), Input::Command(PaletteCommand::Timestamps));
+        assert_eq!(
+            parse(b":log  run 3.log\n"),
+            Input::Command(PaletteCommand::Log(Some(PathBuf::from("run 3.log"))))
+        );
+        assert_eq!(
+            parse(b":log off\n"),
+            Input::Command(PaletteCommand::Log(None))
+        );
+        assert_eq!(
+            parse(b":log\n"),
+            Input::Command(PaletteCommand::Unknown("log".to_string()))
+        );
+    }
+
+    #[test]
+    fn passes_program_input_through() {
+        assert_eq!(parse(b"go\n"), Input::Program(b"go\n"));
+        assert_eq!(parse(b"::go\n"), Input::Program(b":go\n"));
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use venice_program_table::{ProgramBuilder, ProgramFlags, VptBuilder};
+use vex_v5_serial::{commands::file::USER_PROGRAM_LOAD_ADDR, protocol::cdc2::file::FileVendor};
+
+use crate::{
+    BUILD_DIR, VENDOR_ID,
+    artifact::ProgramInfo,
+    auton::AUTONS_PROGRAM,
+    brain::{list_files, vex_crc32},
+    build::{BuildOptions, build},
+    device::open_connection,
+    errors::CliError,
+    manifest::get_project,
+    profile, project_dir,
+    runtime::{Feature, RuntimeSource, capabilities},
+    signing::SIGNATURE_PROGRAM,
+    upload::upload_user_file,
+    vpt::{Program, read_programs},
+};
+
+/// First program of a patch table. Its payload is the CRC32 of the `slot_N.bin` the patch was
+/// made against, little-endian; the runtime only loads a patch's modules over that exact program
+/// table, so one left behind by an earlier upload is ignored.
+pub const PATCH_PROGRAM: &[u8] = b"<venice-patch>";
+
+/// The program table last uploaded to `slot` from the project, which patches are made against.
+fn base_path(build_dir: &Path, slot: u8) -> PathBuf {
+    build_dir.join(format!("uploaded-slot_{slot}.vpt"))
+}
+
+/// Where the name of the profile the [`base_path`] table was built with is kept, since a patch
+/// built with another would differ from it in every module.
+fn base_profile_path(build_dir: &Path, slot: u8) -> PathBuf {
+    build_dir.join(format!("uploaded-slot_{slot}.profile"))
+}
+
+/// Keeps `vpt`, just uploaded to `slot`, for `venice patch` to diff against.
+pub async fn save_base(build_dir: &Path, slot: u8, vpt: &[u8]) -> std::io::Result<()> {
+    tokio::fs::write(base_path(build_dir, slot), vpt).await?;
+    tokio::fs::write(base_profile_path(build_dir, slot), profile().name()).await
+}
+
+/// Brain file holding the patch for `slot`.
+fn patch_file(slot: u8) -> String {
+    format!("venice-patch-{slot}.bin")
+}
+
+/// The modules in `new` that aren't in `base` or differ from it, or why the change can't be
+/// made with a patch.
+fn changed_modules(base: &[Program], new: &[Program]) -> Result<Vec<Program>, CliError> {
+    let is_signature = |program: &Program| program.name == SIGNATURE_PROGRAM;
+    if base.iter().chain(new).any(is_signature) {
+        return Err(CliError::NoPatch(
+            "signed program tables can't be patched".to_string(),
+        ));
+    }
+
+    if let Some(removed) = base
+        .iter()
+        .find(|old| !new.iter().any(|program| program.name == old.name))
+    {
+        return Err(CliError::NoPatch(format!(
+            "`{}` was removed",
+            String::from_utf8_lossy(&removed.name)
+        )));
+    }
+
+    let changed: Vec<Program> = new
+        .iter()
+        .filter(|program| !base.contains(program))
+        .cloned()
+        .collect();
+    if changed.iter().any(|program| program.name == AUTONS_PROGRAM) {
+        return Err(CliError::NoPatch(
+            "the autonomous routines changed".to_string(),
+        ));
+    }
+    Ok(changed)
+}
+
+/// A program table of the changed modules, led by [`PATCH_PROGRAM`].
+fn patch_table(base: &[u8], changed: Vec<Program>) -> Vec<u8> {
+    let mut builder = VptBuilder::new(VENDOR_ID);
+    builder.add_program(ProgramBuilder {
+        name: PATCH_PROGRAM.to_vec(),
+        payload: vex_crc32(base).to_le_bytes().to_vec(),
+        flags: ProgramFlags::empty(),
+    });
+    for program in changed {
+        builder.add_program(ProgramBuilder {
+            name: program.name,
+            payload: program.payload,
+            flags: program.flags,
+        });
+    }
+    builder.build()
+}
+
+/// `venice patch`: uploads only the modules changed since the last upload, for the runtime to
+/// load over the uploaded program the next time it starts.
+pub async fn patch(runtime_source: RuntimeSource) -> Result<(), CliError> {
+    let version = &runtime_source.version;
+    if capabilities(version)
+        .await
+        .is_some_and(|capabilities| !capabilities.supports(Feature::Patches))
+    {
+        return Err(CliError::NoPatch(format!(
+            "venice runtime {version} doesn't load module patches"
+        )));
+    }
+
+    let slot = ProgramInfo::from_project(&get_project().await?)?.slot;
+    let build_dir = project_dir()?.join(BUILD_DIR);
+    let (base, base_profile) = match tokio::try_join!(
+        tokio::fs::read(base_path(&build_dir, slot)),
+        tokio::fs::read_to_string(base_profile_path(&build_dir, slot))
+    ) {
+        Ok(saved) => saved,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CliError::NoPatch(format!(
+                "the project hasn't been uploaded to slot {slot} yet"
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if base_profile != profile().name() {
+        return Err(CliError::NoPatch(format!(
+            "the last upload to slot {slot} was built with the {base_profile} profile; patch it with `--profile {base_profile}`"
+        )));
+    }
+
+    let vpt = build(&BuildOptions {
+        runtime: Some(version.clone()),
+        ..Default::default()
+    })
+    .await?;
+    let changed = changed_modules(&read_programs(&base)?, &read_programs(&vpt)?)?;
+    if changed.is_empty() {
+        println!("Nothing changed since the last upload");
+        return Ok(());
+    }
+
+    let mut conn = open_connection().await?;
+    let uploaded = format!("slot_{slot}.bin");
+    let on_brain = list_files(&mut conn, FileVendor::User)
+        .await?
+        .into_iter()
+        .any(|file| {
+            file.name == uploaded
+                && file.size as usize == base.len()
+                && file.crc32 == vex_crc32(&base)
+        });
+    if !on_brain {
+        return Err(CliError::NoPatch(format!(
+            "slot {slot} on the brain doesn't hold this project's last upload"
+        )));
+    }
+
+    let names: Vec<_> = changed
+        .iter()
+        .map(|program| String::from_utf8_lossy(&program.name).into_owned())
+        .collect();
+    let patch = patch_table(&base, changed);
+    upload_user_file(
+        &mut conn,
+        &patch_file(slot),
+        &patch,
+        USER_PROGRAM_LOAD_ADDR,
+        None,
+    )
+    .await?;
+    println!(
+        "✓ Patched {} into slot {slot} ({} bytes instead of {}); it loads the next time the program starts",
+        names.join(", "),
+        patch.len(),
+        vpt.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use venice_program_table::ProgramFlags;
+
+    use super::changed_modules;
+    use crate::vpt::Program;
+
+    fn module(name: &str, payload: &[u8]) -> Program {
+        Program {
+            name: name.as_bytes().to_vec(),
+            flags: ProgramFlags::empty(),
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn patches_changed_and_added_modules_only() {
+        let base = [module("main", b"1"), module("drive", b"1")];
+        let new = [
+            module("main", b"1"),
+            module("drive", b"2"),
+            module("arm", b"1"),
+        ];
+        let changed = changed_modules(&base, &new).unwrap();
+        assert_eq!(changed, [module("drive", b"2"), module("arm", b"1")]);
+
+        // A removed module would still be loaded from the uploaded table
+        assert!(changed_modules(&new, &base).is_err());
+    }
+}
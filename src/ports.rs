@@ -0,0 +1,90 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use vex_v5_serial::{
+    Connection,
+    protocol::cdc2::system::{DeviceStatus, DeviceStatusPacket, DeviceStatusReplyPacket},
+    serial::{SerialConnection, SerialError},
+};
+
+use crate::{device::open_connection, errors::CliError};
+
+/// Smart devices plugged into the brain, ordered by port.
+pub async fn device_statuses(
+    conn: &mut SerialConnection,
+) -> Result<Vec<DeviceStatus>, SerialError> {
+    let reply = conn
+        .handshake::<DeviceStatusReplyPacket>(
+            Duration::from_secs(1),
+            2,
+            DeviceStatusPacket::new(()),
+        )
+        .await?;
+    let mut devices = reply.payload.map_err(SerialError::Nack)?.devices;
+    devices.sort_by_key(|device| device.port);
+    Ok(devices)
+}
+
+fn device_type(device: &DeviceStatus) -> String {
+    format!("{:?}", device.device_type)
+}
+
+/// Firmware versions compare by release, then beta.
+fn firmware(device: &DeviceStatus) -> (u16, u8) {
+    (device.version, device.beta_version)
+}
+
+/// `venice ports`: lists the smart devices on the brain with their firmware.
+///
+/// VEXos doesn't report which firmware it bundles over the serial protocol, so `check_firmware`
+/// flags devices running older firmware than another device of the same type on the brain. The
+/// brain only updates a device when it's plugged in after a VEXos update, which is how mixed
+/// versions, and the intermittent disconnects they cause, come about.
+pub async fn ports(check_firmware: bool) -> Result<(), CliError> {
+    let mut conn = open_connection().await?;
+    let devices = device_statuses(&mut conn).await?;
+    if devices.is_empty() {
+        println!("No smart devices connected");
+        return Ok(());
+    }
+
+    let mut newest: BTreeMap<String, (u16, u8)> = BTreeMap::new();
+    for device in devices.iter() {
+        let entry = newest.entry(device_type(device)).or_default();
+        *entry = (*entry).max(firmware(device));
+    }
+
+    let mut outdated = 0;
+    for device in devices.iter() {
+        let (version, beta) = firmware(device);
+        let beta = if beta == 0 {
+            String::new()
+        } else {
+            format!("b{beta}")
+        };
+        let note = match newest.get(&device_type(device)) {
+            Some(&(newest_version, newest_beta))
+                if check_firmware && firmware(device) < (newest_version, newest_beta) =>
+            {
+                outdated += 1;
+                format!(
+                    "  outdated: another {} has {newest_version}",
+                    device_type(device)
+                )
+            }
+            _ => String::new(),
+        };
+        println!(
+            "port {:>2}  {:<16} firmware {version}{beta}{note}",
+            device.port,
+            device_type(device)
+        );
+    }
+
+    if outdated > 0 {
+        return Err(CliError::OutdatedFirmware(outdated));
+    }
+    if check_firmware {
+        println!("✓ Devices of the same type all run the same firmware");
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use inquire::Confirm;
+use vex_v5_serial::protocol::cdc2::file::FileVendor;
+
+use crate::{
+    brain::{erase_file, list_files},
+    config::{cache_dir, config_dir, data_dir},
+    device::open_connection,
+    errors::CliError,
+    runtime::RtBin,
+};
+
+/// Every directory venice keeps host state in: downloaded runtimes and release metadata in the
+/// cache, the config and signing/secrets keys in the config dir, and the state store in the data
+/// dir. Some platforms put several of these in the same place, so duplicates are dropped.
+fn host_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = [cache_dir(), config_dir(), data_dir()]
+        .into_iter()
+        .flatten()
+        .collect();
+    dirs.dedup();
+    dirs
+}
+
+/// `venice purge`: deletes all host state, and with `brain` the runtimes uploaded to the brain.
+pub async fn purge(brain: bool, yes: bool) -> Result<(), CliError> {
+    let dirs: Vec<PathBuf> = host_dirs().into_iter().filter(|dir| dir.exists()).collect();
+
+    if !yes {
+        println!("This deletes:");
+        for dir in dirs.iter() {
+            println!("  {}", dir.display());
+        }
+        if brain {
+            println!("  venice runtimes on the connected brain");
+        }
+        println!("including the signing key, so tables signed with it can no longer be verified.");
+        let confirmed = Confirm::new("Continue?")
+            .with_default(false)
+            .prompt()
+            .unwrap_or(false);
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    for dir in dirs.iter() {
+        std::fs::remove_dir_all(dir)?;
+        println!("✓ Deleted {}", dir.display());
+    }
+    if dirs.is_empty() {
+        println!("No venice state on this machine");
+    }
+
+    if brain {
+        let mut conn = open_connection().await?;
+        let runtimes: Vec<String> = list_files(&mut conn, FileVendor::User)
+            .await?
+            .into_iter()
+            .map(|file| file.name)
+            .filter(|name| name.parse::<RtBin>().is_ok())
+            .collect();
+        for name in runtimes.iter() {
+            erase_file(&mut conn, name).await?;
+            println!("✓ Deleted {name} from the brain");
+        }
+        if runtimes.is_empty() {
+            println!("No venice runtimes on the brain");
+        }
+    }
+
+    println!(
+        "Secrets stored in the OS keychain and the venice-cli package itself are left in place."
+    );
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use vex_v5_serial::{commands::file::USER_PROGRAM_LOAD_ADDR, protocol::cdc2::file::FileVendor};
+
+use crate::{data::DataTarget, device::open_connection, errors::CliError, upload::upload_file};
+
+/// Which vendor's namespace a pushed file goes in. Programs only see `user` files.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default)]
+pub enum Vendor {
+    #[default]
+    User,
+    Sys,
+    Dev1,
+    Dev2,
+    Dev3,
+    Dev4,
+    Dev5,
+    Dev6,
+}
+
+impl Vendor {
+    fn file_vendor(self) -> FileVendor {
+        match self {
+            Self::User => FileVendor::User,
+            Self::Sys => FileVendor::Sys,
+            Self::Dev1 => FileVendor::Dev1,
+            Self::Dev2 => FileVendor::Dev2,
+            Self::Dev3 => FileVendor::Dev3,
+            Self::Dev4 => FileVendor::Dev4,
+            Self::Dev5 => FileVendor::Dev5,
+            Self::Dev6 => FileVendor::Dev6,
+        }
+    }
+}
+
+/// Parses a load address in hex (`0x03800000`) or decimal.
+pub fn parse_address(value: &str) -> Result<u32, String> {
+    let result = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    result.map_err(|e| format!("invalid address `{value}`: {e}"))
+}
+
+/// `venice push`: uploads arbitrary files to the brain under their file names, one connection
+/// for all of them.
+pub async fn push(
+    files: &[PathBuf],
+    vendor: Vendor,
+    target: DataTarget,
+    load_address: Option<u32>,
+) -> Result<(), CliError> {
+    let mut contents = Vec::new();
+    for path in files {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| CliError::BrainFileName(path.display().to_string()))?;
+        contents.push((name, tokio::fs::read(path).await?));
+    }
+
+    let mut conn = open_connection().await?;
+    for (name, data) in contents.iter() {
+        upload_file(
+            &mut conn,
+            name,
+            data,
+            vendor.file_vendor(),
+            target.transfer_target(),
+            load_address.unwrap_or(USER_PROGRAM_LOAD_ADDR),
+            None,
+        )
+        .await?;
+    }
+    println!("✓ Pushed {} file(s)", contents.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_address;
+
+    #[test]
+    fn addresses() {
+        assert_eq!(parse_address("0x03800000"), Ok(0x0380_0000));
+        assert_eq!(parse_address("58720256"), Ok(0x0380_0000));
+        assert!(parse_address("0xzz").is_err());
+    }
+}
@@ -0,0 +1,490 @@
+//! Just enough of a Python lexer to find import statements and dotted names in source files,
+//! without being fooled by strings or comments.
+
+use std::ops::Range;
+
+/// A piece of source text and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned {
+    pub text: String,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Alias {
+    pub name: Spanned,
+    pub alias: Option<Spanned>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Import {
+    /// `import a.b as c, d`
+    Import {
+        modules: Vec<Alias>,
+        /// Inside a `try`/`except` block, so the program may cope with it failing
+        guarded: bool,
+    },
+    /// `from ..a import b as c, d`. `names` is empty for `import *`.
+    From {
+        /// Number of leading dots
+        level: usize,
+        module: Option<Spanned>,
+        names: Vec<Alias>,
+        /// Span of the whole statement
+        span: Range<usize>,
+        guarded: bool,
+    },
+}
+
+impl Import {
+    /// Byte offset of the start of the statement.
+    pub fn start(&self) -> usize {
+        match self {
+            Self::Import { modules, .. } => modules[0].name.span.start,
+            Self::From { span, .. } => span.start,
+        }
+    }
+
+    pub fn guarded(&self) -> bool {
+        match self {
+            Self::Import { guarded, .. } | Self::From { guarded, .. } => *guarded,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Scanned {
+    pub imports: Vec<Import>,
+    /// Every `a.b.c` in code that isn't itself an attribute of something else
+    pub dotted_names: Vec<Spanned>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(Range<usize>),
+    Op(char, usize),
+    Newline,
+}
+
+/// 1-based line number of a byte offset.
+pub fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+/// 0-based column of a byte offset, in UTF-16 code units as editors count them.
+pub fn column_of(source: &str, offset: usize) -> usize {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..offset].encode_utf16().count()
+}
+
+fn is_string_prefix(prefix: &str) -> bool {
+    prefix.len() <= 2
+        && prefix
+            .chars()
+            .all(|c| matches!(c.to_ascii_lowercase(), 'r' | 'b' | 'u' | 'f'))
+}
+
+/// Skips a string literal whose opening quote is at `start`, returning the offset after it.
+fn skip_string(source: &str, start: usize) -> usize {
+    let bytes = source.as_bytes();
+    let quote = bytes[start];
+    let triple = bytes.get(start..start + 3) == Some(&[quote; 3][..]);
+    let mut i = start + if triple { 3 } else { 1 };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' if !triple => return i,
+            c if c == quote => {
+                if !triple {
+                    return i + 1;
+                }
+                if bytes.get(i..i + 3) == Some(&[quote; 3][..]) {
+                    return i + 3;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = source[i..].chars().next().unwrap();
+        match c {
+            '#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            '\\' if bytes.get(i + 1) == Some(&b'\n') => i += 2,
+            '\\' if bytes.get(i + 1..i + 3) == Some(b"\r\n") => i += 3,
+            '\n' => {
+                if depth == 0 && tokens.last().is_some_and(|t| *t != Token::Newline) {
+                    tokens.push(Token::Newline);
+                }
+                i += 1;
+            }
+            '"' | '\'' => i = skip_string(source, i),
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while let Some(c) = source[i..].chars().next()
+                    && (c.is_alphanumeric() || c == '_')
+                {
+                    i += c.len_utf8();
+                }
+
+                if matches!(bytes.get(i), Some(b'"' | b'\'')) && is_string_prefix(&source[start..i])
+                {
+                    i = skip_string(source, i);
+                } else {
+                    tokens.push(Token::Name(start..i));
+                }
+            }
+            c if c.is_ascii_digit() => {
+                while let Some(c) = source[i..].chars().next()
+                    && (c.is_alphanumeric() || c == '_' || c == '.')
+                {
+                    i += c.len_utf8();
+                }
+            }
+            c if c.is_whitespace() => i += c.len_utf8(),
+            c => {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                tokens.push(Token::Op(c, i));
+                i += c.len_utf8();
+            }
+        }
+    }
+
+    tokens
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn name(&self, token: Option<&Token>) -> Option<Spanned> {
+        match token {
+            Some(Token::Name(span)) => Some(Spanned {
+                text: self.source[span.clone()].to_string(),
+                span: span.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn eat_name(&mut self) -> Option<Spanned> {
+        let name = self.name(self.peek())?;
+        self.pos += 1;
+        Some(name)
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        self.name(self.peek())
+            .is_some_and(|name| name.text == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let at = self.at_keyword(keyword);
+        if at {
+            self.pos += 1;
+        }
+        at
+    }
+
+    fn eat_op(&mut self, op: char) -> bool {
+        if matches!(self.peek(), Some(Token::Op(c, _)) if *c == op) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    /// `a.b.c`, as a single span
+    fn dotted_name(&mut self) -> Option<Spanned> {
+        let first = self.eat_name()?;
+        let mut span = first.span;
+        while matches!(self.peek(), Some(Token::Op('.', _)))
+            && let Some(next) = self.name(self.tokens.get(self.pos + 1))
+        {
+            self.pos += 2;
+            span.end = next.span.end;
+        }
+
+        Some(Spanned {
+            text: self.source[span.clone()].to_string(),
+            span,
+        })
+    }
+
+    fn alias(&mut self, dotted: bool) -> Option<Alias> {
+        let name = if dotted {
+            self.dotted_name()?
+        } else {
+            self.eat_name()?
+        };
+        let alias = if self.eat_keyword("as") {
+            self.eat_name()
+        } else {
+            None
+        };
+        Some(Alias { name, alias })
+    }
+
+    fn import(&mut self, guarded: bool) -> Option<Import> {
+        let mut modules = Vec::new();
+        loop {
+            modules.push(self.alias(true)?);
+            if !self.eat_op(',') {
+                return Some(Import::Import { modules, guarded });
+            }
+        }
+    }
+
+    fn from_import(&mut self, start: usize, guarded: bool) -> Option<Import> {
+        let mut level = 0;
+        while self.eat_op('.') {
+            level += 1;
+        }
+        let module = if self.at_keyword("import") {
+            None
+        } else {
+            self.dotted_name()
+        };
+        if !self.eat_keyword("import") {
+            return None;
+        }
+
+        let mut names = Vec::new();
+        if !self.eat_op('*') {
+            let parenthesized = self.eat_op('(');
+            while let Some(alias) = self.alias(false) {
+                names.push(alias);
+                if !self.eat_op(',') {
+                    break;
+                }
+            }
+            if parenthesized {
+                self.eat_op(')');
+            }
+        }
+
+        let end = match &self.tokens[self.pos - 1] {
+            Token::Name(span) => span.end,
+            Token::Op(c, offset) => offset + c.len_utf8(),
+            Token::Newline => start,
+        };
+        Some(Import::From {
+            level,
+            module,
+            names,
+            span: start..end,
+            guarded,
+        })
+    }
+}
+
+/// Whether `source` has any statements besides string literals, like a docstring, which do
+/// nothing when run.
+pub fn has_code(source: &str) -> bool {
+    tokenize(source)
+        .iter()
+        .any(|token| *token != Token::Newline)
+}
+
+/// Finds the imports and dotted names in a Python source file. Tolerant of syntax it doesn't
+/// understand: anything that isn't an import is skipped.
+pub fn scan(source: &str) -> Scanned {
+    let mut parser = Parser {
+        source,
+        tokens: tokenize(source),
+        pos: 0,
+    };
+    let mut scanned = Scanned::default();
+    let mut statement_start = true;
+    // Indents of the enclosing `try:`/`except:` blocks
+    let mut guards: Vec<usize> = Vec::new();
+
+    while let Some(token) = parser.peek().cloned() {
+        if statement_start && let Token::Name(span) = &token {
+            let indent = span.start - source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+            while guards.last().is_some_and(|&guard| guard >= indent) {
+                guards.pop();
+            }
+            if matches!(&source[span.clone()], "try" | "except") {
+                guards.push(indent);
+            }
+        }
+
+        match token {
+            Token::Newline | Token::Op(';', _) => {
+                parser.pos += 1;
+                statement_start = true;
+                continue;
+            }
+            Token::Name(span) if statement_start && &source[span.clone()] == "import" => {
+                parser.pos += 1;
+                if let Some(import) = parser.import(!guards.is_empty()) {
+                    scanned.imports.push(import);
+                }
+            }
+            Token::Name(span) if statement_start && &source[span.clone()] == "from" => {
+                parser.pos += 1;
+                if let Some(import) = parser.from_import(span.start, !guards.is_empty()) {
+                    scanned.imports.push(import);
+                }
+            }
+            Token::Name(_) => {
+                let is_attribute =
+                    parser.pos > 0 && matches!(parser.tokens[parser.pos - 1], Token::Op('.', _));
+                let dotted = parser.dotted_name().unwrap();
+                if !is_attribute {
+                    scanned.dotted_names.push(dotted);
+                }
+            }
+            Token::Op(..) => parser.pos += 1,
+        }
+        statement_start = false;
+    }
+
+    scanned
+}
+
+/// Resolves a relative import against the module it appears in. `is_package` is true for
+/// `__init__` modules, whose relative imports are relative to themselves.
+pub fn resolve_relative(
+    importer: &str,
+    is_package: bool,
+    level: usize,
+    module: Option<&str>,
+) -> Option<String> {
+    if level == 0 {
+        return module.map(str::to_string);
+    }
+
+    let mut parts: Vec<&str> = importer.split('.').collect();
+    if !is_package {
+        parts.pop();
+    }
+    for _ in 1..level {
+        parts.pop()?;
+    }
+    parts.extend(module.into_iter().flat_map(|m| m.split('.')));
+    (!parts.is_empty()).then(|| parts.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_imports_outside_strings_and_comments() {
+        let source = "import a.b as c, d\n\
+            # import nope\n\
+            s = \"\"\"\nimport nope\n\"\"\"\n\
+            from ..pkg import (x,\n    y as z)\n\
+            from . import *\n";
+        let scanned = scan(source);
+        assert_eq!(scanned.imports.len(), 3);
+
+        let Import::Import { modules, .. } = &scanned.imports[0] else {
+            panic!("expected a plain import");
+        };
+        assert_eq!(modules[0].name.text, "a.b");
+        assert_eq!(modules[0].alias.as_ref().unwrap().text, "c");
+        assert_eq!(modules[1].name.text, "d");
+
+        let Import::From {
+            level,
+            module,
+            names,
+            span,
+            ..
+        } = &scanned.imports[1]
+        else {
+            panic!("expected a from import");
+        };
+        assert_eq!(*level, 2);
+        assert_eq!(module.as_ref().unwrap().text, "pkg");
+        assert_eq!(names.len(), 2);
+        assert_eq!(&source[span.clone()], "from ..pkg import (x,\n    y as z)");
+
+        let Import::From {
+            level,
+            module,
+            names,
+            ..
+        } = &scanned.imports[2]
+        else {
+            panic!("expected a from import");
+        };
+        assert_eq!(
+            (*level, module.is_none(), names.is_empty()),
+            (1, true, true)
+        );
+    }
+
+    #[test]
+    fn has_code_outside_comments_and_docstrings() {
+        assert!(!has_code(""));
+        assert!(!has_code("# main\n\n"));
+        assert!(!has_code("\"\"\"Docstring\n\nrun()\n\"\"\"\n'another'\n"));
+        assert!(has_code("\"\"\"Docstring\"\"\"\nrun()\n"));
+        assert!(has_code("x = 'string'"));
+    }
+
+    #[test]
+    fn marks_imports_in_try_blocks_as_guarded() {
+        let scanned =
+            scan("try:\n    import ujson\nexcept ImportError:\n    import json\nimport os\n");
+        let guarded: Vec<_> = scanned.imports.iter().map(Import::guarded).collect();
+        assert_eq!(guarded, [true, true, false]);
+    }
+
+    #[test]
+    fn dotted_names_skip_attributes() {
+        let scanned = scan("x = a.b.c(1).d\n");
+        let names: Vec<_> = scanned
+            .dotted_names
+            .iter()
+            .map(|n| n.text.as_str())
+            .collect();
+        assert_eq!(names, ["x", "a.b.c"]);
+    }
+
+    #[test]
+    fn resolves_relative_imports() {
+        assert_eq!(
+            resolve_relative("a.b.c", false, 1, Some("d")).as_deref(),
+            Some("a.b.d")
+        );
+        assert_eq!(
+            resolve_relative("a.b", true, 1, Some("d")).as_deref(),
+            Some("a.b.d")
+        );
+        assert_eq!(
+            resolve_relative("a.b.c", false, 2, None).as_deref(),
+            Some("a")
+        );
+        assert_eq!(resolve_relative("a", false, 2, None), None);
+    }
+}
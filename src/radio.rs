@@ -0,0 +1,163 @@
+use std::{fmt::Display, time::Duration};
+
+use tokio::time::{sleep, timeout};
+use vex_v5_serial::{
+    Connection,
+    protocol::cdc2::{
+        controller::{
+            RadioChannel, SelectRadioChannelPacket, SelectRadioChannelPayload,
+            SelectRadioChannelReplyPacket,
+        },
+        system::{RadioStatusPacket, RadioStatusPayload, RadioStatusReplyPacket},
+    },
+    serial::{SerialConnection, SerialError},
+};
+
+use crate::errors::CliError;
+
+/// VEXnet channel the controller uses while downloading programs.
+const DOWNLOAD_CHANNEL: u8 = 5;
+/// VEXnet channel the controller sits on when idle in the pits.
+const PIT_CHANNEL: u8 = 31;
+
+const SWITCH_TIMEOUT: Duration = Duration::from_secs(8);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Channel {
+    Pit,
+    Download,
+}
+
+impl From<Channel> for RadioChannel {
+    fn from(value: Channel) -> Self {
+        match value {
+            Channel::Pit => Self::Pit,
+            Channel::Download => Self::Download,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LinkType {
+    /// Linked to a brain. The radio status doesn't say whether over VEXnet or Bluetooth, and the
+    /// channel doesn't tell them apart either.
+    Unknown,
+    None,
+}
+
+impl LinkType {
+    pub fn from_status(status: &RadioStatusPayload) -> Self {
+        if status.device_id == 0 {
+            Self::None
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+impl Display for LinkType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Unknown => "linked, type unknown",
+            Self::None => "not linked",
+        })
+    }
+}
+
+/// `channel`, with what venice uses it for if it's one of its own, e.g. `5 (download)`.
+fn channel_label(channel: u8) -> String {
+    match channel {
+        DOWNLOAD_CHANNEL => format!("{channel} (download)"),
+        PIT_CHANNEL => format!("{channel} (pit)"),
+        _ => channel.to_string(),
+    }
+}
+
+pub async fn radio_status(conn: &mut SerialConnection) -> Result<RadioStatusPayload, SerialError> {
+    let reply = conn
+        .handshake::<RadioStatusReplyPacket>(Duration::from_secs(1), 2, RadioStatusPacket::new(()))
+        .await?;
+    reply.payload.map_err(SerialError::Nack)
+}
+
+/// One-line summary of the radio link, e.g. `linked, channel 5 (download), 80% quality`.
+pub fn describe(status: &RadioStatusPayload) -> String {
+    if LinkType::from_status(status) == LinkType::None {
+        return LinkType::None.to_string();
+    }
+    format!(
+        "linked, channel {}, {}% quality",
+        channel_label(status.channel),
+        status.quality
+    )
+}
+
+pub async fn status(conn: &mut SerialConnection) -> Result<(), CliError> {
+    let status = radio_status(conn).await?;
+    let link = LinkType::from_status(&status);
+
+    println!("link:     {link}");
+    if link != LinkType::None {
+        println!("channel:  {}", channel_label(status.channel));
+        println!("quality:  {}%", status.quality);
+        println!("strength: {} dBm", status.strength);
+    }
+
+    Ok(())
+}
+
+/// Switches the controller's radio channel, waiting for the link to drop and come back on the new
+/// channel.
+pub async fn switch_channel(conn: &mut SerialConnection, channel: Channel) -> Result<(), CliError> {
+    let status = radio_status(conn).await?;
+    let target = match channel {
+        Channel::Pit => PIT_CHANNEL,
+        Channel::Download => DOWNLOAD_CHANNEL,
+    };
+    if status.channel == target {
+        return Ok(());
+    }
+    if status.device_id == 0 {
+        return Err(CliError::NoRadioLink);
+    }
+
+    let reply = conn
+        .handshake::<SelectRadioChannelReplyPacket>(
+            Duration::from_secs(1),
+            2,
+            SelectRadioChannelPacket::new(SelectRadioChannelPayload {
+                channel: channel.into(),
+            }),
+        )
+        .await?;
+    reply.payload.map_err(SerialError::Nack)?;
+
+    timeout(SWITCH_TIMEOUT, async {
+        while radio_status(conn).await.is_ok_and(|s| s.device_id != 0) {
+            sleep(POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .map_err(|_| CliError::RadioChannelDisconnectTimeout)?;
+
+    timeout(SWITCH_TIMEOUT, async {
+        while !radio_status(conn)
+            .await
+            .is_ok_and(|s| s.device_id != 0 && s.channel == target)
+        {
+            sleep(POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .map_err(|_| CliError::RadioChannelReconnectTimeout)?;
+
+    println!(
+        "✓ Switched to the {} channel",
+        match channel {
+            Channel::Pit => "pit",
+            Channel::Download => "download",
+        }
+    );
+    Ok(())
+}
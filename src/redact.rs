@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use crate::project_dir;
+
+/// Host paths that identify the machine or the user, with what they're replaced by. The project
+/// comes first since it's usually inside the home dir.
+pub fn host_paths() -> Vec<(String, &'static str)> {
+    let mut paths = Vec::new();
+    if let Ok(dir) = project_dir() {
+        paths.push((dir.to_string_lossy().into_owned(), "<project>"));
+    }
+    if let Some(dir) = dirs::home_dir() {
+        paths.push((dir.to_string_lossy().into_owned(), "~"));
+    }
+    let temp_dir = std::env::temp_dir();
+    let temp_dir = temp_dir.to_string_lossy();
+    paths.push((
+        temp_dir
+            .trim_end_matches(std::path::MAIN_SEPARATOR)
+            .to_string(),
+        "<temp>",
+    ));
+
+    // A home of `/` (as in some containers) would match every path
+    paths.retain(|(path, _)| Path::new(path).parent().is_some());
+    paths
+}
+
+/// Replaces each path in `paths` with its placeholder. Paths are replaced in order, so list
+/// more specific ones (the project inside the home dir) first.
+pub fn redact(text: &str, paths: &[(String, &str)]) -> String {
+    let mut text = text.to_string();
+    for (path, placeholder) in paths {
+        text = text.replace(path.as_str(), placeholder);
+    }
+    text
+}
+
+/// The first of `paths` in `bytes`, e.g. the file name a module is compiled under.
+pub fn find_host_path<'a>(bytes: &[u8], paths: &'a [(String, &str)]) -> Option<&'a str> {
+    paths.iter().map(|(path, _)| path.as_str()).find(|path| {
+        bytes
+            .windows(path.len())
+            .any(|window| window == path.as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_host_path, redact};
+
+    fn paths() -> [(String, &'static str); 2] {
+        [
+            ("/home/ada/robot".to_string(), "<project>"),
+            ("/home/ada".to_string(), "~"),
+        ]
+    }
+
+    #[test]
+    fn redacts_specific_paths_first() {
+        assert_eq!(
+            redact(
+                "couldn't build `/home/ada/robot/main.py` with `/home/ada/.venv/bin/mpy-cross`",
+                &paths()
+            ),
+            "couldn't build `<project>/main.py` with `~/.venv/bin/mpy-cross`"
+        );
+    }
+
+    #[test]
+    fn finds_paths_in_bytecode() {
+        let paths = paths();
+        assert_eq!(
+            find_host_path(b"M\x06\x00\x1f\x0e/home/ada/robot/main.py\x00", &paths),
+            Some("/home/ada/robot")
+        );
+        assert_eq!(
+            find_host_path(b"M\x06\x00\x1f\x07main.py\x00", &paths),
+            None
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{config::Config, errors::CliError, frozen};
+
+/// Environment variable that overrides [registry].token, for CI.
+pub const TOKEN_ENV: &str = "VENICE_REGISTRY_TOKEN";
+
+/// `GET {url}/index/{name}.json`: every published version of a package.
+#[derive(Deserialize, Debug)]
+pub struct IndexEntry {
+    pub name: String,
+    pub versions: Vec<IndexVersion>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct IndexVersion {
+    pub version: semver::Version,
+    /// Hex-encoded SHA-256 of the package tarball
+    pub sha256: String,
+    /// Yanked versions stay downloadable for existing lockfiles but are never newly resolved
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+impl IndexEntry {
+    /// The newest non-yanked version matching `req`.
+    pub fn resolve(&self, req: &semver::VersionReq) -> Result<&IndexVersion, CliError> {
+        self.versions
+            .iter()
+            .filter(|v| !v.yanked && req.matches(&v.version))
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .ok_or_else(|| CliError::NoMatchingVersion {
+                name: self.name.clone(),
+                req: req.clone(),
+            })
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Client for the Venice package registry. Packages are gzipped tarballs of pure-Python
+/// modules plus the pyproject.toml they were published from:
+///
+/// - `GET {url}/index/{name}.json` returns an [`IndexEntry`]
+/// - `GET {url}/packages/{name}/{version}.tar.gz` downloads a package
+/// - `PUT {url}/api/v1/packages/{name}/{version}` publishes one (bearer token)
+/// - `PUT`/`DELETE {url}/api/v1/packages/{name}/{version}/yank` yanks or un-yanks one
+pub struct RegistryClient {
+    agent: ureq::Agent,
+    url: String,
+    token: Option<String>,
+}
+
+impl RegistryClient {
+    pub fn new(config: &Config) -> Result<Self, CliError> {
+        if frozen() {
+            return Err(CliError::FrozenNetwork);
+        }
+
+        let url = config.registry.url.clone().ok_or(CliError::NoRegistry)?;
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(Duration::from_secs(60)))
+            .user_agent(concat!("venice-cli/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .into();
+
+        Ok(Self {
+            agent,
+            url: url.trim_end_matches('/').to_string(),
+            token: std::env::var(TOKEN_ENV)
+                .ok()
+                .or_else(|| config.registry.token.clone()),
+        })
+    }
+
+    pub async fn index(&self, name: &str) -> Result<IndexEntry, CliError> {
+        let body = self
+            .request("GET", &format!("/index/{name}.json"), None, false)
+            .await
+            .map_err(|e| match e {
+                CliError::RegistryStatus { status: 404, .. } => {
+                    CliError::PackageNotFound(name.to_string())
+                }
+                e => e,
+            })?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Downloads a package tarball and checks it against the expected checksum.
+    pub async fn download(
+        &self,
+        name: &str,
+        version: &semver::Version,
+        sha256: &str,
+    ) -> Result<Vec<u8>, CliError> {
+        let tarball = self
+            .request(
+                "GET",
+                &format!("/packages/{name}/{version}.tar.gz"),
+                None,
+                false,
+            )
+            .await?;
+
+        if sha256_hex(&tarball) != sha256 {
+            return Err(CliError::ChecksumMismatch(name.to_string()));
+        }
+        Ok(tarball)
+    }
+
+    pub async fn publish(
+        &self,
+        name: &str,
+        version: &semver::Version,
+        tarball: Vec<u8>,
+    ) -> Result<(), CliError> {
+        self.request(
+            "PUT",
+            &format!("/api/v1/packages/{name}/{version}"),
+            Some(tarball),
+            true,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_yanked(
+        &self,
+        name: &str,
+        version: &semver::Version,
+        yanked: bool,
+    ) -> Result<(), CliError> {
+        self.request(
+            if yanked { "PUT" } else { "DELETE" },
+            &format!("/api/v1/packages/{name}/{version}/yank"),
+            None,
+            true,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn request(
+        &self,
+        method: &'static str,
+        path: &str,
+        body: Option<Vec<u8>>,
+        authenticated: bool,
+    ) -> Result<Vec<u8>, CliError> {
+        let token = match (authenticated, &self.token) {
+            (false, _) => None,
+            (true, Some(token)) => Some(token.clone()),
+            (true, None) => return Err(CliError::NoRegistryToken),
+        };
+        let url = format!("{}{path}", self.url);
+        let agent = self.agent.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<Vec<u8>, CliError> {
+            let auth = token.map(|token| format!("Bearer {token}"));
+            let mut response = match (method, body) {
+                ("PUT", body) => {
+                    let mut request = agent.put(&url).header("Content-Type", "application/gzip");
+                    if let Some(auth) = &auth {
+                        request = request.header("Authorization", auth);
+                    }
+                    request.send(body.unwrap_or_default())?
+                }
+                ("DELETE", _) => {
+                    let mut request = agent.delete(&url);
+                    if let Some(auth) = &auth {
+                        request = request.header("Authorization", auth);
+                    }
+                    request.call()?
+                }
+                _ => agent.get(&url).call()?,
+            };
+
+            match response.status().as_u16() {
+                200..=299 => Ok(response.body_mut().read_to_vec()?),
+                status => Err(CliError::RegistryStatus { status, url }),
+            }
+        })
+        .await
+        .unwrap()
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use semver::Version;
+
+use crate::{
+    errors::CliError,
+    github::{GithubClient, Release},
+};
+
+/// Releases asked for per page, the most GitHub allows. A shorter page is the last one.
+pub const PER_PAGE: usize = 100;
+
+/// Which releases a query on a [`ReleaseIndex`] matches.
+#[derive(Default, Debug, Clone)]
+pub struct ReleaseQuery {
+    /// Include releases marked as prereleases, or with a prerelease version
+    pub prereleases: bool,
+    /// Only releases at or after this version
+    pub since: Option<Version>,
+}
+
+impl ReleaseQuery {
+    /// Stable releases only.
+    pub fn stable() -> Self {
+        Self::default()
+    }
+
+    /// Every release, prereleases included.
+    pub fn all() -> Self {
+        Self {
+            prereleases: true,
+            since: None,
+        }
+    }
+
+    fn matches(&self, version: &Version, release: &Release) -> bool {
+        (self.prereleases || !(release.prerelease || !version.pre.is_empty()))
+            && self.since.as_ref().is_none_or(|since| version >= since)
+    }
+}
+
+/// Every published release of a repository, newest first. Drafts and releases whose tags aren't
+/// versions are left out.
+pub struct ReleaseIndex {
+    releases: Vec<(Version, Release)>,
+}
+
+impl ReleaseIndex {
+    /// Reads every page of `repo`'s releases. Pages cached less than `max_age` ago are used
+    /// without asking GitHub, and older ones are revalidated by their ETag, so a repository that
+    /// hasn't published since costs no rate limit.
+    pub async fn fetch(
+        client: &GithubClient,
+        repo: &str,
+        max_age: Duration,
+    ) -> Result<Self, CliError> {
+        let mut releases = Vec::new();
+        for page in 1.. {
+            let batch = client.releases_page(repo, page, max_age).await?;
+            let last = batch.len() < PER_PAGE;
+            releases.extend(
+                batch
+                    .into_iter()
+                    .filter(|release| !release.draft)
+                    .filter_map(|release| Some((release.version().ok()?, release))),
+            );
+            if last {
+                break;
+            }
+        }
+        Ok(Self::new(releases))
+    }
+
+    fn new(mut releases: Vec<(Version, Release)>) -> Self {
+        releases.sort_by(|(a, _), (b, _)| b.cmp(a));
+        // A page boundary moving while reading can repeat a release
+        releases.dedup_by(|(a, _), (b, _)| a == b);
+        Self { releases }
+    }
+
+    /// Releases matching `query`, newest first.
+    pub fn query<'a>(
+        &'a self,
+        query: &'a ReleaseQuery,
+    ) -> impl Iterator<Item = (&'a Version, &'a Release)> {
+        self.releases
+            .iter()
+            .filter(|(version, release)| query.matches(version, release))
+            .map(|(version, release)| (version, release))
+    }
+
+    /// The newest release matching `query`.
+    pub fn latest(&self, query: &ReleaseQuery) -> Option<(&Version, &Release)> {
+        self.query(query).next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::{ReleaseIndex, ReleaseQuery};
+    use crate::github::Release;
+
+    fn release(tag: &str, prerelease: bool) -> (Version, Release) {
+        let release = Release {
+            tag_name: tag.to_string(),
+            name: None,
+            draft: false,
+            prerelease,
+            html_url: String::new(),
+            assets: Vec::new(),
+        };
+        (release.version().unwrap(), release)
+    }
+
+    #[test]
+    fn filters_and_orders_releases() {
+        let index = ReleaseIndex::new(vec![
+            release("v0.2.0", false),
+            release("v0.4.0-beta.1", false),
+            release("v0.3.0", true),
+            release("v0.1.0", false),
+            release("v0.2.0", false),
+        ]);
+        let tags = |query: &ReleaseQuery| -> Vec<String> {
+            index
+                .query(query)
+                .map(|(version, _)| version.to_string())
+                .collect()
+        };
+
+        assert_eq!(tags(&ReleaseQuery::stable()), ["0.2.0", "0.1.0"]);
+        assert_eq!(
+            tags(&ReleaseQuery::all()),
+            ["0.4.0-beta.1", "0.3.0", "0.2.0", "0.1.0"]
+        );
+        let since = ReleaseQuery {
+            since: Some(Version::new(0, 2, 0)),
+            ..ReleaseQuery::all()
+        };
+        assert_eq!(tags(&since), ["0.4.0-beta.1", "0.3.0", "0.2.0"]);
+        assert_eq!(
+            index.latest(&ReleaseQuery::stable()).unwrap().0,
+            &Version::new(0, 2, 0)
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::{ops::Range, path::Path};
+
+use crate::{
+    build::{SrcModule, find_modules},
+    errors::CliError,
+    manifest::{MANIFEST_NAME, get_project},
+    profile, project_dir,
+    pysource::{Import, line_of, resolve_relative, scan},
+    tr,
+};
+
+/// `name` with the `old` prefix replaced by `new`, if it's `old` or inside it.
+fn renamed(name: &str, old: &str, new: &str) -> Option<String> {
+    if name == old {
+        Some(new.to_string())
+    } else {
+        name.strip_prefix(old)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .map(|rest| format!("{new}.{rest}"))
+    }
+}
+
+fn is_valid_module_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Rewrites the imports in one module's source. Returns the new source and the lines of any
+/// imports that couldn't be rewritten automatically.
+fn rewrite_imports(
+    source: &str,
+    importer: &str,
+    is_package: bool,
+    old: &str,
+    new: &str,
+) -> (String, Vec<usize>) {
+    let scanned = scan(source);
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut manual = Vec::new();
+    // `import old` binds `old` itself, so attribute accesses through it need renaming too
+    let mut binds_old = false;
+
+    for import in scanned.imports.iter() {
+        match import {
+            Import::Import { modules, .. } => {
+                for alias in modules {
+                    if let Some(new_name) = renamed(&alias.name.text, old, new) {
+                        edits.push((alias.name.span.clone(), new_name));
+                        binds_old |= alias.alias.is_none();
+                    }
+                }
+            }
+            Import::From {
+                level,
+                module,
+                names,
+                span,
+                ..
+            } => {
+                let Some(from) = resolve_relative(
+                    importer,
+                    is_package,
+                    *level,
+                    module.as_ref().map(|m| m.text.as_str()),
+                ) else {
+                    continue;
+                };
+
+                if let Some(new_from) = renamed(&from, old, new) {
+                    match module {
+                        Some(module) => {
+                            edits.push((span.start..module.span.end, format!("from {new_from}")))
+                        }
+                        None => manual.push(line_of(source, span.start)),
+                    }
+                    continue;
+                }
+
+                for name in names {
+                    let Some(new_full) = renamed(&format!("{from}.{}", name.text), old, new) else {
+                        continue;
+                    };
+                    let (new_parent, new_leaf) =
+                        new_full.rsplit_once('.').unwrap_or(("", &new_full));
+                    // Keep the name the rest of the file uses
+                    let bound = name.alias.as_ref().unwrap_or(&name.name).text.clone();
+                    let alias = if bound == new_leaf {
+                        String::new()
+                    } else {
+                        format!(" as {bound}")
+                    };
+
+                    if new_parent == from {
+                        let end = name.alias.as_ref().unwrap_or(&name.name).span.end;
+                        edits.push((name.name.span.start..end, format!("{new_leaf}{alias}")));
+                    } else if names.len() == 1 && new_parent.is_empty() {
+                        edits.push((span.clone(), format!("import {new_leaf}{alias}")));
+                    } else if names.len() == 1 {
+                        edits.push((
+                            span.clone(),
+                            format!("from {new_parent} import {new_leaf}{alias}"),
+                        ));
+                    } else {
+                        manual.push(line_of(source, span.start));
+                    }
+                }
+            }
+        }
+    }
+
+    if binds_old {
+        for name in scanned.dotted_names.iter() {
+            if let Some(new_name) = renamed(&name.text, old, new) {
+                edits.push((name.span.clone(), new_name));
+            }
+        }
+    }
+
+    edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+    let mut rewritten = source.to_string();
+    for (span, replacement) in edits {
+        rewritten.replace_range(span, &replacement);
+    }
+    (rewritten, manual)
+}
+
+/// Updates `module:function` entries in [project.scripts].
+async fn rewrite_scripts(manifest_path: &Path, old: &str, new: &str) -> Result<(), CliError> {
+    let content = tokio::fs::read_to_string(manifest_path).await?;
+    let mut doc = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| CliError::ManifestEdit(e.to_string()))?;
+
+    let Some(scripts) = doc
+        .get_mut("project")
+        .and_then(|project| project.get_mut("scripts"))
+        .and_then(|scripts| scripts.as_table_like_mut())
+    else {
+        return Ok(());
+    };
+
+    let mut changed = false;
+    for (_, entry) in scripts.iter_mut() {
+        let Some((module, function)) = entry.as_str().and_then(|s| s.split_once(':')) else {
+            continue;
+        };
+        if let Some(new_module) = renamed(module, old, new) {
+            *entry = toml_edit::value(format!("{new_module}:{function}"));
+            changed = true;
+        }
+    }
+
+    if changed {
+        tokio::fs::write(manifest_path, doc.to_string()).await?;
+    }
+    Ok(())
+}
+
+fn module_name(module: &SrcModule) -> Result<String, CliError> {
+    Ok(String::from_utf8_lossy(&module.python_name()?).into_owned())
+}
+
+/// `venice rename`: moves a module or package and rewrites every import of it.
+pub async fn rename(old: &str, new: &str) -> Result<(), CliError> {
+    if !is_valid_module_name(new) {
+        return Err(CliError::InvalidModuleName(new.to_string()));
+    }
+
+    let project_dir = project_dir()?;
+    let project = get_project().await?;
+    let compiler = project.compiler(profile());
+    let modules = find_modules(project_dir, &compiler, &project.build).await?;
+
+    let mut target = None;
+    for module in modules.iter() {
+        let name = module_name(module)?;
+        if name == old {
+            target = Some(module);
+        }
+        // Covers both `new` itself and anything inside a `new` package
+        if renamed(&name, new, new).is_some() {
+            return Err(CliError::ModuleExists(new.to_string()));
+        }
+    }
+    let target = target.ok_or_else(|| CliError::UnknownModule(old.to_string()))?;
+
+    let new_path = project_dir.join(new.replace('.', std::path::MAIN_SEPARATOR_STR));
+    if let Some(parent) = new_path.parent()
+        && !tokio::fs::try_exists(parent).await?
+    {
+        return Err(CliError::UnknownModule(
+            new.rsplit_once('.').unwrap().0.to_string(),
+        ));
+    }
+
+    let mut rewritten = 0;
+    for module in modules.iter() {
+        let path = module.src_path(project_dir);
+        if module.is_precompiled() {
+            eprintln!(
+                "{}",
+                tr!("rename-precompiled", path = path.display().to_string())
+            );
+            continue;
+        }
+        let source = tokio::fs::read_to_string(&path).await?;
+        let (new_source, manual) = rewrite_imports(
+            &source,
+            &module_name(module)?,
+            module.is_package(),
+            old,
+            new,
+        );
+
+        if new_source != source {
+            tokio::fs::write(&path, new_source).await?;
+            rewritten += 1;
+        }
+        for line in manual {
+            eprintln!(
+                "{}",
+                tr!(
+                    "rename-manual",
+                    location = format!("{}:{line}", path.display())
+                )
+            );
+        }
+    }
+
+    rewrite_scripts(&project_dir.join(MANIFEST_NAME), old, new).await?;
+
+    let old_path = target.src_path(project_dir);
+    if target.is_package() {
+        tokio::fs::rename(old_path.parent().unwrap(), &new_path).await?;
+    } else {
+        tokio::fs::rename(
+            &old_path,
+            new_path.with_extension(old_path.extension().unwrap()),
+        )
+        .await?;
+    }
+
+    println!(
+        "{}",
+        tr!("rename-done", old = old, new = new, count = rewritten)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_plain_and_from_imports() {
+        let source = "import drive\nimport drive.pid as p\nfrom drive import pid\n\ndrive.go()\n";
+        let (rewritten, manual) = rewrite_imports(source, "main", false, "drive", "chassis");
+        assert_eq!(
+            rewritten,
+            "import chassis\nimport chassis.pid as p\nfrom chassis import pid\n\nchassis.go()\n"
+        );
+        assert!(manual.is_empty());
+    }
+
+    #[test]
+    fn keeps_bound_names_when_renaming_submodules() {
+        let source = "from drive import pid, odom\npid.step()\n";
+        let (rewritten, _) = rewrite_imports(source, "main", false, "drive.pid", "drive.control");
+        assert_eq!(
+            rewritten,
+            "from drive import control as pid, odom\npid.step()\n"
+        );
+    }
+}
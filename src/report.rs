@@ -0,0 +1,137 @@
+use std::{
+    fmt::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{Compression, write::GzEncoder};
+
+use crate::{
+    doctor::{run_checks, tool_version},
+    errors::CliError,
+    manifest::MANIFEST_NAME,
+    project_dir,
+    redact::{host_paths, redact},
+    store::{Failure, Store},
+    toolchain::find,
+    uv_path,
+};
+
+fn versions(runtime: Option<&semver::Version>) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "venice-cli {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        text,
+        "runtime {}",
+        runtime.map_or("not installed".to_string(), ToString::to_string)
+    );
+    let _ = writeln!(
+        text,
+        "mpy-cross {}",
+        find()
+            .and_then(|path| tool_version(&path.to_string_lossy(), "--version"))
+            .unwrap_or_else(|| "not found".to_string())
+    );
+    let _ = writeln!(
+        text,
+        "uv {}",
+        uv_path()
+            .ok()
+            .and_then(|uv| tool_version(uv, "--version"))
+            .unwrap_or_else(|| "not found".to_string())
+    );
+    let _ = writeln!(
+        text,
+        "os {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    text
+}
+
+async fn doctor_output() -> String {
+    let mut text = String::new();
+    for check in run_checks().await {
+        let _ = writeln!(
+            text,
+            "{} {}",
+            if check.ok { "ok:" } else { "problem:" },
+            check.message
+        );
+        if let Some(hint) = &check.hint {
+            let _ = writeln!(text, "  {hint}");
+        }
+    }
+    text
+}
+
+fn last_failure() -> String {
+    match Store::load().ok().and_then(|store| store.last_failure) {
+        Some(failure) => format!(
+            "at {} (seconds since the Unix epoch)\n$ {}\n\n{}\n",
+            failure.time,
+            failure.args.join(" "),
+            failure.error
+        ),
+        None => "No failed command recorded\n".to_string(),
+    }
+}
+
+/// Remembers a failed command for the next `venice report`.
+pub fn record_failure(args: &[String], error: &miette::Report) {
+    let mut text = String::new();
+    let _ = miette::NarratableReportHandler::new().render_report(&mut text, error.as_ref());
+    let failure = Failure {
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        args: args.to_vec(),
+        error: text,
+    };
+    let _ = Store::update(|store| store.last_failure = Some(failure));
+}
+
+/// `venice report`: bundles what a bug report needs into a tarball, with host paths redacted.
+pub async fn report(
+    output: Option<PathBuf>,
+    runtime: Option<&semver::Version>,
+) -> Result<(), CliError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut files = vec![
+        ("versions.txt", versions(runtime)),
+        ("doctor.txt", doctor_output().await),
+        ("last-failure.txt", last_failure()),
+    ];
+    if let Ok(dir) = project_dir()
+        && let Ok(manifest) = tokio::fs::read_to_string(dir.join(MANIFEST_NAME)).await
+    {
+        files.push((MANIFEST_NAME, manifest));
+    }
+
+    let paths = host_paths();
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (name, contents) in files {
+        let contents = redact(&contents, &paths);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(now);
+        builder.append_data(
+            &mut header,
+            format!("venice-report/{name}"),
+            contents.as_bytes(),
+        )?;
+    }
+    let tarball = builder.into_inner()?.finish()?;
+
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("venice-report-{now}.tar.gz")));
+    tokio::fs::write(&output, tarball).await?;
+    println!(
+        "✓ Wrote {}. Look through it before attaching it to an issue.",
+        output.display()
+    );
+    Ok(())
+}
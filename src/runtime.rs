@@ -1,11 +1,22 @@
-use std::{fmt::Display, path::Path, path::PathBuf, str::FromStr};
+use std::{fmt::Display, path::Path, path::PathBuf, str::FromStr, time::Duration};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::errors::CliError;
+use crate::{
+    config,
+    config::cache_dir,
+    errors::CliError,
+    frozen,
+    github::{GithubClient, RUNTIME_REPO},
+    releases::{ReleaseIndex, ReleaseQuery},
+};
 
 pub const VPT_LOAD_ADDR: u32 = 0x07c00000;
 
+/// Release asset describing a runtime's built-in modules and their API surface.
+pub const DESCRIPTION_ASSET: &str = "runtime-description.json";
+
 /// Runtime source configuration provided by the venice package
 #[derive(Clone)]
 pub struct RuntimeSource {
@@ -108,9 +119,281 @@ pub async fn bin_exists(bin: &RtBin, dir: &Path) -> Result<bool, std::io::Error>
     tokio::fs::try_exists(dir.join(format!("{bin}"))).await
 }
 
+/// Machine-readable description of a runtime release, published as [`DESCRIPTION_ASSET`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RuntimeDescription {
+    pub version: semver::Version,
+    pub modules: Vec<BuiltinModule>,
+    /// Absent from descriptions published before runtimes listed their capabilities
+    #[serde(default)]
+    pub capabilities: Option<Capabilities>,
+}
+
+/// Optional features a runtime supports, so the CLI can hold back the ones it can't honor instead
+/// of sending it something it doesn't understand.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Version of the telemetry protocol it speaks, if any
+    #[serde(default)]
+    pub telemetry: Option<u32>,
+    /// Whether it inflates gzipped program payloads
+    #[serde(default)]
+    pub compression: bool,
+    /// Whether it runs a REPL on the serial port when no program is running
+    #[serde(default)]
+    pub repl: bool,
+    /// Whether it reads the autonomous routine selected with `auton select` or `run --auton`
+    #[serde(default)]
+    pub autons: bool,
+    /// Whether it loads calibration.toml from the brain
+    #[serde(default)]
+    pub calibration: bool,
+    /// Whether it answers the terminal's clock sync requests
+    #[serde(default)]
+    pub time_sync: bool,
+    /// Whether it loads modules uploaded by `venice patch` over the program's own
+    #[serde(default)]
+    pub patches: bool,
+    /// Whether it reads and sets the brain's date and time for `venice clock`
+    #[serde(default)]
+    pub clock: bool,
+}
+
+impl Capabilities {
+    /// What runtimes from before capability flags support: everything the CLI used at the time,
+    /// and nothing newer.
+    pub const LEGACY: Self = Self {
+        telemetry: None,
+        compression: false,
+        repl: false,
+        autons: true,
+        calibration: true,
+        time_sync: false,
+        patches: false,
+        clock: false,
+    };
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::Telemetry(version) => self.telemetry.is_some_and(|v| v >= version),
+            Feature::Compression => self.compression,
+            Feature::Repl => self.repl,
+            Feature::Autons => self.autons,
+            Feature::Calibration => self.calibration,
+            Feature::TimeSync => self.time_sync,
+            Feature::Patches => self.patches,
+            Feature::Clock => self.clock,
+        }
+    }
+}
+
+/// A CLI feature that needs the runtime's cooperation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// At least this telemetry protocol version
+    Telemetry(u32),
+    Compression,
+    Repl,
+    Autons,
+    Calibration,
+    TimeSync,
+    Patches,
+    Clock,
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Telemetry(version) => write!(f, "telemetry protocol v{version}"),
+            Self::Compression => write!(f, "compressed uploads"),
+            Self::Repl => write!(f, "the REPL"),
+            Self::Autons => write!(f, "autonomous routine selection"),
+            Self::Calibration => write!(f, "calibration"),
+            Self::TimeSync => write!(f, "clock sync"),
+            Self::Patches => write!(f, "module patches"),
+            Self::Clock => write!(f, "the brain clock"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BuiltinModule {
+    pub name: String,
+    #[serde(default)]
+    pub doc: Option<String>,
+    /// Names the module exports
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+fn description_cache_path(version: &semver::Version) -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("runtime").join(format!("v{version}.json")))
+}
+
+/// Fetches the description of a runtime release. Releases never change, so descriptions are
+/// cached forever once downloaded.
+pub async fn describe(version: &semver::Version) -> Result<RuntimeDescription, CliError> {
+    let cache_path = description_cache_path(version);
+    if let Some(path) = &cache_path
+        && let Ok(contents) = tokio::fs::read_to_string(path).await
+        && let Ok(description) = serde_json::from_str(&contents)
+    {
+        return Ok(description);
+    }
+
+    if frozen() {
+        return Err(CliError::FrozenNetwork);
+    }
+
+    let client = GithubClient::new(config());
+    let release = client
+        .release_by_tag(RUNTIME_REPO, &format!("v{version}"))
+        .await?;
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == DESCRIPTION_ASSET)
+        .ok_or_else(|| CliError::NoRuntimeDescription(version.clone()))?;
+    let contents = client.download_asset(asset).await?;
+    let description: RuntimeDescription = serde_json::from_slice(&contents)?;
+
+    if let Some(path) = &cache_path {
+        let _ = tokio::fs::create_dir_all(path.parent().unwrap()).await;
+        let _ = tokio::fs::write(path, &contents).await;
+    }
+    Ok(description)
+}
+
+/// The capabilities of runtime `version`, from its description. `None` if the description can't
+/// be had, e.g. offline before it was ever cached; nothing is held back then.
+pub async fn capabilities(version: &semver::Version) -> Option<Capabilities> {
+    describe(version)
+        .await
+        .ok()
+        .map(|description| description.capabilities.unwrap_or(Capabilities::LEGACY))
+}
+
+/// Whether runtime `version` can honor `feature`, warning that it's skipped if not.
+pub async fn check_feature(version: &semver::Version, feature: Feature) -> bool {
+    let supported = capabilities(version)
+        .await
+        .is_none_or(|capabilities| capabilities.supports(feature));
+    if !supported {
+        eprintln!(
+            "warning: venice runtime {version} doesn't support {feature}, so it's skipped - update the venice package in pyproject.toml to use it"
+        );
+    }
+    supported
+}
+
+/// A runtime release other than the one the venice package provides, for trying the program
+/// against it. Downloaded from its GitHub release once, then cached.
+pub async fn fetch_source(version: &semver::Version) -> Result<RuntimeSource, CliError> {
+    let bin = RtBin::from_version(version.clone());
+    let path = cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("runtime")
+        .join(bin.to_string());
+    if tokio::fs::try_exists(&path).await? {
+        return Ok(RuntimeSource::new(path, version.clone()));
+    }
+
+    if frozen() {
+        return Err(CliError::FrozenNetwork);
+    }
+
+    let client = GithubClient::new(config());
+    let release = client
+        .release_by_tag(RUNTIME_REPO, &format!("v{version}"))
+        .await?;
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == bin.to_string())
+        .ok_or_else(|| CliError::NoRuntimeBinary(version.clone()))?;
+    let contents = client.download_asset(asset).await?;
+    tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+    tokio::fs::write(&path, &contents).await?;
+    Ok(RuntimeSource::new(path, version.clone()))
+}
+
+/// How long the runtime release list is used before GitHub is asked for changes.
+const RELEASE_INDEX_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Published runtime versions matching `query`, newest first.
+pub async fn available_versions(query: &ReleaseQuery) -> Result<Vec<semver::Version>, CliError> {
+    if frozen() {
+        return Err(CliError::FrozenNetwork);
+    }
+
+    let client = GithubClient::new(config());
+    let index = ReleaseIndex::fetch(&client, RUNTIME_REPO, RELEASE_INDEX_MAX_AGE).await?;
+    Ok(index
+        .query(query)
+        .map(|(version, _)| version.clone())
+        .collect())
+}
+
+/// Runtime versions downloaded by [`fetch_source`], newest first.
+pub async fn downloaded_versions() -> Result<Vec<semver::Version>, CliError> {
+    let mut versions = Vec::new();
+    let Some(dir) = cache_dir().map(|dir| dir.join("runtime")) else {
+        return Ok(versions);
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return Ok(versions);
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        if let Some(bin) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<RtBin>().ok())
+        {
+            versions.push(bin.version);
+        }
+    }
+    versions.sort_by(|a, b| b.cmp(a));
+    Ok(versions)
+}
+
+/// `venice runtime describe`
+pub async fn print_description(version: &semver::Version) -> Result<(), CliError> {
+    let description = describe(version).await?;
+    println!("venice runtime {}\n", description.version);
+    let capabilities = description.capabilities.unwrap_or(Capabilities::LEGACY);
+    let supported: Vec<_> = [
+        capabilities.telemetry.map(Feature::Telemetry),
+        Some(Feature::Compression),
+        Some(Feature::Repl),
+        Some(Feature::Autons),
+        Some(Feature::Calibration),
+        Some(Feature::TimeSync),
+        Some(Feature::Patches),
+        Some(Feature::Clock),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|feature| capabilities.supports(*feature))
+    .map(|feature| feature.to_string())
+    .collect();
+    println!("Supports: {}\n", supported.join(", "));
+    for module in description.modules.iter() {
+        match &module.doc {
+            Some(doc) => println!("{} - {}", module.name, doc.lines().next().unwrap_or("")),
+            None => println!("{}", module.name),
+        }
+        if !module.members.is_empty() {
+            println!("    {}", module.members.join(", "));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{RtBin, RtBinParseError};
+    use proptest::prelude::*;
+
+    use super::{Capabilities, Feature, RtBin, RtBinParseError, RuntimeDescription};
 
     #[test]
     fn bin_parse() {
@@ -142,4 +425,38 @@ mod tests {
         };
         assert_eq!(format!("{bin}"), "venice-v1.2.3.bin");
     }
+
+    #[test]
+    fn capabilities() {
+        let old: RuntimeDescription =
+            serde_json::from_str(r#"{"version": "0.1.0", "modules": []}"#).unwrap();
+        assert_eq!(old.capabilities, None);
+        assert!(Capabilities::LEGACY.supports(Feature::Autons));
+        assert!(!Capabilities::LEGACY.supports(Feature::Telemetry(1)));
+
+        let new: RuntimeDescription = serde_json::from_str(
+            r#"{"version": "0.2.0", "modules": [], "capabilities": {"telemetry": 2, "repl": true}}"#,
+        )
+        .unwrap();
+        let capabilities = new.capabilities.unwrap();
+        assert!(capabilities.supports(Feature::Telemetry(1)));
+        assert!(!capabilities.supports(Feature::Telemetry(3)));
+        assert!(capabilities.supports(Feature::Repl));
+        assert!(!capabilities.supports(Feature::Calibration));
+    }
+
+    proptest! {
+        #[test]
+        fn bin_roundtrips(major: u64, minor: u64, patch: u64, pre in "(alpha|rc)\\.[0-9]{1,3}") {
+            let mut version = semver::Version::new(major, minor, patch);
+            version.pre = semver::Prerelease::new(&pre).unwrap();
+            let bin = RtBin::from_version(version);
+            prop_assert_eq!(bin.to_string().parse::<RtBin>().unwrap(), bin);
+        }
+
+        #[test]
+        fn bin_parse_never_panics(name in "(venice-v)?\\PC*(\\.bin)?") {
+            let _ = name.parse::<RtBin>();
+        }
+    }
 }
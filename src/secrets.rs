@@ -0,0 +1,167 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+use inquire::Password;
+
+use crate::{
+    config::{config_dir, write_private_file},
+    errors::CliError,
+    project_dir,
+};
+
+/// Per-project file of encrypted secrets. Encrypted with a per-user key, so it's only readable by
+/// whoever set the secrets; keep it out of version control.
+pub const SECRETS_FILE: &str = ".venice-secrets";
+pub const SECRETS_KEY_FILE: &str = "secrets-key";
+/// Service name secrets are stored under in the OS keychain.
+const KEYCHAIN_SERVICE: &str = "venice";
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+type SecretsFile = BTreeMap<String, String>;
+
+fn cipher() -> Result<ChaCha20Poly1305, CliError> {
+    cipher_at(
+        &config_dir()
+            .ok_or(CliError::NoConfigDir)?
+            .join(SECRETS_KEY_FILE),
+    )
+}
+
+/// The cipher for the key at `path`, which is generated on first use.
+fn cipher_at(path: &Path) -> Result<ChaCha20Poly1305, CliError> {
+    let key = match std::fs::read(path) {
+        Ok(key) if key.len() == KEY_LENGTH => key,
+        Ok(_) => return Err(CliError::InvalidSecretsKey(path.to_path_buf())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng).to_vec();
+            write_private_file(path, &key)?;
+            key
+        }
+        Err(e) => return Err(CliError::Io(e)),
+    };
+
+    Ok(ChaCha20Poly1305::new_from_slice(&key).unwrap())
+}
+
+/// `value` encrypted under a fresh nonce, as it's kept in the secrets file: the nonce and the
+/// ciphertext, hex-encoded.
+fn encrypt(cipher: &ChaCha20Poly1305, value: &str) -> String {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend(cipher.encrypt(&nonce, value.as_bytes()).unwrap());
+    hex::encode(encrypted)
+}
+
+/// The value of the secret `name`, from what [`encrypt`] made of it.
+fn decrypt(cipher: &ChaCha20Poly1305, name: &str, encoded: &str) -> Result<String, CliError> {
+    let undecryptable = || CliError::UndecryptableSecret(name.to_string());
+    let bytes = hex::decode(encoded).map_err(|_| undecryptable())?;
+    if bytes.len() < NONCE_LENGTH {
+        return Err(undecryptable());
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| undecryptable())?;
+    String::from_utf8(plaintext).map_err(|_| undecryptable())
+}
+
+fn secrets_path() -> Result<PathBuf, CliError> {
+    Ok(project_dir()?.join(SECRETS_FILE))
+}
+
+fn read_secrets_file() -> Result<SecretsFile, CliError> {
+    let path = secrets_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => {
+            toml::from_str(&contents).map_err(|source| CliError::Config { path, source })
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SecretsFile::new()),
+        Err(e) => Err(CliError::Io(e)),
+    }
+}
+
+fn keychain_entry(name: &str) -> Result<keyring::Entry, CliError> {
+    let project = project_dir()?.to_string_lossy().into_owned();
+    // Scope entries to the project so two projects can use the same secret names
+    Ok(keyring::Entry::new(
+        KEYCHAIN_SERVICE,
+        &format!("{project}:{name}"),
+    )?)
+}
+
+/// Looks a secret up in the project's secrets file, then in the OS keychain.
+pub fn get_secret(name: &str) -> Result<String, CliError> {
+    if let Some(encoded) = read_secrets_file()?.get(name) {
+        return decrypt(&cipher()?, name, encoded);
+    }
+
+    match keychain_entry(name)?.get_password() {
+        Ok(value) => Ok(value),
+        Err(keyring::Error::NoEntry) => Err(CliError::MissingSecret(name.to_string())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn set_secret(name: &str, value: &str, keychain: bool) -> Result<(), CliError> {
+    if keychain {
+        keychain_entry(name)?.set_password(value)?;
+        return Ok(());
+    }
+
+    let encrypted = encrypt(&cipher()?, value);
+    let mut secrets = read_secrets_file()?;
+    secrets.insert(name.to_string(), encrypted);
+    let contents = toml::to_string(&secrets).map_err(|e| CliError::ConfigEdit(e.to_string()))?;
+    std::fs::write(secrets_path()?, contents)?;
+    Ok(())
+}
+
+/// `venice secret set`: prompts for the value so it never ends up in shell history.
+pub fn prompt_and_set_secret(name: &str, keychain: bool) -> Result<(), CliError> {
+    let value = Password::new(&format!("Value for `{name}`:"))
+        .without_confirmation()
+        .prompt()
+        .map_err(|e| CliError::Io(std::io::Error::other(e)))?;
+
+    set_secret(name, &value, keychain)?;
+    println!("✓ Saved secret `{name}`");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cipher_at, decrypt, encrypt};
+    use crate::errors::CliError;
+
+    #[test]
+    fn round_trips_secrets() {
+        let dir = std::env::temp_dir().join(format!("venice-secrets-{}", std::process::id()));
+        let key_path = dir.join("secrets-key");
+
+        let encrypted = encrypt(&cipher_at(&key_path).unwrap(), "hunter2");
+        // The key generated for the first secret decrypts it on the next run
+        let cipher = cipher_at(&key_path).unwrap();
+        assert_eq!(decrypt(&cipher, "password", &encrypted).unwrap(), "hunter2");
+        assert_ne!(encrypt(&cipher, "hunter2"), encrypted);
+
+        let mut tampered = encrypted.into_bytes();
+        let last = tampered.last_mut().unwrap();
+        *last = if *last == b'0' { b'1' } else { b'0' };
+        assert!(matches!(
+            decrypt(&cipher, "password", &String::from_utf8(tampered).unwrap()),
+            Err(CliError::UndecryptableSecret(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use ed25519_dalek::{
+    PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH, Signature, Signer, SigningKey,
+    VerifyingKey,
+};
+use venice_program_table::ProgramFlags;
+
+use crate::{
+    config,
+    config::{config_dir, write_private_file},
+    errors::CliError,
+    vpt::Program,
+};
+
+/// Name of the trailer program carrying the public key and signature. It isn't a valid module
+/// name, so it can never collide with one.
+pub const SIGNATURE_PROGRAM: &[u8] = b"<venice-signature>";
+pub const KEY_FILE: &str = "signing-key";
+
+fn key_path() -> Result<PathBuf, CliError> {
+    Ok(config_dir().ok_or(CliError::NoConfigDir)?.join(KEY_FILE))
+}
+
+/// Loads the signing key from the config dir, generating one on first use.
+pub fn load_or_create_key() -> Result<SigningKey, CliError> {
+    let path = key_path()?;
+    match std::fs::read(&path) {
+        Ok(bytes) => {
+            let secret: [u8; SECRET_KEY_LENGTH] = bytes
+                .try_into()
+                .map_err(|_| CliError::InvalidSigningKey(path))?;
+            Ok(SigningKey::from_bytes(&secret))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut secret = [0; SECRET_KEY_LENGTH];
+            getrandom::fill(&mut secret).map_err(|e| CliError::Io(std::io::Error::other(e)))?;
+            let key = SigningKey::from_bytes(&secret);
+
+            write_private_file(&path, &secret)?;
+            println!(
+                "Generated a new signing key at `{}` (public key {})",
+                path.display(),
+                hex::encode(key.verifying_key().to_bytes())
+            );
+            Ok(key)
+        }
+        Err(e) => Err(CliError::Io(e)),
+    }
+}
+
+/// The bytes covered by the signature: every program's name, flags and payload, length-prefixed
+/// so that no two tables encode to the same message.
+fn signed_message<'a>(
+    programs: impl IntoIterator<Item = (&'a [u8], ProgramFlags, &'a [u8])>,
+) -> Vec<u8> {
+    let mut message = Vec::new();
+    for (name, flags, payload) in programs {
+        message.extend_from_slice(&(name.len() as u64).to_le_bytes());
+        message.extend_from_slice(name);
+        message.extend_from_slice(&(flags.bits() as u64).to_le_bytes());
+        message.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        message.extend_from_slice(payload);
+    }
+    message
+}
+
+/// Payload of the [`SIGNATURE_PROGRAM`] trailer: the public key followed by the signature.
+pub fn signature_payload<'a>(
+    key: &SigningKey,
+    programs: impl IntoIterator<Item = (&'a [u8], ProgramFlags, &'a [u8])>,
+) -> Vec<u8> {
+    let signature = key.sign(&signed_message(programs));
+
+    let mut payload = key.verifying_key().to_bytes().to_vec();
+    payload.extend_from_slice(&signature.to_bytes());
+    payload
+}
+
+/// Checks the signature trailer of a decoded table. Returns the signer's public key, or `None` if
+/// the table isn't signed.
+pub fn verify(programs: &[Program]) -> Result<Option<VerifyingKey>, CliError> {
+    let Some((trailer, signed)) = programs.split_last() else {
+        return Ok(None);
+    };
+    if trailer.name != SIGNATURE_PROGRAM {
+        return Ok(None);
+    }
+
+    if trailer.payload.len() != PUBLIC_KEY_LENGTH + SIGNATURE_LENGTH {
+        return Err(CliError::BadSignature);
+    }
+    let (public_key, signature) = trailer.payload.split_at(PUBLIC_KEY_LENGTH);
+    let public_key = VerifyingKey::from_bytes(public_key.try_into().unwrap())
+        .map_err(|_| CliError::BadSignature)?;
+    let signature = Signature::from_bytes(signature.try_into().unwrap());
+
+    let message = signed_message(
+        signed
+            .iter()
+            .map(|p| (p.name.as_slice(), p.flags, p.payload.as_slice())),
+    );
+    public_key
+        .verify_strict(&message, &signature)
+        .map_err(|_| CliError::BadSignature)?;
+
+    Ok(Some(public_key))
+}
+
+/// Verifies a table and, if the config lists trusted keys, checks that one of them signed it.
+pub fn check_trusted(programs: &[Program]) -> Result<(), CliError> {
+    let trusted_keys = &config().signing.trusted_keys;
+    if trusted_keys.is_empty() {
+        // Still reject tables with a broken signature
+        verify(programs)?;
+        return Ok(());
+    }
+
+    let signer = verify(programs)?.ok_or(CliError::Unsigned)?;
+    let signer = hex::encode(signer.to_bytes());
+    if !trusted_keys
+        .iter()
+        .any(|key| key.eq_ignore_ascii_case(&signer))
+    {
+        return Err(CliError::UntrustedSigner(signer));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{SECRET_KEY_LENGTH, SigningKey};
+    use venice_program_table::ProgramFlags;
+
+    use super::{SIGNATURE_PROGRAM, signature_payload, verify};
+    use crate::{errors::CliError, vpt::Program};
+
+    #[test]
+    fn detects_tampering() {
+        let key = SigningKey::from_bytes(&[7; SECRET_KEY_LENGTH]);
+        let mut programs = vec![Program {
+            name: b"main".to_vec(),
+            flags: ProgramFlags::empty(),
+            payload: b"M\x06\x00\x1f".to_vec(),
+        }];
+        assert!(verify(&programs).unwrap().is_none());
+
+        let payload = signature_payload(
+            &key,
+            programs
+                .iter()
+                .map(|p| (p.name.as_slice(), p.flags, p.payload.as_slice())),
+        );
+        programs.push(Program {
+            name: SIGNATURE_PROGRAM.to_vec(),
+            flags: ProgramFlags::empty(),
+            payload,
+        });
+        assert_eq!(verify(&programs).unwrap(), Some(key.verifying_key()));
+
+        programs[0].payload[3] ^= 1;
+        assert!(matches!(verify(&programs), Err(CliError::BadSignature)));
+    }
+}
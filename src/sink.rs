@@ -0,0 +1,379 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Write},
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    path::Path,
+    sync::mpsc::{RecvTimeoutError, SyncSender, sync_channel},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rumqttc::{Client, MqttOptions, Packet, QoS};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use tungstenite::{Message, WebSocket};
+
+use crate::{
+    config,
+    config::SinkConfig,
+    device::{DeviceIdentity, identity},
+    errors::CliError,
+};
+
+/// How long a sink may take to connect or to take a line before it's given up on.
+const SINK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Lines waiting for the sinks. Past this, lines are dropped until they catch up.
+const QUEUED_LINES: usize = 1024;
+
+/// How often the sinks hear from their servers while the program is quiet, so a WebSocket
+/// server's pings are answered.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long MQTT brokers may go without hearing from the sink before dropping it. The client
+/// pings them in between.
+const MQTT_KEEPALIVE: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+struct Event {
+    time: f64,
+    device: Option<&'static DeviceIdentity>,
+    text: String,
+}
+
+enum Sink {
+    File(File),
+    Udp(UdpSocket),
+    Mqtt { client: Client, topic: String },
+    Sqlite(Connection),
+    Websocket(WebSocket<TcpStream>),
+}
+
+impl Sink {
+    fn open(config: &SinkConfig) -> Result<Self, CliError> {
+        Ok(match config {
+            SinkConfig::File { path } => {
+                Self::File(File::options().create(true).append(true).open(path)?)
+            }
+            SinkConfig::Udp { address } => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket.connect(address)?;
+                Self::Udp(socket)
+            }
+            SinkConfig::Mqtt { broker, topic } => Self::Mqtt {
+                client: mqtt_connect(broker)?,
+                topic: topic.clone(),
+            },
+            SinkConfig::Sqlite { path } => {
+                Self::Sqlite(sqlite_open(path).map_err(std::io::Error::other)?)
+            }
+            SinkConfig::Websocket { url } => Self::Websocket(websocket_connect(url)?),
+        })
+    }
+
+    /// Sends `event`, which is `json` serialized.
+    fn send(&mut self, event: &Event, json: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::File(file) => {
+                file.write_all(json)?;
+                file.write_all(b"\n")
+            }
+            Self::Udp(socket) => socket.send(json).map(|_| ()),
+            // QoS 0: fire and forget, which is all telemetry needs
+            Self::Mqtt { client, topic } => client
+                .try_publish(topic.as_str(), QoS::AtMostOnce, false, json.to_vec())
+                .map_err(std::io::Error::other),
+            Self::Sqlite(db) => sqlite_insert(db, event).map_err(std::io::Error::other),
+            Self::Websocket(socket) => socket
+                .send(Message::text(String::from_utf8_lossy(json).into_owned()))
+                .map_err(std::io::Error::other),
+        }
+    }
+
+    /// Reads what the server sent, answering pings and noticing when it closed the connection.
+    fn poll(&mut self) -> std::io::Result<()> {
+        let Self::Websocket(socket) = self else {
+            return Ok(());
+        };
+        loop {
+            match socket.read() {
+                // Pongs and close replies are queued by the read and sent with the next one
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    return Ok(());
+                }
+                Err(e) => return Err(std::io::Error::other(e)),
+            }
+        }
+    }
+}
+
+/// Sends program output to the [[sinks]] in the venice config, a line at a time. The sinks are
+/// connected to and written from a thread of their own, so a slow or unreachable one can't hold
+/// up the terminal. A sink that fails is warned about and dropped rather than ending the
+/// terminal session.
+pub struct Sinks {
+    sender: Option<SyncSender<Event>>,
+    partial: Vec<u8>,
+}
+
+impl Sinks {
+    pub fn from_config() -> Self {
+        let configs = &config().sinks;
+        let sender = (!configs.is_empty()).then(|| {
+            let (sender, receiver) = sync_channel::<Event>(QUEUED_LINES);
+            std::thread::spawn(move || {
+                let mut sinks = open_sinks(configs);
+                loop {
+                    match receiver.recv_timeout(POLL_INTERVAL) {
+                        Ok(event) => {
+                            let json = serde_json::to_vec(&event).unwrap();
+                            sinks.retain_mut(|(name, sink)| keep(name, sink.send(&event, &json)));
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                    sinks.retain_mut(|(name, sink)| keep(name, sink.poll()));
+                }
+            });
+            sender
+        });
+        Self {
+            sender,
+            partial: Vec::new(),
+        }
+    }
+
+    pub fn output(&mut self, bytes: &[u8]) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        self.partial.extend_from_slice(bytes);
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            // Dropped if the sinks are behind, or all of them have failed
+            let _ = sender.try_send(Event {
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0.0, |d| d.as_secs_f64()),
+                device: identity(),
+                text: String::from_utf8_lossy(&line).trim_end().to_string(),
+            });
+        }
+    }
+}
+
+/// Whether a sink is still usable after `result`, warning about it if not.
+fn keep(name: &str, result: std::io::Result<()>) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("warning: stopped sending output to the {name} sink: {e}");
+            false
+        }
+    }
+}
+
+fn open_sinks(configs: &[SinkConfig]) -> Vec<(String, Sink)> {
+    configs
+        .iter()
+        .filter_map(|sink_config| {
+            let name = describe(sink_config);
+            match Sink::open(sink_config) {
+                Ok(sink) => Some((name, sink)),
+                Err(e) => {
+                    eprintln!("warning: couldn't open the {name} sink: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn describe(config: &SinkConfig) -> String {
+    match config {
+        SinkConfig::File { path } => format!("file {}", path.display()),
+        SinkConfig::Udp { address } => format!("UDP {address}"),
+        SinkConfig::Mqtt { broker, topic } => format!("MQTT {broker} ({topic})"),
+        SinkConfig::Sqlite { path } => format!("SQLite {}", path.display()),
+        SinkConfig::Websocket { url } => format!("WebSocket {url}"),
+    }
+}
+
+/// Opens the database at `path`, creating it and its `events` table if needed.
+fn sqlite_open(path: &Path) -> rusqlite::Result<Connection> {
+    let db = Connection::open(path)?;
+    // Another process may be reading the table while the terminal writes to it
+    db.busy_timeout(SINK_TIMEOUT)?;
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS events (
+            time REAL NOT NULL,
+            device_name TEXT,
+            device_serial TEXT,
+            text TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(db)
+}
+
+fn sqlite_insert(db: &Connection, event: &Event) -> rusqlite::Result<()> {
+    db.execute(
+        "INSERT INTO events (time, device_name, device_serial, text) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            event.time,
+            event.device.and_then(|device| device.name.as_deref()),
+            event.device.and_then(|device| device.serial.as_deref()),
+            event.text,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Opens a WebSocket to `url`, like `ws://192.168.1.20:8080/telemetry`. Only plain `ws://` is
+/// supported; put a proxy in front of a server that needs TLS.
+fn websocket_connect(url: &str) -> Result<WebSocket<TcpStream>, CliError> {
+    let rest = url
+        .strip_prefix("ws://")
+        .ok_or_else(|| std::io::Error::other(format!("`{url}` isn't a ws:// URL")))?;
+    let host = rest.split_once('/').map_or(rest, |(host, _)| host);
+    let has_port = host
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    let stream = if has_port {
+        connect(host)?
+    } else {
+        connect(&format!("{host}:80"))?
+    };
+
+    let (socket, _) = tungstenite::client::client(url, stream).map_err(|e| {
+        std::io::Error::other(format!("the WebSocket handshake with `{url}` failed: {e}"))
+    })?;
+    // Reads only check for pings and closes between lines, so they mustn't wait
+    socket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(1)))?;
+    Ok(socket)
+}
+
+/// Connects to `address`, like `localhost:1883`, trying each address it resolves to for up to
+/// [`SINK_TIMEOUT`].
+fn connect(address: &str) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, SINK_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(SINK_TIMEOUT))?;
+                stream.set_write_timeout(Some(SINK_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::other(format!("`{address}` doesn't resolve to any address"))
+    }))
+}
+
+/// Connects to an MQTT broker at `broker`, like `localhost:1883`, with a clean session. The
+/// client runs on a thread of its own, which keeps the connection alive, until the sink is
+/// dropped or the broker goes away.
+fn mqtt_connect(broker: &str) -> Result<Client, CliError> {
+    let (host, port) = broker
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+        .unwrap_or((broker, 1883));
+    let mut options = MqttOptions::new(format!("venice-{}", std::process::id()), host, port);
+    options.set_keep_alive(MQTT_KEEPALIVE);
+    let (client, mut connection) = Client::new(options, QUEUED_LINES);
+
+    // Waits for the broker to accept, so one that's down is reported when the sink opens
+    loop {
+        match connection.recv_timeout(SINK_TIMEOUT) {
+            Ok(Ok(rumqttc::Event::Incoming(Packet::ConnAck(_)))) => break,
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(std::io::Error::other(e).into()),
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!("the MQTT broker at `{broker}` didn't answer"),
+                )
+                .into());
+            }
+        }
+    }
+    std::thread::spawn(move || {
+        // Ends once the client is dropped or the connection fails, which makes publishing fail
+        for notification in connection.iter() {
+            if notification.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, sync::mpsc, time::Duration};
+
+    use tungstenite::Message;
+
+    use super::{Event, Sink, sqlite_insert, sqlite_open, websocket_connect};
+
+    #[test]
+    fn answers_websocket_pings() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}/telemetry", listener.local_addr().unwrap());
+        let (pinged, wait_for_ping) = mpsc::channel();
+        let server = std::thread::spawn(move || {
+            let mut socket = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+            socket
+                .send(Message::Ping(b"are you there".to_vec().into()))
+                .unwrap();
+            pinged.send(()).unwrap();
+            let pong = socket.read().unwrap();
+            let text = socket.read().unwrap();
+            (pong, text)
+        });
+
+        let mut sink = Sink::Websocket(websocket_connect(&url).unwrap());
+        wait_for_ping.recv().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        sink.poll().unwrap();
+        let event = Event {
+            time: 1.5,
+            device: None,
+            text: String::from("hello"),
+        };
+        sink.send(&event, br#"{"text":"hello"}"#).unwrap();
+
+        let (pong, text) = server.join().unwrap();
+        assert!(matches!(pong, Message::Pong(_)));
+        assert_eq!(text.to_text().unwrap(), r#"{"text":"hello"}"#);
+    }
+
+    #[test]
+    fn writes_sqlite_events() {
+        let path = std::env::temp_dir().join(format!("venice-sink-{}.db", std::process::id()));
+        let db = sqlite_open(&path).unwrap();
+        let event = Event {
+            time: 1.5,
+            device: None,
+            text: String::from("hello"),
+        };
+        sqlite_insert(&db, &event).unwrap();
+        sqlite_insert(&db, &event).unwrap();
+
+        let (count, text): (i64, String) = db
+            .query_row("SELECT COUNT(*), MAX(text) FROM events", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((count, text.as_str()), (2, "hello"));
+
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
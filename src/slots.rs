@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use vex_v5_serial::protocol::cdc2::file::{FileExitAction, FileVendor};
+
+use crate::{
+    TABLE_FILE, artifact,
+    brain::{list_files, vex_crc32},
+    build::BuildProfile,
+    device::open_connection,
+    errors::CliError,
+    manifest::get_project_at,
+    runtime::RuntimeSource,
+    upload::{UploadSource, upload_from},
+};
+
+/// Presets of what goes in each of the brain's slots, shared by a team by checking it in:
+///
+/// ```toml
+/// [presets.competition]
+/// 1 = "robot"                      # a project's release build, from
+///                                  # `venice build --profile release --artifact-metadata`
+/// 2 = "skills/build/debug/out.vpt" # or a program table
+/// ```
+///
+/// Paths are relative to the file.
+pub const SLOTS_FILE: &str = "slots.toml";
+
+#[derive(Deserialize, Default, Debug)]
+struct SlotsFile {
+    #[serde(default)]
+    presets: BTreeMap<String, BTreeMap<String, PathBuf>>,
+}
+
+fn load(path: &Path) -> Result<SlotsFile, CliError> {
+    let contents = std::fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|source| CliError::Config {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The program table for each slot in preset `name`, by slot.
+async fn preset(path: &Path, name: &str) -> Result<Vec<(u8, PathBuf)>, CliError> {
+    let mut file = load(path)?;
+    let Some(preset) = file.presets.remove(name) else {
+        return Err(CliError::UnknownPreset {
+            name: name.to_string(),
+            available: file.presets.into_keys().collect::<Vec<_>>().join(", "),
+        });
+    };
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut slots = Vec::new();
+    for (slot, entry) in preset {
+        let slot = slot
+            .parse()
+            .ok()
+            .filter(|slot| (1..=8).contains(slot))
+            .ok_or_else(|| CliError::PresetSlot {
+                preset: name.to_string(),
+                slot,
+            })?;
+        let entry = base.join(entry);
+        let vpt = if entry.is_dir() {
+            get_project_at(&entry)
+                .await?
+                .out_dir(&entry, BuildProfile::Release)
+                .join(TABLE_FILE)
+        } else {
+            entry
+        };
+        slots.push((slot, vpt));
+    }
+    slots.sort();
+    Ok(slots)
+}
+
+/// `venice slots list`
+pub async fn list(path: &Path) -> Result<(), CliError> {
+    let file = load(path)?;
+    if file.presets.is_empty() {
+        println!("No presets in {}", path.display());
+    }
+    for name in file.presets.keys() {
+        println!("{name}");
+        for (slot, vpt) in preset(path, name).await? {
+            println!("  {slot}: {}", vpt.display());
+        }
+    }
+    Ok(())
+}
+
+/// `venice slots apply`: uploads each program in the preset to its slot, skipping those the
+/// brain already has unless `force` is set. Every program table is checked against its metadata
+/// before anything is uploaded, so a bad entry doesn't leave the brain half changed.
+pub async fn apply(
+    path: &Path,
+    name: &str,
+    force: bool,
+    runtime_source: Option<RuntimeSource>,
+) -> Result<(), CliError> {
+    let mut programs = Vec::new();
+    for (slot, vpt_path) in preset(path, name).await? {
+        let (metadata, vpt) = artifact::load(&vpt_path).await?;
+        programs.push((slot, vpt_path, metadata.program.name, vpt));
+    }
+
+    let on_brain = list_files(&mut open_connection().await?, FileVendor::User).await?;
+    let mut uploaded = 0;
+    for (slot, vpt_path, program, vpt) in &programs {
+        let file_name = format!("slot_{slot}.bin");
+        let current = on_brain.iter().any(|file| {
+            file.name == file_name
+                && file.size as usize == vpt.len()
+                && file.crc32 == vex_crc32(vpt)
+        });
+        if current && !force {
+            println!("Slot {slot}: `{program}` is up to date");
+            continue;
+        }
+
+        println!("Slot {slot}: uploading `{program}`");
+        upload_from(
+            &UploadSource::Artifact(vpt_path.clone()),
+            Some(*slot),
+            Some(FileExitAction::DoNothing),
+            runtime_source.clone(),
+            false,
+        )
+        .await?;
+        uploaded += 1;
+    }
+
+    println!(
+        "✓ Applied `{name}`: {uploaded} uploaded, {} already up to date",
+        programs.len() - uploaded
+    );
+    Ok(())
+}
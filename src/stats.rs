@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    build::{ENTRYPOINT, all_sources, checked_sources, find_modules},
+    deps::install,
+    errors::CliError,
+    imports::{import_graph, unreachable_modules},
+    manifest::get_project,
+    profile, project_dir,
+    store::{BuildRecord, MAX_BUILD_RECORDS, Store},
+};
+
+/// Modules listed under "Largest modules".
+const LARGEST_MODULES: usize = 5;
+
+/// Builds the size trend compares against.
+const TREND_BUILDS: usize = 10;
+
+/// Appends a build to the project's history. Never fails, like the battery history.
+pub fn record_build(project_dir: &Path, size: usize, programs: usize) {
+    let record = BuildRecord {
+        time: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        size: size as u64,
+        programs,
+    };
+    let key = project_dir.to_string_lossy().into_owned();
+    let _ = Store::update(|store| {
+        let history = store.build_history.entry(key).or_default();
+        history.push(record);
+        let excess = history.len().saturating_sub(MAX_BUILD_RECORDS);
+        history.drain(..excess);
+    });
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// How the latest build's size compares to the one [`TREND_BUILDS`] builds earlier.
+fn size_trend(history: &[BuildRecord]) -> Option<String> {
+    let latest = history.last()?;
+    let start = history.len().saturating_sub(TREND_BUILDS + 1);
+    let earlier = &history[start];
+    let builds = history.len() - 1 - start;
+    if builds == 0 {
+        return None;
+    }
+    let change = match latest.size.cmp(&earlier.size) {
+        std::cmp::Ordering::Greater => format!("up {}", format_size(latest.size - earlier.size)),
+        std::cmp::Ordering::Less => format!("down {}", format_size(earlier.size - latest.size)),
+        std::cmp::Ordering::Equal => "unchanged".to_string(),
+    };
+    let plural = if builds == 1 { "" } else { "s" };
+    Some(format!("{change} over the last {builds} build{plural}"))
+}
+
+/// `venice stats project`: a quick overview of the project's size and shape.
+pub async fn project() -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let project = get_project().await?;
+    let compiler = project.compiler(profile());
+
+    let mut modules = Vec::new();
+    for module in find_modules(project_dir, &compiler, &project.build).await? {
+        let path = module.src_path(project_dir);
+        let name = path
+            .strip_prefix(project_dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        if module.is_precompiled() {
+            let size = tokio::fs::metadata(&path).await?.len();
+            modules.push((name, 0, size));
+            continue;
+        }
+        let source = tokio::fs::read_to_string(&path).await?;
+        modules.push((name, source.lines().count(), source.len() as u64));
+    }
+    let lines: usize = modules.iter().map(|(_, lines, _)| lines).sum();
+
+    println!("{}", project.name);
+    println!(
+        "  source:        {lines} lines in {} modules",
+        modules.len()
+    );
+    println!(
+        "  dependencies:  {} from the registry, {} local",
+        project.dependencies.len(),
+        project.path_dependencies.len()
+            + project.git_dependencies.len()
+            + project.dev_dependencies.len()
+    );
+
+    let history = Store::load()?
+        .build_history
+        .remove(&project_dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match history.last() {
+        Some(latest) => {
+            let trend = size_trend(&history)
+                .map(|trend| format!(", {trend}"))
+                .unwrap_or_default();
+            println!(
+                "  program table: {} ({} programs){trend}",
+                format_size(latest.size),
+                latest.programs
+            );
+        }
+        None => println!("  program table: not built yet"),
+    }
+
+    if !modules.is_empty() {
+        modules.sort_by(|a, b| b.2.cmp(&a.2));
+        println!("  largest modules:");
+        for (name, lines, size) in modules.iter().take(LARGEST_MODULES) {
+            println!("    {:>10}  {lines:>5} lines  {name}", format_size(*size));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::size_trend;
+    use crate::store::BuildRecord;
+
+    fn builds(sizes: &[u64]) -> Vec<BuildRecord> {
+        sizes
+            .iter()
+            .map(|&size| BuildRecord {
+                time: 0,
+                size,
+                programs: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compares_to_earlier_builds() {
+        assert_eq!(size_trend(&builds(&[2048])), None);
+        assert_eq!(
+            size_trend(&builds(&[2048, 1024, 3072])).as_deref(),
+            Some("up 1.0 KiB over the last 2 builds")
+        );
+        let mut sizes = vec![100_000; 5];
+        sizes.extend([4096; 10]);
+        assert_eq!(
+            size_trend(&builds(&sizes)).as_deref(),
+            Some("down 93.7 KiB over the last 10 builds")
+        );
+    }
+}
+
+/// `venice stats imports`: which packed modules each module imports, and which ones `main` never
+/// reaches. With `dot`, the graph is printed for Graphviz instead.
+pub async fn imports(dot: bool) -> Result<(), CliError> {
+    let project_dir = project_dir()?;
+    let project = get_project().await?;
+    install(&project).await?;
+    let compiler = project.compiler(profile());
+    let build_dir = project.out_dir(project_dir, profile());
+
+    let sources = all_sources(project_dir, &project, &compiler, &build_dir).await?;
+    let mut packed = BTreeSet::new();
+    for module in sources.iter().flat_map(|(_, modules)| modules) {
+        packed.insert(String::from_utf8_lossy(&module.python_name()?).into_owned());
+    }
+    let graph = import_graph(&checked_sources(&sources).await?, &packed);
+    let unreachable = unreachable_modules(&graph, ENTRYPOINT);
+
+    if dot {
+        println!("digraph imports {{");
+        for (module, imported) in &graph {
+            for target in imported {
+                println!("  \"{module}\" -> \"{target}\";");
+            }
+        }
+        for module in &unreachable {
+            println!("  \"{module}\" [style=dashed];");
+        }
+        println!("}}");
+        return Ok(());
+    }
+
+    for (module, imported) in &graph {
+        let mark = if unreachable.contains(module) {
+            "  (unreachable)"
+        } else {
+            ""
+        };
+        println!("{module}{mark}");
+        for target in imported {
+            println!("  → {target}");
+        }
+    }
+    println!();
+    if unreachable.is_empty() {
+        println!("Every module is reachable from {ENTRYPOINT}");
+    } else {
+        let names: Vec<_> = unreachable.iter().map(String::as_str).collect();
+        println!(
+            "{} module(s) aren't imported from {ENTRYPOINT}: {}",
+            unreachable.len(),
+            names.join(", ")
+        );
+        println!("Set [tool.venice.build].unreachable-modules = \"exclude\" to leave them out.");
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{battery::BatteryReading, config::data_dir, errors::CliError, usage::CommandUsage};
+
+/// Everything venice remembers between runs, in the venice data directory.
+pub const STORE_FILE: &str = "state.json";
+
+/// Battery history file used before the store existed, imported by the first migration.
+const LEGACY_BATTERY_FILE: &str = "battery-history.jsonl";
+
+/// Oldest readings are dropped past this, since the whole store is rewritten on every change.
+pub const MAX_BATTERY_READINGS: usize = 1000;
+
+/// Builds remembered per project, oldest dropped first.
+pub const MAX_BUILD_RECORDS: usize = 100;
+
+/// Upgrades a store from version `i` to `i + 1`, where `i` is the index in this list. The
+/// directory is the data directory, for migrations that import older files.
+type Migration = fn(&mut Value, &Path);
+
+const MIGRATIONS: &[Migration] = &[import_battery_history];
+
+/// Version written by this build of the CLI.
+pub const VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Persistent state shared by features that need to remember something across runs. Add a
+/// field here rather than a new file, and a migration if existing data needs reshaping.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Store {
+    pub version: u32,
+    /// Oldest first
+    pub battery_history: Vec<BatteryReading>,
+    /// Programs last seen in each slot, by brain USB serial number. Only as fresh as the last
+    /// upload or slot read, so use it for suggestions, not decisions.
+    pub brain_slots: BTreeMap<String, BTreeMap<u8, String>>,
+    /// CRC32 of the calibration last pushed to each brain, by USB serial number
+    pub calibration_synced: BTreeMap<String, u32>,
+    /// The most recent command that failed, for `venice report`
+    pub last_failure: Option<Failure>,
+    /// Program table sizes, by project directory, oldest first
+    pub build_history: BTreeMap<String, Vec<BuildRecord>>,
+    /// Usage metrics by top-level command, when the config turns them on
+    pub usage: BTreeMap<String, CommandUsage>,
+    /// When the update check last failed to reach GitHub, in seconds since the Unix epoch, so it
+    /// isn't tried again on every command while offline
+    pub update_check_failed_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildRecord {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    /// Size of the program table in bytes
+    pub size: u64,
+    /// Programs in the table, including generated ones
+    pub programs: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Failure {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    pub args: Vec<String>,
+    /// The error as plain text, with its code and any compiler output
+    pub error: String,
+}
+
+fn import_battery_history(store: &mut Value, dir: &Path) {
+    let Ok(contents) = std::fs::read_to_string(dir.join(LEGACY_BATTERY_FILE)) else {
+        return;
+    };
+    let readings: Vec<Value> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    store["battery-history"] = Value::Array(readings);
+}
+
+fn migrate(mut store: Value, dir: &Path) -> Result<Value, CliError> {
+    if !store.is_object() {
+        store = Value::Object(Default::default());
+    }
+    let version = store["version"].as_u64().unwrap_or(0) as u32;
+    if version > VERSION {
+        return Err(CliError::StoreTooNew(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut store, dir);
+    }
+    store["version"] = VERSION.into();
+    Ok(store)
+}
+
+fn store_path() -> Result<(PathBuf, PathBuf), CliError> {
+    let dir = data_dir().ok_or(CliError::NoConfigDir)?;
+    let path = dir.join(STORE_FILE);
+    Ok((dir, path))
+}
+
+impl Store {
+    /// Reads the store, migrating it to the current version. A missing store is empty.
+    pub fn load() -> Result<Self, CliError> {
+        let (dir, path) = store_path()?;
+        let store = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Value::Null,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_value(migrate(store, &dir)?)?)
+    }
+
+    /// Writes the store through a temporary file, so a crash never leaves it half-written.
+    pub fn save(&self) -> Result<(), CliError> {
+        let (dir, path) = store_path()?;
+        std::fs::create_dir_all(&dir)?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// Loads the store, applies `change` and saves it.
+    pub fn update(change: impl FnOnce(&mut Self)) -> Result<(), CliError> {
+        let mut store = Self::load()?;
+        change(&mut store);
+        store.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Store, VERSION, migrate};
+
+    #[test]
+    fn migrates_to_current_version() {
+        let dir = std::env::temp_dir().join("venice-store-test-missing");
+        let store = migrate(json!(null), &dir).unwrap();
+        assert_eq!(store["version"], VERSION);
+
+        let store: Store = serde_json::from_value(store).unwrap();
+        assert!(store.battery_history.is_empty());
+    }
+
+    #[test]
+    fn rejects_newer_store() {
+        let dir = std::env::temp_dir();
+        assert!(migrate(json!({ "version": VERSION + 1 }), &dir).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+use vex_v5_serial::protocol::cdc2::file::FileVendor;
+
+use crate::{
+    brain::{
+        ROBOT_NAME_KEY, SlotIni, TEAM_NUMBER_KEY, download_file, list_files, read_key, write_key,
+    },
+    device::open_connection,
+    errors::CliError,
+    runtime::RtBin,
+    upload::upload_user_file,
+};
+
+pub const STATE_FILE: &str = "brain-state.toml";
+/// Directory next to [`STATE_FILE`] holding the downloaded file contents.
+pub const STATE_DIR: &str = "brain-state";
+
+/// Snapshot of everything Venice cares about on a brain.
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct BrainState {
+    pub team_number: Option<String>,
+    pub robot_name: Option<String>,
+    #[serde(default, rename = "file")]
+    pub files: Vec<StateFile>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct StateFile {
+    pub name: String,
+    pub load_address: u32,
+    pub crc32: u32,
+    /// Runtime the file must be linked against when re-uploaded
+    pub linked_file: Option<String>,
+}
+
+fn is_slot_binary(name: &str) -> bool {
+    name.starts_with("slot_") && name.ends_with(".bin")
+}
+
+/// Reads the brain's settings and files into `dir`.
+pub async fn pull(dir: &Path) -> Result<(), CliError> {
+    let mut conn = open_connection().await?;
+
+    let team_number = read_key(&mut conn, TEAM_NUMBER_KEY).await?;
+    let robot_name = read_key(&mut conn, ROBOT_NAME_KEY).await?;
+    let brain_files = list_files(&mut conn, FileVendor::User).await?;
+
+    // Program binaries don't record which runtime they're linked against, so assume the newest
+    // runtime on the brain; that's the one the last upload used.
+    let runtime = brain_files
+        .iter()
+        .filter_map(|file| file.name.parse::<RtBin>().ok())
+        .max()
+        .map(|bin| bin.to_string());
+
+    let files_dir = dir.join(STATE_DIR);
+    tokio::fs::create_dir_all(&files_dir).await?;
+
+    let mut files = Vec::new();
+    for file in brain_files.iter() {
+        // The name comes from the brain, and mustn't put the file anywhere but the state dir
+        let mut components = Path::new(&file.name).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(CliError::UnsafeBrainFileName(file.name.clone()));
+        }
+        let contents = download_file(&mut conn, file).await?;
+        tokio::fs::write(files_dir.join(&file.name), &contents).await?;
+
+        if file.name.ends_with(".ini")
+            && let Ok(ini) = serde_ini::from_str::<SlotIni>(&String::from_utf8_lossy(&contents))
+        {
+            println!("slot {}: {}", ini.program.slot, ini.program.name);
+        }
+
+        files.push(StateFile {
+            name: file.name.clone(),
+            load_address: file.load_address,
+            crc32: file.crc32,
+            linked_file: if is_slot_binary(&file.name) {
+                runtime.clone()
+            } else {
+                None
+            },
+        });
+    }
+
+    let state = BrainState {
+        team_number,
+        robot_name,
+        files,
+    };
+    let state_toml =
+        toml::to_string_pretty(&state).map_err(|e| CliError::StateFile(e.to_string()))?;
+    tokio::fs::write(dir.join(STATE_FILE), state_toml).await?;
+
+    println!("✓ Saved {} file(s) to {STATE_FILE}", state.files.len());
+    Ok(())
+}
+
+/// Applies a previously pulled snapshot to the connected brain.
+pub async fn push(dir: &Path) -> Result<(), CliError> {
+    let state_toml = tokio::fs::read_to_string(dir.join(STATE_FILE)).await?;
+    let state: BrainState =
+        toml::from_str(&state_toml).map_err(|e| CliError::StateFile(e.to_string()))?;
+
+    let mut conn = open_connection().await?;
+
+    if let Some(team_number) = &state.team_number {
+        write_key(&mut conn, TEAM_NUMBER_KEY, team_number).await?;
+    }
+    if let Some(robot_name) = &state.robot_name {
+        write_key(&mut conn, ROBOT_NAME_KEY, robot_name).await?;
+    }
+
+    // Linked files have to be on the brain before anything linking against them
+    let (unlinked, linked): (Vec<_>, Vec<_>) = state
+        .files
+        .iter()
+        .partition(|file| file.linked_file.is_none());
+    for file in unlinked.into_iter().chain(linked) {
+        let contents = tokio::fs::read(dir.join(STATE_DIR).join(&file.name)).await?;
+        upload_user_file(
+            &mut conn,
+            &file.name,
+            &contents,
+            file.load_address,
+            file.linked_file.as_deref(),
+        )
+        .await?;
+    }
+
+    println!("✓ Restored {} file(s) from {STATE_FILE}", state.files.len());
+    Ok(())
+}
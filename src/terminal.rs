@@ -1,31 +1,446 @@
-use std::time::Duration;
+use std::{
+    io::Write,
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt, stdin, stdout},
     select,
-    time::sleep,
+    time::{MissedTickBehavior, interval, sleep},
+};
+use vex_v5_serial::{
+    Connection,
+    protocol::cdc2::file::FileExitAction,
+    serial::{SerialConnection, SerialError},
 };
-use vex_v5_serial::{Connection, serial::SerialConnection};
 
-use crate::errors::CliError;
+use crate::{
+    brain,
+    clock::{self, ClockCheck},
+    config,
+    device::{Keepalive, identity, reconnect},
+    errors::CliError,
+    log::{MARKER_PREFIX, format_line},
+    palette::{self, CLEAR_SCREEN, Input, PaletteCommand, TERMINAL_HELP},
+    radio::{self, Channel, LinkType},
+    runtime::{Feature, RuntimeSource, capabilities},
+    sink::Sinks,
+    throttle::OutputThrottle,
+    timesync::TimeSync,
+    upload::upload,
+};
+
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often the controller's radio is polled for status changes.
+const CONTROLLER_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the keepalive checks whether the device has been quiet long enough to ping.
+const KEEPALIVE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often a flood that stopped is checked for, to report the lines it dropped.
+const THROTTLE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a due clock sync request, or a clock check gone unanswered, is checked for.
+const TIME_SYNC_INTERVAL: Duration = Duration::from_millis(50);
+
+const BRAIN_PREFIX: &[u8] = b"[brain] ";
+const CONTROLLER_PREFIX: &str = "[controller] ";
+
+/// Records the program's output, stamped with the time since the terminal opened, along with
+/// markers typed into the terminal and, if the runtime answers clock sync requests, the brain's
+/// clock at a moment on the log's.
+pub struct TerminalLog {
+    file: std::fs::File,
+    start: Instant,
+    /// Output after the last newline, written once its line is complete
+    partial: Vec<u8>,
+}
+
+impl TerminalLog {
+    pub fn create(path: &Path) -> Result<Self, CliError> {
+        Ok(Self {
+            file: std::fs::File::create(path)?,
+            start: Instant::now(),
+            partial: Vec::new(),
+        })
+    }
+
+    fn write_line(&mut self, text: &str) -> Result<(), CliError> {
+        writeln!(self.file, "{}", format_line(self.start.elapsed(), text))?;
+        Ok(())
+    }
+
+    fn output(&mut self, bytes: &[u8]) -> Result<(), CliError> {
+        self.partial.extend_from_slice(bytes);
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            self.write_line(String::from_utf8_lossy(&line).trim_end())?;
+        }
+        Ok(())
+    }
+
+    /// Handles a line typed into the terminal if it's a marker: `!! text`, or `!1` to `!9` for
+    /// the presets in [terminal].markers. Returns whether it was.
+    fn input(&mut self, bytes: &[u8]) -> Result<bool, CliError> {
+        let input = String::from_utf8_lossy(bytes);
+        let input = input.trim();
+        let text = if let Some(text) = input.strip_prefix("!!") {
+            text.trim().to_string()
+        } else if let Some(preset) = input
+            .strip_prefix('!')
+            .and_then(|n| n.parse::<usize>().ok())
+            .and_then(|n| config().terminal.markers.get(n.checked_sub(1)?))
+        {
+            preset.clone()
+        } else {
+            return Ok(false);
+        };
+
+        self.write_line(&format!("{MARKER_PREFIX}{text}"))?;
+        eprintln!(
+            "[marked {:.3}s: {text}]",
+            self.start.elapsed().as_secs_f64()
+        );
+        Ok(true)
+    }
+}
+
+#[derive(Default)]
+pub struct TerminalOptions {
+    /// Also write the program's output here
+    pub log: Option<TerminalLog>,
+    /// Connected through a controller: interleave its radio status with the brain's output,
+    /// prefixing each line with where it came from.
+    pub controller: bool,
+    /// Runtime used by `:upload`
+    pub runtime_source: Option<RuntimeSource>,
+}
+
+/// Prefixes each line of the program's output with the time and where it came from, as turned
+/// on. Remembers across reads whether the last one ended mid-line.
+struct LinePrefixer {
+    at_line_start: bool,
+    brain: bool,
+    /// Stamp lines with the time since the terminal opened, toggled by `:ts`
+    timestamps: bool,
+    start: Instant,
+}
+
+impl LinePrefixer {
+    fn prefix(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len() + BRAIN_PREFIX.len());
+        for &byte in bytes {
+            if self.at_line_start {
+                if self.timestamps {
+                    out.extend_from_slice(format_line(self.start.elapsed(), "").as_bytes());
+                }
+                if self.brain {
+                    out.extend_from_slice(BRAIN_PREFIX);
+                }
+            }
+            out.push(byte);
+            self.at_line_start = byte == b'\n';
+        }
+        out
+    }
+}
+
+/// Runs a line typed as `@controller <command>`. Only the radio can be driven from here.
+async fn controller_command(connection: &mut SerialConnection, command: &str) -> String {
+    let result = match command.split_whitespace().collect::<Vec<_>>()[..] {
+        ["status"] => radio::radio_status(connection)
+            .await
+            .map(|status| radio::describe(&status))
+            .map_err(CliError::from),
+        ["channel", "pit"] => radio::switch_channel(connection, Channel::Pit)
+            .await
+            .map(|()| "switched to the pit channel".to_string()),
+        ["channel", "download"] => radio::switch_channel(connection, Channel::Download)
+            .await
+            .map(|()| "switched to the download channel".to_string()),
+        _ => {
+            return "commands: `status`, `channel pit`, `channel download`".to_string();
+        }
+    };
+    result.unwrap_or_else(|e| format!("error: {e}"))
+}
 
-pub async fn terminal(connection: &mut SerialConnection) -> Result<(), CliError> {
+pub async fn terminal(
+    connection: SerialConnection,
+    runtime_source: Option<RuntimeSource>,
+) -> Result<(), CliError> {
+    terminal_with(
+        connection,
+        TerminalOptions {
+            runtime_source,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Like [`terminal`], with logging or controller status as set in `options`. Lines starting
+/// with `:` are commands for venice; see [`palette`](crate::palette).
+pub async fn terminal_with(
+    mut connection: SerialConnection,
+    options: TerminalOptions,
+) -> Result<(), CliError> {
+    let TerminalOptions {
+        mut log,
+        controller,
+        runtime_source,
+    } = options;
     let mut stdin = stdin();
     let mut program_output = [0; 2048];
     let mut program_input = [0; 4096];
 
+    let mut prefixer = LinePrefixer {
+        at_line_start: true,
+        brain: controller,
+        timestamps: false,
+        start: Instant::now(),
+    };
+    let mut controller_poll = interval(CONTROLLER_POLL_INTERVAL);
+    controller_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_link = None;
+    let mut keepalive = Keepalive::from_config();
+    let mut keepalive_check = interval(KEEPALIVE_CHECK_INTERVAL);
+    keepalive_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut sinks = Sinks::from_config();
+    let mut throttle = OutputThrottle::from_config();
+    let mut throttle_check = interval(THROTTLE_CHECK_INTERVAL);
+    throttle_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // Only a log can make use of the brain's clock
+    let time_sync_supported = match &runtime_source {
+        Some(source) => capabilities(&source.version)
+            .await
+            .is_some_and(|capabilities| capabilities.supports(Feature::TimeSync)),
+        None => false,
+    };
+    let mut time_sync = log
+        .as_ref()
+        .filter(|_| time_sync_supported)
+        .map(|log| TimeSync::new(log.start));
+    let mut time_sync_check = interval(TIME_SYNC_INTERVAL);
+    time_sync_check.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut clock_check = None;
+    if clock::supported(runtime_source.as_ref()).await {
+        let (check, request) = ClockCheck::start(Instant::now());
+        connection.write_user(&request).await.unwrap();
+        clock_check = Some(check);
+    }
+    if let Some(identity) = identity() {
+        eprintln!("Connected to {identity}");
+        if let Some(log) = &mut log {
+            log.write_line(&format!("connected to {identity}"))?;
+        }
+    }
+    if controller {
+        eprintln!(
+            "Connected through a controller. Type `@controller status` or `@controller channel \
+             pit|download` to talk to it; everything else goes to the brain."
+        );
+    }
+
     loop {
+        let mut controller_line = None;
+        let mut command = None;
+
         select! {
             read = connection.read_user(&mut program_output) => {
-                if let Ok(size) = read {
-                    stdout().write_all(&program_output[..size]).await.unwrap();
+                match read {
+                    Ok(size) => {
+                        if let Some(keepalive) = &mut keepalive {
+                            keepalive.heard();
+                        }
+                        let output = match &mut clock_check {
+                            Some(check) => {
+                                let (shown, request) =
+                                    check.filter(&program_output[..size], Instant::now());
+                                if let Some(request) = request {
+                                    connection.write_user(&request).await.unwrap();
+                                }
+                                shown
+                            }
+                            None => program_output[..size].to_vec(),
+                        };
+                        let output = match &mut time_sync {
+                            Some(sync) => sync.filter(&output, Instant::now()),
+                            None => output,
+                        };
+                        // The log and sinks keep everything, even what the throttle drops
+                        let output = output.as_slice();
+                        if let Some(log) = &mut log {
+                            log.output(output)?;
+                        }
+                        sinks.output(output);
+                        let shown = match &mut throttle {
+                            Some(throttle) => throttle.filter(output, Instant::now()),
+                            None => output.to_vec(),
+                        };
+                        stdout().write_all(&prefixer.prefix(&shown)).await.unwrap();
+                    }
+                    // The port went away (unplugged, or the host slept); it may come back
+                    // under a different name
+                    Err(SerialError::IoError(_) | SerialError::SerialportError(_)) => {
+                        eprintln!("\nLost connection to the brain, waiting for it to come back...");
+                        connection = reconnect(RECONNECT_TIMEOUT).await?;
+                        eprintln!("Reconnected.");
+                        if let Some(keepalive) = &mut keepalive {
+                            keepalive.heard();
+                        }
+                    }
+                    Err(_) => {}
                 }
             },
             read = stdin.read(&mut program_input) => {
                 if let Ok(size) = read {
-                    connection.write_user(&program_input[..size]).await.unwrap();
+                    let marked = match &mut log {
+                        Some(log) => log.input(&program_input[..size])?,
+                        None => false,
+                    };
+                    let input = match palette::parse(&program_input[..size]) {
+                        _ if marked => None,
+                        Input::Command(palette_command) => {
+                            command = Some(palette_command);
+                            None
+                        }
+                        Input::Program(input) => Some(input),
+                    };
+                    if let Some(input) = input {
+                        let text = String::from_utf8_lossy(input);
+                        let controller_command_text =
+                            text.trim().strip_prefix("@controller").filter(|_| controller);
+                        if let Some(text) = controller_command_text {
+                            controller_line = Some(controller_command(&mut connection, text).await);
+                        } else {
+                            let input = match text.strip_prefix("@brain ") {
+                                Some(text) if controller => text.as_bytes(),
+                                _ => input,
+                            };
+                            connection.write_user(input).await.unwrap();
+                        }
+                    }
+                }
+            }
+            _ = keepalive_check.tick(), if keepalive.is_some() => {
+                if let Some(keepalive) = &mut keepalive {
+                    keepalive.check(&mut connection).await?;
+                }
+            }
+            _ = time_sync_check.tick(), if time_sync.is_some() || clock_check.is_some() => {
+                let now = Instant::now();
+                if clock_check.as_ref().is_some_and(|check| check.finished(now)) {
+                    let pending = clock_check.take().unwrap().into_pending();
+                    if let Some(log) = &mut log {
+                        log.output(&pending)?;
+                    }
+                    stdout().write_all(&prefixer.prefix(&pending)).await.unwrap();
+                }
+                if let Some(request) = time_sync.as_mut().and_then(|sync| sync.poll(now)) {
+                    connection.write_user(&request).await.unwrap();
+                }
+                if let Some(sample) = time_sync.as_ref().and_then(|sync| sync.finished(now)) {
+                    let pending = time_sync.take().unwrap().into_pending();
+                    if let Some(log) = &mut log {
+                        log.output(&pending)?;
+                        match sample {
+                            Some(sample) => log.write_line(&sample.describe())?,
+                            None => eprintln!(
+                                "[the runtime didn't answer the clock sync, so the log can't be lined up with the brain's clock]"
+                            ),
+                        }
+                    }
+                    stdout().write_all(&prefixer.prefix(&pending)).await.unwrap();
                 }
             }
+            _ = throttle_check.tick(), if throttle.is_some() => {
+                if let Some(notice) = throttle.as_mut().and_then(|t| t.tick(Instant::now())) {
+                    stdout().write_all(&prefixer.prefix(notice.as_bytes())).await.unwrap();
+                }
+            }
+            _ = controller_poll.tick(), if controller => {
+                // Only report changes, since quality moves around constantly
+                if let Ok(status) = radio::radio_status(&mut connection).await {
+                    if let Some(keepalive) = &mut keepalive {
+                        keepalive.heard();
+                    }
+                    let link = (LinkType::from_status(&status), status.channel);
+                    if last_link != Some(link) {
+                        last_link = Some(link);
+                        controller_line = Some(radio::describe(&status));
+                    }
+                }
+            }
+        }
+
+        if let Some(line) = controller_line {
+            let mut out = String::new();
+            if !prefixer.at_line_start {
+                out.push('\n');
+                prefixer.at_line_start = true;
+            }
+            out.push_str(CONTROLLER_PREFIX);
+            out.push_str(&line);
+            out.push('\n');
+            stdout().write_all(out.as_bytes()).await.unwrap();
+        }
+
+        match command {
+            None => {}
+            Some(PaletteCommand::Timestamps) => {
+                prefixer.timestamps = !prefixer.timestamps;
+                eprintln!(
+                    "[timestamps {}]",
+                    if prefixer.timestamps { "on" } else { "off" }
+                );
+            }
+            Some(PaletteCommand::Log(Some(path))) => match TerminalLog::create(&path) {
+                Ok(new_log) => {
+                    if time_sync_supported {
+                        time_sync = Some(TimeSync::new(new_log.start));
+                    }
+                    log = Some(new_log);
+                    eprintln!("[logging to {}]", path.display());
+                }
+                Err(e) => eprintln!("[couldn't create {}: {e}]", path.display()),
+            },
+            Some(PaletteCommand::Log(None)) => {
+                log = None;
+                eprintln!("[logging stopped]");
+            }
+            Some(PaletteCommand::Stop) => match brain::stop_program(&mut connection).await {
+                Ok(()) => eprintln!("[program stopped]"),
+                Err(e) => eprintln!("[couldn't stop the program: {e}]"),
+            },
+            Some(PaletteCommand::Upload) => {
+                // The upload opens the port itself
+                drop(connection);
+                connection = match upload(
+                    Some(FileExitAction::RunProgram),
+                    runtime_source.clone(),
+                    false,
+                )
+                .await
+                {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        eprintln!("{:?}", miette::Report::new(e));
+                        reconnect(RECONNECT_TIMEOUT).await?
+                    }
+                };
+                prefixer.at_line_start = true;
+                if let Some(keepalive) = &mut keepalive {
+                    keepalive.heard();
+                }
+            }
+            Some(PaletteCommand::Clear) => stdout().write_all(CLEAR_SCREEN).await.unwrap(),
+            Some(PaletteCommand::Help) => eprintln!("{TERMINAL_HELP}"),
+            Some(PaletteCommand::Unknown(name)) => {
+                eprintln!("[unknown command `:{name}`, type `:help` for the list]")
+            }
         }
 
         sleep(Duration::from_millis(10)).await;
@@ -0,0 +1,101 @@
+use std::io::IsTerminal;
+
+use miette::{GraphicalTheme, ThemeCharacters, ThemeStyles};
+use owo_colors::Style;
+use serde::Deserialize;
+
+use crate::config;
+
+/// Colors for progress bars and diagnostics, set with `theme` in the venice config.
+#[derive(Deserialize, Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    /// Bold, bright colors that stay readable on washed-out projectors and in sunlight
+    HighContrast,
+    /// Blue, orange and yellow from the Okabe-Ito palette, which stay distinct with red-green
+    /// color blindness
+    ColorBlind,
+    /// No color at all
+    Monochrome,
+}
+
+// Okabe-Ito colors
+const ORANGE: (u8, u8, u8) = (230, 159, 0);
+const SKY_BLUE: (u8, u8, u8) = (86, 180, 233);
+const YELLOW: (u8, u8, u8) = (240, 228, 66);
+const BLUE: (u8, u8, u8) = (0, 114, 178);
+const VERMILLION: (u8, u8, u8) = (213, 94, 0);
+const PURPLE: (u8, u8, u8) = (204, 121, 167);
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Style {
+    Style::new().truecolor(r, g, b)
+}
+
+pub fn theme() -> Theme {
+    config().theme
+}
+
+/// indicatif template for upload progress bars.
+pub fn progress_template() -> &'static str {
+    match theme() {
+        Theme::Default => "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>3}% {msg}",
+        Theme::HighContrast => "[{elapsed_precise}] {bar:40.white.bold/white.dim} {pos:>3}% {msg}",
+        // 256-color approximations of Okabe-Ito blue and orange
+        Theme::ColorBlind => "[{elapsed_precise}] {bar:40.32/214} {pos:>3}% {msg}",
+        Theme::Monochrome => "[{elapsed_precise}] {bar:40} {pos:>3}% {msg}",
+    }
+}
+
+fn diagnostic_styles(theme: Theme) -> Option<ThemeStyles> {
+    match theme {
+        Theme::Default => None,
+        Theme::HighContrast => Some(ThemeStyles {
+            error: Style::new().bright_red().bold(),
+            warning: Style::new().bright_yellow().bold(),
+            advice: Style::new().bright_cyan().bold(),
+            help: Style::new().bright_white().bold(),
+            link: Style::new().bright_cyan().underline().bold(),
+            linum: Style::new().bright_white(),
+            highlights: vec![
+                Style::new().bright_magenta().bold(),
+                Style::new().bright_yellow().bold(),
+                Style::new().bright_green().bold(),
+            ],
+        }),
+        // Severities differ in hue and lightness, not only red against green
+        Theme::ColorBlind => Some(ThemeStyles {
+            error: rgb(VERMILLION).bold(),
+            warning: rgb(YELLOW),
+            advice: rgb(SKY_BLUE),
+            help: rgb(SKY_BLUE),
+            link: rgb(BLUE).underline().bold(),
+            linum: Style::new().dimmed(),
+            highlights: vec![rgb(ORANGE), rgb(SKY_BLUE), rgb(PURPLE)],
+        }),
+        Theme::Monochrome => Some(ThemeStyles::none()),
+    }
+}
+
+/// Draws diagnostics in the configured theme. The default theme, output that isn't a terminal
+/// and `NO_COLOR` are left to miette's own choice of colors.
+pub fn install_report_hook() {
+    let Some(styles) = diagnostic_styles(theme()) else {
+        return;
+    };
+    if !std::io::stderr().is_terminal() || std::env::var_os("NO_COLOR").is_some() {
+        return;
+    }
+    let graphical_theme = GraphicalTheme {
+        characters: ThemeCharacters::unicode(),
+        styles,
+    };
+    let _ = miette::set_hook(Box::new(move |_| {
+        Box::new(
+            miette::MietteHandlerOpts::new()
+                .graphical_theme(graphical_theme.clone())
+                .build(),
+        )
+    }));
+}
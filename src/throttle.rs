@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Drops lines of program output past a per-second limit, so a program printing in a tight loop
+/// can't bury the terminal. Whole lines are kept or dropped, and each second that dropped some
+/// ends with a notice saying how many.
+pub struct OutputThrottle {
+    max_lines: u32,
+    window_start: Instant,
+    /// Lines shown in the current window
+    lines: u32,
+    /// Lines dropped in the current window
+    dropped: u64,
+    at_line_start: bool,
+    dropping_line: bool,
+}
+
+impl OutputThrottle {
+    pub fn new(max_lines_per_second: u32, now: Instant) -> Self {
+        Self {
+            max_lines: max_lines_per_second,
+            window_start: now,
+            lines: 0,
+            dropped: 0,
+            at_line_start: true,
+            dropping_line: false,
+        }
+    }
+
+    /// Uses [terminal].max-lines-per-second of the venice config. `None` when it's 0.
+    pub fn from_config() -> Option<Self> {
+        let max_lines = config().terminal.max_lines_per_second;
+        (max_lines != 0).then(|| Self::new(max_lines, Instant::now()))
+    }
+
+    /// The part of `bytes` to show, preceded by the notice for the previous window if it
+    /// dropped anything.
+    pub fn filter(&mut self, bytes: &[u8], now: Instant) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            if self.at_line_start {
+                if let Some(notice) = self.end_window(now) {
+                    out.extend_from_slice(notice.as_bytes());
+                }
+                self.dropping_line = self.lines >= self.max_lines;
+                if self.dropping_line {
+                    self.dropped += 1;
+                } else {
+                    self.lines += 1;
+                }
+            }
+            if !self.dropping_line {
+                out.push(byte);
+            }
+            self.at_line_start = byte == b'\n';
+        }
+        out
+    }
+
+    /// The notice for a window that ended with no output after it, so a flood that stopped is
+    /// still reported. Call it periodically.
+    pub fn tick(&mut self, now: Instant) -> Option<String> {
+        // Mid-line, the notice would land inside a line that's still being shown
+        if !self.at_line_start && !self.dropping_line {
+            return None;
+        }
+        self.end_window(now)
+    }
+
+    fn end_window(&mut self, now: Instant) -> Option<String> {
+        if now.duration_since(self.window_start) < WINDOW {
+            return None;
+        }
+        self.window_start = now;
+        self.lines = 0;
+        let dropped = std::mem::take(&mut self.dropped);
+        let plural = if dropped == 1 { "" } else { "s" };
+        (dropped > 0).then(|| format!("[output truncated, {dropped} line{plural} dropped]\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::OutputThrottle;
+
+    #[test]
+    fn drops_lines_past_the_limit() {
+        let start = Instant::now();
+        let mut throttle = OutputThrottle::new(2, start);
+        assert_eq!(throttle.filter(b"a\nb\nc\nd", start), b"a\nb\n");
+        assert_eq!(throttle.filter(b"d\n", start), b"");
+        assert_eq!(throttle.tick(start), None);
+
+        let later = start + Duration::from_secs(1);
+        assert_eq!(
+            throttle.filter(b"e\n", later),
+            b"[output truncated, 2 lines dropped]\ne\n"
+        );
+    }
+
+    #[test]
+    fn reports_a_flood_that_stopped() {
+        let start = Instant::now();
+        let mut throttle = OutputThrottle::new(1, start);
+        throttle.filter(b"a\nb\n", start);
+        assert_eq!(
+            throttle.tick(start + Duration::from_secs(1)).as_deref(),
+            Some("[output truncated, 1 line dropped]\n")
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::{Duration, Instant};
+
+/// Written to the program's input, followed by an exchange number and [`REPLY_END`], to ask for
+/// the brain's clock. The runtime takes it out of the input and answers on its output with
+/// [`REPLY_PREFIX`], the same number, a comma, the milliseconds since the brain started and
+/// [`REPLY_END`]. The number keeps a late answer from being taken for the next one's.
+const REQUEST_PREFIX: &[u8] = b"\x1b]venice;time?";
+const REPLY_PREFIX: &[u8] = b"\x1b]venice;time=";
+pub const REPLY_END: u8 = 0x07;
+
+/// Exchanges made; the one with the shortest round trip is kept.
+const EXCHANGES: usize = 5;
+
+/// Longest answer there is; output that starts like one but runs longer is shown after all.
+const MAX_REPLY: usize = 64;
+
+/// How long to wait for an answer before asking again.
+const REPLY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The brain's clock at a moment on the host's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSample {
+    /// Time since the log started
+    pub host: Duration,
+    pub brain_ms: f64,
+    /// Half the round trip: how far off the match could be
+    pub uncertainty: Duration,
+}
+
+impl ClockSample {
+    /// `brain clock 1234.567s at 0.512s (±1.5ms)`, the line recorded in the log.
+    pub fn describe(&self) -> String {
+        format!(
+            "brain clock {:.3}s at {:.3}s (±{:.1}ms)",
+            self.brain_ms / 1000.0,
+            self.host.as_secs_f64(),
+            self.uncertainty.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// A few request/reply exchanges with the runtime, NTP-style, to line the brain's clock up with
+/// the log's. Replies are taken out of the program's output so they never reach the screen.
+pub struct TimeSync {
+    start: Instant,
+    /// The unanswered request's number and when it was sent
+    sent: Option<(usize, Instant)>,
+    attempts: usize,
+    best: Option<ClockSample>,
+    replies: ReplyFilter,
+}
+
+impl TimeSync {
+    /// `start` is when the log's clock started.
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            sent: None,
+            attempts: 0,
+            best: None,
+            replies: ReplyFilter::new(REPLY_PREFIX),
+        }
+    }
+
+    /// The request to write to the program now, if one's due.
+    pub fn poll(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.waiting(now) || self.attempts >= EXCHANGES {
+            return None;
+        }
+        self.attempts += 1;
+        self.sent = Some((self.attempts, now));
+
+        let mut request = REQUEST_PREFIX.to_vec();
+        request.extend_from_slice(self.attempts.to_string().as_bytes());
+        request.push(REPLY_END);
+        Some(request)
+    }
+
+    fn waiting(&self, now: Instant) -> bool {
+        self.sent
+            .is_some_and(|(_, sent)| now.duration_since(sent) < REPLY_TIMEOUT)
+    }
+
+    /// The best match, once every exchange has been answered or has timed out.
+    pub fn finished(&self, now: Instant) -> Option<Option<ClockSample>> {
+        (self.attempts >= EXCHANGES && !self.waiting(now)).then_some(self.best)
+    }
+
+    /// Takes replies out of the program's output, returning what's left to show.
+    pub fn filter(&mut self, output: &[u8], now: Instant) -> Vec<u8> {
+        if self.sent.is_none() && !self.replies.holding() {
+            return output.to_vec();
+        }
+        let (shown, replies) = self.replies.filter(output);
+        for reply in replies {
+            let answer = reply
+                .split_once(',')
+                .and_then(|(id, ms)| Some((id.parse::<usize>().ok()?, ms.parse::<f64>().ok()?)));
+            if let (Some((id, brain_ms)), Some((sent_id, sent))) = (answer, self.sent)
+                && id == sent_id
+            {
+                self.sent = None;
+                self.record(sent, now, brain_ms);
+            }
+        }
+        shown
+    }
+
+    /// Output still held back, for showing once the exchanges are over.
+    pub fn into_pending(self) -> Vec<u8> {
+        self.replies.into_pending()
+    }
+
+    fn record(&mut self, sent: Instant, received: Instant, brain_ms: f64) {
+        let round_trip = received.duration_since(sent);
+        let sample = ClockSample {
+            host: sent.duration_since(self.start) + round_trip / 2,
+            brain_ms,
+            uncertainty: round_trip / 2,
+        };
+        if self
+            .best
+            .is_none_or(|best| sample.uncertainty < best.uncertainty)
+        {
+            self.best = Some(sample);
+        }
+    }
+}
+
+/// Takes the runtime's replies, from a prefix like `\x1b]venice;time=` to [`REPLY_END`], out of
+/// the program's output.
+pub struct ReplyFilter {
+    prefix: &'static [u8],
+    /// Output held back because it may be the start of a reply
+    pending: Vec<u8>,
+}
+
+impl ReplyFilter {
+    pub fn new(prefix: &'static [u8]) -> Self {
+        Self {
+            prefix,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Whether output is held back, waiting for the rest of a reply.
+    pub fn holding(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Returns what's left of the output to show, and what each complete reply says after its
+    /// prefix.
+    pub fn filter(&mut self, output: &[u8]) -> (Vec<u8>, Vec<String>) {
+        self.pending.extend_from_slice(output);
+
+        let mut shown = Vec::new();
+        let mut replies = Vec::new();
+        loop {
+            let Some(start) = find(&self.pending, self.prefix) else {
+                // Hold back anything that could still become a reply
+                let keep = (1..self.prefix.len())
+                    .rev()
+                    .find(|&n| self.pending.ends_with(&self.prefix[..n]))
+                    .unwrap_or(0);
+                shown.extend(self.pending.drain(..self.pending.len() - keep));
+                return (shown, replies);
+            };
+            shown.extend(self.pending.drain(..start));
+            let Some(end) = self.pending.iter().position(|&b| b == REPLY_END) else {
+                if self.pending.len() > MAX_REPLY {
+                    shown.append(&mut self.pending);
+                }
+                return (shown, replies);
+            };
+            let reply: Vec<u8> = self.pending.drain(..=end).collect();
+            replies.push(
+                String::from_utf8_lossy(&reply[self.prefix.len()..reply.len() - 1]).into_owned(),
+            );
+        }
+    }
+
+    /// Output still held back.
+    pub fn into_pending(self) -> Vec<u8> {
+        self.pending
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::TimeSync;
+
+    #[test]
+    fn takes_replies_out_of_output() {
+        let start = Instant::now();
+        let mut sync = TimeSync::new(start);
+        let sent = start + Duration::from_millis(100);
+        assert_eq!(sync.poll(sent).unwrap(), b"\x1b]venice;time?1\x07");
+        assert!(sync.poll(sent).is_none());
+
+        let received = sent + Duration::from_millis(4);
+        // The reply arrives split across reads, between lines of program output
+        assert_eq!(sync.filter(b"hello\n\x1b]venice;ti", received), b"hello\n");
+        assert_eq!(
+            sync.filter(b"me=1,5000.25\x07world\n", received),
+            b"world\n"
+        );
+
+        let best = sync.best.unwrap();
+        assert_eq!(best.brain_ms, 5000.25);
+        assert_eq!(best.host, Duration::from_millis(102));
+        assert_eq!(best.uncertainty, Duration::from_millis(2));
+        // Answered, so the next exchange can start, and a repeat of the old answer is ignored
+        assert!(sync.poll(received).is_some());
+        assert_eq!(sync.filter(b"\x1b]venice;time=1,6000\x07", received), b"");
+        assert_eq!(sync.best.unwrap().brain_ms, 5000.25);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::errors::CliError;
+
+/// Report written to the build dir by `build --timings`.
+pub const TIMINGS_FILE: &str = "venice-timings.html";
+
+/// Rows shown in the terminal table; the HTML report lists every module.
+const TABLE_ROWS: usize = 10;
+
+/// How one module's compile went.
+#[derive(Debug, Clone)]
+pub struct ModuleTiming {
+    pub name: String,
+    pub duration: Duration,
+    /// Whether the bytecode was up to date or in the shared cache, so compiling was skipped
+    pub cached: bool,
+}
+
+/// Per-module compile times for one build.
+#[derive(Debug, Default)]
+pub struct BuildTimings {
+    pub modules: Vec<ModuleTiming>,
+    /// Wall-clock time of the whole build
+    pub total: Duration,
+}
+
+impl BuildTimings {
+    fn compiled(&self) -> impl Iterator<Item = &ModuleTiming> {
+        self.modules.iter().filter(|module| !module.cached)
+    }
+
+    fn cache_hits(&self) -> usize {
+        self.modules.len() - self.compiled().count()
+    }
+
+    fn hit_rate(&self) -> f64 {
+        if self.modules.is_empty() {
+            return 0.0;
+        }
+        self.cache_hits() as f64 / self.modules.len() as f64 * 100.0
+    }
+
+    /// Summed compile time. Compared to `total`, shows how much the build overlaps compiles.
+    fn compile_time(&self) -> Duration {
+        self.compiled().map(|module| module.duration).sum()
+    }
+
+    /// Compiled modules, slowest first.
+    fn slowest(&self) -> Vec<&ModuleTiming> {
+        let mut modules: Vec<_> = self.compiled().collect();
+        modules.sort_by(|a, b| b.duration.cmp(&a.duration));
+        modules
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} modules, {} cached ({:.0}%), {:.2}s compiling, {:.2}s total",
+            self.modules.len(),
+            self.cache_hits(),
+            self.hit_rate(),
+            self.compile_time().as_secs_f64(),
+            self.total.as_secs_f64(),
+        )
+    }
+
+    /// Prints the slowest modules and the cache hit rate.
+    pub fn print_table(&self) {
+        println!("Build timings: {}", self.summary());
+        let slowest = self.slowest();
+        if slowest.is_empty() {
+            println!("  every module was up to date");
+            return;
+        }
+
+        let compile_time = self.compile_time().as_secs_f64().max(f64::EPSILON);
+        for module in slowest.iter().take(TABLE_ROWS) {
+            println!(
+                "  {:>8.3}s  {:>5.1}%  {}",
+                module.duration.as_secs_f64(),
+                module.duration.as_secs_f64() / compile_time * 100.0,
+                module.name
+            );
+        }
+        if slowest.len() > TABLE_ROWS {
+            println!("  ... and {} more", slowest.len() - TABLE_ROWS);
+        }
+    }
+
+    fn html(&self) -> String {
+        let slowest = self
+            .slowest()
+            .first()
+            .map_or(0.0, |m| m.duration.as_secs_f64());
+        let mut rows = String::new();
+        let mut modules: Vec<_> = self.modules.iter().collect();
+        modules.sort_by(|a, b| b.duration.cmp(&a.duration));
+        for module in modules {
+            let width = if module.cached || slowest == 0.0 {
+                0.0
+            } else {
+                module.duration.as_secs_f64() / slowest * 100.0
+            };
+            let _ = writeln!(
+                rows,
+                "<tr><td>{}</td><td>{:.3}s</td><td>{}</td><td><div class=\"bar\" style=\"width: {width:.1}%\"></div></td></tr>",
+                escape_html(&module.name),
+                module.duration.as_secs_f64(),
+                if module.cached { "cached" } else { "compiled" },
+            );
+        }
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>venice build timings</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td, th {{ text-align: left; padding: 0.2em 0.8em; border-bottom: 1px solid #ddd; }}
+td:last-child {{ width: 40%; }}
+.bar {{ background: #4a90d9; height: 1em; }}
+</style>
+</head>
+<body>
+<h1>venice build timings</h1>
+<p>{}</p>
+<table>
+<tr><th>Module</th><th>Time</th><th>Status</th><th></th></tr>
+{rows}</table>
+</body>
+</html>
+"#,
+            escape_html(&self.summary())
+        )
+    }
+
+    /// Writes the HTML report to the build dir, returning its path.
+    pub async fn write_html(&self, build_dir: &Path) -> Result<PathBuf, CliError> {
+        let path = build_dir.join(TIMINGS_FILE);
+        tokio::fs::write(&path, self.html()).await?;
+        Ok(path)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use semver::Version;
+use tokio::sync::Mutex;
+
+use crate::{
+    MPY_CROSS_PATH, config::cache_dir, doctor::tool_version, errors::CliError, frozen, uv_path,
+};
+
+/// mpy-cross release downloaded when the one from the venice-cli package can't run and the
+/// project doesn't pin one. Its bytecode version must be one the runtime loads.
+pub const MPY_CROSS_VERSION: Version = Version::new(1, 25, 0);
+
+/// The mpy-cross found for each pinned version, or for none.
+static RESOLVED: Mutex<BTreeMap<Option<Version>, PathBuf>> = Mutex::const_new(BTreeMap::new());
+
+/// The mpy-cross from the venice-cli package.
+fn configured() -> PathBuf {
+    PathBuf::from(MPY_CROSS_PATH.get().map_or("mpy-cross", String::as_str))
+}
+
+fn banner(program: &Path) -> Option<String> {
+    tool_version(&program.to_string_lossy(), "--version")
+}
+
+/// The version in an mpy-cross banner, e.g. 1.25.0 from
+/// `MicroPython v1.25.0 on 2025-04-15; mpy-cross emitting mpy v6.3`.
+fn parse_version(banner: &str) -> Option<Version> {
+    banner
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix('v')?.trim_end_matches(';').parse().ok())
+}
+
+/// Where mpy-cross `version` is installed in the venice cache.
+fn cached_dir(version: &Version) -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("toolchain").join(format!("mpy-cross-{version}")))
+}
+
+/// The executable inside a pip install of the mpy-cross package.
+fn cached_binary(dir: &Path) -> PathBuf {
+    dir.join("mpy_cross")
+        .join("mpy-cross")
+        .with_extension(std::env::consts::EXE_EXTENSION)
+}
+
+/// An mpy-cross that runs, without installing one: the venice-cli package's, or one venice
+/// downloaded earlier.
+pub fn find() -> Option<PathBuf> {
+    let configured = configured();
+    if banner(&configured).is_some() {
+        return Some(configured);
+    }
+    cached_dir(&MPY_CROSS_VERSION)
+        .map(|dir| cached_binary(&dir))
+        .filter(|binary| banner(binary).is_some())
+}
+
+async fn install(dir: &Path, version: &Version) -> Result<(), CliError> {
+    if frozen() {
+        return Err(CliError::FrozenNetwork);
+    }
+    let uv = uv_path()?;
+    eprintln!("Downloading mpy-cross {version} into the venice cache...");
+    let output = tokio::process::Command::new(uv)
+        .args(["pip", "install", "--quiet", "--target"])
+        .arg(dir)
+        .arg(format!("mpy-cross=={version}"))
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(CliError::UvFailed {
+            status: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    Ok(())
+}
+
+async fn resolve(pinned: Option<&Version>) -> Result<PathBuf, CliError> {
+    let suits =
+        |banner: &str| pinned.is_none_or(|pinned| parse_version(banner).as_ref() == Some(pinned));
+
+    let configured = configured();
+    if banner(&configured).is_some_and(|banner| suits(&banner)) {
+        return Ok(configured);
+    }
+
+    let version = pinned.unwrap_or(&MPY_CROSS_VERSION);
+    let dir = cached_dir(version).ok_or(CliError::NoConfigDir)?;
+    let binary = cached_binary(&dir);
+    if !banner(&binary).is_some_and(|banner| suits(&banner)) {
+        install(&dir, version).await?;
+    }
+    match banner(&binary) {
+        Some(banner) if suits(&banner) => Ok(binary),
+        Some(banner) => Err(CliError::MpyCrossVersion {
+            pinned: version.clone(),
+            found: banner,
+        }),
+        None => Err(CliError::MpyCrossMissing(binary)),
+    }
+}
+
+/// The mpy-cross to compile with: exactly `pinned` if the project pins a version, downloading it
+/// if needed, or otherwise any that runs, downloading [`MPY_CROSS_VERSION`] if none does.
+pub async fn mpy_cross(pinned: Option<&Version>) -> Result<PathBuf, CliError> {
+    // Held while resolving, so modules compiling in parallel don't each start a download
+    let mut resolved = RESOLVED.lock().await;
+    if let Some(path) = resolved.get(&pinned.cloned()) {
+        return Ok(path.clone());
+    }
+    let path = resolve(pinned).await?;
+    resolved.insert(pinned.cloned(), path.clone());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+
+    use super::parse_version;
+
+    #[test]
+    fn reads_version_from_banner() {
+        assert_eq!(
+            parse_version("MicroPython v1.25.0 on 2025-04-15; mpy-cross emitting mpy v6.3"),
+            Some(Version::new(1, 25, 0))
+        );
+        assert_eq!(parse_version("mpy-cross emitting mpy v6.3"), None);
+    }
+}
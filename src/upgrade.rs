@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::{cache_dir, config_path, data_dir},
+    errors::CliError,
+    store::STORE_FILE,
+};
+
+/// The CLI version that last ran, in the data directory.
+const LAST_VERSION_FILE: &str = "last-version";
+
+/// Copies of the config and state store as they were before a different CLI version first ran,
+/// one folder per version that wrote them.
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Snapshots kept, oldest deleted first.
+const MAX_SNAPSHOTS: usize = 5;
+
+/// Layout version of the cache, in the cache directory.
+const CACHE_VERSION_FILE: &str = "layout-version";
+
+/// Upgrades the config file from version `i` to `i + 1`, where `i` is the index in this list.
+/// Rename keys here rather than dropping support for the old name, so upgrading never loses a
+/// setting.
+type ConfigMigration = fn(&mut toml_edit::DocumentMut);
+
+const CONFIG_MIGRATIONS: &[ConfigMigration] = &[];
+
+/// Config key holding the config's version. Missing means 0.
+const CONFIG_VERSION_KEY: &str = "config-version";
+
+/// Upgrades the cache directory from layout `i` to `i + 1`. Move files rather than deleting
+/// them: runtimes and release metadata are slow to download again.
+type CacheMigration = fn(&Path) -> std::io::Result<()>;
+
+const CACHE_MIGRATIONS: &[CacheMigration] = &[];
+
+/// Runs before anything reads the config. The first time a CLI version runs, whether newer or
+/// older than the last, it snapshots the config and state store, then migrates the config and
+/// cache. The state store migrates itself whenever it's loaded.
+pub fn run() -> Result<(), CliError> {
+    let Some(data) = data_dir() else {
+        return Ok(());
+    };
+    let current = env!("CARGO_PKG_VERSION");
+    let last = std::fs::read_to_string(data.join(LAST_VERSION_FILE)).ok();
+    let last = last.as_deref().map(str::trim);
+    if last == Some(current) {
+        return Ok(());
+    }
+
+    if let Some(last) = last {
+        snapshot(&data, last)?;
+    }
+    migrate_config()?;
+    migrate_cache()?;
+    std::fs::create_dir_all(&data)?;
+    std::fs::write(data.join(LAST_VERSION_FILE), current)?;
+    Ok(())
+}
+
+/// The files a snapshot holds, with where each lives.
+fn snapshot_files(data: &Path) -> Vec<(&'static str, PathBuf)> {
+    let mut files = vec![(STORE_FILE, data.join(STORE_FILE))];
+    if let Some(config) = config_path() {
+        files.push(("config.toml", config));
+    }
+    files
+}
+
+fn snapshot(data: &Path, version: &str) -> Result<(), CliError> {
+    let dest = data.join(SNAPSHOT_DIR).join(format!("v{version}"));
+    let mut copied = false;
+    for (name, path) in snapshot_files(data) {
+        if path.exists() {
+            std::fs::create_dir_all(&dest)?;
+            std::fs::copy(&path, dest.join(name))?;
+            copied = true;
+        }
+    }
+
+    if copied {
+        let mut snapshots = snapshots(data)?;
+        while snapshots.len() > MAX_SNAPSHOTS {
+            let (_, oldest) = snapshots.remove(0);
+            std::fs::remove_dir_all(oldest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Snapshots by the version that wrote them, oldest first.
+fn snapshots(data: &Path) -> Result<Vec<(semver::Version, PathBuf)>, CliError> {
+    let entries = match std::fs::read_dir(data.join(SNAPSHOT_DIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut snapshots: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let version = name.to_str()?.strip_prefix('v')?.parse().ok()?;
+            Some((version, entry.path()))
+        })
+        .collect();
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+fn migrate_config() -> Result<(), CliError> {
+    let Some(path) = config_path() else {
+        return Ok(());
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut doc = contents
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| CliError::ConfigEdit(e.to_string()))?;
+
+    let version = doc
+        .get(CONFIG_VERSION_KEY)
+        .and_then(|v| v.as_integer())
+        .unwrap_or(0) as usize;
+    // A config from a newer CLI is left alone; keys this one doesn't know are ignored
+    let Some(migrations) = CONFIG_MIGRATIONS.get(version..).filter(|m| !m.is_empty()) else {
+        return Ok(());
+    };
+    for migration in migrations {
+        migration(&mut doc);
+    }
+    doc.insert(
+        CONFIG_VERSION_KEY,
+        toml_edit::value(CONFIG_MIGRATIONS.len() as i64),
+    );
+    std::fs::write(&path, doc.to_string())?;
+    Ok(())
+}
+
+fn migrate_cache() -> Result<(), CliError> {
+    let Some(cache) = cache_dir().filter(|dir| dir.exists()) else {
+        return Ok(());
+    };
+    let version_path = cache.join(CACHE_VERSION_FILE);
+    let version = std::fs::read_to_string(&version_path)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0usize);
+    let Some(migrations) = CACHE_MIGRATIONS.get(version..).filter(|m| !m.is_empty()) else {
+        return Ok(());
+    };
+    for migration in migrations {
+        migration(&cache)?;
+    }
+    std::fs::write(version_path, CACHE_MIGRATIONS.len().to_string())?;
+    Ok(())
+}
+
+/// `venice restore-state`: puts back the config and state store as they were when `version`
+/// last ran, for going back to that version. Without a version, lists the snapshots.
+pub fn restore(version: Option<&semver::Version>) -> Result<(), CliError> {
+    let data = data_dir().ok_or(CliError::NoConfigDir)?;
+    let snapshots = snapshots(&data)?;
+    let Some(version) = version else {
+        if snapshots.is_empty() {
+            println!("No snapshots yet; one is taken the first time a new version of venice runs.");
+        }
+        for (version, _) in snapshots.iter().rev() {
+            println!("{version}");
+        }
+        return Ok(());
+    };
+
+    let (_, dir) = snapshots
+        .iter()
+        .find(|(snapshot, _)| snapshot == version)
+        .ok_or_else(|| CliError::NoSnapshot(version.clone()))?;
+    for (name, path) in snapshot_files(&data) {
+        let saved = dir.join(name);
+        if saved.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&saved, &path)?;
+            println!("✓ Restored {}", path.display());
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,14 @@
-use std::time::Duration;
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 use indicatif::{ProgressBar, ProgressStyle};
-use tokio::task::spawn_blocking;
+use inquire::Confirm;
 use vex_v5_serial::{
     Connection,
     commands::file::{LinkedFile, USER_PROGRAM_LOAD_ADDR, UploadFile, j2000_timestamp},
@@ -16,29 +23,29 @@ use vex_v5_serial::{
             },
         },
     },
-    serial::{self, SerialConnection, SerialError},
+    serial::{SerialConnection, SerialError},
 };
 
 use crate::{
-    build::build,
+    BUILD_DIR,
+    artifact::{self, ProgramInfo},
+    battery::{read_battery, warn_if_low},
+    brain::{erase_vendor_file, foreign_slot_files},
+    build::{BuildOptions, build},
+    calib,
+    data::sync_data_files,
+    device::{identity, open_device, system_port, usb_serial_number},
     errors::CliError,
     manifest::get_project,
-    runtime::{RuntimeSource, VPT_LOAD_ADDR},
+    patch, profile, project_dir, quiet,
+    runtime::{Feature, RuntimeSource, VPT_LOAD_ADDR, check_feature},
+    screen_reader,
+    signing::check_trusted,
+    store::Store,
+    theme,
+    vpt::read_programs,
 };
 
-pub async fn open_connection() -> Result<SerialConnection, CliError> {
-    let devices = serial::find_devices()?;
-
-    spawn_blocking(move || {
-        Ok(devices
-            .first()
-            .ok_or(CliError::NoDevice)?
-            .connect(Duration::from_secs(5))?)
-    })
-    .await
-    .unwrap()
-}
-
 /// # Errors
 ///
 /// - Returns Err(e) if a serial error occurred.
@@ -66,35 +73,237 @@ pub async fn brain_file_metadata(
     }
 }
 
-fn ini_config(name: &str, slot: u8, icon: u16, description: &str) -> String {
-    format!(
-        "[project]\
-    \r\nide=Venice\
-    \r\n[program]\
-    \r\nname={name}\
-    \r\nslot={slot}\
-    \r\nicon=USER{icon:03}x.bmp\
-    \r\niconalt=\
-    \r\ndescription={description}\r\n",
-    )
+/// Project file replacing the generated `slot_N.ini`, for fields venice doesn't set itself like
+/// `iconalt` or a custom `ide`. `{name}`, `{slot}`, `{icon}` and `{description}` are filled in.
+pub const INI_TEMPLATE: &str = "program.ini";
+
+const DEFAULT_INI: &str = "\
+[project]
+ide=Venice
+[program]
+name={name}
+slot={slot}
+icon={icon}
+iconalt=
+description={description}
+";
+
+fn ini_config(
+    template: Option<&str>,
+    name: &str,
+    slot: u8,
+    icon: u16,
+    description: &str,
+) -> String {
+    // A line break in a value would end it early and start a key of the value's choosing
+    let single_line = |value: &str| value.replace(['\r', '\n'], " ");
+    let ini = template
+        .unwrap_or(DEFAULT_INI)
+        .replace("{slot}", &slot.to_string())
+        .replace("{icon}", &format!("USER{icon:03}x.bmp"))
+        .replace("{name}", &single_line(name))
+        // Last, so a `{...}` in the user's description is left alone
+        .replace("{description}", &single_line(description));
+    // VEXos expects CRLF line endings, whatever the template was saved with
+    ini.lines().map(|line| format!("{line}\r\n")).collect()
 }
 
-fn create_upload_progress_bar(message: &str) -> ProgressBar {
-    let pb = ProgressBar::new(100);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>3}% {msg}")
-            .unwrap()
-            .progress_chars("##-"),
+/// Warns about files other tools stored for `slot` under their own vendor, which can make the
+/// brain show their program instead of ours, and offers to delete them.
+async fn check_foreign_files(conn: &mut SerialConnection, slot: u8) -> Result<(), CliError> {
+    let foreign = foreign_slot_files(conn, slot).await?;
+    if foreign.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!(
+        "warning: slot {slot} also holds files from another tool, and the brain may show that program instead of this one:"
     );
-    pb.set_message(message.to_string());
-    pb
+    for file in &foreign {
+        eprintln!("  {} (probably {})", file.name, file.owner);
+    }
+    let delete = !quiet()
+        && Confirm::new("Delete them?")
+            .with_default(true)
+            .prompt()
+            .unwrap_or(false);
+    if !delete {
+        eprintln!("Uploading anyway. If the wrong program runs, delete them on the brain.");
+        return Ok(());
+    }
+    for file in &foreign {
+        erase_vendor_file(conn, file.vendor, &file.name).await?;
+    }
+    eprintln!("✓ Deleted {} file(s)", foreign.len());
+    Ok(())
+}
+
+/// With `--screen-reader`, how often (in percent) upload progress is announced.
+const STATUS_STEP: u64 = 25;
+
+/// Progress of one upload: a progress bar, or with `--screen-reader`, a plain status line every
+/// [`STATUS_STEP`] percent. `run --ci` hides the bar.
+#[derive(Clone)]
+enum UploadProgress {
+    Bar(ProgressBar),
+    Text {
+        message: String,
+        reported: Arc<AtomicU64>,
+    },
+}
+
+impl UploadProgress {
+    fn new(message: &str) -> Self {
+        if quiet() {
+            return Self::Bar(ProgressBar::hidden());
+        }
+        if screen_reader() {
+            println!("{message}...");
+            return Self::Text {
+                message: message.to_string(),
+                reported: Arc::new(AtomicU64::new(0)),
+            };
+        }
+
+        let pb = ProgressBar::new(100);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(theme::progress_template())
+                .unwrap()
+                .progress_chars("##-"),
+        );
+        pb.set_message(message.to_string());
+        Self::Bar(pb)
+    }
+
+    fn set_position(&self, percent: u64) {
+        match self {
+            Self::Bar(pb) => pb.set_position(percent),
+            Self::Text { message, reported } => {
+                let step = percent / STATUS_STEP * STATUS_STEP;
+                // 100% is announced by `finish`
+                if step < 100 && step > reported.fetch_max(step, Ordering::Relaxed) {
+                    println!("{message}: {step}%");
+                }
+            }
+        }
+    }
+
+    fn finish(&self) {
+        match self {
+            Self::Bar(pb) => pb.finish_with_message(format!("{} - done", pb.message())),
+            Self::Text { message, .. } => println!("{message} - done"),
+        }
+    }
+}
+
+/// Uploads an arbitrary user-vendor file to flash with a progress bar, optionally linking it
+/// against another file already on the brain.
+pub async fn upload_user_file(
+    conn: &mut SerialConnection,
+    file_name: &str,
+    data: &[u8],
+    load_address: u32,
+    linked_file: Option<&str>,
+) -> Result<(), CliError> {
+    upload_file(
+        conn,
+        file_name,
+        data,
+        FileVendor::User,
+        FileTransferTarget::Qspi,
+        load_address,
+        linked_file,
+    )
+    .await
+}
+
+/// Uploads a file with a progress bar to wherever on the brain it's asked to go.
+pub async fn upload_file(
+    conn: &mut SerialConnection,
+    file_name: &str,
+    data: &[u8],
+    vendor: FileVendor,
+    target: FileTransferTarget,
+    load_address: u32,
+    linked_file: Option<&str>,
+) -> Result<(), CliError> {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.chars().take(3).collect::<String>())
+        .unwrap_or_else(|| String::from("bin"));
+
+    let linked_file = linked_file
+        .map(|name| {
+            Ok::<_, CliError>(LinkedFile {
+                file_name: FixedString::new(name.to_string())
+                    .map_err(|_| CliError::BrainFileName(name.to_string()))?,
+                vendor: FileVendor::User,
+            })
+        })
+        .transpose()?;
+
+    let pb = UploadProgress::new(&format!("Uploading {file_name}"));
+    let pb_clone = pb.clone();
+    conn.execute_command(UploadFile {
+        file_name: FixedString::new(file_name.to_string())
+            .map_err(|_| CliError::BrainFileName(file_name.to_string()))?,
+        metadata: FileMetadata {
+            extension: FixedString::new(extension).unwrap(),
+            extension_type: ExtensionType::Binary,
+            timestamp: j2000_timestamp(),
+            version: Version {
+                major: 0,
+                minor: 1,
+                build: 0,
+                beta: 0,
+            },
+        },
+        vendor,
+        data,
+        target,
+        load_address,
+        linked_file,
+        after_upload: FileExitAction::DoNothing,
+        progress_callback: Some(Box::new(move |progress| {
+            pb_clone.set_position(progress as u64);
+        })),
+    })
+    .await?;
+    pb.finish();
+    Ok(())
 }
 
 // I swear this wasn't vibe coded. I only added the superfluous amount of comments to make sure all
 // the logic was correct.
 // I believe you -- aadish 2025-08-23
 pub async fn upload(
+    after_upload: Option<FileExitAction>,
+    runtime_source: Option<RuntimeSource>,
+    force_reupload_runtime: bool,
+) -> Result<SerialConnection, CliError> {
+    upload_from(
+        &UploadSource::Project,
+        None,
+        after_upload,
+        runtime_source,
+        force_reupload_runtime,
+    )
+    .await
+}
+
+/// What to upload.
+pub enum UploadSource {
+    /// The current project, built fresh
+    Project,
+    /// A program table built earlier with `build --artifact-metadata`, flashed without the project
+    Artifact(PathBuf),
+}
+
+/// Uploads `source`. `slot` overrides the slot from the manifest or artifact metadata.
+pub async fn upload_from(
+    source: &UploadSource,
+    slot: Option<u8>,
     after_upload: Option<FileExitAction>,
     runtime_source: Option<RuntimeSource>,
     _force_reupload_runtime: bool,
@@ -102,35 +311,68 @@ pub async fn upload(
     let bin_string = FixedString::new(String::from("bin")).unwrap();
 
     // background opening a serial conn
-    let conn_task = tokio::spawn(open_connection());
-
-    // read and parse manifest
-    let manifest = get_project().await?;
-
-    if let Some(slot) = manifest.slot {
-        if !(1..=8).contains(&slot) {
-            return Err(CliError::SlotOutOfRange);
-        }
-    } else {
-        return Err(CliError::SlotOutOfRange); // This shouldn't happen if ensure_project_config worked
-    }
+    let conn_task = tokio::spawn(open_device());
 
     // Get the runtime source or error if none provided
     let runtime_source = runtime_source.ok_or(CliError::NoRuntimeSource)?;
     let rtbin = runtime_source.as_rtbin();
     let runtime_contents = runtime_source.read_binary().await?;
 
+    // read the program's details from the manifest, or from the artifact's metadata
+    let (mut program, data, prebuilt, ini_template, stripped) = match source {
+        UploadSource::Project => {
+            let manifest = get_project().await?;
+            let template_path = project_dir()?.join(INI_TEMPLATE);
+            let ini_template = match tokio::fs::read_to_string(&template_path).await {
+                Ok(template) => Some(template),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+            let stripped = manifest.stripped(profile());
+            (
+                ProgramInfo::from_project(&manifest)?,
+                Some(manifest.data),
+                None,
+                ini_template,
+                stripped,
+            )
+        }
+        UploadSource::Artifact(path) => {
+            let (metadata, vpt) = artifact::load(path).await?;
+            if let Some(built) = metadata.runtime
+                && built != runtime_source.version
+            {
+                return Err(CliError::ArtifactRuntimeMismatch {
+                    built,
+                    installed: runtime_source.version.clone(),
+                });
+            }
+            (metadata.program, None, Some(vpt), None, metadata.stripped)
+        }
+    };
+    if let Some(slot) = slot {
+        program.slot = slot;
+    }
+
     let config = ini_config(
-        &manifest.name,
-        manifest.slot.unwrap_or(1), // Default to 1 if somehow missing
-        manifest.icon as u16,
-        manifest.description.as_deref().unwrap_or("Made in Heaven!"),
+        ini_template.as_deref(),
+        &program.name,
+        program.slot,
+        program.icon,
+        program.description.as_deref().unwrap_or("Made in Heaven!"),
     );
 
-    let mut conn = conn_task.await.unwrap()?;
-    let ini_name = FixedString::new(format!("slot_{}.ini", manifest.slot.unwrap_or(1))).unwrap();
+    let (mut conn, device) = conn_task.await.unwrap()?;
+    if let Some(identity) = identity() {
+        eprintln!("Uploading to {identity}");
+    }
+    if let Ok((brain_percent, _)) = read_battery(&mut conn).await {
+        warn_if_low(brain_percent);
+    }
+    check_foreign_files(&mut conn, program.slot).await?;
+    let ini_name = FixedString::new(format!("slot_{}.ini", program.slot)).unwrap();
 
-    let ini_pb = create_upload_progress_bar("Uploading ini");
+    let ini_pb = UploadProgress::new("Uploading ini");
     let ini_pb_clone = ini_pb.clone();
     conn.execute_command(UploadFile {
         // Must be "slot_{n}.ini"
@@ -158,7 +400,7 @@ pub async fn upload(
         })),
     })
     .await?;
-    ini_pb.finish_with_message("Uploading ini - done");
+    ini_pb.finish();
 
     // Check if the runtime is already on the brain; if not, upload it
     let rtbin_name = FixedString::new(format!("{rtbin}")).unwrap();
@@ -167,7 +409,7 @@ pub async fn upload(
     let reupload_rt = rt_metadata.is_none();
 
     if reupload_rt {
-        let rt_pb = create_upload_progress_bar("Uploading runtime");
+        let rt_pb = UploadProgress::new("Uploading runtime");
         let rt_pb_clone = rt_pb.clone();
         conn.execute_command(UploadFile {
             file_name: rtbin_name.clone(),
@@ -194,16 +436,42 @@ pub async fn upload(
             })),
         })
         .await?;
-        rt_pb.finish_with_message("Uploading runtime - done");
+        rt_pb.finish();
     }
 
-    let vpt = build().await?;
+    // Data files go up before the program so they're in place when it starts
+    if let Some(data) = &data {
+        let device_serial = usb_serial_number(system_port(&device)).unwrap_or_default();
+        sync_data_files(&mut conn, &device_serial, data).await?;
+        if project_dir()?.join(calib::CALIB_FILE).exists()
+            && check_feature(&runtime_source.version, Feature::Calibration).await
+        {
+            calib::push_if_changed(&mut conn, &device_serial).await?;
+        }
+    }
+
+    let vpt = match prebuilt {
+        Some(vpt) => vpt,
+        None => {
+            build(&BuildOptions {
+                // Signed with the key in the config dir, which has to be one of the trusted ones
+                sign: !crate::config().signing.trusted_keys.is_empty(),
+                runtime: Some(runtime_source.version.clone()),
+                ..Default::default()
+            })
+            .await?
+        }
+    };
+    check_trusted(&read_programs(&vpt)?)?;
+    if stripped {
+        eprintln!("note: this build is stripped of debug info, so tracebacks have no line numbers");
+    }
 
-    let vpt_pb = create_upload_progress_bar("Uploading VPT");
+    let vpt_pb = UploadProgress::new("Uploading VPT");
     let vpt_pb_clone = vpt_pb.clone();
     conn.execute_command(UploadFile {
         // It's not technically a binary, but I believe it must still be named this way.
-        file_name: FixedString::new(format!("slot_{}.bin", manifest.slot.unwrap_or(1))).unwrap(),
+        file_name: FixedString::new(format!("slot_{}.bin", program.slot)).unwrap(),
         metadata: FileMetadata {
             extension: bin_string.clone(),
             extension_type: ExtensionType::Binary,
@@ -229,6 +497,66 @@ pub async fn upload(
         })),
     })
     .await?;
-    vpt_pb.finish_with_message("Uploading VPT - done");
+    vpt_pb.finish();
+
+    // What `venice patch` diffs against; without it, the next patch just asks for an upload
+    if matches!(source, UploadSource::Project) {
+        let build_dir = project_dir()?.join(BUILD_DIR);
+        if let Err(e) = patch::save_base(&build_dir, program.slot, &vpt).await {
+            eprintln!("warning: couldn't keep the upload for `venice patch` to diff against: {e}");
+        }
+    }
+
+    // Keep the cached slots fresh for completions; a stale cache only means worse suggestions
+    if let Some(device_serial) = usb_serial_number(system_port(&device)) {
+        let _ = Store::update(|store| {
+            store
+                .brain_slots
+                .entry(device_serial)
+                .or_default()
+                .insert(program.slot, program.name.clone());
+        });
+    }
     Ok(conn)
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{DEFAULT_INI, ini_config};
+
+    #[test]
+    fn renders_ini() {
+        assert_eq!(
+            ini_config(None, "Robot", 2, 1, "Made in Heaven!"),
+            "[project]\r\nide=Venice\r\n[program]\r\nname=Robot\r\nslot=2\r\n\
+             icon=USER001x.bmp\r\niconalt=\r\ndescription=Made in Heaven!\r\n"
+        );
+        assert_eq!(
+            ini_config(
+                Some("[program]\nname={name}\niconalt=USER002x.bmp\n"),
+                "{slot}",
+                3,
+                0,
+                ""
+            ),
+            "[program]\r\nname={slot}\r\niconalt=USER002x.bmp\r\n"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn ini_values_stay_on_their_line(
+            name in ".*",
+            description in ".*",
+            slot in 1u8..=8,
+            icon: u16,
+        ) {
+            let ini = ini_config(None, &name, slot, icon, &description);
+            prop_assert_eq!(ini.split("\r\n").count(), DEFAULT_INI.lines().count() + 1);
+            prop_assert!(ini.ends_with("\r\n"));
+            prop_assert!(!ini.replace("\r\n", "").contains(['\r', '\n']));
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config,
+    config::{CONFIG_NAME, UsageCollection, config_dir},
+    errors::CliError,
+    frozen,
+    store::Store,
+};
+
+/// How often one command ran and how it went, summed over every run since metrics were turned
+/// on. Only the command's name is kept: never its arguments, paths or the project's code.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CommandUsage {
+    pub runs: u64,
+    pub failures: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+    /// Failures by error code, or `other` for errors without one
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl CommandUsage {
+    fn record(&mut self, duration: Duration, error: Option<&str>) {
+        let ms = duration.as_millis() as u64;
+        self.runs += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        if let Some(error) = error {
+            self.failures += 1;
+            *self.errors.entry(error.to_string()).or_default() += 1;
+        }
+    }
+}
+
+/// Adds one run of `command` to the metrics, if the config turns them on. `error` is the failure's
+/// error code, if it failed.
+pub fn record(command: &str, duration: Duration, error: Option<&miette::Report>) {
+    if config().usage.collect == UsageCollection::Off {
+        return;
+    }
+    let error = error.map(|error| error.code().map_or("other".to_string(), |c| c.to_string()));
+    let _ = Store::update(|store| {
+        store
+            .usage
+            .entry(command.to_string())
+            .or_default()
+            .record(duration, error.as_deref());
+    });
+}
+
+fn print(usage: &BTreeMap<String, CommandUsage>) {
+    println!(
+        "{:<16} {:>6} {:>8} {:>10} {:>10}  most common errors",
+        "command", "runs", "failed", "avg", "max"
+    );
+    for (command, usage) in usage {
+        let mut errors: Vec<_> = usage.errors.iter().collect();
+        errors.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let errors: Vec<_> = errors
+            .iter()
+            .take(3)
+            .map(|(code, count)| format!("{code} ×{count}"))
+            .collect();
+        println!(
+            "{command:<16} {:>6} {:>8} {:>8}ms {:>8}ms  {}",
+            usage.runs,
+            usage.failures,
+            usage.total_ms / usage.runs.max(1),
+            usage.max_ms,
+            errors.join(", ")
+        );
+    }
+}
+
+/// Sends the metrics, with the venice version and platform but nothing identifying the machine.
+async fn send(url: &str, usage: &BTreeMap<String, CommandUsage>) -> Result<(), CliError> {
+    if frozen() {
+        return Err(CliError::FrozenNetwork);
+    }
+    let body = serde_json::json!({
+        "venice-cli": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "commands": usage,
+    })
+    .to_string();
+    let url = url.to_string();
+    tokio::task::spawn_blocking(move || {
+        ureq::post(&url)
+            .header("Content-Type", "application/json")
+            .send(body)
+    })
+    .await
+    .unwrap()?;
+    Ok(())
+}
+
+/// `venice stats usage`: shows the metrics kept on this machine, exactly as they'd be sent.
+/// `submit` sends them if the config allows sharing, and `reset` forgets them.
+pub async fn usage(submit: bool, reset: bool) -> Result<(), CliError> {
+    let settings = &config().usage;
+    let usage = Store::load()?.usage;
+
+    match settings.collect {
+        UsageCollection::Off => {
+            let path = config_dir().ok_or(CliError::NoConfigDir)?.join(CONFIG_NAME);
+            println!(
+                "Usage metrics are off. Set `collect = \"local\"` under [usage] in {} to keep them.",
+                path.display()
+            )
+        }
+        UsageCollection::Local => println!("Usage metrics are kept on this machine only."),
+        UsageCollection::Share => {
+            println!("Usage metrics are kept on this machine and can be sent with --submit.")
+        }
+    }
+    if usage.is_empty() {
+        println!("No commands recorded");
+    } else {
+        println!();
+        print(&usage);
+    }
+
+    if submit {
+        let url = match (settings.collect, &settings.submit_url) {
+            (UsageCollection::Share, Some(url)) => url,
+            _ => return Err(CliError::UsageNotShared),
+        };
+        send(url, &usage).await?;
+        println!("✓ Sent metrics for {} command(s) to {url}", usage.len());
+    }
+    if reset {
+        Store::update(|store| store.usage.clear())?;
+        println!("✓ Forgot the recorded metrics");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::CommandUsage;
+
+    #[test]
+    fn aggregates_runs() {
+        let mut usage = CommandUsage::default();
+        usage.record(Duration::from_millis(30), None);
+        usage.record(Duration::from_millis(10), Some("VE0008"));
+        usage.record(Duration::from_millis(20), Some("VE0008"));
+
+        assert_eq!((usage.runs, usage.failures), (3, 2));
+        assert_eq!((usage.total_ms, usage.max_ms), (60, 30));
+        assert_eq!(usage.errors["VE0008"], 2);
+    }
+}
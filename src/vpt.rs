@@ -0,0 +1,186 @@
+use std::path::{Component, Path, PathBuf};
+
+use venice_program_table::{ProgramFlags, Vpt};
+
+use crate::{
+    VENDOR_ID,
+    assets::ASSET_PREFIX,
+    build::{BUILD_EXT, decompressed},
+    errors::CliError,
+};
+
+/// Where `venice vpt unpack` writes programs unless given a directory.
+pub const UNPACK_DIR: &str = "unpacked";
+/// Directory under the unpack output that assets go in.
+const UNPACKED_ASSETS_DIR: &str = "assets";
+
+/// An owned copy of one program in a VPT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub name: Vec<u8>,
+    pub flags: ProgramFlags,
+    pub payload: Vec<u8>,
+}
+
+/// Decodes every program in a VPT produced by [`crate::build::build`].
+pub fn read_programs(vpt: &[u8]) -> Result<Vec<Program>, CliError> {
+    let table = Vpt::new(vpt, VENDOR_ID).map_err(|e| CliError::InvalidVpt(format!("{e:?}")))?;
+
+    Ok(table
+        .program_iter()
+        .map(|program| Program {
+            name: program.name().to_vec(),
+            flags: program.flags(),
+            payload: program.payload().to_vec(),
+        })
+        .collect())
+}
+
+/// Where `venice vpt unpack` writes `program`, relative to its output directory: modules as the
+/// bytecode files the build compiled them into, assets under `assets/` by their path, and the
+/// CLI's own programs like `<venice-autons>` as `venice-autons.bin`.
+fn unpacked_path(program: &Program) -> Result<PathBuf, CliError> {
+    let name = String::from_utf8_lossy(&program.name);
+    let invalid = || CliError::InvalidVpt(format!("program `{name}` can't be unpacked safely"));
+
+    if let Some(asset) = name.strip_prefix(ASSET_PREFIX) {
+        let path = Path::new(asset);
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(invalid());
+        }
+        return Ok(Path::new(UNPACKED_ASSETS_DIR).join(path));
+    }
+    if let Some(special) = name.strip_prefix('<').and_then(|n| n.strip_suffix('>')) {
+        if !special
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(invalid());
+        }
+        return Ok(PathBuf::from(format!("{special}.bin")));
+    }
+
+    let mut path = PathBuf::new();
+    for part in name.split('.') {
+        if part.is_empty() || !part.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+        path.push(part);
+    }
+    if program.flags.contains(ProgramFlags::IS_PACKAGE) {
+        path.push("__init__");
+    }
+    Ok(path.with_extension(BUILD_EXT))
+}
+
+/// `venice vpt unpack`: writes every program in the table at `path` into `out`, the inverse of
+/// packing them in `build`.
+pub async fn unpack(path: &Path, out: &Path) -> Result<(), CliError> {
+    let programs = read_programs(&tokio::fs::read(path).await?)?;
+    for program in &programs {
+        let dest = out.join(unpacked_path(program)?);
+        tokio::fs::create_dir_all(dest.parent().unwrap()).await?;
+        let payload = decompressed(program.flags, &program.payload)?;
+        tokio::fs::write(&dest, &payload).await?;
+        println!("{} ({} bytes)", dest.display(), payload.len());
+    }
+    println!(
+        "✓ Unpacked {} programs into {}",
+        programs.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use proptest::prelude::*;
+    use venice_program_table::{ProgramBuilder, ProgramFlags, VptBuilder};
+
+    use super::{Program, read_programs, unpacked_path};
+    use crate::VENDOR_ID;
+
+    fn program(name: &str, flags: ProgramFlags) -> Program {
+        Program {
+            name: name.as_bytes().to_vec(),
+            flags,
+            payload: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn unpacks_to_build_paths() {
+        let path = |name, flags| unpacked_path(&program(name, flags)).unwrap();
+        assert_eq!(path("main", ProgramFlags::empty()), Path::new("main.mpy"));
+        assert_eq!(
+            path("robot.drive", ProgramFlags::IS_PACKAGE),
+            Path::new("robot/drive/__init__.mpy")
+        );
+        assert_eq!(
+            path("<venice-asset>paths/skills.json", ProgramFlags::empty()),
+            Path::new("assets/paths/skills.json")
+        );
+        assert_eq!(
+            path("<venice-autons>", ProgramFlags::empty()),
+            Path::new("venice-autons.bin")
+        );
+
+        assert!(unpacked_path(&program("<venice-asset>../escape", ProgramFlags::empty())).is_err());
+        assert!(unpacked_path(&program("robot/../escape", ProgramFlags::empty())).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn reads_what_was_built(
+            programs in prop::collection::vec(
+                (
+                    prop::collection::vec(any::<u8>(), 1..32),
+                    prop::collection::vec(any::<u8>(), 0..512),
+                ),
+                0..8,
+            ),
+        ) {
+            let mut builder = VptBuilder::new(VENDOR_ID);
+            for (name, payload) in &programs {
+                builder.add_program(ProgramBuilder {
+                    name: name.clone(),
+                    payload: payload.clone(),
+                    flags: ProgramFlags::empty(),
+                });
+            }
+            let expected: Vec<_> = programs
+                .into_iter()
+                .map(|(name, payload)| Program {
+                    name,
+                    flags: ProgramFlags::empty(),
+                    payload,
+                })
+                .collect();
+            prop_assert_eq!(read_programs(&builder.build()).unwrap(), expected);
+        }
+
+        /// Program tables come from the brain and from downloaded artifacts, so a corrupt one must
+        /// be an error rather than a panic.
+        #[test]
+        fn rejects_garbage_without_panicking(vpt in prop::collection::vec(any::<u8>(), 0..1024)) {
+            let _ = read_programs(&vpt);
+        }
+
+        #[test]
+        fn rejects_truncation_without_panicking(len in 0usize..256) {
+            let mut builder = VptBuilder::new(VENDOR_ID);
+            builder.add_program(ProgramBuilder {
+                name: b"main".to_vec(),
+                payload: vec![0x4d; 200],
+                flags: ProgramFlags::empty(),
+            });
+            let vpt = builder.build();
+            let _ = read_programs(&vpt[..len.min(vpt.len())]);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    errors::CliError,
+    manifest::{Project, get_project_at, read_workspace_config},
+    workspace,
+};
+
+/// Projects sharing one [tool.venice.workspace]: their dependencies are resolved together into a
+/// single venice.lock at the root, installed once, and built under the root's build directory.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// Member project directories
+    pub members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Whether the project in `dir` belongs to the workspace.
+    pub fn contains(&self, dir: &Path) -> bool {
+        self.members.iter().any(|member| member == dir)
+    }
+
+    /// The members' directory names, for listing them in errors.
+    pub fn member_names(&self) -> Vec<String> {
+        self.members
+            .iter()
+            .map(|member| member_name(member))
+            .collect()
+    }
+
+    /// The directory of the member called `name`, by its directory's name or its project's.
+    pub async fn member(&self, name: &str) -> Result<PathBuf, CliError> {
+        if let Some(member) = self
+            .members
+            .iter()
+            .find(|member| member_name(member) == name)
+        {
+            return Ok(member.clone());
+        }
+        for member in &self.members {
+            if get_project_at(member).await?.name == name {
+                return Ok(member.clone());
+            }
+        }
+        Err(CliError::UnknownMember {
+            name: name.to_string(),
+            members: self.member_names().join(", "),
+        })
+    }
+
+    /// Every member's project.
+    pub async fn member_projects(&self) -> Result<Vec<Project>, CliError> {
+        let mut projects = Vec::new();
+        for member in &self.members {
+            projects.push(get_project_at(member).await?);
+        }
+        Ok(projects)
+    }
+}
+
+fn member_name(dir: &Path) -> String {
+    dir.file_name().map_or_else(
+        || dir.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// The nearest workspace at or above `start_dir`, if there is one.
+pub fn find_workspace(start_dir: &Path) -> Option<Workspace> {
+    let mut search_dir = start_dir.to_path_buf();
+
+    loop {
+        if let Some(config) = read_workspace_config(&search_dir) {
+            return Some(Workspace {
+                members: config
+                    .members
+                    .iter()
+                    .map(|member| search_dir.join(member))
+                    .collect(),
+                root: search_dir,
+            });
+        }
+
+        if !search_dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Where the project in `project_dir` keeps venice.lock and its installed and vendored
+/// dependencies: the workspace root if it's a member, so members share them, or else itself.
+pub fn deps_root(project_dir: &Path) -> &Path {
+    match workspace() {
+        Some(workspace) if workspace.contains(project_dir) => &workspace.root,
+        _ => project_dir,
+    }
+}
+
+/// Every member's project if the project in `project_dir` is in the workspace, itself included,
+/// or none if it isn't. They all share its installed dependencies.
+pub async fn sharing_projects(project_dir: &Path) -> Result<Vec<Project>, CliError> {
+    match workspace() {
+        Some(workspace) if workspace.contains(project_dir) => workspace.member_projects().await,
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// The version requirements `project`'s registry dependencies must meet, including those of the
+/// [`sharing_projects`] for the same packages, so that all of them resolve each package to one
+/// version.
+pub fn shared_requirements(
+    project: &Project,
+    sharing: &[Project],
+) -> BTreeMap<String, semver::VersionReq> {
+    let mut requirements = project.dependencies.clone();
+    for other in sharing {
+        for (name, req) in &other.dependencies {
+            if let Some(shared) = requirements.get_mut(name)
+                && shared != req
+            {
+                shared.comparators.extend(req.comparators.iter().cloned());
+            }
+        }
+    }
+    requirements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_workspace;
+    use crate::manifest::MANIFEST_NAME;
+
+    #[test]
+    fn finds_enclosing_workspace() {
+        let root = std::env::temp_dir().join(format!("venice-workspace-{}", std::process::id()));
+        let member = root.join("skills");
+        std::fs::create_dir_all(member.join("src")).unwrap();
+        std::fs::write(
+            root.join(MANIFEST_NAME),
+            "[tool.venice.workspace]\nmembers = [\"competition\", \"skills\"]\n",
+        )
+        .unwrap();
+        std::fs::write(member.join(MANIFEST_NAME), "[project]\nname = \"skills\"\n").unwrap();
+
+        let workspace = find_workspace(&member.join("src")).unwrap();
+        assert_eq!(workspace.root, root);
+        assert!(workspace.contains(&member));
+        assert!(!workspace.contains(&root));
+        assert_eq!(workspace.member_names(), ["competition", "skills"]);
+
+        // A manifest that doesn't parse is skipped, not an error
+        std::fs::write(root.join(MANIFEST_NAME), "[tool.venice.workspace\n").unwrap();
+        assert!(find_workspace(&member).is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+//! `cargo xtask e2e`: runs the installed venice CLI against throwaway projects and checks what it
+//! leaves behind. Install the CLI under test first with `maturin develop`.
+//!
+//! The steps that need a brain (`upload`, `run --ci`) only run with `--device <port>`, against a
+//! real brain on that port.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::Value;
+
+/// What `venice new`'s main.py prints.
+const HELLO: &str = "Hello, Venice!";
+
+struct Harness {
+    venice: PathBuf,
+    device: Option<String>,
+    root: PathBuf,
+    failures: Vec<String>,
+}
+
+impl Harness {
+    fn venice(&self, dir: &Path, args: &[&str]) -> Output {
+        let mut command = Command::new(&self.venice);
+        if let Some(device) = &self.device {
+            command.args(["--device", device]);
+        }
+        let output = command
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap_or_else(|e| panic!("couldn't run {}: {e}", self.venice.display()));
+        println!("$ venice {}", args.join(" "));
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        output
+    }
+
+    fn check(&mut self, name: &str, passed: bool) {
+        if passed {
+            println!("✓ {name}");
+        } else {
+            println!("✗ {name}");
+            self.failures.push(name.to_string());
+        }
+    }
+
+    /// Creates a project in the harness' temporary folder, returning its path.
+    fn new_project(&mut self, name: &str) -> Option<PathBuf> {
+        let root = self.root.clone();
+        let output = self.venice(&root, &["new", name]);
+        let dir = root.join(name);
+        self.check(
+            &format!("new {name}"),
+            output.status.success()
+                && dir.join("pyproject.toml").is_file()
+                && dir.join("main.py").is_file(),
+        );
+        dir.exists().then_some(dir)
+    }
+}
+
+fn build(harness: &mut Harness, dir: &Path) {
+    let output = harness.venice(dir, &["build"]);
+    let vpt = dir.join("build").join("debug").join("out.vpt");
+    harness.check(
+        "build writes a program table",
+        output.status.success() && std::fs::metadata(&vpt).is_ok_and(|meta| meta.len() > 0),
+    );
+
+    let output = harness.venice(dir, &["build", "--json"]);
+    let result: Option<Value> = serde_json::from_slice(&output.stdout).ok();
+    harness.check(
+        "build --json reports success",
+        result.is_some_and(|result| result["success"] == true),
+    );
+}
+
+fn build_error(harness: &mut Harness, dir: &Path) {
+    std::fs::write(dir.join("main.py"), "print(\"unterminated)\n").unwrap();
+    let output = harness.venice(dir, &["build", "--json"]);
+    let result: Option<Value> = serde_json::from_slice(&output.stdout).ok();
+    let reported = result.is_some_and(|result| {
+        result["success"] == false
+            && result["diagnostics"]
+                .as_array()
+                .is_some_and(|diagnostics| !diagnostics.is_empty())
+    });
+    harness.check(
+        "build --json reports a syntax error",
+        !output.status.success() && reported,
+    );
+}
+
+fn on_brain(harness: &mut Harness, dir: &Path) {
+    let output = harness.venice(dir, &["upload"]);
+    harness.check("upload", output.status.success());
+
+    let output = harness.venice(dir, &["run", "--ci", "--timeout", "60", "--expect", HELLO]);
+    harness.check(
+        "run --ci sees the program's output",
+        output.status.success(),
+    );
+}
+
+fn e2e(args: &[String]) -> Result<(), String> {
+    let mut venice = PathBuf::from("venice-cli");
+    let mut device = None;
+    let mut keep = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--venice" => venice = args.next().ok_or("--venice needs a path")?.into(),
+            "--device" => device = Some(args.next().ok_or("--device needs a port")?.clone()),
+            "--keep" => keep = true,
+            other => return Err(format!("unknown option {other}")),
+        }
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let root = std::env::temp_dir().join(format!("venice-e2e-{stamp}"));
+    std::fs::create_dir_all(&root).map_err(|e| e.to_string())?;
+    let mut harness = Harness {
+        venice,
+        device,
+        root,
+        failures: Vec::new(),
+    };
+
+    if let Some(dir) = harness.new_project("e2e-hello") {
+        build(&mut harness, &dir);
+        if harness.device.is_some() {
+            on_brain(&mut harness, &dir);
+        } else {
+            println!("- skipping upload and run --ci; pass --device <port> to test on a brain");
+        }
+    }
+    if let Some(dir) = harness.new_project("e2e-broken") {
+        build_error(&mut harness, &dir);
+    }
+
+    if keep {
+        println!("Projects kept in {}", harness.root.display());
+    } else {
+        let _ = std::fs::remove_dir_all(&harness.root);
+    }
+    match harness.failures.len() {
+        0 => Ok(()),
+        n => Err(format!(
+            "{n} check(s) failed: {}",
+            harness.failures.join(", ")
+        )),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((task, rest)) if task == "e2e" => e2e(rest),
+        _ => Err("usage: cargo xtask e2e [--venice <path>] [--device <port>] [--keep]".into()),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}